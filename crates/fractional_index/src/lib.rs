@@ -195,6 +195,76 @@ impl FractionalIndex {
     }
 }
 
+impl FractionalIndex {
+    /// Generate `n` evenly spaced indexes between `lower` and `upper`, using the
+    /// shortest key that leaves room for all of them.
+    ///
+    /// Unlike [`FractionalIndex::generate_n_evenly`], the length of the result does
+    /// not depend on how many times the range has been split before, so it can be
+    /// used to rebalance keys that have grown too long.
+    ///
+    /// Returns `None` if `lower >= upper`, or if the bounds are too close to fit `n`
+    /// keys within 14 extra bytes.
+    pub fn generate_n_compact(
+        lower: Option<&FractionalIndex>,
+        upper: Option<&FractionalIndex>,
+        n: usize,
+    ) -> Option<Vec<Self>> {
+        const MAX_WIDTH: usize = 14;
+
+        if n == 0 {
+            return Some(Vec::new());
+        }
+
+        let lower = lower.map(|x| x.as_bytes()).unwrap_or(&[]);
+        let upper = upper.map(|x| x.as_bytes());
+        if let Some(upper) = upper {
+            if lower >= upper {
+                return None;
+            }
+        }
+
+        // Keys are compared as base-256 fractions. With a fixed width `w` after the
+        // common prefix, any integer `x` with `floor(lower) < x < floor(upper)` gives
+        // a key `prefix + x + TERMINATOR` that is strictly between the bounds.
+        let prefix_len = upper.map_or(0, |upper| {
+            lower
+                .iter()
+                .zip(upper.iter())
+                .take_while(|(a, b)| a == b)
+                .count()
+        });
+        let digits = |bytes: &[u8], width: usize| -> u128 {
+            (0..width).fold(0, |acc, i| {
+                (acc << 8) | u128::from(*bytes.get(prefix_len + i).unwrap_or(&0))
+            })
+        };
+
+        let n = n as u128;
+        for width in 1..=MAX_WIDTH {
+            let lo = digits(lower, width);
+            let hi = upper.map_or(1 << (8 * width), |upper| digits(upper, width));
+            if hi - lo <= n {
+                continue;
+            }
+
+            let step = hi - lo;
+            let ans = (1..=n)
+                .map(|i| {
+                    let x = lo + i * step / (n + 1);
+                    let mut bytes = Vec::with_capacity(prefix_len + width + 1);
+                    bytes.extend_from_slice(&lower[..prefix_len]);
+                    bytes.extend_from_slice(&x.to_be_bytes()[16 - width..]);
+                    FractionalIndex::from_vec_unterminated(bytes)
+                })
+                .collect();
+            return Some(ans);
+        }
+
+        None
+    }
+}
+
 impl Display for FractionalIndex {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", bytes_to_hex(&self.0))
@@ -222,4 +292,21 @@ mod tests {
         assert!(index < upper);
         assert_eq!(index.to_string(), "00817F80");
     }
+
+    #[test]
+    fn generate_n_compact_uses_short_keys() {
+        let keys = FractionalIndex::generate_n_compact(None, None, 300).unwrap();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(keys.iter().all(|k| k.as_bytes().len() == 3));
+
+        let lower = FractionalIndex::from_hex_string("7F00FFFF80");
+        let upper = FractionalIndex::from_hex_string("7F0180");
+        let keys = FractionalIndex::generate_n_compact(Some(&lower), Some(&upper), 10).unwrap();
+        assert!(lower < keys[0]);
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(keys[9] < upper);
+        assert!(keys.iter().all(|k| k.as_bytes().len() <= 5));
+
+        assert!(FractionalIndex::generate_n_compact(Some(&upper), Some(&lower), 1).is_none());
+    }
 }
@@ -49,7 +49,7 @@ impl ActionInner {
             ContainerType::Text => Self::Text(TextAction::from_generic_action(action)),
             ContainerType::Tree => Self::Tree(TreeAction::from_generic_action(action)),
             ContainerType::Counter => Self::Counter(CounterAction::from_generic_action(action)),
-            ContainerType::Unknown(_) => unreachable!(),
+            _ => unreachable!(),
         }
     }
 }
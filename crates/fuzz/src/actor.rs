@@ -413,7 +413,7 @@ impl Actor {
                     ActionExecutor::CounterActor(CounterActor::new(self.loro.clone())),
                 );
             }
-            ContainerType::Unknown(_) => unreachable!(),
+            _ => unreachable!(),
         }
     }
 
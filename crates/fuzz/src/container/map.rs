@@ -225,7 +225,7 @@ impl Actionable for MapAction {
                     ContainerType::Counter => {
                         handler.ensure_mergeable_counter(key).map(|c| c.to_container())
                     }
-                    ContainerType::Unknown(_) => return None,
+                    _ => return None,
                 };
                 match result {
                     Ok(c) => Some(c),
@@ -401,7 +401,7 @@ impl CRDTFuzzer {
                         let counter = actor.loro.get_counter("counter");
                         let _ = counter.get();
                     }
-                    ContainerType::Unknown(_) => {}
+                    _ => {}
                 }
             }
             Action::ExportShallow { site } => {
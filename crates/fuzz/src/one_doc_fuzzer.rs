@@ -800,7 +800,7 @@ impl OneDocFuzzer {
                         let counter = self.doc.get_counter("counter");
                         let _ = counter.get();
                     }
-                    ContainerType::Unknown(_) => {}
+                    _ => {}
                 }
             }
             Action::ExportShallow { site } => {
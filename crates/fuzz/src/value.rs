@@ -32,7 +32,7 @@ impl Value {
             ContainerType::Counter => {
                 Value::Container(ContainerTracker::Counter(CounterTracker::empty(id)))
             }
-            ContainerType::Unknown(_) => unreachable!(),
+            _ => unreachable!(),
         }
     }
 }
@@ -473,7 +473,7 @@ impl ContainerTracker {
                 ContainerType::Counter => {
                     value.as_counter_mut().unwrap().apply_diff(diff);
                 }
-                ContainerType::Unknown(_) => unreachable!(),
+                _ => unreachable!(),
             }
         }
     }
@@ -79,12 +79,20 @@ pub struct CompactId {
 /// `Root` itself and is not duplicated in the name. `check_root_container_name`
 /// rejects user-created root names that start with this prefix.
 ///
+/// Children of lists and movable lists are keyed by a user-provided id instead of a
+/// map key. Their key segment starts with `\l` or `\v`, which no escaped map key
+/// can start with, so the parent kind of every segment can be recovered.
+///
 /// **Cost of nesting mergeable maps inside mergeable maps**: the payload embeds
 /// the nearest non-mergeable map ancestor once, followed by escaped map keys.
 /// Names grow linearly with nesting depth × average key length and ride through
 /// every op header, snapshot, and event path.
 pub const MERGEABLE_NAMESPACE_PREFIX: &str = "🤝:";
 
+/// The name of the root map whose inactive mergeable children store the document metadata.
+/// See [`ContainerID::new_metadata`].
+pub const METADATA_PARENT_NAME: &str = "🏷";
+
 fn write_len_prefixed_segment(out: &mut Vec<u8>, bytes: &[u8]) {
     leb128::write::unsigned(out, bytes.len() as u64).unwrap();
     out.extend_from_slice(bytes);
@@ -234,7 +242,41 @@ fn parse_i32_base36(s: &str) -> Option<i32> {
     }
 }
 
-fn parse_mergeable_base_parent(segment: &str) -> Option<ContainerID> {
+/// Tag at the start of a key segment whose parent is a list.
+const MERGEABLE_LIST_PARENT_TAG: &str = "\\l";
+/// Tag at the start of a key segment whose parent is a movable list.
+const MERGEABLE_MOVABLE_LIST_PARENT_TAG: &str = "\\v";
+
+fn mergeable_parent_tag(parent_type: ContainerType) -> &'static str {
+    match parent_type {
+        ContainerType::List => MERGEABLE_LIST_PARENT_TAG,
+        ContainerType::MovableList => MERGEABLE_MOVABLE_LIST_PARENT_TAG,
+        _ => "",
+    }
+}
+
+/// Split the parent kind tag off a raw (still escaped) key segment.
+fn split_mergeable_parent_tag(segment: &str) -> (ContainerType, &str) {
+    if let Some(rest) = segment.strip_prefix(MERGEABLE_LIST_PARENT_TAG) {
+        (ContainerType::List, rest)
+    } else if let Some(rest) = segment.strip_prefix(MERGEABLE_MOVABLE_LIST_PARENT_TAG) {
+        (ContainerType::MovableList, rest)
+    } else {
+        (ContainerType::Map, segment)
+    }
+}
+
+fn is_mergeable_parent_type(container_type: ContainerType) -> bool {
+    matches!(
+        container_type,
+        ContainerType::Map | ContainerType::List | ContainerType::MovableList
+    )
+}
+
+fn parse_mergeable_base_parent(
+    segment: &str,
+    container_type: ContainerType,
+) -> Option<ContainerID> {
     let mut chars = segment.chars();
     match chars.next()? {
         '$' => {
@@ -244,7 +286,7 @@ fn parse_mergeable_base_parent(segment: &str) -> Option<ContainerID> {
             }
             Some(ContainerID::Root {
                 name: name.into(),
-                container_type: ContainerType::Map,
+                container_type,
             })
         }
         '@' => {
@@ -253,7 +295,7 @@ fn parse_mergeable_base_parent(segment: &str) -> Option<ContainerID> {
             Some(ContainerID::Normal {
                 peer: parse_u64_base36(peer)?,
                 counter: parse_i32_base36(counter)?,
-                container_type: ContainerType::Map,
+                container_type,
             })
         }
         _ => None,
@@ -305,15 +347,23 @@ fn validate_mergeable_normal_base_parent(segment: &str) -> Option<()> {
 
 fn validate_mergeable_key_path(path: &str) -> Option<()> {
     let mut chars = path.chars();
+    let mut segment_start = true;
     while let Some(ch) = chars.next() {
         match ch {
             '\\' => match chars.next()? {
                 '\\' | '>' | 's' | '0' => {}
+                // Parent kind tags are only valid at the start of a key segment.
+                'l' | 'v' if segment_start => {}
                 _ => return None,
             },
+            '>' => {
+                segment_start = true;
+                continue;
+            }
             '/' | '\0' => return None,
             _ => {}
         }
+        segment_start = false;
     }
 
     Some(())
@@ -323,16 +373,17 @@ fn parse_mergeable_payload(payload: &str) -> Option<(ContainerID, String)> {
     validate_mergeable_payload(payload)?;
     let separator = find_last_mergeable_separator(payload)?;
     let parent_payload = &payload[..separator];
-    let key = decode_mergeable_segment(&payload[separator + '>'.len_utf8()..])?;
+    let (parent_type, key) = split_mergeable_parent_tag(&payload[separator + '>'.len_utf8()..]);
+    let key = decode_mergeable_segment(key)?;
 
     let parent = if find_last_mergeable_separator(parent_payload).is_some() {
         ContainerID::Root {
             name: format!("{MERGEABLE_NAMESPACE_PREFIX}{parent_payload}").into(),
-            container_type: ContainerType::Map,
+            container_type: parent_type,
         }
     } else {
         let parent_segment = decode_mergeable_segment(parent_payload)?;
-        parse_mergeable_base_parent(&parent_segment)?
+        parse_mergeable_base_parent(&parent_segment, parent_type)?
     };
 
     Some((parent, key))
@@ -341,11 +392,14 @@ fn parse_mergeable_payload(payload: &str) -> Option<(ContainerID, String)> {
 fn mergeable_payload_for_parent(parent: &ContainerID) -> Option<&str> {
     let ContainerID::Root {
         name,
-        container_type: ContainerType::Map,
+        container_type,
     } = parent
     else {
         return None;
     };
+    if !is_mergeable_parent_type(*container_type) {
+        return None;
+    }
     let payload = name.as_str().strip_prefix(MERGEABLE_NAMESPACE_PREFIX)?;
     validate_mergeable_payload(payload)?;
     Some(payload)
@@ -457,6 +511,77 @@ pub fn translate_mergeable_marker_value(
     }
 }
 
+/// Binary marker stored as a list element to activate a mergeable child.
+///
+/// A list element has no key, so unlike [`MERGEABLE_MARKER_MAGIC`] markers, the user-provided
+/// stable key of the child is stored in the marker itself.
+pub const MERGEABLE_LIST_MARKER_MAGIC: [u8; 4] = [0x00, b'L', b'M', 0x02];
+
+/// Build the [`LoroValue`] a list or movable list stores to hold the mergeable child keyed by
+/// `key`.
+///
+/// Layout: `MAGIC[4] + KIND[1] + KEY[..] + CRC24(parent_id, key, kind)[3]`.
+pub fn mergeable_list_marker(
+    parent: &ContainerID,
+    key: &str,
+    container_type: ContainerType,
+) -> LoroValue {
+    let mut marker = Vec::with_capacity(MERGEABLE_MARKER_LEN + key.len());
+    marker.extend_from_slice(&MERGEABLE_LIST_MARKER_MAGIC);
+    marker.push(container_type.to_u8());
+    marker.extend_from_slice(key.as_bytes());
+    let digest = mergeable_marker_crc24(parent, key, container_type);
+    marker.extend_from_slice(&digest);
+    LoroValue::Binary(marker.into())
+}
+
+/// Parse a list element back into the key and [`ContainerType`] of the mergeable child it holds.
+///
+/// Like [`parse_mergeable_marker`], the digest binds the marker to its parent list, so
+/// malformed markers or markers copied from another list are treated as ordinary values.
+pub fn parse_mergeable_list_marker(
+    parent: &ContainerID,
+    value: &LoroValue,
+) -> Option<(String, ContainerType)> {
+    if !matches!(
+        parent.container_type(),
+        ContainerType::List | ContainerType::MovableList
+    ) {
+        return None;
+    }
+    let LoroValue::Binary(bytes) = value else {
+        return None;
+    };
+    if bytes.len() < MERGEABLE_MARKER_LEN || !bytes.starts_with(&MERGEABLE_LIST_MARKER_MAGIC) {
+        return None;
+    }
+
+    let kind = ContainerType::try_from_u8(bytes[MERGEABLE_LIST_MARKER_MAGIC.len()]).ok()?;
+    if matches!(kind, ContainerType::Unknown(_)) {
+        return None;
+    }
+
+    let key_start = MERGEABLE_LIST_MARKER_MAGIC.len() + 1;
+    let digest_start = bytes.len() - MERGEABLE_MARKER_DIGEST_LEN;
+    let key = std::str::from_utf8(&bytes[key_start..digest_start]).ok()?;
+    let expected = mergeable_marker_crc24(parent, key, kind);
+    if &bytes[digest_start..] != expected.as_slice() {
+        return None;
+    }
+
+    Some((key.to_string(), kind))
+}
+
+/// Translate a raw list element into the user-visible Container view for a list at `parent`.
+///
+/// This is the list counterpart of [`translate_mergeable_marker_value`].
+pub fn translate_mergeable_list_marker_value(parent: &ContainerID, value: LoroValue) -> LoroValue {
+    match parse_mergeable_list_marker(parent, &value) {
+        Some((key, kind)) => LoroValue::Container(ContainerID::new_mergeable(parent, &key, kind)),
+        None => value,
+    }
+}
+
 fn mergeable_marker_crc24(parent: &ContainerID, key: &str, kind: ContainerType) -> [u8; 3] {
     let mut input = Vec::new();
     input.extend_from_slice(MERGEABLE_MARKER_CRC_DOMAIN);
@@ -742,26 +867,31 @@ pub enum ContainerType {
     Tree,
     #[cfg(feature = "counter")]
     Counter,
+    #[cfg(feature = "set")]
+    Set,
+    #[cfg(feature = "typed-counter")]
+    TypedCounter,
     Unknown(u8),
 }
 
+const ALL_TYPES_LEN: usize = 5
+    + cfg!(feature = "counter") as usize
+    + cfg!(feature = "set") as usize
+    + cfg!(feature = "typed-counter") as usize;
+
 impl ContainerType {
-    #[cfg(feature = "counter")]
-    pub const ALL_TYPES: [ContainerType; 6] = [
+    pub const ALL_TYPES: [ContainerType; ALL_TYPES_LEN] = [
         ContainerType::Map,
         ContainerType::List,
         ContainerType::Text,
         ContainerType::Tree,
         ContainerType::MovableList,
+        #[cfg(feature = "counter")]
         ContainerType::Counter,
-    ];
-    #[cfg(not(feature = "counter"))]
-    pub const ALL_TYPES: [ContainerType; 5] = [
-        ContainerType::Map,
-        ContainerType::List,
-        ContainerType::Text,
-        ContainerType::Tree,
-        ContainerType::MovableList,
+        #[cfg(feature = "set")]
+        ContainerType::Set,
+        #[cfg(feature = "typed-counter")]
+        ContainerType::TypedCounter,
     ];
 
     pub fn default_value(&self) -> LoroValue {
@@ -773,10 +903,25 @@ impl ContainerType {
             ContainerType::MovableList => LoroValue::List(Default::default()),
             #[cfg(feature = "counter")]
             ContainerType::Counter => LoroValue::Double(0.),
+            #[cfg(feature = "set")]
+            ContainerType::Set => LoroValue::List(Default::default()),
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter => LoroValue::Double(0.),
             ContainerType::Unknown(_) => unreachable!(),
         }
     }
 
+    /// Whether it's a counter, including a typed counter.
+    #[cfg(feature = "counter")]
+    pub fn is_counter(&self) -> bool {
+        match self {
+            ContainerType::Counter => true,
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter => true,
+            _ => false,
+        }
+    }
+
     pub fn to_u8(self) -> u8 {
         match self {
             ContainerType::Map => 0,
@@ -786,6 +931,10 @@ impl ContainerType {
             ContainerType::MovableList => 4,
             #[cfg(feature = "counter")]
             ContainerType::Counter => 5,
+            #[cfg(feature = "set")]
+            ContainerType::Set => 6,
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter => 7,
             ContainerType::Unknown(k) => k,
         }
     }
@@ -799,6 +948,10 @@ impl ContainerType {
             4 => Ok(ContainerType::MovableList),
             #[cfg(feature = "counter")]
             5 => Ok(ContainerType::Counter),
+            #[cfg(feature = "set")]
+            6 => Ok(ContainerType::Set),
+            #[cfg(feature = "typed-counter")]
+            7 => Ok(ContainerType::TypedCounter),
             x => Ok(ContainerType::Unknown(x)),
         }
     }
@@ -814,6 +967,10 @@ enum ContainerTypeSerdeRepr {
     Tree,
     #[cfg(feature = "counter")]
     Counter,
+    #[cfg(feature = "set")]
+    Set,
+    #[cfg(feature = "typed-counter")]
+    TypedCounter,
     Unknown(u8),
 }
 
@@ -828,6 +985,10 @@ fn historical_container_type_to_byte(c: ContainerType) -> u8 {
         ContainerType::Tree => 4,
         #[cfg(feature = "counter")]
         ContainerType::Counter => 5,
+        #[cfg(feature = "set")]
+        ContainerType::Set => 6,
+        #[cfg(feature = "typed-counter")]
+        ContainerType::TypedCounter => 7,
         ContainerType::Unknown(k) => k,
     }
 }
@@ -841,6 +1002,10 @@ fn historical_try_byte_to_container(byte: u8) -> ContainerType {
         4 => ContainerType::Tree,
         #[cfg(feature = "counter")]
         5 => ContainerType::Counter,
+        #[cfg(feature = "set")]
+        6 => ContainerType::Set,
+        #[cfg(feature = "typed-counter")]
+        7 => ContainerType::TypedCounter,
         _ => ContainerType::Unknown(byte),
     }
 }
@@ -855,6 +1020,10 @@ impl From<ContainerType> for ContainerTypeSerdeRepr {
             ContainerType::Tree => Self::Tree,
             #[cfg(feature = "counter")]
             ContainerType::Counter => Self::Counter,
+            #[cfg(feature = "set")]
+            ContainerType::Set => Self::Set,
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter => Self::TypedCounter,
             ContainerType::Unknown(value) => Self::Unknown(value),
         }
     }
@@ -870,6 +1039,10 @@ impl From<ContainerTypeSerdeRepr> for ContainerType {
             ContainerTypeSerdeRepr::Tree => ContainerType::Tree,
             #[cfg(feature = "counter")]
             ContainerTypeSerdeRepr::Counter => ContainerType::Counter,
+            #[cfg(feature = "set")]
+            ContainerTypeSerdeRepr::Set => ContainerType::Set,
+            #[cfg(feature = "typed-counter")]
+            ContainerTypeSerdeRepr::TypedCounter => ContainerType::TypedCounter,
             ContainerTypeSerdeRepr::Unknown(value) => ContainerType::Unknown(value),
         }
     }
@@ -918,6 +1091,10 @@ mod container {
                 ContainerType::Tree => "Tree",
                 #[cfg(feature = "counter")]
                 ContainerType::Counter => "Counter",
+                #[cfg(feature = "set")]
+                ContainerType::Set => "Set",
+                #[cfg(feature = "typed-counter")]
+                ContainerType::TypedCounter => "TypedCounter",
                 ContainerType::Unknown(k) => return f.write_fmt(format_args!("Unknown({k})")),
             })
         }
@@ -1029,6 +1206,26 @@ mod container {
             matches!(self.container_type(), ContainerType::Unknown(_))
         }
 
+        /// Create the ID of the container that stores the document metadata with the given
+        /// name, such as branches.
+        ///
+        /// It's a mergeable child of the root map [`METADATA_PARENT_NAME`]. Nothing writes
+        /// its marker into that map, so the child is never active. Like any inactive mergeable
+        /// child, it replicates with the document but is left out of `get_value` /
+        /// `get_deep_value`, also by peers that don't know about metadata, and no root name is
+        /// reserved for it. It's also left out of undo and of the diffs between versions.
+        pub fn new_metadata(name: &str, container_type: ContainerType) -> Self {
+            let parent = ContainerID::new_root(METADATA_PARENT_NAME, ContainerType::Map);
+            ContainerID::new_mergeable(&parent, name, container_type)
+        }
+
+        /// Returns `true` if this is the ID of a metadata container, see [`Self::new_metadata`].
+        pub fn is_metadata(&self) -> bool {
+            self.parse_mergeable().is_some_and(|(parent, _, _)| {
+                matches!(parent, ContainerID::Root { name, .. } if name.as_str() == METADATA_PARENT_NAME)
+            })
+        }
+
         /// Create a mergeable container ID for the given parent, key, and container type.
         ///
         /// The cid is a Root container with a reserved namespace prefix and a flattened
@@ -1040,19 +1237,19 @@ mod container {
         /// bytes per cid, and `ContainerID` equality already keeps two `(parent, key)`
         /// mergeable cids of different kinds distinct.
         ///
-        /// Only maps can be mergeable parents. The encoded payload records the nearest
-        /// non-mergeable map ancestor (`$root-name` or `@peer:counter`) once, then appends
-        /// escaped map keys separated by `>`. This keeps nested mergeable map names linear
-        /// in path length instead of recursively embedding the full parent cid.
+        /// Maps, lists and movable lists can be mergeable parents. The encoded payload records
+        /// the nearest non-mergeable ancestor (`$root-name` or `@peer:counter`) once, then
+        /// appends escaped keys separated by `>`. This keeps nested mergeable names linear
+        /// in path length instead of recursively embedding the full parent cid. A key whose
+        /// parent is a list or movable list is tagged with `\l` or `\v`.
         pub fn new_mergeable(
             parent: &ContainerID,
             key: &str,
             container_type: ContainerType,
         ) -> Self {
-            assert_eq!(
-                parent.container_type(),
-                ContainerType::Map,
-                "mergeable child parent must be a map"
+            assert!(
+                is_mergeable_parent_type(parent.container_type()),
+                "mergeable child parent must be a map, list or movable list"
             );
             let parent_len_hint = match parent {
                 ContainerID::Root { name, .. } => name.len(),
@@ -1063,6 +1260,7 @@ mod container {
             name.push_str(MERGEABLE_NAMESPACE_PREFIX);
             push_mergeable_parent(&mut name, parent);
             name.push('>');
+            name.push_str(mergeable_parent_tag(parent.container_type()));
             push_mergeable_escaped(&mut name, key);
 
             Self::Root {
@@ -1123,6 +1321,10 @@ mod container {
                 "MovableList" | "movableList" => Ok(ContainerType::MovableList),
                 #[cfg(feature = "counter")]
                 "Counter" | "counter" => Ok(ContainerType::Counter),
+                #[cfg(feature = "set")]
+                "Set" | "set" => Ok(ContainerType::Set),
+                #[cfg(feature = "typed-counter")]
+                "TypedCounter" | "typedCounter" => Ok(ContainerType::TypedCounter),
                 a => {
                     if a.ends_with(')') {
                         let start = a.find('(').ok_or_else(|| {
@@ -1249,8 +1451,9 @@ pub mod wasm {
 #[cfg(test)]
 mod test {
     use crate::{
-        mergeable_marker, parse_mergeable_marker, ContainerID, ContainerType, LoroValue, ID,
-        MERGEABLE_MARKER_MAGIC,
+        mergeable_list_marker, mergeable_marker, parse_mergeable_list_marker,
+        parse_mergeable_marker, translate_mergeable_list_marker_value, ContainerID, ContainerType,
+        LoroValue, ID, MERGEABLE_MARKER_MAGIC,
     };
 
     #[test]
@@ -1265,6 +1468,10 @@ mod test {
             ContainerType::MovableList,
             #[cfg(feature = "counter")]
             ContainerType::Counter,
+            #[cfg(feature = "set")]
+            ContainerType::Set,
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter,
         ];
         for kind in kinds {
             assert_eq!(
@@ -1429,6 +1636,55 @@ mod test {
         );
     }
 
+    #[test]
+    fn mergeable_list_marker_round_trips_key_and_kind() {
+        let parent = ContainerID::new_root("sections", ContainerType::List);
+        for key in ["", "intro", "with\0nul>and/slash", "😀"] {
+            let marker = mergeable_list_marker(&parent, key, ContainerType::Text);
+            assert_eq!(
+                parse_mergeable_list_marker(&parent, &marker),
+                Some((key.to_string(), ContainerType::Text))
+            );
+            assert_eq!(
+                translate_mergeable_list_marker_value(&parent, marker),
+                LoroValue::Container(ContainerID::new_mergeable(
+                    &parent,
+                    key,
+                    ContainerType::Text
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn parse_mergeable_list_marker_rejects_non_markers() {
+        let parent = ContainerID::new_root("sections", ContainerType::List);
+        let marker = mergeable_list_marker(&parent, "intro", ContainerType::Map);
+
+        let other = ContainerID::new_root("other", ContainerType::List);
+        assert_eq!(parse_mergeable_list_marker(&other, &marker), None);
+        let movable = ContainerID::new_root("sections", ContainerType::MovableList);
+        assert_eq!(parse_mergeable_list_marker(&movable, &marker), None);
+        let map = ContainerID::new_root("sections", ContainerType::Map);
+        assert_eq!(parse_mergeable_list_marker(&map, &marker), None);
+
+        // Map markers are not list markers, and the other way around.
+        let map_marker = mergeable_marker(&map, "intro", ContainerType::Map);
+        assert_eq!(parse_mergeable_list_marker(&parent, &map_marker), None);
+        assert_eq!(parse_mergeable_marker(&parent, "intro", &marker), None);
+
+        let mut wrong_digest = marker_bytes(marker);
+        *wrong_digest.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            parse_mergeable_list_marker(&parent, &LoroValue::Binary(wrong_digest.into())),
+            None
+        );
+        assert_eq!(
+            parse_mergeable_list_marker(&parent, &LoroValue::String("intro".into())),
+            None
+        );
+    }
+
     fn marker_bytes(value: LoroValue) -> Vec<u8> {
         let LoroValue::Binary(bytes) = value else {
             panic!("expected binary mergeable marker");
@@ -1501,6 +1757,20 @@ mod test {
             assert_eq!(ContainerID::from_bytes(&bytes), id);
         }
 
+        #[cfg(feature = "set")]
+        {
+            let id = ContainerID::new_normal(ID::new(42, 100), ContainerType::Set);
+            let bytes = id.to_bytes();
+            assert_eq!(ContainerID::from_bytes(&bytes), id);
+        }
+
+        #[cfg(feature = "typed-counter")]
+        {
+            let id = ContainerID::new_normal(ID::new(42, 100), ContainerType::TypedCounter);
+            let bytes = id.to_bytes();
+            assert_eq!(ContainerID::from_bytes(&bytes), id);
+        }
+
         let id = ContainerID::new_normal(ID::new(1, 1), ContainerType::Unknown(100));
         let bytes = id.to_bytes();
         assert_eq!(ContainerID::from_bytes(&bytes), id);
@@ -0,0 +1,394 @@
+//! Derive macro for the typed bindings in `loro::typed`.
+//!
+//! Don't depend on this crate directly: enable the `derive` feature of `loro` and use
+//! `loro::Loro`, which also documents the generated code and the supported attributes.
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, Ident, LitStr,
+    Member, Type, Visibility,
+};
+
+/// Derive `loro::typed::LoroMapped` and a typed handle for a struct or an enum.
+#[proc_macro_derive(Loro, attributes(loro))]
+pub fn derive_loro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() || input.generics.where_clause.is_some() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "#[derive(Loro)] does not support generic types",
+        ));
+    }
+
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let ident = &input.ident;
+    let handle = format_ident!("{}Handle", ident);
+    let (load, save, accessors) = match &input.data {
+        Data::Struct(data) => {
+            if container.tag.is_some() {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "#[loro(tag = \"...\")] only applies to enums",
+                ));
+            }
+            let Fields::Named(fields) = &data.fields else {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "#[derive(Loro)] only supports structs with named fields",
+                ));
+            };
+            let fields = fields
+                .named
+                .iter()
+                .enumerate()
+                .map(|(i, f)| FieldInfo::parse(f, i))
+                .collect::<syn::Result<Vec<_>>>()?;
+            check_duplicate_keys(&fields, None)?;
+            expand_struct(&fields)
+        }
+        Data::Enum(data) => {
+            let tag = container.tag.unwrap_or_else(|| "type".to_string());
+            expand_enum(ident, &tag, data)?
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                ident.span(),
+                "#[derive(Loro)] does not support unions",
+            ))
+        }
+    };
+
+    let vis = &input.vis;
+    let handle_doc = format!("Typed handle of a `LoroMap` holding a [`{ident}`].");
+    Ok(quote! {
+        impl ::loro::typed::LoroMapped for #ident {
+            type Handle = #handle;
+
+            fn load(map: &::loro::LoroMap) -> ::loro::LoroResult<Self> {
+                #load
+            }
+
+            fn save(&self, map: &::loro::LoroMap) -> ::loro::LoroResult<()> {
+                #save
+            }
+        }
+
+        #[doc = #handle_doc]
+        #[derive(Debug, Clone)]
+        #vis struct #handle {
+            map: ::loro::LoroMap,
+        }
+
+        impl ::core::convert::From<::loro::LoroMap> for #handle {
+            fn from(map: ::loro::LoroMap) -> Self {
+                Self { map }
+            }
+        }
+
+        impl ::loro::typed::MappedHandle for #handle {
+            type Value = #ident;
+
+            fn map(&self) -> &::loro::LoroMap {
+                &self.map
+            }
+        }
+
+        impl #handle {
+            #accessors
+        }
+    })
+}
+
+fn expand_struct(fields: &[FieldInfo]) -> (TokenStream, TokenStream, TokenStream) {
+    let members = fields.iter().map(|f| &f.member);
+    let loads = fields.iter().map(FieldInfo::load_expr);
+    let load = quote! {
+        ::core::result::Result::Ok(Self {
+            #(#members: #loads,)*
+        })
+    };
+
+    let saves = fields.iter().map(|f| {
+        let member = &f.member;
+        f.save_stmt(quote!(&self.#member))
+    });
+    let save = quote! {
+        #(#saves)*
+        ::core::result::Result::Ok(())
+    };
+
+    let accessors = fields.iter().map(FieldInfo::accessor);
+    (load, save, quote!(#(#accessors)*))
+}
+
+fn expand_enum(
+    ident: &Ident,
+    tag: &str,
+    data: &syn::DataEnum,
+) -> syn::Result<(TokenStream, TokenStream, TokenStream)> {
+    let mut load_arms = Vec::new();
+    let mut save_arms = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    for variant in data.variants.iter() {
+        let attrs = VariantAttrs::parse(&variant.attrs)?;
+        let v = &variant.ident;
+        let name = attrs.rename.unwrap_or_else(|| v.unraw().to_string());
+        if names.contains(&name) {
+            return Err(syn::Error::new(
+                variant.span(),
+                format!("duplicate variant name {name:?}"),
+            ));
+        }
+        names.push(name.clone());
+
+        let fields = variant
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| FieldInfo::parse(f, i))
+            .collect::<syn::Result<Vec<_>>>()?;
+        check_duplicate_keys(&fields, Some(tag))?;
+
+        let members: Vec<_> = fields.iter().map(|f| &f.member).collect();
+        let loads = fields.iter().map(FieldInfo::load_expr);
+        let binds: Vec<_> = (0..fields.len())
+            .map(|i| format_ident!("__field{}", i))
+            .collect();
+        let saves = fields
+            .iter()
+            .zip(binds.iter())
+            .map(|(f, bind)| f.save_stmt(quote!(#bind)));
+        let pattern = fields.iter().zip(binds.iter()).map(|(f, bind)| {
+            let member = &f.member;
+            if f.attrs.skip {
+                quote!(#member: _)
+            } else {
+                quote!(#member: #bind)
+            }
+        });
+
+        load_arms.push(quote! {
+            #name => ::core::result::Result::Ok(Self::#v { #(#members: #loads,)* }),
+        });
+        save_arms.push(quote! {
+            Self::#v { #(#pattern,)* } => {
+                ::loro::typed::save_tag(map, #tag, #name)?;
+                #(#saves)*
+                ::core::result::Result::Ok(())
+            }
+        });
+    }
+
+    let ty = ident.to_string();
+    let load = quote! {
+        let tag = ::loro::typed::load_tag(map, #tag)?;
+        match tag.as_str() {
+            #(#load_arms)*
+            other => ::core::result::Result::Err(::loro::typed::unknown_variant(#ty, other)),
+        }
+    };
+    let save = if save_arms.is_empty() {
+        quote!(match *self {})
+    } else {
+        quote! {
+            match self {
+                #(#save_arms)*
+            }
+        }
+    };
+    Ok((load, save, TokenStream::new()))
+}
+
+fn check_duplicate_keys(fields: &[FieldInfo], tag: Option<&str>) -> syn::Result<()> {
+    let mut keys: Vec<&str> = tag.into_iter().collect();
+    for field in fields.iter().filter(|f| !f.attrs.skip) {
+        if keys.contains(&field.key.as_str()) {
+            return Err(syn::Error::new(
+                field.span,
+                format!("duplicate key {:?}", field.key),
+            ));
+        }
+        keys.push(&field.key);
+    }
+    Ok(())
+}
+
+struct FieldInfo {
+    member: Member,
+    key: String,
+    ty: Type,
+    vis: Visibility,
+    attrs: FieldAttrs,
+    span: Span,
+}
+
+impl FieldInfo {
+    fn parse(field: &syn::Field, index: usize) -> syn::Result<Self> {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        let (member, default_key) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), ident.unraw().to_string()),
+            None => (Member::Unnamed(index.into()), index.to_string()),
+        };
+        Ok(Self {
+            member,
+            key: attrs.rename.clone().unwrap_or(default_key),
+            ty: field.ty.clone(),
+            vis: field.vis.clone(),
+            attrs,
+            span: field.span(),
+        })
+    }
+
+    fn load_expr(&self) -> TokenStream {
+        let key = &self.key;
+        if self.attrs.skip {
+            quote!(::core::default::Default::default())
+        } else if self.attrs.text && self.attrs.default {
+            quote! {
+                match map.get(#key) {
+                    ::core::option::Option::None => ::core::default::Default::default(),
+                    ::core::option::Option::Some(_) => ::loro::typed::load_text(map, #key)?,
+                }
+            }
+        } else if self.attrs.text {
+            quote!(::loro::typed::load_text(map, #key)?)
+        } else if self.attrs.default {
+            quote!(::loro::typed::load_field_or_default(map, #key)?)
+        } else {
+            quote!(::loro::typed::load_field(map, #key)?)
+        }
+    }
+
+    /// `value` is an expression of type `&T`.
+    fn save_stmt(&self, value: TokenStream) -> TokenStream {
+        let key = &self.key;
+        if self.attrs.skip {
+            TokenStream::new()
+        } else if self.attrs.text {
+            quote!(::loro::typed::save_text(map, #key, #value)?;)
+        } else {
+            quote!(::loro::typed::LoroType::save_in_map(#value, map, #key)?;)
+        }
+    }
+
+    fn accessor(&self) -> TokenStream {
+        let Member::Named(ident) = &self.member else {
+            return TokenStream::new();
+        };
+        if self.attrs.skip {
+            return TokenStream::new();
+        }
+
+        let key = &self.key;
+        let vis = &self.vis;
+        let ty = &self.ty;
+        if self.attrs.text {
+            let doc = format!("Get the text of `{key}`, creating it if needed.");
+            quote! {
+                #[doc = #doc]
+                #vis fn #ident(&self) -> ::loro::LoroResult<::loro::LoroText> {
+                    ::loro::typed::text_handle(&self.map, #key)
+                }
+            }
+        } else {
+            let doc = format!("Get the typed handle of `{key}`.");
+            quote! {
+                #[doc = #doc]
+                #vis fn #ident(
+                    &self,
+                ) -> ::loro::LoroResult<<#ty as ::loro::typed::LoroType>::Handle> {
+                    <#ty as ::loro::typed::LoroType>::handle(&self.map, #key)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct ContainerAttrs {
+    tag: Option<String>,
+}
+
+impl ContainerAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut ans = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("loro")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("tag") {
+                    ans.tag = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown loro attribute, expected `tag`"))
+                }
+            })?;
+        }
+        Ok(ans)
+    }
+}
+
+#[derive(Default)]
+struct VariantAttrs {
+    rename: Option<String>,
+}
+
+impl VariantAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut ans = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("loro")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    ans.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown loro attribute, expected `rename`"))
+                }
+            })?;
+        }
+        Ok(ans)
+    }
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    text: bool,
+    default: bool,
+    skip: bool,
+}
+
+impl FieldAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut ans = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("loro")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    ans.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("text") {
+                    ans.text = true;
+                } else if meta.path.is_ident("default") {
+                    ans.default = true;
+                } else if meta.path.is_ident("skip") {
+                    ans.skip = true;
+                } else {
+                    return Err(meta.error(
+                        "unknown loro attribute, expected `rename`, `text`, `default` or `skip`",
+                    ));
+                }
+                Ok(())
+            })?;
+            if ans.skip && (ans.text || ans.rename.is_some()) {
+                return Err(syn::Error::new(
+                    attr.span(),
+                    "#[loro(skip)] cannot be combined with `text` or `rename`",
+                ));
+            }
+        }
+        Ok(ans)
+    }
+}
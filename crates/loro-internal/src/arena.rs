@@ -625,6 +625,36 @@ impl SharedArena {
                 container,
                 content: crate::op::InnerContent::Future(crate::op::FutureInnerContent::Counter(c)),
             },
+            #[cfg(feature = "counter")]
+            crate::op::RawOpContent::IntCounter(c) => Op {
+                counter,
+                container,
+                content: crate::op::InnerContent::Future(
+                    crate::op::FutureInnerContent::IntCounter(c),
+                ),
+            },
+            #[cfg(feature = "counter")]
+            crate::op::RawOpContent::BoundedCounter { delta, lower_bound } => Op {
+                counter,
+                container,
+                content: crate::op::InnerContent::Future(
+                    crate::op::FutureInnerContent::BoundedCounter { delta, lower_bound },
+                ),
+            },
+            #[cfg(feature = "counter")]
+            crate::op::RawOpContent::MaxCounter(c) => Op {
+                counter,
+                container,
+                content: crate::op::InnerContent::Future(
+                    crate::op::FutureInnerContent::MaxCounter(c),
+                ),
+            },
+            #[cfg(feature = "set")]
+            crate::op::RawOpContent::Set(s) => Op {
+                counter,
+                container,
+                content: crate::op::InnerContent::Future(crate::op::FutureInnerContent::Set(s)),
+            },
             crate::op::RawOpContent::Unknown { prop, value } => Op {
                 counter,
                 container,
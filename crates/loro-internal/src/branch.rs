@@ -0,0 +1,261 @@
+//! Named branches inside the history of a document.
+//!
+//! A branch is a named head ([`Frontiers`]) stored in the `branches` metadata map, so it syncs
+//! with the document. Every peer records its own view of the head of a branch under
+//! `"{branch}/{peer}"`, and the head of the branch is the union of them. Because a head only
+//! moves forward, this is the same head no matter in which order the records arrive, and
+//! concurrent edits of the same branch on different peers are merged into it.
+use loro_common::{InternalString, LoroError, LoroResult, LoroValue, PeerID};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    dag::{Dag, DagUtils},
+    txn::Transaction,
+    undo::DiffBatch,
+    version::{shrink_frontiers, Frontiers},
+    LoroDoc,
+};
+
+const BRANCHES: &str = "branches";
+
+/// The branch the document has been checked out to with [`LoroDoc::checkout_branch`].
+#[derive(Debug)]
+pub(crate) struct CurrentBranch {
+    name: InternalString,
+    /// The head the next commit has to be based on to extend the branch.
+    head: Frontiers,
+}
+
+/// The result of [`LoroDoc::merge_branch`].
+#[derive(Debug, Clone)]
+pub struct BranchMerge {
+    /// The common ancestor of the two branches before the merge.
+    pub base: Frontiers,
+    /// The new head of the target branch.
+    pub head: Frontiers,
+    /// What changed on the source branch since `base`.
+    pub source_diff: DiffBatch,
+    /// What changed on the target branch since `base`.
+    pub target_diff: DiffBatch,
+}
+
+fn branch_key(name: &str, peer: PeerID) -> String {
+    format!("{name}/{peer}")
+}
+
+impl LoroDoc {
+    /// Create a branch with the given head.
+    ///
+    /// Returns [`LoroError::ArgErr`] if the branch already exists.
+    pub fn create_branch(&self, name: &str, head: &Frontiers) -> LoroResult<()> {
+        if name.is_empty() {
+            return Err(LoroError::ArgErr("Branch name cannot be empty".into()));
+        }
+        if self.branch_head(name).is_some() {
+            return Err(LoroError::ArgErr(
+                format!("Branch {name} already exists").into_boxed_str(),
+            ));
+        }
+        self.set_branch_head(name, head)
+    }
+
+    /// Delete a branch.
+    ///
+    /// Returns [`LoroError::NotFoundError`] if the branch doesn't exist.
+    pub fn delete_branch(&self, name: &str) -> LoroResult<()> {
+        let keys = self
+            .metadata_entries(BRANCHES)
+            .into_iter()
+            .filter(|(key, _)| key.rsplit_once('/').is_some_and(|(n, _)| n == name))
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            return Err(LoroError::NotFoundError(
+                format!("Branch {name} doesn't exist").into_boxed_str(),
+            ));
+        }
+
+        self.leave_branch(name);
+        let map = self.metadata_map(BRANCHES);
+        for key in keys {
+            map.delete(&key)?;
+        }
+        self.commit_then_renew();
+        Ok(())
+    }
+
+    /// All the branches with their heads, sorted by name.
+    pub fn branches(&self) -> Vec<(String, Frontiers)> {
+        let mut heads: FxHashMap<String, Frontiers> = FxHashMap::default();
+        for (key, value) in self.metadata_entries(BRANCHES) {
+            let Some((name, _peer)) = key.rsplit_once('/') else {
+                continue;
+            };
+            let LoroValue::Binary(bytes) = value else {
+                continue;
+            };
+            let Ok(head) = Frontiers::decode(&bytes) else {
+                continue;
+            };
+            let ids = heads.entry(name.to_string()).or_default();
+            for id in head.iter() {
+                ids.push(id);
+            }
+        }
+
+        let oplog = self.oplog().lock();
+        let mut ans: Vec<(String, Frontiers)> = heads
+            .into_iter()
+            .filter_map(|(name, ids)| {
+                // Heads before the start of a shallow doc can't be checked out anymore
+                if oplog.dag.is_before_shallow_root(&ids) {
+                    return None;
+                }
+                let head = shrink_frontiers(&ids, &oplog.dag).ok()?;
+                Some((name, head))
+            })
+            .collect();
+        ans.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        ans
+    }
+
+    /// The head of the branch, or `None` if the branch doesn't exist.
+    pub fn branch_head(&self, name: &str) -> Option<Frontiers> {
+        self.branches()
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, head)| head)
+    }
+
+    /// Move the head of a branch, creating the branch if it doesn't exist.
+    ///
+    /// The head can only move forward: the new head is merged with the head recorded by the
+    /// other peers. If the doc is checked out to the branch, it leaves the branch.
+    pub fn set_branch_head(&self, name: &str, head: &Frontiers) -> LoroResult<()> {
+        {
+            let oplog = self.oplog().lock();
+            for id in head.iter() {
+                if !oplog.dag.contains(id) {
+                    return Err(LoroError::FrontiersNotFound(id));
+                }
+            }
+        }
+        self.leave_branch(name);
+        self.metadata_map(BRANCHES).insert(
+            &branch_key(name, self.peer_id()),
+            LoroValue::Binary(head.encode().into()),
+        )?;
+        self.commit_then_renew();
+        Ok(())
+    }
+
+    /// The name of the branch the doc is checked out to.
+    ///
+    /// The doc leaves the branch when it's checked out to another version.
+    pub fn current_branch(&self) -> Option<String> {
+        let mut current = self.current_branch.lock();
+        if current
+            .as_ref()
+            .is_some_and(|b| b.head != self.state_frontiers())
+        {
+            *current = None;
+        }
+        current.as_ref().map(|b| b.name.to_string())
+    }
+
+    /// Checkout the head of a branch, and edit the branch from now on.
+    ///
+    /// This enables detached editing. Every following commit extends the branch and moves
+    /// its head, until the doc is checked out to another version.
+    pub fn checkout_branch(&self, name: &str) -> LoroResult<()> {
+        let head = self.branch_head(name).ok_or_else(|| {
+            LoroError::NotFoundError(format!("Branch {name} doesn't exist").into_boxed_str())
+        })?;
+        if !self.is_detached_editing_enabled() {
+            self.set_detached_editing(true);
+        }
+        self.checkout(&head)?;
+        *self.current_branch.lock() = Some(CurrentBranch {
+            name: name.into(),
+            head: self.state_frontiers(),
+        });
+        Ok(())
+    }
+
+    /// Merge the `source` branch into the `target` branch.
+    ///
+    /// The head of `target` moves to include the head of `source`. The returned [`BranchMerge`]
+    /// has the diffs of both sides since their common ancestor. If the doc is checked out to
+    /// `target`, it's checked out to the merged head.
+    pub fn merge_branch(&self, source: &str, target: &str) -> LoroResult<BranchMerge> {
+        let not_found = |name: &str| {
+            LoroError::NotFoundError(format!("Branch {name} doesn't exist").into_boxed_str())
+        };
+        let source_head = self.branch_head(source).ok_or_else(|| not_found(source))?;
+        let target_head = self.branch_head(target).ok_or_else(|| not_found(target))?;
+        let (base, head) = {
+            let oplog = self.oplog().lock();
+            let (base, _) = oplog.dag.find_common_ancestor(&source_head, &target_head);
+            let ids: Frontiers = source_head.iter().chain(target_head.iter()).collect();
+            let head = shrink_frontiers(&ids, &oplog.dag).map_err(LoroError::FrontiersNotFound)?;
+            (base, head)
+        };
+
+        let source_diff = self.diff(&base, &source_head)?;
+        let target_diff = self.diff(&base, &target_head)?;
+        let on_target = self.current_branch().as_deref() == Some(target);
+        self.set_branch_head(target, &head)?;
+        if on_target {
+            self.checkout(&head)?;
+            *self.current_branch.lock() = Some(CurrentBranch {
+                name: target.into(),
+                head: self.state_frontiers(),
+            });
+        }
+        Ok(BranchMerge {
+            base,
+            head,
+            source_diff,
+            target_diff,
+        })
+    }
+
+    fn leave_branch(&self, name: &str) {
+        let mut current = self.current_branch.lock();
+        if current.as_ref().is_some_and(|b| b.name.as_str() == name) {
+            *current = None;
+        }
+    }
+
+    /// Record the head of the current branch in the transaction that is about to be committed.
+    ///
+    /// The record is the last op of the transaction, so the new head is the ID of the record.
+    /// Returns the new head, which is moved to by [`Self::move_branch_head`] once the
+    /// transaction is committed, as a pre-commit callback may still reject it.
+    pub(crate) fn record_branch_head(&self, txn: &mut Transaction) -> Option<Frontiers> {
+        let mut current = self.current_branch.lock();
+        let branch = current.as_mut()?;
+        if txn.frontiers() != &branch.head {
+            // The doc has left the branch
+            *current = None;
+            return None;
+        }
+
+        let head = Frontiers::from_id(txn.next_id());
+        self.metadata_map(BRANCHES)
+            .insert_with_txn(
+                txn,
+                &branch_key(&branch.name, *txn.peer()),
+                LoroValue::Binary(head.encode().into()),
+            )
+            .ok()?;
+        Some(head)
+    }
+
+    /// Move the current branch to the head recorded by [`Self::record_branch_head`].
+    pub(crate) fn move_branch_head(&self, head: Frontiers) {
+        if let Some(branch) = self.current_branch.lock().as_mut() {
+            branch.head = head;
+        }
+    }
+}
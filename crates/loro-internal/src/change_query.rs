@@ -0,0 +1,393 @@
+//! Paginated queries over the changes of a document, e.g. for a version history view.
+//!
+//! Every peer's changes are already sorted by lamport, so a causal query merges the changes of
+//! every peer, newest first, and loads a change from the change store only when it's its peer's
+//! turn. A page therefore only parses the blocks of the changes it visits, and the start of the
+//! next page is found by binary searching each peer's changes.
+//!
+//! Timestamps don't have this guarantee: a commit is only kept after the timestamps of its deps,
+//! and imported changes aren't checked at all, so the timestamps of a peer can go backwards. A
+//! timestamp order query therefore reads [`ChangeTimestamps`], an index of the changes sorted by
+//! timestamp. It's built in memory by the first timestamp order query of the doc and only
+//! catches up with the new changes afterwards, so a page is found by a range lookup and a time
+//! range ends the scan.
+use std::{
+    collections::{BTreeMap, BinaryHeap},
+    ops::{Bound, Range},
+};
+
+use loro_common::{ContainerID, Counter, HasLamportSpan, IdSpan, Lamport, PeerID, ID};
+use rustc_hash::FxHashMap;
+
+use crate::{change::Timestamp, oplog::OpLog, version::VersionVector, ChangeMeta, LoroDoc};
+
+/// The order of the changes returned by [`LoroDoc::query_changes`]. Both orders are newest
+/// first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangeOrder {
+    /// By the lamport of the last op, then by peer. A change always comes before its deps.
+    #[default]
+    Causal,
+    /// By timestamp, then in causal order.
+    ///
+    /// The first query in this order loads every change once to index their timestamps.
+    Timestamp,
+}
+
+/// The position after the last change of a [`ChangePage`].
+///
+/// It's only meaningful for a query with the same [`ChangeOrder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChangeCursor {
+    timestamp: Timestamp,
+    lamport: Lamport,
+    peer: PeerID,
+}
+
+/// A query of the changes of a document, see [`LoroDoc::query_changes`].
+///
+/// All the filters must match for a change to be returned.
+#[derive(Debug, Clone)]
+pub struct ChangeQuery {
+    order: ChangeOrder,
+    peer: Option<PeerID>,
+    time_range: Option<Range<Timestamp>>,
+    message: Option<String>,
+    containers: Vec<ContainerID>,
+    after: Option<ChangeCursor>,
+    limit: usize,
+}
+
+impl Default for ChangeQuery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChangeQuery {
+    /// A query of all the changes in causal order.
+    pub fn new() -> Self {
+        Self {
+            order: ChangeOrder::Causal,
+            peer: None,
+            time_range: None,
+            message: None,
+            containers: Vec::new(),
+            after: None,
+            limit: usize::MAX,
+        }
+    }
+
+    /// Sets the order of the changes.
+    pub fn order(mut self, order: ChangeOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Only the changes of the given peer.
+    pub fn peer(mut self, peer: PeerID) -> Self {
+        self.peer = Some(peer);
+        self
+    }
+
+    /// Only the changes whose timestamp is in the range.
+    pub fn time_range(mut self, range: Range<Timestamp>) -> Self {
+        self.time_range = Some(range);
+        self
+    }
+
+    /// Only the changes whose commit message contains the given string.
+    pub fn message_contains(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    /// Only the changes that touch the given container. Calling it again adds another
+    /// container, and a change has to touch any of them.
+    pub fn container(mut self, container: ContainerID) -> Self {
+        self.containers.push(container);
+        self
+    }
+
+    /// Start after the given cursor, i.e. return the next page.
+    pub fn after(mut self, cursor: ChangeCursor) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+
+    /// Return at most `limit` changes.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn key(&self, change: &ChangeMeta) -> ChangeCursor {
+        ChangeCursor {
+            timestamp: match self.order {
+                ChangeOrder::Causal => 0,
+                ChangeOrder::Timestamp => change.timestamp,
+            },
+            lamport: change.lamport_last(),
+            peer: change.id.peer,
+        }
+    }
+
+    /// The last change of the peer.
+    fn last_of_peer(&self, oplog: &OpLog, peer: PeerID) -> Option<ChangeMeta> {
+        let end = oplog.vv().get(&peer).copied().unwrap_or(0);
+        if oplog
+            .dag
+            .shallow_since_vv()
+            .get(&peer)
+            .copied()
+            .unwrap_or(0)
+            >= end
+        {
+            return None;
+        }
+        oplog
+            .get_change_at(ID::new(peer, end - 1))
+            .map(|c| ChangeMeta::from_change(&c))
+    }
+
+    /// The last change of the peer that comes after the cursor in causal order.
+    fn start_of_peer(&self, oplog: &OpLog, peer: PeerID) -> Option<ChangeMeta> {
+        let end = oplog.vv().get(&peer).copied().unwrap_or(0);
+        let start = oplog
+            .dag
+            .shallow_since_vv()
+            .get(&peer)
+            .copied()
+            .unwrap_or(0);
+        if start >= end {
+            return None;
+        }
+
+        let get = |counter: Counter| {
+            oplog
+                .get_change_at(ID::new(peer, counter))
+                .map(|c| ChangeMeta::from_change(&c))
+        };
+        let Some(after) = self.after else {
+            return self.last_of_peer(oplog, peer);
+        };
+        // Find the first change that doesn't come after the cursor
+        let (mut lo, mut hi) = (start, end);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let change = get(mid)?;
+            if self.key(&change) < after {
+                lo = change.id.counter + change.len as Counter;
+            } else {
+                hi = change.id.counter;
+            }
+        }
+        if lo == start {
+            None
+        } else {
+            get(lo - 1)
+        }
+    }
+
+    fn prev_of_peer(&self, oplog: &OpLog, change: &ChangeMeta) -> Option<ChangeMeta> {
+        if change.id.counter == 0 {
+            return None;
+        }
+        let id = ID::new(change.id.peer, change.id.counter - 1);
+        if oplog.dag.shallow_since_vv().includes_id(id) {
+            return None;
+        }
+        oplog.get_change_at(id).map(|c| ChangeMeta::from_change(&c))
+    }
+
+    fn matches(&self, oplog: &OpLog, change: &ChangeMeta) -> bool {
+        if self
+            .time_range
+            .as_ref()
+            .is_some_and(|r| !r.contains(&change.timestamp))
+        {
+            return false;
+        }
+        if self
+            .message
+            .as_ref()
+            .is_some_and(|m| !change.message().contains(m.as_str()))
+        {
+            return false;
+        }
+        if !self.containers.is_empty() {
+            let span = IdSpan::new(
+                change.id.peer,
+                change.id.counter,
+                change.id.counter + change.len as Counter,
+            );
+            return oplog.iter_ops(span).any(|op| {
+                oplog
+                    .arena
+                    .get_container_id(op.container())
+                    .is_some_and(|id| self.containers.contains(&id))
+            });
+        }
+        true
+    }
+
+    /// `timestamps` is only read by a timestamp order query, and must be synced with `oplog`.
+    fn run(&self, oplog: &OpLog, timestamps: &ChangeTimestamps) -> ChangePage {
+        let mut page = ChangePage {
+            changes: Vec::new(),
+            next: None,
+        };
+        if self.limit == 0 {
+            return page;
+        }
+
+        let mut take = |key: ChangeCursor, change: ChangeMeta| {
+            if self.matches(oplog, &change) {
+                page.changes.push(change);
+                if page.changes.len() == self.limit {
+                    page.next = Some(key);
+                    return false;
+                }
+            }
+            true
+        };
+        match self.order {
+            ChangeOrder::Causal => {
+                let peers: Vec<PeerID> = match self.peer {
+                    Some(peer) => vec![peer],
+                    None => oplog.vv().keys().copied().collect(),
+                };
+                // The next change of every peer, and their keys
+                let mut heap: BinaryHeap<ChangeCursor> = BinaryHeap::new();
+                let mut pending: FxHashMap<PeerID, ChangeMeta> = FxHashMap::default();
+                for peer in peers {
+                    if let Some(change) = self.start_of_peer(oplog, peer) {
+                        heap.push(self.key(&change));
+                        pending.insert(peer, change);
+                    }
+                }
+                while let Some(key) = heap.pop() {
+                    let change = pending.remove(&key.peer).unwrap();
+                    let prev = self.prev_of_peer(oplog, &change);
+                    if !take(key, change) {
+                        break;
+                    }
+                    if let Some(prev) = prev {
+                        heap.push(self.key(&prev));
+                        pending.insert(key.peer, prev);
+                    }
+                }
+            }
+            ChangeOrder::Timestamp => {
+                let mut end = match self.after {
+                    Some(after) => Bound::Excluded(after),
+                    None => Bound::Unbounded,
+                };
+                if let Some(range) = &self.time_range {
+                    let range_end = ChangeCursor {
+                        timestamp: range.end,
+                        lamport: 0,
+                        peer: 0,
+                    };
+                    if self.after.is_none_or(|after| range_end < after) {
+                        end = Bound::Excluded(range_end);
+                    }
+                }
+                for (&key, &counter) in timestamps.sorted.range((Bound::Unbounded, end)).rev() {
+                    if self
+                        .time_range
+                        .as_ref()
+                        .is_some_and(|r| key.timestamp < r.start)
+                    {
+                        break;
+                    }
+                    if self.peer.is_some_and(|peer| peer != key.peer) {
+                        continue;
+                    }
+                    let Some(change) = oplog.get_change_at(ID::new(key.peer, counter)) else {
+                        continue;
+                    };
+                    if !take(key, ChangeMeta::from_change(&change)) {
+                        break;
+                    }
+                }
+            }
+        }
+        page
+    }
+}
+
+/// A page of the result of [`LoroDoc::query_changes`].
+#[derive(Debug, Clone)]
+pub struct ChangePage {
+    /// The changes, newest first.
+    pub changes: Vec<ChangeMeta>,
+    /// The cursor to get the next page with [`ChangeQuery::after`], or `None` if this is the
+    /// last page.
+    pub next: Option<ChangeCursor>,
+}
+
+/// The changes of the doc sorted by their [`ChangeOrder::Timestamp`] key.
+#[derive(Debug, Default)]
+pub(crate) struct ChangeTimestamps {
+    /// The version the index covers
+    vv: VersionVector,
+    /// The key and the start counter of every change
+    sorted: BTreeMap<ChangeCursor, Counter>,
+    /// The key of every change by its start counter, to replace the key of a change that grew
+    keys: FxHashMap<PeerID, BTreeMap<Counter, ChangeCursor>>,
+}
+
+impl ChangeTimestamps {
+    /// Index the changes of `oplog` that are not indexed yet.
+    fn sync(&mut self, oplog: &OpLog) {
+        if !oplog.vv().includes_vv(&self.vv) {
+            // Changes were rolled back
+            *self = Default::default();
+        }
+        let shallow = oplog.dag.shallow_since_vv();
+        for (&peer, &end) in oplog.vv().iter() {
+            let indexed = self.vv.get(&peer).copied().unwrap_or(0);
+            let mut counter = indexed.max(shallow.get(&peer).copied().unwrap_or(0));
+            while counter < end {
+                // A local change can be merged into the last one, so it may start before `counter`
+                let Some(change) = oplog.get_change_at(ID::new(peer, counter)) else {
+                    break;
+                };
+                let change = ChangeMeta::from_change(&change);
+                let key = ChangeCursor {
+                    timestamp: change.timestamp,
+                    lamport: change.lamport_last(),
+                    peer,
+                };
+                let keys = self.keys.entry(peer).or_default();
+                if let Some(old) = keys.insert(change.id.counter, key) {
+                    self.sorted.remove(&old);
+                }
+                self.sorted.insert(key, change.id.counter);
+                counter = change.id.counter + change.len as Counter;
+            }
+        }
+        self.vv = oplog.vv().clone();
+    }
+}
+
+impl LoroDoc {
+    /// Query the changes of the doc, see [`ChangeQuery`].
+    ///
+    /// It returns at most one page of changes, and only loads the changes it visits.
+    pub fn query_changes(&self, query: &ChangeQuery) -> ChangePage {
+        let (options, guard) = self.implicit_commit_then_stop();
+        drop(guard);
+        let page = {
+            let oplog = self.oplog().lock();
+            let mut timestamps = self.change_timestamps.lock();
+            if query.order == ChangeOrder::Timestamp {
+                timestamps.sync(&oplog);
+            }
+            query.run(&oplog, &timestamps)
+        };
+        self.renew_txn_if_auto_commit(options);
+        page
+    }
+}
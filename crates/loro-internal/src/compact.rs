@@ -0,0 +1,141 @@
+//! Squash the history of a document into coarse checkpoints.
+//!
+//! [`LoroDoc::compact_history`] cuts the history at the end of every period that has changes,
+//! and replays the diff between two cuts as a single change in a new doc. Timestamps can go
+//! backwards along the history, so a cut also takes the deps of its changes, even if they're
+//! from a later period.
+use loro_common::{Counter, HasIdSpan, LoroError, LoroResult, ID};
+use rustc_hash::FxHashMap;
+
+use crate::{change::Timestamp, loro::CommitOptions, version::Frontiers, LoroDoc, VersionVector};
+
+/// How [`LoroDoc::compact_history`] squashes the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionPolicy {
+    /// The length of the period squashed into a checkpoint, in seconds.
+    pub interval: Timestamp,
+}
+
+impl CompactionPolicy {
+    /// One checkpoint per hour.
+    pub fn hourly() -> Self {
+        Self { interval: 60 * 60 }
+    }
+
+    /// One checkpoint per day.
+    pub fn daily() -> Self {
+        Self {
+            interval: 24 * 60 * 60,
+        }
+    }
+}
+
+/// A version point kept by [`LoroDoc::compact_history`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The timestamp of the last change squashed into the checkpoint.
+    pub timestamp: Timestamp,
+    /// The version in the history of the original doc.
+    pub source: Frontiers,
+    /// The same version in the history of the compacted doc.
+    pub target: Frontiers,
+}
+
+/// The result of [`LoroDoc::compact_history`].
+#[derive(Debug)]
+pub struct CompactedHistory {
+    /// The new doc, with one change per checkpoint.
+    pub doc: LoroDoc,
+    /// The checkpoints, oldest first. The last one is the latest version.
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+impl LoroDoc {
+    /// Squash the history into one change per period of `policy.interval` seconds, in a new doc.
+    ///
+    /// It needs the full history, and it parses all of it. The new doc has the same state, but
+    /// its ops have new IDs, so its history is unrelated to the history of this doc:
+    ///
+    /// - The new doc must not exchange updates with this doc or with any doc that shares its
+    ///   history. Merging the two histories would duplicate the content.
+    /// - Peers have to sync their edits into this doc before the compaction, and switch to a
+    ///   snapshot of the new doc afterwards. Edits made on the old history after that are
+    ///   lost, unless they're replayed as a diff.
+    /// - The branches and tags refer to versions of the old history, so they're not copied.
+    ///   The [`Checkpoint`]s map the old versions to the new ones.
+    pub fn compact_history(&self, policy: CompactionPolicy) -> LoroResult<CompactedHistory> {
+        if policy.interval <= 0 {
+            return Err(LoroError::ArgErr(
+                "The compaction interval must be positive".into(),
+            ));
+        }
+        if self.is_shallow() {
+            return Err(LoroError::ArgErr(
+                "Cannot compact the history of a shallow doc".into(),
+            ));
+        }
+        self.commit_then_renew();
+
+        // The last op of every change, grouped by period
+        let mut periods: FxHashMap<Timestamp, Vec<ID>> = FxHashMap::default();
+        self.oplog()
+            .lock()
+            .change_store()
+            .visit_all_changes(&mut |change| {
+                periods
+                    .entry(change.timestamp.div_euclid(policy.interval))
+                    .or_default()
+                    .push(change.id_last());
+            });
+        let mut periods: Vec<_> = periods.into_iter().collect();
+        periods.sort_unstable_by_key(|(period, _)| *period);
+
+        let doc = LoroDoc::new();
+        doc.set_config(&self.config);
+        // Keep the checkpoints apart
+        doc.set_change_merge_interval(-1);
+        doc.start_auto_commit();
+        let mut container_remap = FxHashMap::default();
+        let mut checkpoints: Vec<Checkpoint> = Vec::new();
+        let mut vv = VersionVector::default();
+        let mut source = Frontiers::default();
+        for (_, changes) in periods {
+            let mut next_vv = vv.clone();
+            for last in changes {
+                next_vv.extend_to_include_last_id(last);
+            }
+            let (next_vv, next, timestamp) = {
+                let oplog = self.oplog().lock();
+                let next_vv = oplog.dag.frontiers_to_vv(&next_vv.get_frontiers()).unwrap();
+                // The changes of the period may have been taken by an earlier cut already
+                if next_vv == vv {
+                    continue;
+                }
+                let mut timestamp = Timestamp::MIN;
+                for span in next_vv.sub_iter(&vv) {
+                    let mut counter = span.counter.start;
+                    while counter < span.counter.end {
+                        let change = oplog.get_change_at(ID::new(span.peer, counter)).unwrap();
+                        timestamp = timestamp.max(change.timestamp);
+                        counter = change.id.counter + change.len() as Counter;
+                    }
+                }
+                let next = oplog.dag.vv_to_frontiers(&next_vv);
+                (next_vv, next, timestamp)
+            };
+            let diff = self.diff(&source, &next)?;
+            doc._apply_diff(diff, &mut container_remap, true)?;
+            doc.commit_with(CommitOptions::new().timestamp(timestamp));
+            checkpoints.push(Checkpoint {
+                timestamp,
+                source: next.clone(),
+                target: doc.state_frontiers(),
+            });
+            source = next;
+            vv = next_vv;
+        }
+
+        doc.set_change_merge_interval(self.config.merge_interval());
+        Ok(CompactedHistory { doc, checkpoints })
+    }
+}
@@ -6,9 +6,13 @@
 //!
 use crate::{arena::SharedArena, InternalString, ID};
 
+#[cfg(feature = "counter")]
+pub mod counter;
 pub mod list;
 pub mod map;
 pub mod richtext;
+#[cfg(feature = "set")]
+pub mod set;
 pub mod tree;
 pub mod idx {
     use super::super::ContainerType;
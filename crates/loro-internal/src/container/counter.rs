@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    op::{FutureInnerContent, RawOpContent},
+    LoroValue,
+};
+
+/// The kind of a counter.
+///
+/// A typed counter takes the kind of the ops applied to it. If ops of
+/// different kinds are applied, the kind declared later in this enum wins, and
+/// the ops of the other kinds are folded into it: float increments are rounded
+/// and added to int and bounded counters, and a max register treats the value
+/// of the other ops as one more proposal. Every peer shows the same value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CounterKind {
+    /// A counter of `f64` increments.
+    #[default]
+    Float,
+    /// A counter of `i64` increments with exact integer arithmetic.
+    Int,
+    /// An `i64` counter whose value never shows below its lower bound.
+    Bounded,
+    /// A register that keeps the max value that has been proposed.
+    Max,
+}
+
+/// An op on a counter container.
+///
+/// Only typed counter containers accept ops other than [`CounterOp::Float`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CounterOp {
+    Float(f64),
+    Int(i64),
+    Bounded { delta: i64, lower_bound: i64 },
+    Max(f64),
+}
+
+impl CounterOp {
+    pub(crate) fn kind(&self) -> CounterKind {
+        match self {
+            CounterOp::Float(_) => CounterKind::Float,
+            CounterOp::Int(_) => CounterKind::Int,
+            CounterOp::Bounded { .. } => CounterKind::Bounded,
+            CounterOp::Max(_) => CounterKind::Max,
+        }
+    }
+
+    pub(crate) fn from_future(content: &FutureInnerContent) -> Option<Self> {
+        match content {
+            FutureInnerContent::Counter(x) => Some(CounterOp::Float(*x)),
+            FutureInnerContent::IntCounter(x) => Some(CounterOp::Int(*x)),
+            FutureInnerContent::BoundedCounter { delta, lower_bound } => Some(CounterOp::Bounded {
+                delta: *delta,
+                lower_bound: *lower_bound,
+            }),
+            FutureInnerContent::MaxCounter(x) => Some(CounterOp::Max(*x)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_raw(content: &RawOpContent) -> Option<Self> {
+        match content {
+            RawOpContent::Counter(x) => Some(CounterOp::Float(*x)),
+            RawOpContent::IntCounter(x) => Some(CounterOp::Int(*x)),
+            RawOpContent::BoundedCounter { delta, lower_bound } => Some(CounterOp::Bounded {
+                delta: *delta,
+                lower_bound: *lower_bound,
+            }),
+            RawOpContent::MaxCounter(x) => Some(CounterOp::Max(*x)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_raw(self) -> RawOpContent<'static> {
+        match self {
+            CounterOp::Float(x) => RawOpContent::Counter(x),
+            CounterOp::Int(x) => RawOpContent::IntCounter(x),
+            CounterOp::Bounded { delta, lower_bound } => {
+                RawOpContent::BoundedCounter { delta, lower_bound }
+            }
+            CounterOp::Max(x) => RawOpContent::MaxCounter(x),
+        }
+    }
+}
+
+/// A summary of the ops applied to a counter.
+///
+/// Every field can be updated by applying or retracting a single op, so the
+/// difference between two versions is also a summary, with negative op counts
+/// for the retracted ops.
+// Note: It will be encoded into the snapshot, so the order of its fields should not be changed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CounterSummary {
+    float: f64,
+    int_ops: i64,
+    int: i64,
+    bounded_ops: i64,
+    /// The sum of the deltas of bounded ops
+    bounded: i64,
+    /// The op count of each lower bound used by bounded ops
+    lower_bounds: BTreeMap<i64, i64>,
+    /// The op count of each value proposed by max ops, keyed by [`max_key`]
+    max_values: BTreeMap<i64, i64>,
+}
+
+/// Map a f64 to an i64 key that sorts in the same order as [`f64::total_cmp`].
+fn max_key(value: f64) -> i64 {
+    let bits = value.to_bits() as i64;
+    bits ^ ((((bits >> 63) as u64) >> 1) as i64)
+}
+
+fn max_value_from_key(key: i64) -> f64 {
+    f64::from_bits((key ^ ((((key >> 63) as u64) >> 1) as i64)) as u64)
+}
+
+fn add_count(map: &mut BTreeMap<i64, i64>, key: i64, count: i64) {
+    let entry = map.entry(key).or_insert(0);
+    *entry += count;
+    if *entry == 0 {
+        map.remove(&key);
+    }
+}
+
+impl CounterSummary {
+    pub(crate) fn from_float(value: f64) -> Self {
+        Self {
+            float: value,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn add_float(&mut self, x: f64) {
+        self.float += x;
+    }
+
+    /// Apply or retract an op.
+    pub(crate) fn apply(&mut self, op: CounterOp, retract: bool) {
+        let count = if retract { -1 } else { 1 };
+        match op {
+            CounterOp::Float(x) => self.float += x * count as f64,
+            CounterOp::Int(x) => {
+                self.int_ops += count;
+                self.int = self.int.wrapping_add(x.wrapping_mul(count));
+            }
+            CounterOp::Bounded { delta, lower_bound } => {
+                self.bounded_ops += count;
+                self.bounded = self.bounded.wrapping_add(delta.wrapping_mul(count));
+                add_count(&mut self.lower_bounds, lower_bound, count);
+            }
+            CounterOp::Max(x) => add_count(&mut self.max_values, max_key(x), count),
+        }
+    }
+
+    pub(crate) fn merge(&mut self, other: &CounterSummary) {
+        self.float += other.float;
+        self.int_ops += other.int_ops;
+        self.int = self.int.wrapping_add(other.int);
+        self.bounded_ops += other.bounded_ops;
+        self.bounded = self.bounded.wrapping_add(other.bounded);
+        for (&k, &c) in other.lower_bounds.iter() {
+            add_count(&mut self.lower_bounds, k, c);
+        }
+        for (&k, &c) in other.max_values.iter() {
+            add_count(&mut self.max_values, k, c);
+        }
+    }
+
+    pub(crate) fn float(&self) -> f64 {
+        self.float
+    }
+
+    /// Whether only float ops have been applied to this summary.
+    pub(crate) fn is_float_only(&self) -> bool {
+        self.int_ops == 0
+            && self.int == 0
+            && self.bounded_ops == 0
+            && self.bounded == 0
+            && self.lower_bounds.is_empty()
+            && self.max_values.is_empty()
+    }
+
+    pub(crate) fn kind(&self) -> CounterKind {
+        if !self.max_values.is_empty() {
+            CounterKind::Max
+        } else if self.bounded_ops > 0 {
+            CounterKind::Bounded
+        } else if self.int_ops > 0 {
+            CounterKind::Int
+        } else {
+            CounterKind::Float
+        }
+    }
+
+    /// The lower bound of a bounded counter, which is the largest lower bound
+    /// used by its ops.
+    pub(crate) fn lower_bound(&self) -> Option<i64> {
+        self.lower_bounds.keys().next_back().copied()
+    }
+
+    pub(crate) fn value(&self) -> LoroValue {
+        match self.kind() {
+            CounterKind::Max => {
+                let max = max_value_from_key(*self.max_values.keys().next_back().unwrap());
+                if self.int_ops == 0 && self.bounded_ops == 0 && self.float == 0. {
+                    return LoroValue::Double(max);
+                }
+
+                let others = match self.value_without_max() {
+                    LoroValue::I64(x) => x as f64,
+                    LoroValue::Double(x) => x,
+                    _ => unreachable!(),
+                };
+                LoroValue::Double(max.max(others))
+            }
+            _ => self.value_without_max(),
+        }
+    }
+
+    fn value_without_max(&self) -> LoroValue {
+        let int = self.int.wrapping_add(self.float.round() as i64);
+        if let Some(lower_bound) = self.lower_bound() {
+            // Concurrent decrements can take the sum below the bound. The value
+            // stays at the bound until later increments pay the overdraft back.
+            LoroValue::I64(int.wrapping_add(self.bounded).max(lower_bound))
+        } else if self.int_ops > 0 {
+            LoroValue::I64(int)
+        } else {
+            LoroValue::Double(self.float)
+        }
+    }
+
+    pub(crate) fn value_f64(&self) -> f64 {
+        match self.value() {
+            LoroValue::Double(x) => x,
+            LoroValue::I64(x) => x as f64,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn max_key_keeps_f64_order() {
+        let values = [f64::NEG_INFINITY, -2.5, -0.0, 0.0, 1.0, 3.5, f64::INFINITY];
+        for w in values.windows(2) {
+            assert!(max_key(w[0]) < max_key(w[1]));
+        }
+        for v in values {
+            assert_eq!(max_value_from_key(max_key(v)).to_bits(), v.to_bits());
+        }
+    }
+
+    #[test]
+    fn retracting_ops_restores_the_summary() {
+        let ops = [
+            CounterOp::Int(5),
+            CounterOp::Bounded {
+                delta: -3,
+                lower_bound: 0,
+            },
+            CounterOp::Max(2.0),
+            CounterOp::Max(7.0),
+        ];
+        let mut summary = CounterSummary::default();
+        for op in ops {
+            summary.apply(op, false);
+        }
+        assert_eq!(summary.kind(), CounterKind::Max);
+        assert_eq!(summary.value(), LoroValue::Double(7.0));
+
+        summary.apply(CounterOp::Max(7.0), true);
+        assert_eq!(summary.value(), LoroValue::Double(2.0));
+        summary.apply(CounterOp::Max(2.0), true);
+        assert_eq!(summary.kind(), CounterKind::Bounded);
+        assert_eq!(summary.value(), LoroValue::I64(2));
+        for op in ops.into_iter().take(2) {
+            summary.apply(op, true);
+        }
+        assert_eq!(summary, CounterSummary::default());
+    }
+
+    #[test]
+    fn bounded_ops_pay_back_the_overdraft() {
+        let bounded = |delta| CounterOp::Bounded {
+            delta,
+            lower_bound: 0,
+        };
+        let mut summary = CounterSummary::default();
+        summary.apply(bounded(10), false);
+        // concurrent decrements from two peers
+        summary.apply(bounded(-8), false);
+        summary.apply(bounded(-8), false);
+        assert_eq!(summary.value(), LoroValue::I64(0));
+        summary.apply(bounded(5), false);
+        assert_eq!(summary.value(), LoroValue::I64(0));
+        summary.apply(bounded(5), false);
+        assert_eq!(summary.value(), LoroValue::I64(4));
+    }
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+use crate::LoroValue;
+
+/// An op on a set container.
+///
+/// A remove op only removes the adds it has observed, so a concurrent add of
+/// the same value survives it (add-wins).
+// Note: It will be encoded into binary format, so the order of its fields should not be changed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SetOp {
+    Add(LoroValue),
+    Remove(LoroValue),
+}
+
+impl SetOp {
+    pub fn value(&self) -> &LoroValue {
+        match self {
+            SetOp::Add(v) | SetOp::Remove(v) => v,
+        }
+    }
+
+    pub fn is_add(&self) -> bool {
+        matches!(self, SetOp::Add(_))
+    }
+}
@@ -1,5 +1,11 @@
 mod movable_list;
 pub(crate) use movable_list::{ElementDelta, MovableListInnerDelta};
+#[cfg(feature = "set")]
+mod set;
+#[cfg(feature = "set")]
+pub(crate) use set::SetDelta;
+#[cfg(feature = "set")]
+pub use set::SetDiff;
 mod seq;
 pub use seq::{Delta, DeltaItem, DeltaType, DeltaValue, Meta};
 mod map;
@@ -0,0 +1,44 @@
+use loro_common::{IdLp, LoroValue};
+use rustc_hash::FxHashMap;
+
+/// The internal diff of a set container.
+///
+/// Every touched value maps to its new state: `Some` with the id of the
+/// winning add if the value is in the set, `None` if it's not.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SetDelta {
+    pub(crate) updated: FxHashMap<LoroValue, Option<IdLp>>,
+}
+
+/// The diff of a set container, in the order the changes should be applied.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SetDiff {
+    pub added: Vec<LoroValue>,
+    pub removed: Vec<LoroValue>,
+}
+
+impl SetDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn compose(mut self, other: Self) -> Self {
+        for v in other.added {
+            if let Some(pos) = self.removed.iter().position(|x| x == &v) {
+                self.removed.swap_remove(pos);
+            } else if !self.added.contains(&v) {
+                self.added.push(v);
+            }
+        }
+
+        for v in other.removed {
+            if let Some(pos) = self.added.iter().position(|x| x == &v) {
+                self.added.swap_remove(pos);
+            } else if !self.removed.contains(&v) {
+                self.removed.push(v);
+            }
+        }
+
+        self
+    }
+}
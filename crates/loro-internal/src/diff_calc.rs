@@ -4,6 +4,10 @@ use std::{num::NonZeroU16, sync::Arc};
 mod counter;
 #[cfg(feature = "counter")]
 pub(crate) use counter::CounterDiffCalculator;
+#[cfg(feature = "set")]
+mod set;
+#[cfg(feature = "set")]
+pub(crate) use set::SetDiffCalculator;
 pub(super) mod tree;
 mod unknown;
 use either::Either;
@@ -375,6 +379,16 @@ impl DiffCalculator {
                     depth,
                     ContainerDiffCalculator::Counter(CounterDiffCalculator::new(idx)),
                 ),
+                #[cfg(feature = "typed-counter")]
+                crate::ContainerType::TypedCounter => (
+                    depth,
+                    ContainerDiffCalculator::Counter(CounterDiffCalculator::new(idx)),
+                ),
+                #[cfg(feature = "set")]
+                crate::ContainerType::Set => (
+                    depth,
+                    ContainerDiffCalculator::Set(SetDiffCalculator::new(idx)),
+                ),
             })
     }
 }
@@ -416,6 +430,8 @@ pub(crate) enum ContainerDiffCalculator {
     MovableList(MovableListDiffCalculator),
     #[cfg(feature = "counter")]
     Counter(counter::CounterDiffCalculator),
+    #[cfg(feature = "set")]
+    Set(set::SetDiffCalculator),
     Unknown(UnknownDiffCalculator),
 }
 
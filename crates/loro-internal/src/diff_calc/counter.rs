@@ -2,13 +2,20 @@ use std::collections::BTreeMap;
 
 use loro_common::{ContainerID, ID};
 
-use crate::{container::idx::ContainerIdx, event::InternalDiff, OpLog};
+use crate::{
+    container::{
+        counter::{CounterOp, CounterSummary},
+        idx::ContainerIdx,
+    },
+    event::InternalDiff,
+    OpLog,
+};
 
 use super::{DiffCalcVersionInfo, DiffCalculatorTrait, DiffMode};
 
 #[derive(Debug)]
 pub(crate) struct CounterDiffCalculator {
-    ops: BTreeMap<ID, f64>,
+    ops: BTreeMap<ID, CounterOp>,
 }
 
 impl CounterDiffCalculator {
@@ -29,10 +36,8 @@ impl DiffCalculatorTrait for CounterDiffCalculator {
         _vv: Option<&crate::VersionVector>,
     ) {
         let id = op.id();
-        self.ops.insert(
-            id,
-            *op.op().content.as_future().unwrap().as_counter().unwrap(),
-        );
+        let counter_op = CounterOp::from_future(op.op().content.as_future().unwrap()).unwrap();
+        self.ops.insert(id, counter_op);
     }
 
     fn finish_this_round(&mut self) {}
@@ -44,20 +49,24 @@ impl DiffCalculatorTrait for CounterDiffCalculator {
         info: DiffCalcVersionInfo,
         _on_new_container: impl FnMut(&ContainerID),
     ) -> (InternalDiff, DiffMode) {
-        let mut diff = 0.;
+        let mut diff = CounterSummary::default();
         let (b, a) = info.from_vv.diff_iter(info.to_vv);
 
         for sub in b {
             for (_, c) in self.ops.range(sub.norm_id_start()..sub.norm_id_end()) {
-                diff -= c;
+                diff.apply(*c, true);
             }
         }
         for sub in a {
             for (_, c) in self.ops.range(sub.norm_id_start()..sub.norm_id_end()) {
-                diff += c;
+                diff.apply(*c, false);
             }
         }
 
-        (InternalDiff::Counter(diff), DiffMode::Linear)
+        if diff.is_float_only() {
+            (InternalDiff::Counter(diff.float()), DiffMode::Linear)
+        } else {
+            (InternalDiff::TypedCounter(diff), DiffMode::Linear)
+        }
     }
 }
@@ -0,0 +1,95 @@
+use loro_common::{ContainerID, IdLp, LoroValue};
+use rustc_hash::FxHashMap;
+
+use crate::{container::idx::ContainerIdx, delta::SetDelta, event::InternalDiff, OpLog};
+
+use super::{DiffCalcVersionInfo, DiffCalculatorTrait, DiffMode};
+
+#[derive(Debug)]
+pub(crate) struct SetDiffCalculator {
+    container_idx: ContainerIdx,
+    changed: FxHashMap<LoroValue, Option<IdLp>>,
+    current_mode: DiffMode,
+}
+
+impl SetDiffCalculator {
+    pub(crate) fn new(container_idx: ContainerIdx) -> Self {
+        Self {
+            container_idx,
+            changed: Default::default(),
+            current_mode: DiffMode::Checkout,
+        }
+    }
+}
+
+impl DiffCalculatorTrait for SetDiffCalculator {
+    fn start_tracking(&mut self, _oplog: &OpLog, _vv: &crate::VersionVector, mode: DiffMode) {
+        self.changed.clear();
+        self.current_mode = mode;
+    }
+
+    fn apply_change(
+        &mut self,
+        _oplog: &OpLog,
+        op: crate::op::RichOp,
+        _vv: Option<&crate::VersionVector>,
+    ) {
+        // Only linear updates can be applied one by one. Concurrent adds and
+        // removes need the history cache to find out which adds survive.
+        if !matches!(self.current_mode, DiffMode::Linear) {
+            return;
+        }
+
+        let op_ref = op.op();
+        let set_op = op_ref.content.as_future().unwrap().as_set().unwrap();
+        let state = set_op.is_add().then(|| op.idlp());
+        self.changed.insert(set_op.value().clone(), state);
+    }
+
+    fn finish_this_round(&mut self) {
+        self.changed.clear();
+        self.current_mode = DiffMode::Checkout;
+    }
+
+    fn calculate_diff(
+        &mut self,
+        _idx: ContainerIdx,
+        oplog: &OpLog,
+        DiffCalcVersionInfo { from_vv, to_vv, .. }: DiffCalcVersionInfo,
+        _on_new_container: impl FnMut(&ContainerID),
+    ) -> (InternalDiff, DiffMode) {
+        match self.current_mode {
+            DiffMode::Linear => {
+                let updated = std::mem::take(&mut self.changed);
+                self.current_mode = DiffMode::Checkout;
+                (InternalDiff::Set(SetDelta { updated }), DiffMode::Linear)
+            }
+            _ => oplog.with_history_cache(|h| {
+                let checkout_index = &h.get_checkout_index().set;
+                let from =
+                    checkout_index.get_container_elements_at_vv(self.container_idx, from_vv, oplog);
+                let mut to =
+                    checkout_index.get_container_elements_at_vv(self.container_idx, to_vv, oplog);
+                let mut updated = FxHashMap::default();
+                for (v, from_idlp) in from {
+                    match to.remove(&v) {
+                        None => {
+                            updated.insert(v, None);
+                        }
+                        Some(to_idlp) => {
+                            if to_idlp != from_idlp {
+                                updated.insert(v, Some(to_idlp));
+                            }
+                        }
+                    }
+                }
+
+                for (v, to_idlp) in to {
+                    updated.insert(v, Some(to_idlp));
+                }
+
+                (InternalDiff::Set(SetDelta { updated }), DiffMode::Checkout)
+            }),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! A summary of the difference between two versions, for review UIs.
+//!
+//! It's built from the diffs of [`LoroDoc::diff`] in both directions: the forward diff has what
+//! changed, and the backward diff has the old values of the changed map entries.
+use std::collections::BTreeMap;
+
+use loro_common::{ContainerID, Counter, IdSpan, LoroResult, LoroValue, PeerID, TreeID, ID};
+use loro_delta::DeltaItem;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    change::Timestamp,
+    delta::TreeExternalDiff,
+    event::{Diff, Index},
+    version::Frontiers,
+    LoroDoc,
+};
+
+/// The summary of the difference between two versions, see [`LoroDoc::diff_summary`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffSummary {
+    /// The changed containers, in the order of [`LoroDoc::diff`].
+    pub containers: Vec<ContainerSummary>,
+    /// The peers whose changes are between the two versions, sorted by peer.
+    pub authors: Vec<AuthorSummary>,
+}
+
+/// What changed in a container.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContainerSummary {
+    /// The changed container.
+    pub id: ContainerID,
+    /// The path from the root to the container in the current state, or `None` if the
+    /// container isn't reachable from a root anymore.
+    pub path: Option<Vec<Index>>,
+    /// What changed, depending on the type of the container.
+    pub changes: ChangeSummary,
+}
+
+/// What changed in a container, depending on its type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ChangeSummary {
+    /// The lengths are in Unicode code points, or in UTF-16 code units with feature `wasm`.
+    Text {
+        inserted: usize,
+        deleted: usize,
+        /// The length of the text whose style changed.
+        formatted: usize,
+    },
+    List {
+        inserted: usize,
+        deleted: usize,
+        moved: usize,
+    },
+    Map {
+        keys: Vec<MapKeyChange>,
+    },
+    Tree {
+        created: Vec<TreeID>,
+        deleted: Vec<TreeID>,
+        moved: Vec<TreeID>,
+    },
+    Counter {
+        delta: f64,
+    },
+    Set {
+        added: Vec<LoroValue>,
+        removed: Vec<LoroValue>,
+    },
+    Unknown,
+}
+
+/// A changed map entry. A missing value means the key doesn't exist in that version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapKeyChange {
+    pub key: String,
+    pub old: Option<LoroValue>,
+    pub new: Option<LoroValue>,
+}
+
+/// The changes of a peer between the two versions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthorSummary {
+    pub peer: PeerID,
+    /// The number of changes, including the ones that are only partly between the versions.
+    pub changes: usize,
+    /// The number of ops between the versions.
+    pub ops: usize,
+    pub first_timestamp: Timestamp,
+    pub last_timestamp: Timestamp,
+}
+
+fn text_len(s: &crate::utils::string_slice::StringSlice) -> usize {
+    if cfg!(feature = "wasm") {
+        s.len_utf16()
+    } else {
+        s.len_unicode()
+    }
+}
+
+fn summarize(diff: &Diff, old: Option<&Diff>) -> ChangeSummary {
+    match diff {
+        Diff::Text(text) => {
+            let (mut inserted, mut deleted, mut formatted) = (0, 0, 0);
+            for item in text.iter() {
+                match item {
+                    DeltaItem::Retain { len, attr } => {
+                        if !attr.0.is_empty() {
+                            formatted += len;
+                        }
+                    }
+                    DeltaItem::Replace { value, delete, .. } => {
+                        inserted += text_len(value);
+                        deleted += delete;
+                    }
+                }
+            }
+            ChangeSummary::Text {
+                inserted,
+                deleted,
+                formatted,
+            }
+        }
+        Diff::List(list) => {
+            let (mut inserted, mut deleted, mut moved) = (0, 0, 0);
+            for item in list.iter() {
+                if let DeltaItem::Replace {
+                    value,
+                    attr,
+                    delete,
+                } = item
+                {
+                    if attr.from_move {
+                        moved += value.len();
+                    } else {
+                        inserted += value.len();
+                    }
+                    deleted += delete;
+                }
+            }
+            ChangeSummary::List {
+                inserted,
+                // A move deletes the value from its old position
+                deleted: deleted.saturating_sub(moved),
+                moved,
+            }
+        }
+        Diff::Map(map) => {
+            let old = old.and_then(|d| d.as_map());
+            let mut keys: Vec<MapKeyChange> = map
+                .updated
+                .iter()
+                .map(|(key, v)| MapKeyChange {
+                    key: key.to_string(),
+                    old: old
+                        .and_then(|old| old.updated.get(key))
+                        .and_then(|v| v.value.as_ref())
+                        .map(|v| v.to_value()),
+                    new: v.value.as_ref().map(|v| v.to_value()),
+                })
+                .collect();
+            keys.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+            ChangeSummary::Map { keys }
+        }
+        Diff::Tree(tree) => {
+            let (mut created, mut deleted, mut moved) = (Vec::new(), Vec::new(), Vec::new());
+            for item in tree.diff.iter() {
+                match item.action {
+                    TreeExternalDiff::Create { .. } => created.push(item.target),
+                    TreeExternalDiff::Move { .. } => moved.push(item.target),
+                    TreeExternalDiff::Delete { .. } => deleted.push(item.target),
+                }
+            }
+            ChangeSummary::Tree {
+                created,
+                deleted,
+                moved,
+            }
+        }
+        #[cfg(feature = "counter")]
+        Diff::Counter(delta) => ChangeSummary::Counter { delta: *delta },
+        #[cfg(feature = "set")]
+        Diff::Set(set) => ChangeSummary::Set {
+            added: set.added.clone(),
+            removed: set.removed.clone(),
+        },
+        Diff::Unknown => ChangeSummary::Unknown,
+    }
+}
+
+impl LoroDoc {
+    /// Summarize the difference between two versions: what changed in every container, and
+    /// who changed it.
+    pub fn diff_summary(&self, a: &Frontiers, b: &Frontiers) -> LoroResult<DiffSummary> {
+        let forward = self.diff(a, b)?;
+        let backward = self.diff(b, a)?;
+        let containers = forward
+            .order
+            .iter()
+            .map(|id| ContainerSummary {
+                id: id.clone(),
+                path: self
+                    .get_path_to_container(id)
+                    .map(|path| path.into_iter().map(|(_, index)| index).collect()),
+                changes: summarize(&forward.cid_to_events[id], backward.cid_to_events.get(id)),
+            })
+            .collect();
+
+        let spans = self.find_id_spans_between(a, b);
+        let oplog = self.oplog().lock();
+        // A peer can have changes on both sides, but it only gets one summary
+        let mut authors: BTreeMap<PeerID, AuthorSummary> = BTreeMap::new();
+        for (peer, span) in spans.retreat.iter().chain(spans.forward.iter()) {
+            let span = IdSpan::new(*peer, span.min(), span.norm_end());
+            let author = authors.entry(*peer).or_insert(AuthorSummary {
+                peer: *peer,
+                changes: 0,
+                ops: 0,
+                first_timestamp: Timestamp::MAX,
+                last_timestamp: Timestamp::MIN,
+            });
+            let mut counter = span.counter.start;
+            while counter < span.counter.end {
+                let Some(change) = oplog.get_change_at(ID::new(*peer, counter)) else {
+                    break;
+                };
+                let end = (change.id.counter + change.len() as Counter).min(span.counter.end);
+                author.changes += 1;
+                author.ops += (end - counter) as usize;
+                author.first_timestamp = author.first_timestamp.min(change.timestamp);
+                author.last_timestamp = author.last_timestamp.max(change.timestamp);
+                counter = end;
+            }
+        }
+        Ok(DiffSummary {
+            containers,
+            authors: authors.into_values().filter(|a| a.changes > 0).collect(),
+        })
+    }
+}
@@ -511,6 +511,54 @@ pub(crate) fn encode_change(
                     _ => unreachable!(),
                 }
             }
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter => {
+                let InnerContent::Future(f) = content else {
+                    unreachable!()
+                };
+                match f {
+                    FutureInnerContent::Counter(x) => {
+                        JsonOpContent::Future(json::FutureOpWrapper {
+                            prop: 0,
+                            value: json::FutureOp::Counter(super::OwnedValue::F64(*x)),
+                        })
+                    }
+                    FutureInnerContent::IntCounter(x) => {
+                        JsonOpContent::Future(json::FutureOpWrapper {
+                            prop: 1,
+                            value: json::FutureOp::IntCounter { delta: *x },
+                        })
+                    }
+                    FutureInnerContent::BoundedCounter { delta, lower_bound } => {
+                        JsonOpContent::Future(json::FutureOpWrapper {
+                            prop: 2,
+                            value: json::FutureOp::BoundedCounter {
+                                delta: *delta,
+                                lower_bound: *lower_bound,
+                            },
+                        })
+                    }
+                    FutureInnerContent::MaxCounter(x) => {
+                        JsonOpContent::Future(json::FutureOpWrapper {
+                            prop: 3,
+                            value: json::FutureOp::MaxCounter { value: *x },
+                        })
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            #[cfg(feature = "set")]
+            ContainerType::Set => {
+                let InnerContent::Future(FutureInnerContent::Set(set_op)) = content else {
+                    unreachable!()
+                };
+                JsonOpContent::Future(json::FutureOpWrapper {
+                    prop: if set_op.is_add() { 0 } else { 1 },
+                    value: json::FutureOp::Set(super::OwnedValue::LoroValue(
+                        set_op.value().clone(),
+                    )),
+                })
+            }
         };
         ops.push(json::JsonOp {
             counter: *counter,
@@ -1047,6 +1095,57 @@ fn decode_op(op: json::JsonOp, arena: &SharedArena, peers: &Option<Vec<PeerID>>)
                     ))
                 }
             }
+        }
+        #[cfg(feature = "typed-counter")]
+        ContainerType::TypedCounter => {
+            let JsonOpContent::Future(json::FutureOpWrapper { prop: _, value }) = content else {
+                return Err(LoroError::DecodeError(
+                    "invalid op content for typed counter container".into(),
+                ));
+            };
+            use crate::encoding::OwnedValue;
+            match value {
+                json::FutureOp::Counter(OwnedValue::F64(c)) => {
+                    InnerContent::Future(FutureInnerContent::Counter(c))
+                }
+                json::FutureOp::Counter(OwnedValue::I64(c)) => {
+                    InnerContent::Future(FutureInnerContent::Counter(c as f64))
+                }
+                json::FutureOp::IntCounter { delta } => {
+                    InnerContent::Future(FutureInnerContent::IntCounter(delta))
+                }
+                json::FutureOp::BoundedCounter { delta, lower_bound } => {
+                    InnerContent::Future(FutureInnerContent::BoundedCounter { delta, lower_bound })
+                }
+                json::FutureOp::MaxCounter { value } => {
+                    InnerContent::Future(FutureInnerContent::MaxCounter(value))
+                }
+                _ => {
+                    return Err(LoroError::DecodeError(
+                        "invalid counter op value type".into(),
+                    ))
+                }
+            }
+        }
+        #[cfg(feature = "set")]
+        ContainerType::Set => {
+            let JsonOpContent::Future(json::FutureOpWrapper { prop, value }) = content else {
+                return Err(LoroError::DecodeError(
+                    "invalid op content for set container".into(),
+                ));
+            };
+            use crate::{container::set::SetOp, encoding::OwnedValue};
+            let value = match value {
+                json::FutureOp::Set(OwnedValue::LoroValue(v))
+                | json::FutureOp::Unknown(OwnedValue::LoroValue(v)) => v,
+                _ => return Err(LoroError::DecodeError("invalid set op value type".into())),
+            };
+            let op = match prop {
+                0 => SetOp::Add(value),
+                1 => SetOp::Remove(value),
+                _ => return Err(LoroError::DecodeError("invalid set op prop".into())),
+            };
+            InnerContent::Future(FutureInnerContent::Set(op))
         } // Note: The Future Type need try to parse Op from the unknown content
     };
     Ok(Op {
@@ -1304,6 +1403,21 @@ pub mod json {
     pub enum FutureOp {
         #[cfg(feature = "counter")]
         Counter(OwnedValue),
+        #[cfg(feature = "counter")]
+        IntCounter {
+            delta: i64,
+        },
+        #[cfg(feature = "counter")]
+        BoundedCounter {
+            delta: i64,
+            lower_bound: i64,
+        },
+        #[cfg(feature = "counter")]
+        MaxCounter {
+            value: f64,
+        },
+        #[cfg(feature = "set")]
+        Set(OwnedValue),
         Unknown(OwnedValue),
     }
 
@@ -1437,6 +1551,14 @@ pub mod json {
                         }
                     }
                 }
+                #[cfg(feature = "set")]
+                ContainerType::Set => serde_json::from_value(value)
+                    .map(super::JsonOpContent::Future)
+                    .map_err(E::custom),
+                #[cfg(feature = "typed-counter")]
+                ContainerType::TypedCounter => serde_json::from_value(value)
+                    .map(super::JsonOpContent::Future)
+                    .map_err(E::custom),
             }
         }
 
@@ -1742,6 +1864,7 @@ pub mod json {
     /// - Replaces text mark values with `LoroValue::Null`
     /// - Preserves map insertion and text annotation keys
     /// - Resets counter operations to zero
+    /// - Substitutes set values with `LoroValue::Null`
     /// - Leaves unknown operation types (from future Loro versions) unchanged
     ///
     /// This approach ensures sensitive data removal while preserving the document's overall
@@ -1867,6 +1990,18 @@ pub mod json {
                 FutureOp::Counter(owned_value) => {
                     *owned_value = OwnedValue::I64(0);
                 }
+                #[cfg(feature = "counter")]
+                FutureOp::IntCounter { delta } | FutureOp::BoundedCounter { delta, .. } => {
+                    *delta = 0;
+                }
+                #[cfg(feature = "counter")]
+                FutureOp::MaxCounter { value } => {
+                    *value = 0.0;
+                }
+                #[cfg(feature = "set")]
+                FutureOp::Set(owned_value) => {
+                    *owned_value = OwnedValue::LoroValue(LoroValue::Null);
+                }
                 FutureOp::Unknown(..) => {
                     return Err(RedactError::UnknownOperationType);
                 }
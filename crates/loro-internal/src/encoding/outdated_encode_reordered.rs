@@ -97,6 +97,20 @@ mod encode {
         match op {
             #[cfg(feature = "counter")]
             FutureInnerContent::Counter(_) => 0,
+            #[cfg(feature = "counter")]
+            FutureInnerContent::IntCounter(_) => 1,
+            #[cfg(feature = "counter")]
+            FutureInnerContent::BoundedCounter { .. } => 2,
+            #[cfg(feature = "counter")]
+            FutureInnerContent::MaxCounter(_) => 3,
+            #[cfg(feature = "set")]
+            FutureInnerContent::Set(op) => {
+                if op.is_add() {
+                    0
+                } else {
+                    1
+                }
+            }
             FutureInnerContent::Unknown { prop, .. } => *prop,
         }
     }
@@ -203,6 +217,26 @@ mod encode {
                         Value::F64(*c)
                     }
                 }
+                // The typed counter ops only live in typed counter containers,
+                // whose ops are kept as unknown ops by peers without them.
+                #[cfg(feature = "counter")]
+                FutureInnerContent::IntCounter(c) => {
+                    Value::LoroValue(loro_common::LoroValue::I64(*c))
+                }
+                #[cfg(feature = "counter")]
+                FutureInnerContent::BoundedCounter { delta, lower_bound } => Value::LoroValue(
+                    vec![
+                        loro_common::LoroValue::I64(*delta),
+                        loro_common::LoroValue::I64(*lower_bound),
+                    ]
+                    .into(),
+                ),
+                #[cfg(feature = "counter")]
+                FutureInnerContent::MaxCounter(c) => {
+                    Value::LoroValue(loro_common::LoroValue::Double(*c))
+                }
+                #[cfg(feature = "set")]
+                FutureInnerContent::Set(op) => Value::LoroValue(op.value().clone()),
                 FutureInnerContent::Unknown { value, .. } => Value::from_owned(value),
             },
         };
@@ -465,6 +499,39 @@ pub(crate) fn decode_op(
             Value::I64(c) => crate::op::InnerContent::Future(FutureInnerContent::Counter(c as f64)),
             _ => return Err(LoroError::DecodeDataCorruptionError),
         },
+        #[cfg(feature = "typed-counter")]
+        ContainerType::TypedCounter => match (prop, value) {
+            (1, Value::LoroValue(loro_common::LoroValue::I64(c))) => {
+                crate::op::InnerContent::Future(FutureInnerContent::IntCounter(c))
+            }
+            (2, Value::LoroValue(loro_common::LoroValue::List(list))) => match list.as_slice() {
+                [loro_common::LoroValue::I64(delta), loro_common::LoroValue::I64(lower_bound)] => {
+                    crate::op::InnerContent::Future(FutureInnerContent::BoundedCounter {
+                        delta: *delta,
+                        lower_bound: *lower_bound,
+                    })
+                }
+                _ => return Err(LoroError::DecodeDataCorruptionError),
+            },
+            (3, Value::LoroValue(loro_common::LoroValue::Double(c))) => {
+                crate::op::InnerContent::Future(FutureInnerContent::MaxCounter(c))
+            }
+            (1..=3, _) => return Err(LoroError::DecodeDataCorruptionError),
+            (_, Value::F64(c)) => crate::op::InnerContent::Future(FutureInnerContent::Counter(c)),
+            (_, Value::I64(c)) => {
+                crate::op::InnerContent::Future(FutureInnerContent::Counter(c as f64))
+            }
+            _ => return Err(LoroError::DecodeDataCorruptionError),
+        },
+        #[cfg(feature = "set")]
+        ContainerType::Set => {
+            let op = match (prop, value) {
+                (0, Value::LoroValue(v)) => crate::container::set::SetOp::Add(v),
+                (1, Value::LoroValue(v)) => crate::container::set::SetOp::Remove(v),
+                _ => return Err(LoroError::DecodeDataCorruptionError),
+            };
+            crate::op::InnerContent::Future(FutureInnerContent::Set(op))
+        }
         // NOTE: The future container type need also try to parse the unknown type
         ContainerType::Unknown(_) => crate::op::InnerContent::Future(FutureInnerContent::Unknown {
             prop,
@@ -19,6 +19,10 @@ use std::{borrow::Cow, hash::Hash};
 
 use loro_common::{ContainerID, LoroValue, TreeID};
 
+#[cfg(feature = "counter")]
+use crate::container::counter::CounterSummary;
+#[cfg(feature = "set")]
+use crate::delta::{SetDelta, SetDiff};
 use crate::{container::idx::ContainerIdx, version::Frontiers};
 
 #[derive(Debug, Clone)]
@@ -141,7 +145,7 @@ impl InternalDocDiff<'_> {
 
 pub type Path = SmallVec<[Index; 4]>;
 
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, enum_as_inner::EnumAsInner)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize, enum_as_inner::EnumAsInner)]
 pub enum Index {
     Key(InternalString),
     Seq(usize),
@@ -229,6 +233,11 @@ pub(crate) enum InternalDiff {
     MovableList(MovableListInnerDelta),
     #[cfg(feature = "counter")]
     Counter(f64),
+    /// The change of a counter that has ops other than float increments.
+    #[cfg(feature = "counter")]
+    TypedCounter(CounterSummary),
+    #[cfg(feature = "set")]
+    Set(SetDelta),
     Unknown,
 }
 
@@ -340,6 +349,8 @@ pub enum Diff {
     Tree(TreeDiff),
     #[cfg(feature = "counter")]
     Counter(f64),
+    #[cfg(feature = "set")]
+    Set(SetDiff),
     Unknown,
 }
 
@@ -359,6 +370,10 @@ impl InternalDiff {
             InternalDiff::MovableList(t) => t.is_empty(),
             #[cfg(feature = "counter")]
             InternalDiff::Counter(c) => c.abs() < f64::EPSILON,
+            #[cfg(feature = "counter")]
+            InternalDiff::TypedCounter(c) => c.is_float_only() && c.float().abs() < f64::EPSILON,
+            #[cfg(feature = "set")]
+            InternalDiff::Set(s) => s.updated.is_empty(),
             InternalDiff::Unknown => true,
         }
     }
@@ -375,6 +390,11 @@ impl InternalDiff {
             }
             (InternalDiff::Map(a), InternalDiff::Map(b)) => Ok(InternalDiff::Map(a.compose(b))),
             (InternalDiff::Tree(a), InternalDiff::Tree(b)) => Ok(InternalDiff::Tree(a.compose(b))),
+            #[cfg(feature = "set")]
+            (InternalDiff::Set(mut a), InternalDiff::Set(b)) => {
+                a.updated.extend(b.updated);
+                Ok(InternalDiff::Set(a))
+            }
             (a, _) => Err(a),
         }
     }
@@ -399,6 +419,10 @@ impl Diff {
             }
             #[cfg(feature = "counter")]
             (Diff::Counter(a), Diff::Counter(b)) => *a += b,
+            #[cfg(feature = "set")]
+            (Diff::Set(a), Diff::Set(b)) => {
+                *a = std::mem::take(a).compose(b.clone());
+            }
             (_, _) => unreachable!(),
         }
     }
@@ -419,6 +443,8 @@ impl Diff {
             (Diff::Tree(a), Diff::Tree(b)) => Ok(Diff::Tree(a.compose(b))),
             #[cfg(feature = "counter")]
             (Diff::Counter(a), Diff::Counter(b)) => Ok(Diff::Counter(a + b)),
+            #[cfg(feature = "set")]
+            (Diff::Set(a), Diff::Set(b)) => Ok(Diff::Set(a.compose(b))),
             (a, _) => Err(a),
         }
     }
@@ -434,6 +460,10 @@ impl Diff {
             (Diff::Counter(_a), Diff::Counter(_b)) => {
                 // Counter operations commute; no transformation is needed.
             }
+            #[cfg(feature = "set")]
+            (Diff::Set(_a), Diff::Set(_b)) => {
+                // Set diffs are keyed by value, so they don't shift each other.
+            }
             _ => {}
         }
     }
@@ -446,6 +476,8 @@ impl Diff {
             Diff::Tree(t) => t.diff.is_empty(),
             #[cfg(feature = "counter")]
             Diff::Counter(c) => c.abs() < f64::EPSILON,
+            #[cfg(feature = "set")]
+            Diff::Set(s) => s.is_empty(),
             Diff::Unknown => true,
         }
     }
@@ -472,6 +504,8 @@ impl Diff {
             (Diff::Tree(a), Diff::Tree(b)) => Diff::Tree(a.extend(b.diff)),
             #[cfg(feature = "counter")]
             (Diff::Counter(a), Diff::Counter(b)) => Diff::Counter(a + b),
+            #[cfg(feature = "set")]
+            (Diff::Set(a), Diff::Set(b)) => Diff::Set(a.compose(b)),
             _ => unreachable!(),
         }
     }
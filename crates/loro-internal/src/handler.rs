@@ -20,25 +20,32 @@ use append_only_bytes::BytesSlice;
 use enum_as_inner::EnumAsInner;
 use generic_btree::rle::HasLength;
 use loro_common::{
-    ContainerID, ContainerType, IdFull, InternalString, LoroError, LoroResult, LoroValue, PeerID,
-    TreeID, ID,
+    ContainerID, ContainerType, IdFull, IdLp, InternalString, LoroError, LoroResult, LoroValue,
+    PeerID, TreeID, ID,
 };
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, cmp::Reverse, collections::BinaryHeap, fmt::Debug, ops::Deref, sync::Arc};
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::Debug,
+    ops::{Bound, Deref, Range, RangeBounds},
+    sync::Arc,
+};
 use tracing::{error, instrument};
 
 pub use crate::diff::diff_impl::UpdateOptions;
-pub use tree::TreeHandler;
+pub use tree::{FractionalIndexStats, TreeHandler};
 mod movable_list_apply_delta;
 mod tree;
 
 const REGULAR_CONTAINER_VALUE_ARG_ERROR: &str =
-    "Cannot use a LoroValue::Container as a regular value. To create a child container, use insert_container/set_container, or ensure_mergeable_* on maps for mergeable children";
+    "Cannot use a LoroValue::Container as a regular value. To create a child container, use insert_container/set_container, or ensure_mergeable_* on maps and lists for mergeable children";
 
 mod text_update;
 
-fn ensure_no_regular_container_value(value: &LoroValue) -> LoroResult<()> {
+pub(crate) fn ensure_no_regular_container_value(value: &LoroValue) -> LoroResult<()> {
     // Fast path: scalar values can never transitively hold a container, so we
     // skip the heap allocation + traversal below. This is the common case on
     // the per-op insert hot path (inserting numbers/strings/bools), where the
@@ -163,6 +170,9 @@ fn create_handler(inner: &BasicHandler, id: ContainerID) -> Handler {
 }
 
 fn value_to_value_or_handler(inner: &BasicHandler, value: LoroValue) -> ValueOrHandler {
+    // List elements that hold mergeable children surface as their containers. This is a no-op
+    // when `inner` is not a list.
+    let value = loro_common::translate_mergeable_list_marker_value(&inner.id, value);
     match value {
         LoroValue::Container(container_id) => {
             ValueOrHandler::Handler(create_handler(inner, container_id))
@@ -293,6 +303,14 @@ impl BasicHandler {
                 ContainerType::Counter => Handler::Counter(counter::CounterHandler {
                     inner: handler.into(),
                 }),
+                #[cfg(feature = "typed-counter")]
+                ContainerType::TypedCounter => Handler::Counter(counter::CounterHandler {
+                    inner: handler.into(),
+                }),
+                #[cfg(feature = "set")]
+                ContainerType::Set => Handler::Set(set::SetHandler {
+                    inner: handler.into(),
+                }),
                 ContainerType::Unknown(_) => unreachable!(),
             })
         }
@@ -325,6 +343,20 @@ impl BasicHandler {
     fn has_decoded_state(&self) -> bool {
         self.with_doc_state(|state| state.has_decoded_container_state(self.container_idx))
     }
+
+    fn create_key_index(&self, field: &str) {
+        self.with_doc_state(|state| state.create_list_key_index(self.container_idx, field.into()))
+    }
+
+    fn remove_key_index(&self) -> bool {
+        self.with_doc_state(|state| state.remove_list_key_index(self.container_idx))
+    }
+
+    fn find_by_key(&self, key: &LoroValue) -> LoroResult<Option<(usize, MapHandler)>> {
+        let found =
+            self.with_doc_state(|state| state.find_in_list_key_index(self.container_idx, key))?;
+        Ok(found.map(|(pos, id)| (pos, create_handler(self, id).into_map().unwrap())))
+    }
 }
 
 /// Flatten attributes that allow overlap
@@ -538,6 +570,17 @@ pub struct MapHandler {
     inner: MaybeDetached<FxHashMap<String, ValueOrHandler>>,
 }
 
+/// A value written to a map key concurrently with other values of the same key.
+///
+/// See [`MapHandler::get_conflicts`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapConflict {
+    /// The written value. `None` if the op deleted the key.
+    pub value: Option<LoroValue>,
+    /// The peer and lamport of the op that wrote the value.
+    pub idlp: IdLp,
+}
+
 impl HandlerTrait for MapHandler {
     fn is_attached(&self) -> bool {
         matches!(&self.inner, MaybeDetached::Attached(..))
@@ -970,6 +1013,8 @@ pub enum Handler {
     Tree(TreeHandler),
     #[cfg(feature = "counter")]
     Counter(counter::CounterHandler),
+    #[cfg(feature = "set")]
+    Set(set::SetHandler),
     Unknown(UnknownHandler),
 }
 
@@ -983,6 +1028,8 @@ impl HandlerTrait for Handler {
             Self::MovableList(x) => x.is_attached(),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.is_attached(),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.is_attached(),
             Self::Unknown(x) => x.is_attached(),
         }
     }
@@ -996,6 +1043,8 @@ impl HandlerTrait for Handler {
             Self::Tree(x) => x.attached_handler(),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.attached_handler(),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.attached_handler(),
             Self::Unknown(x) => x.attached_handler(),
         }
     }
@@ -1009,6 +1058,8 @@ impl HandlerTrait for Handler {
             Self::Tree(x) => x.get_value(),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.get_value(),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.get_value(),
             Self::Unknown(x) => x.get_value(),
         }
     }
@@ -1022,6 +1073,8 @@ impl HandlerTrait for Handler {
             Self::Tree(x) => x.get_deep_value(),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.get_deep_value(),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.get_deep_value(),
             Self::Unknown(x) => x.get_deep_value(),
         }
     }
@@ -1035,6 +1088,8 @@ impl HandlerTrait for Handler {
             Self::Tree(x) => x.kind(),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.kind(),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.kind(),
             Self::Unknown(x) => x.kind(),
         }
     }
@@ -1048,6 +1103,8 @@ impl HandlerTrait for Handler {
             Self::Tree(x) => x.to_handler(),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.to_handler(),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.to_handler(),
             Self::Unknown(x) => x.to_handler(),
         }
     }
@@ -1066,6 +1123,8 @@ impl HandlerTrait for Handler {
             Self::Tree(x) => Ok(Handler::Tree(x.attach(txn, parent, self_id)?)),
             #[cfg(feature = "counter")]
             Self::Counter(x) => Ok(Handler::Counter(x.attach(txn, parent, self_id)?)),
+            #[cfg(feature = "set")]
+            Self::Set(x) => Ok(Handler::Set(x.attach(txn, parent, self_id)?)),
             Self::Unknown(x) => Ok(Handler::Unknown(x.attach(txn, parent, self_id)?)),
         }
     }
@@ -1079,6 +1138,8 @@ impl HandlerTrait for Handler {
             Self::Tree(x) => x.get_attached().map(Handler::Tree),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.get_attached().map(Handler::Counter),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.get_attached().map(Handler::Set),
             Self::Unknown(x) => x.get_attached().map(Handler::Unknown),
         }
     }
@@ -1096,6 +1157,8 @@ impl HandlerTrait for Handler {
             Self::Tree(x) => x.doc(),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.doc(),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.doc(),
             Self::Unknown(x) => x.doc(),
         }
     }
@@ -1152,6 +1215,14 @@ impl Handler {
             ContainerType::Counter => Self::Counter(counter::CounterHandler {
                 inner: handler.into(),
             }),
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter => Self::Counter(counter::CounterHandler {
+                inner: handler.into(),
+            }),
+            #[cfg(feature = "set")]
+            ContainerType::Set => Self::Set(set::SetHandler {
+                inner: handler.into(),
+            }),
             ContainerType::Unknown(_) => Self::Unknown(UnknownHandler { inner: handler }),
         }
     }
@@ -1166,6 +1237,12 @@ impl Handler {
             ContainerType::MovableList => Self::MovableList(MovableListHandler::new_detached()),
             #[cfg(feature = "counter")]
             ContainerType::Counter => Self::Counter(counter::CounterHandler::new_detached()),
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter => {
+                Self::Counter(counter::CounterHandler::new_typed_detached())
+            }
+            #[cfg(feature = "set")]
+            ContainerType::Set => Self::Set(set::SetHandler::new_detached()),
             ContainerType::Unknown(_) => unreachable!(),
         }
     }
@@ -1179,6 +1256,8 @@ impl Handler {
             Self::MovableList(x) => x.id(),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.id(),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.id(),
             Self::Unknown(x) => x.id(),
         }
     }
@@ -1192,6 +1271,8 @@ impl Handler {
             Self::MovableList(x) => x.idx(),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.idx(),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.idx(),
             Self::Unknown(x) => x.idx(),
         }
     }
@@ -1204,7 +1285,9 @@ impl Handler {
             Self::Tree(_) => ContainerType::Tree,
             Self::MovableList(_) => ContainerType::MovableList,
             #[cfg(feature = "counter")]
-            Self::Counter(_) => ContainerType::Counter,
+            Self::Counter(x) => x.kind(),
+            #[cfg(feature = "set")]
+            Self::Set(_) => ContainerType::Set,
             Self::Unknown(x) => x.id().container_type(),
         }
     }
@@ -1218,6 +1301,8 @@ impl Handler {
             Self::Tree(x) => x.get_deep_value(),
             #[cfg(feature = "counter")]
             Self::Counter(x) => x.get_deep_value(),
+            #[cfg(feature = "set")]
+            Self::Set(x) => x.get_deep_value(),
             Self::Unknown(x) => x.get_deep_value(),
         }
     }
@@ -1414,7 +1499,24 @@ impl Handler {
                         ));
                     }
                 };
-                x.increment(delta)?;
+                x.apply_delta(delta)?;
+            }
+            #[cfg(feature = "set")]
+            Self::Set(x) => {
+                let diff = match diff {
+                    crate::event::Diff::Set(d) => d,
+                    _ => {
+                        return Err(LoroError::DecodeError(
+                            "Invalid diff type for set container".into(),
+                        ));
+                    }
+                };
+                for v in diff.removed {
+                    x.remove(&v)?;
+                }
+                for v in diff.added {
+                    x.add(v)?;
+                }
             }
             Self::Unknown(_) => {
                 // do nothing
@@ -1433,6 +1535,8 @@ impl Handler {
             Handler::Tree(tree_handler) => tree_handler.clear(),
             #[cfg(feature = "counter")]
             Handler::Counter(counter_handler) => counter_handler.clear(),
+            #[cfg(feature = "set")]
+            Handler::Set(set_handler) => set_handler.clear(),
             Handler::Unknown(_unknown_handler) => Ok(()),
         }
     }
@@ -3021,6 +3125,64 @@ fn text_slice(s: &str, start: usize, end: usize, pos_type: PosType) -> LoroResul
     Ok(s[start..end].to_string())
 }
 
+/// Shared implementation for the `ensure_mergeable_*` methods of lists and movable lists.
+///
+/// The element at the first list marker carrying `key` is the child. Concurrent first calls on
+/// different peers each insert a marker; they all resolve to the same deterministic cid, so the
+/// child state merges, and later calls delete the same-kind duplicates after the first one.
+///
+/// Returns [`LoroError::ArgErr`] if the first marker for `key` names a different container kind.
+fn ensure_mergeable_list_child<C: HandlerTrait>(
+    parent: &BasicHandler,
+    key: &str,
+    child: C,
+    push: impl FnOnce(LoroValue) -> LoroResult<()>,
+    mut delete: impl FnMut(usize) -> LoroResult<()>,
+) -> LoroResult<C> {
+    let kind = child.kind();
+    let values = parent.with_doc_state(|state| state.get_list_values(parent.container_idx));
+    let mut occupants = values.iter().enumerate().filter_map(|(i, v)| {
+        loro_common::parse_mergeable_list_marker(&parent.id, v)
+            .filter(|(k, _)| k == key)
+            .map(|(_, ty)| (i, ty))
+    });
+
+    match occupants.next() {
+        None => push(loro_common::mergeable_list_marker(&parent.id, key, kind))?,
+        Some((_, existing)) if existing != kind => {
+            return Err(LoroError::ArgErr(
+                format!(
+                    "Cannot create a mergeable {kind} for key {key:?}: the list already holds a mergeable {existing} for it"
+                )
+                .into_boxed_str(),
+            ));
+        }
+        Some(_) => {
+            let duplicates: Vec<usize> = occupants
+                .filter(|(_, ty)| *ty == kind)
+                .map(|(i, _)| i)
+                .collect();
+            // Delete the duplicates concurrent ensures left behind, from the back so the
+            // remaining indexes stay valid.
+            for pos in duplicates.into_iter().rev() {
+                delete(pos)?;
+            }
+        }
+    }
+
+    let cid = ContainerID::new_mergeable(&parent.id, key, kind);
+    C::from_handler(create_handler(parent, cid.clone())).ok_or_else(|| {
+        LoroError::ArgErr(
+            format!(
+                "Expected value type {} but found {}",
+                kind,
+                cid.container_type()
+            )
+            .into_boxed_str(),
+        )
+    })
+}
+
 impl ListHandler {
     /// Create a new container that is detached from the document.
     /// The edits on a detached container will not be persisted.
@@ -3100,6 +3262,160 @@ impl ListHandler {
         self.insert_with_txn(txn, pos, v)
     }
 
+    /// Get the values in `range`. Child containers are returned as handlers.
+    pub fn slice(&self, range: Range<usize>) -> LoroResult<Vec<ValueOrHandler>> {
+        let len = self.len();
+        if range.start > range.end {
+            return Err(LoroError::EndIndexLessThanStartIndex {
+                start: range.start,
+                end: range.end,
+            });
+        }
+        if range.end > len {
+            return Err(LoroError::OutOfBound {
+                pos: range.end,
+                info: format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+                len,
+            });
+        }
+
+        match &self.inner {
+            MaybeDetached::Detached(l) => Ok(l.lock().value[range].to_vec()),
+            MaybeDetached::Attached(inner) => {
+                let values = inner.with_doc_state(|state| {
+                    range
+                        .map(|i| state.get_list_value_at(inner.container_idx, i).unwrap())
+                        .collect::<Vec<_>>()
+                });
+                Ok(values
+                    .into_iter()
+                    .map(|v| value_to_value_or_handler(inner, v))
+                    .collect())
+            }
+        }
+    }
+
+    /// Insert all `values` at `pos`.
+    ///
+    /// They are recorded as a single insert op, which is much cheaper than inserting
+    /// them one by one.
+    pub fn insert_many(&self, pos: usize, values: Vec<LoroValue>) -> LoroResult<()> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut list = l.lock();
+                if pos > list.value.len() {
+                    return Err(LoroError::OutOfBound {
+                        pos,
+                        info: format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+                        len: list.value.len(),
+                    });
+                }
+                for v in values.iter() {
+                    ensure_no_regular_container_value(v)?;
+                }
+                list.value
+                    .splice(pos..pos, values.into_iter().map(ValueOrHandler::Value));
+                Ok(())
+            }
+            MaybeDetached::Attached(a) => {
+                a.with_txn(|txn| self.insert_many_with_txn(txn, pos, values))
+            }
+        }
+    }
+
+    pub fn insert_many_with_txn(
+        &self,
+        txn: &mut Transaction,
+        pos: usize,
+        values: Vec<LoroValue>,
+    ) -> LoroResult<()> {
+        if pos > self.len() {
+            return Err(LoroError::OutOfBound {
+                pos,
+                info: format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+                len: self.len(),
+            });
+        }
+        for v in values.iter() {
+            ensure_no_regular_container_value(v)?;
+        }
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        let inner = self.inner.try_attached_state()?;
+        let len = values.len() as u32;
+        txn.apply_local_op(
+            inner.container_idx,
+            crate::op::RawOpContent::List(crate::container::list::list_op::ListOp::Insert {
+                slice: ListSlice::RawData(Cow::Owned(values)),
+                pos,
+            }),
+            EventHint::InsertList { len, pos },
+            &inner.doc,
+        )
+    }
+
+    /// Delete `len` values from `pos` and insert `values` there, in one transaction.
+    ///
+    /// Returns the deleted values.
+    pub fn splice(
+        &self,
+        pos: usize,
+        len: usize,
+        values: Vec<LoroValue>,
+    ) -> LoroResult<Vec<LoroValue>> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut list = l.lock();
+                let end = checked_range_end(
+                    pos,
+                    len,
+                    list.value.len(),
+                    format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+                )?;
+                for v in values.iter() {
+                    ensure_no_regular_container_value(v)?;
+                }
+                Ok(list
+                    .value
+                    .splice(pos..end, values.into_iter().map(ValueOrHandler::Value))
+                    .map(|v| v.to_value())
+                    .collect())
+            }
+            MaybeDetached::Attached(a) => {
+                a.with_txn(|txn| self.splice_with_txn(txn, pos, len, values))
+            }
+        }
+    }
+
+    pub fn splice_with_txn(
+        &self,
+        txn: &mut Transaction,
+        pos: usize,
+        len: usize,
+        values: Vec<LoroValue>,
+    ) -> LoroResult<Vec<LoroValue>> {
+        let end = checked_range_end(
+            pos,
+            len,
+            self.len(),
+            format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+        )?;
+        for v in values.iter() {
+            ensure_no_regular_container_value(v)?;
+        }
+
+        let deleted = self
+            .slice(pos..end)?
+            .into_iter()
+            .map(|v| v.to_value())
+            .collect();
+        self.delete_with_txn(txn, pos, len)?;
+        self.insert_many_with_txn(txn, pos, values)?;
+        Ok(deleted)
+    }
+
     pub fn pop(&self) -> LoroResult<Option<LoroValue>> {
         match &self.inner {
             MaybeDetached::Detached(l) => {
@@ -3291,11 +3607,97 @@ impl ListHandler {
         }))
     }
 
+    fn ensure_mergeable_container<C: HandlerTrait>(&self, key: &str, child: C) -> LoroResult<C> {
+        let MaybeDetached::Attached(parent) = &self.inner else {
+            return Err(LoroError::MisuseDetachedContainer {
+                method: "ensure_mergeable_container",
+            });
+        };
+        ensure_mergeable_list_child(
+            parent,
+            key,
+            child,
+            |marker| self.push(marker),
+            |pos| self.delete(pos, 1),
+        )
+    }
+
+    #[cfg(feature = "counter")]
+    /// Ensure a mergeable Counter child keyed by `key` exists in this list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_counter(&self, key: &str) -> LoroResult<counter::CounterHandler> {
+        self.ensure_mergeable_container(key, counter::CounterHandler::new_detached())
+    }
+
+    /// Ensure a mergeable Map child keyed by `key` exists in this list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_map(&self, key: &str) -> LoroResult<MapHandler> {
+        self.ensure_mergeable_container(key, MapHandler::new_detached())
+    }
+
+    /// Ensure a mergeable List child keyed by `key` exists in this list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_list(&self, key: &str) -> LoroResult<ListHandler> {
+        self.ensure_mergeable_container(key, ListHandler::new_detached())
+    }
+
+    /// Ensure a mergeable MovableList child keyed by `key` exists in this list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_movable_list(&self, key: &str) -> LoroResult<MovableListHandler> {
+        self.ensure_mergeable_container(key, MovableListHandler::new_detached())
+    }
+
+    /// Ensure a mergeable Text child keyed by `key` exists in this list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_text(&self, key: &str) -> LoroResult<TextHandler> {
+        self.ensure_mergeable_container(key, TextHandler::new_detached())
+    }
+
+    /// Ensure a mergeable Tree child keyed by `key` exists in this list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_tree(&self, key: &str) -> LoroResult<TreeHandler> {
+        self.ensure_mergeable_container(key, TreeHandler::new_detached())
+    }
+
     pub fn get(&self, index: usize) -> Option<LoroValue> {
         match &self.inner {
             MaybeDetached::Detached(l) => l.lock().value.get(index).map(|x| x.to_value()),
             MaybeDetached::Attached(a) => {
-                a.with_doc_state(|state| state.get_list_value_at(a.container_idx, index))
+                let value =
+                    a.with_doc_state(|state| state.get_list_value_at(a.container_idx, index))?;
+                Some(loro_common::translate_mergeable_list_marker_value(
+                    &a.id, value,
+                ))
             }
         }
     }
@@ -3341,6 +3743,37 @@ impl ListHandler {
         }
     }
 
+    /// Index the child maps of this list by the value of their `field`, so that
+    /// [`Self::find_by_key`] doesn't need to scan the list.
+    ///
+    /// The index is kept up to date on local edits, imports and checkouts. It's
+    /// not persisted, so it needs to be created again after the doc is reloaded.
+    pub fn create_key_index(&self, field: &str) -> LoroResult<()> {
+        let inner = self.inner.try_attached_state()?;
+        inner.create_key_index(field);
+        Ok(())
+    }
+
+    /// Remove the index created by [`Self::create_key_index`].
+    ///
+    /// Returns whether there was an index.
+    pub fn remove_key_index(&self) -> bool {
+        match &self.inner {
+            MaybeDetached::Detached(_) => false,
+            MaybeDetached::Attached(a) => a.remove_key_index(),
+        }
+    }
+
+    /// Find the child map whose indexed field equals `key`, and return its index
+    /// and handler. If several children share the key, the first one is returned.
+    /// The key is found in O(1) and its index in O(log n).
+    ///
+    /// It returns an error if [`Self::create_key_index`] has not been called.
+    pub fn find_by_key(&self, key: &LoroValue) -> LoroResult<Option<(usize, MapHandler)>> {
+        let inner = self.inner.try_attached_state()?;
+        inner.find_by_key(key)
+    }
+
     pub fn get_cursor(&self, pos: usize, side: Side) -> Option<Cursor> {
         match &self.inner {
             MaybeDetached::Detached(_) => None,
@@ -3617,42 +4050,258 @@ impl MovableListHandler {
         )
     }
 
-    pub fn push(&self, v: LoroValue) -> LoroResult<()> {
+    /// Move the elements in `from` so that they start at position `to`.
+    ///
+    /// After this op, the moved elements are at `to..to + from.len()`, in their original order.
+    pub fn mov_range(&self, from: Range<usize>, to: usize) -> LoroResult<()> {
+        let len = self.len();
+        if from.start > from.end {
+            return Err(LoroError::EndIndexLessThanStartIndex {
+                start: from.start,
+                end: from.end,
+            });
+        }
+        if from.end > len {
+            return Err(LoroError::OutOfBound {
+                pos: from.end,
+                info: format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+                len,
+            });
+        }
+        checked_range_end(
+            to,
+            from.len(),
+            len,
+            format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+        )?;
+
         match &self.inner {
             MaybeDetached::Detached(d) => {
                 let mut d = d.lock();
-                d.value.push(v.into());
+                let moved: Vec<_> = d.value.drain(from).collect();
+                d.value.splice(to..to, moved);
                 Ok(())
             }
-            MaybeDetached::Attached(a) => a.with_txn(|txn| self.push_with_txn(txn, v)),
+            MaybeDetached::Attached(a) => a.with_txn(|txn| self.mov_range_with_txn(txn, from, to)),
         }
     }
 
-    pub fn push_with_txn(&self, txn: &mut Transaction, v: LoroValue) -> LoroResult<()> {
-        let pos = self.len();
-        self.insert_with_txn(txn, pos, v)
+    pub fn mov_range_with_txn(
+        &self,
+        txn: &mut Transaction,
+        from: Range<usize>,
+        to: usize,
+    ) -> LoroResult<()> {
+        // Moving the elements one by one keeps the untouched ones in place, so the
+        // i-th moved element is always found at `from.start + i`.
+        if to < from.start {
+            for i in 0..from.len() {
+                self.move_with_txn(txn, from.start + i, to + i)?;
+            }
+        } else {
+            for i in (0..from.len()).rev() {
+                self.move_with_txn(txn, from.start + i, to + i)?;
+            }
+        }
+        Ok(())
     }
 
-    pub fn pop_(&self) -> LoroResult<Option<ValueOrHandler>> {
+    pub fn push(&self, v: LoroValue) -> LoroResult<()> {
         match &self.inner {
             MaybeDetached::Detached(d) => {
                 let mut d = d.lock();
-                Ok(d.value.pop())
-            }
-            MaybeDetached::Attached(a) => {
-                if self.is_empty() {
-                    return Ok(None);
-                }
-                let last = self.len() - 1;
-                let ans = self.get_(last);
-                a.with_txn(|txn| self.pop_with_txn(txn))?;
-                Ok(ans)
+                d.value.push(v.into());
+                Ok(())
             }
+            MaybeDetached::Attached(a) => a.with_txn(|txn| self.push_with_txn(txn, v)),
         }
     }
 
-    pub fn pop(&self) -> LoroResult<Option<LoroValue>> {
-        match &self.inner {
+    pub fn push_with_txn(&self, txn: &mut Transaction, v: LoroValue) -> LoroResult<()> {
+        let pos = self.len();
+        self.insert_with_txn(txn, pos, v)
+    }
+
+    /// Get the values in `range`. Child containers are returned as handlers.
+    pub fn slice(&self, range: Range<usize>) -> LoroResult<Vec<ValueOrHandler>> {
+        let len = self.len();
+        if range.start > range.end {
+            return Err(LoroError::EndIndexLessThanStartIndex {
+                start: range.start,
+                end: range.end,
+            });
+        }
+        if range.end > len {
+            return Err(LoroError::OutOfBound {
+                pos: range.end,
+                info: format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+                len,
+            });
+        }
+
+        match &self.inner {
+            MaybeDetached::Detached(l) => Ok(l.lock().value[range].to_vec()),
+            MaybeDetached::Attached(inner) => {
+                let values = inner.with_doc_state(|state| {
+                    range
+                        .map(|i| state.get_list_value_at(inner.container_idx, i).unwrap())
+                        .collect::<Vec<_>>()
+                });
+                Ok(values
+                    .into_iter()
+                    .map(|v| value_to_value_or_handler(inner, v))
+                    .collect())
+            }
+        }
+    }
+
+    /// Insert all `values` at `pos`.
+    ///
+    /// They are recorded as a single insert op, which is much cheaper than inserting
+    /// them one by one.
+    pub fn insert_many(&self, pos: usize, values: Vec<LoroValue>) -> LoroResult<()> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut list = l.lock();
+                if pos > list.value.len() {
+                    return Err(LoroError::OutOfBound {
+                        pos,
+                        info: format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+                        len: list.value.len(),
+                    });
+                }
+                for v in values.iter() {
+                    ensure_no_regular_container_value(v)?;
+                }
+                list.value
+                    .splice(pos..pos, values.into_iter().map(ValueOrHandler::Value));
+                Ok(())
+            }
+            MaybeDetached::Attached(a) => {
+                a.with_txn(|txn| self.insert_many_with_txn(txn, pos, values))
+            }
+        }
+    }
+
+    pub fn insert_many_with_txn(
+        &self,
+        txn: &mut Transaction,
+        pos: usize,
+        values: Vec<LoroValue>,
+    ) -> LoroResult<()> {
+        if pos > self.len() {
+            return Err(LoroError::OutOfBound {
+                pos,
+                info: format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+                len: self.len(),
+            });
+        }
+        for v in values.iter() {
+            ensure_no_regular_container_value(v)?;
+        }
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        let op_index = self.with_state(|state| {
+            let list = state.as_movable_list_state().unwrap();
+            Ok(list
+                .convert_index(pos, IndexType::ForUser, IndexType::ForOp)
+                .unwrap())
+        })?;
+        let inner = self.inner.try_attached_state()?;
+        let len = values.len() as u32;
+        txn.apply_local_op(
+            inner.container_idx,
+            crate::op::RawOpContent::List(crate::container::list::list_op::ListOp::Insert {
+                slice: ListSlice::RawData(Cow::Owned(values)),
+                pos: op_index,
+            }),
+            EventHint::InsertList { len, pos },
+            &inner.doc,
+        )
+    }
+
+    /// Delete `len` values from `pos` and insert `values` there, in one transaction.
+    ///
+    /// Returns the deleted values.
+    pub fn splice(
+        &self,
+        pos: usize,
+        len: usize,
+        values: Vec<LoroValue>,
+    ) -> LoroResult<Vec<LoroValue>> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut list = l.lock();
+                let end = checked_range_end(
+                    pos,
+                    len,
+                    list.value.len(),
+                    format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+                )?;
+                for v in values.iter() {
+                    ensure_no_regular_container_value(v)?;
+                }
+                Ok(list
+                    .value
+                    .splice(pos..end, values.into_iter().map(ValueOrHandler::Value))
+                    .map(|v| v.to_value())
+                    .collect())
+            }
+            MaybeDetached::Attached(a) => {
+                a.with_txn(|txn| self.splice_with_txn(txn, pos, len, values))
+            }
+        }
+    }
+
+    pub fn splice_with_txn(
+        &self,
+        txn: &mut Transaction,
+        pos: usize,
+        len: usize,
+        values: Vec<LoroValue>,
+    ) -> LoroResult<Vec<LoroValue>> {
+        let end = checked_range_end(
+            pos,
+            len,
+            self.len(),
+            format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+        )?;
+        for v in values.iter() {
+            ensure_no_regular_container_value(v)?;
+        }
+
+        let deleted = self
+            .slice(pos..end)?
+            .into_iter()
+            .map(|v| v.to_value())
+            .collect();
+        self.delete_with_txn(txn, pos, len)?;
+        self.insert_many_with_txn(txn, pos, values)?;
+        Ok(deleted)
+    }
+
+    pub fn pop_(&self) -> LoroResult<Option<ValueOrHandler>> {
+        match &self.inner {
+            MaybeDetached::Detached(d) => {
+                let mut d = d.lock();
+                Ok(d.value.pop())
+            }
+            MaybeDetached::Attached(a) => {
+                if self.is_empty() {
+                    return Ok(None);
+                }
+                let last = self.len() - 1;
+                let ans = self.get_(last);
+                a.with_txn(|txn| self.pop_with_txn(txn))?;
+                Ok(ans)
+            }
+        }
+    }
+
+    pub fn pop(&self) -> LoroResult<Option<LoroValue>> {
+        match &self.inner {
             MaybeDetached::Detached(a) => {
                 let mut a = a.lock();
                 Ok(a.value.pop().map(|x| x.to_value()))
@@ -3986,6 +4635,88 @@ impl MovableListHandler {
             .get_container_deep_value_with_id(inner.container_idx, None)
     }
 
+    fn ensure_mergeable_container<C: HandlerTrait>(&self, key: &str, child: C) -> LoroResult<C> {
+        let MaybeDetached::Attached(parent) = &self.inner else {
+            return Err(LoroError::MisuseDetachedContainer {
+                method: "ensure_mergeable_container",
+            });
+        };
+        ensure_mergeable_list_child(
+            parent,
+            key,
+            child,
+            |marker| self.push(marker),
+            |pos| self.delete(pos, 1),
+        )
+    }
+
+    #[cfg(feature = "counter")]
+    /// Ensure a mergeable Counter child keyed by `key` exists in this movable list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached movable list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_counter(&self, key: &str) -> LoroResult<counter::CounterHandler> {
+        self.ensure_mergeable_container(key, counter::CounterHandler::new_detached())
+    }
+
+    /// Ensure a mergeable Map child keyed by `key` exists in this movable list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached movable list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_map(&self, key: &str) -> LoroResult<MapHandler> {
+        self.ensure_mergeable_container(key, MapHandler::new_detached())
+    }
+
+    /// Ensure a mergeable List child keyed by `key` exists in this movable list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached movable list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_list(&self, key: &str) -> LoroResult<ListHandler> {
+        self.ensure_mergeable_container(key, ListHandler::new_detached())
+    }
+
+    /// Ensure a mergeable MovableList child keyed by `key` exists in this movable list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached movable list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_movable_list(&self, key: &str) -> LoroResult<MovableListHandler> {
+        self.ensure_mergeable_container(key, MovableListHandler::new_detached())
+    }
+
+    /// Ensure a mergeable Text child keyed by `key` exists in this movable list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached movable list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_text(&self, key: &str) -> LoroResult<TextHandler> {
+        self.ensure_mergeable_container(key, TextHandler::new_detached())
+    }
+
+    /// Ensure a mergeable Tree child keyed by `key` exists in this movable list and return its handler.
+    ///
+    /// The child is stored as a list element, appended on first use. Peers that call this with
+    /// the same `key` get the same container, even if they do so concurrently.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] when called on a detached movable list.
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another kind.
+    pub fn ensure_mergeable_tree(&self, key: &str) -> LoroResult<TreeHandler> {
+        self.ensure_mergeable_container(key, TreeHandler::new_detached())
+    }
+
     pub fn get(&self, index: usize) -> Option<LoroValue> {
         match &self.inner {
             MaybeDetached::Detached(d) => {
@@ -3993,7 +4724,11 @@ impl MovableListHandler {
                 d.value.get(index).map(|v| v.to_value())
             }
             MaybeDetached::Attached(a) => {
-                a.with_doc_state(|state| state.get_list_value_at(a.container_idx, index))
+                let value =
+                    a.with_doc_state(|state| state.get_list_value_at(a.container_idx, index))?;
+                Some(loro_common::translate_mergeable_list_marker_value(
+                    &a.id, value,
+                ))
             }
         }
     }
@@ -4059,6 +4794,37 @@ impl MovableListHandler {
         }
     }
 
+    /// Index the child maps of this list by the value of their `field`, so that
+    /// [`Self::find_by_key`] doesn't need to scan the list.
+    ///
+    /// The index is kept up to date on local edits, imports and checkouts. It's
+    /// not persisted, so it needs to be created again after the doc is reloaded.
+    pub fn create_key_index(&self, field: &str) -> LoroResult<()> {
+        let inner = self.inner.try_attached_state()?;
+        inner.create_key_index(field);
+        Ok(())
+    }
+
+    /// Remove the index created by [`Self::create_key_index`].
+    ///
+    /// Returns whether there was an index.
+    pub fn remove_key_index(&self) -> bool {
+        match &self.inner {
+            MaybeDetached::Detached(_) => false,
+            MaybeDetached::Attached(a) => a.remove_key_index(),
+        }
+    }
+
+    /// Find the child map whose indexed field equals `key`, and return its index
+    /// and handler. If several children share the key, the first one is returned.
+    /// The key is found in O(1) and its index in O(log n).
+    ///
+    /// It returns an error if [`Self::create_key_index`] has not been called.
+    pub fn find_by_key(&self, key: &LoroValue) -> LoroResult<Option<(usize, MapHandler)>> {
+        let inner = self.inner.try_attached_state()?;
+        inner.find_by_key(key)
+    }
+
     pub fn get_cursor(&self, pos: usize, side: Side) -> Option<Cursor> {
         match &self.inner {
             MaybeDetached::Detached(_) => None,
@@ -4148,6 +4914,20 @@ impl MovableListHandler {
         }
     }
 
+    /// Get the position of the child container `id`, or `None` if it's not in the list.
+    /// The position is found in O(log n).
+    pub fn get_index_of_child(&self, id: &ContainerID) -> Option<usize> {
+        match &self.inner {
+            MaybeDetached::Detached(_) => None,
+            MaybeDetached::Attached(a) => a.with_state(|state| {
+                state
+                    .as_movable_list_state()
+                    .unwrap()
+                    .get_index_of_child(id)
+            }),
+        }
+    }
+
     pub fn get_last_mover_at(&self, pos: usize) -> Option<PeerID> {
         match &self.inner {
             MaybeDetached::Detached(_) => None,
@@ -4198,6 +4978,15 @@ impl MapHandler {
         }
     }
 
+    /// Set `key` to `value` to resolve the conflicts of the key.
+    ///
+    /// Unlike [`MapHandler::insert`], it always creates an op even if `value` equals the current
+    /// value, so that every conflicting value seen by this peer is overwritten. It syncs like a
+    /// normal map op.
+    pub fn resolve(&self, key: &str, value: impl Into<LoroValue>) -> LoroResult<()> {
+        self.insert_without_skipping(key, value)
+    }
+
     /// This method will insert the value even if the same value is already in the given entry.
     fn insert_without_skipping(&self, key: &str, value: impl Into<LoroValue>) -> LoroResult<()> {
         match &self.inner {
@@ -4694,91 +5483,414 @@ impl MapHandler {
         values.into_iter()
     }
 
-    pub fn get_last_editor(&self, key: &str) -> Option<PeerID> {
+    /// Get the entries whose keys are in `range`, sorted by key.
+    ///
+    /// Keys are compared as strings, so numeric keys should be fixed-width (e.g. zero-padded)
+    /// to keep their numeric order. An attached map finds the range in O(log n).
+    pub fn range<K: AsRef<str>>(
+        &self,
+        range: impl RangeBounds<K>,
+    ) -> Vec<(String, ValueOrHandler)> {
         match &self.inner {
-            MaybeDetached::Detached(_) => None,
-            MaybeDetached::Attached(a) => a.with_state(|state| {
-                let m = state.as_map_state().unwrap();
-                m.get_last_edit_peer(key)
-            }),
-        }
-    }
-}
+            MaybeDetached::Detached(m) => {
+                fn str_bound<K: AsRef<str>>(bound: Bound<&K>) -> Bound<&str> {
+                    match bound {
+                        Bound::Included(key) => Bound::Included(key.as_ref()),
+                        Bound::Excluded(key) => Bound::Excluded(key.as_ref()),
+                        Bound::Unbounded => Bound::Unbounded,
+                    }
+                }
 
-fn with_txn<R>(doc: &LoroDoc, f: impl FnOnce(&mut Transaction) -> LoroResult<R>) -> LoroResult<R> {
-    let txn = &doc.txn;
-    let mut txn = txn.lock();
-    loop {
-        if let Some(txn) = &mut *txn {
-            return f(txn);
-        } else if cfg!(target_arch = "wasm32") || !doc.can_edit() {
-            return Err(LoroError::AutoCommitNotStarted);
-        } else {
-            drop(txn);
-            #[cfg(loom)]
-            loom::thread::yield_now();
-            doc.start_auto_commit();
-            txn = doc.txn.lock();
+                let bounds: (Bound<&str>, Bound<&str>) =
+                    (str_bound(range.start_bound()), str_bound(range.end_bound()));
+                let m = m.lock();
+                let mut ans: Vec<_> = m
+                    .value
+                    .iter()
+                    .filter(|(k, _)| RangeBounds::<str>::contains(&bounds, k.as_str()))
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                ans.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+                ans
+            }
+            MaybeDetached::Attached(a) => {
+                let entries: Vec<(InternalString, LoroValue)> = a.with_state(|state| {
+                    let m = state.as_map_state().unwrap();
+                    m.range(range)
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect()
+                });
+                entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let value = loro_common::translate_mergeable_marker_value(
+                            &a.id,
+                            key.as_ref(),
+                            value,
+                        );
+                        (key.to_string(), value_to_value_or_handler(a, value))
+                    })
+                    .collect()
+            }
         }
     }
-}
 
-#[cfg(feature = "counter")]
+    /// Get the entry with the smallest key.
+    pub fn first(&self) -> Option<(String, ValueOrHandler)> {
+        match &self.inner {
+            MaybeDetached::Detached(m) => {
+                let m = m.lock();
+                m.value
+                    .iter()
+                    .min_by(|(a, _), (b, _)| a.cmp(b))
+                    .map(|(k, v)| (k.clone(), v.clone()))
+            }
+            MaybeDetached::Attached(a) => {
+                let (key, value) = a.with_state(|state| {
+                    let m = state.as_map_state().unwrap();
+                    m.first().map(|(k, v)| (k.clone(), v.clone()))
+                })?;
+                let value =
+                    loro_common::translate_mergeable_marker_value(&a.id, key.as_ref(), value);
+                Some((key.to_string(), value_to_value_or_handler(a, value)))
+            }
+        }
+    }
+
+    /// Get the entry with the largest key.
+    pub fn last(&self) -> Option<(String, ValueOrHandler)> {
+        match &self.inner {
+            MaybeDetached::Detached(m) => {
+                let m = m.lock();
+                m.value
+                    .iter()
+                    .max_by(|(a, _), (b, _)| a.cmp(b))
+                    .map(|(k, v)| (k.clone(), v.clone()))
+            }
+            MaybeDetached::Attached(a) => {
+                let (key, value) = a.with_state(|state| {
+                    let m = state.as_map_state().unwrap();
+                    m.last().map(|(k, v)| (k.clone(), v.clone()))
+                })?;
+                let value =
+                    loro_common::translate_mergeable_marker_value(&a.id, key.as_ref(), value);
+                Some((key.to_string(), value_to_value_or_handler(a, value)))
+            }
+        }
+    }
+
+    pub fn get_last_editor(&self, key: &str) -> Option<PeerID> {
+        match &self.inner {
+            MaybeDetached::Detached(_) => None,
+            MaybeDetached::Attached(a) => a.with_state(|state| {
+                let m = state.as_map_state().unwrap();
+                m.get_last_edit_peer(key)
+            }),
+        }
+    }
+
+    /// Get the values written to `key` concurrently, as a multi-value register would keep them.
+    ///
+    /// The map keeps only the last-writer-wins value of a key, but the values it dropped are still
+    /// in the history. This returns every value written by an op that is not overwritten by
+    /// another op on the same key at the current version, sorted from the LWW winner (the current
+    /// value) to the losers. It has more than one entry only if there are unresolved concurrent
+    /// writes. The list is empty if the key was never set.
+    pub fn get_conflicts(&self, key: &str) -> LoroResult<Vec<MapConflict>> {
+        let inner = self.inner.try_attached_state()?;
+        let idx = inner.container_idx;
+        let oplog = inner.doc.oplog().lock();
+        let mut state = inner.doc.state.lock();
+        let vv = oplog.dag().frontiers_to_vv(state.frontiers()).unwrap();
+        let current = state.with_state_mut(idx, |s| {
+            s.as_map_state().unwrap().get_map_value(key).cloned()
+        });
+        drop(state);
+
+        let key: InternalString = key.into();
+        let ops = oplog.with_history_cache(|h| {
+            h.get_checkout_index()
+                .map
+                .get_key_concurrent_ops_at_vv(idx, &key, &vv, &oplog)
+        });
+        if let Some(current) = current {
+            // The state keeps the id of the older op when a newer op writes the same value,
+            // so only a state value newer than every op in the history is a pending write.
+            if ops
+                .first()
+                .is_none_or(|x| (current.lamp, current.peer) > (x.lamport, x.peer))
+            {
+                // The current value comes from the pending transaction, which overwrites all
+                // the ops in the history.
+                return Ok(vec![MapConflict {
+                    value: current.value,
+                    idlp: IdLp::new(current.peer, current.lamp),
+                }]);
+            }
+        }
+
+        Ok(ops
+            .into_iter()
+            .map(|x| MapConflict {
+                value: x.value,
+                idlp: IdLp::new(x.peer, x.lamport),
+            })
+            .collect())
+    }
+}
+
+pub(crate) fn with_txn<R>(
+    doc: &LoroDoc,
+    f: impl FnOnce(&mut Transaction) -> LoroResult<R>,
+) -> LoroResult<R> {
+    let txn = &doc.txn;
+    let mut txn = txn.lock();
+    loop {
+        if let Some(txn) = &mut *txn {
+            return f(txn);
+        } else if cfg!(target_arch = "wasm32") || !doc.can_edit() {
+            return Err(LoroError::AutoCommitNotStarted);
+        } else {
+            drop(txn);
+            #[cfg(loom)]
+            loom::thread::yield_now();
+            doc.start_auto_commit();
+            txn = doc.txn.lock();
+        }
+    }
+}
+
+#[cfg(feature = "counter")]
 pub mod counter {
 
-    use loro_common::LoroResult;
+    use loro_common::{ContainerType, LoroError, LoroResult, LoroValue};
 
     use crate::{
+        container::counter::{CounterKind, CounterOp, CounterSummary},
         txn::{EventHint, Transaction},
         HandlerTrait,
     };
 
     use super::{create_handler, Handler, MaybeDetached};
 
+    /// A handler of a counter or a typed counter.
+    ///
+    /// A typed counter can be a float counter, an int counter, a bounded counter
+    /// or a max register, see [`CounterKind`]. It takes the kind of the ops
+    /// applied to it, and the ops of other kinds are folded into that kind.
     #[derive(Clone)]
     pub struct CounterHandler {
-        pub(super) inner: MaybeDetached<f64>,
+        pub(super) inner: MaybeDetached<DetachedCounter>,
+    }
+
+    #[derive(Debug)]
+    pub(super) struct DetachedCounter {
+        summary: CounterSummary,
+        kind: ContainerType,
     }
 
     impl CounterHandler {
         pub fn new_detached() -> Self {
             Self {
-                inner: MaybeDetached::new_detached(0.),
+                inner: MaybeDetached::new_detached(DetachedCounter {
+                    summary: CounterSummary::default(),
+                    kind: ContainerType::Counter,
+                }),
+            }
+        }
+
+        /// Create a detached typed counter.
+        #[cfg(feature = "typed-counter")]
+        pub fn new_typed_detached() -> Self {
+            Self {
+                inner: MaybeDetached::new_detached(DetachedCounter {
+                    summary: CounterSummary::default(),
+                    kind: ContainerType::TypedCounter,
+                }),
             }
         }
 
         pub fn increment(&self, n: f64) -> LoroResult<()> {
+            self.apply_op(CounterOp::Float(n))
+        }
+
+        pub fn decrement(&self, n: f64) -> LoroResult<()> {
+            self.apply_op(CounterOp::Float(-n))
+        }
+
+        /// Increment an int counter by `n`.
+        #[cfg(feature = "typed-counter")]
+        pub fn increment_int(&self, n: i64) -> LoroResult<()> {
+            self.apply_op(CounterOp::Int(n))
+        }
+
+        /// Decrement an int counter by `n`.
+        #[cfg(feature = "typed-counter")]
+        pub fn decrement_int(&self, n: i64) -> LoroResult<()> {
+            self.apply_op(CounterOp::Int(n.wrapping_neg()))
+        }
+
+        /// Increment a bounded counter by `n`.
+        ///
+        /// The value of a bounded counter never shows below the largest
+        /// `lower_bound` used by its ops. If concurrent decrements take the sum
+        /// below it, the later increments pay the overdraft back first.
+        #[cfg(feature = "typed-counter")]
+        pub fn increment_bounded(&self, n: i64, lower_bound: i64) -> LoroResult<()> {
+            self.apply_op(CounterOp::Bounded {
+                delta: n,
+                lower_bound,
+            })
+        }
+
+        /// Decrement a bounded counter by at most `n`, without going below the
+        /// lower bound.
+        ///
+        /// Returns the amount that is actually decremented.
+        #[cfg(feature = "typed-counter")]
+        pub fn decrement_bounded(&self, n: i64, lower_bound: i64) -> LoroResult<i64> {
+            self.bounded_decrement(n, lower_bound)
+        }
+
+        /// Propose a value for a max register.
+        ///
+        /// Returns `false` if the register already holds a value that is not
+        /// smaller than `value`.
+        #[cfg(feature = "typed-counter")]
+        pub fn update_max(&self, value: f64) -> LoroResult<bool> {
+            self.propose_max(value)
+        }
+
+        fn bounded_decrement(&self, n: i64, lower_bound: i64) -> LoroResult<i64> {
+            let summary = self.summary();
+            let current = summary.value().into_i64().unwrap_or(0);
+            let bound = summary.lower_bound().unwrap_or(i64::MIN).max(lower_bound);
+            let amount = n.min(current.saturating_sub(bound)).max(0);
+            if amount == 0 {
+                return Ok(0);
+            }
+
+            self.apply_op(CounterOp::Bounded {
+                delta: -amount,
+                lower_bound,
+            })?;
+            Ok(amount)
+        }
+
+        fn propose_max(&self, value: f64) -> LoroResult<bool> {
+            if value.is_nan() {
+                return Err(LoroError::ArgErr("Cannot use NaN as a max value".into()));
+            }
+
+            let summary = self.summary();
+            if summary.kind() == CounterKind::Max && summary.value_f64() >= value {
+                return Ok(false);
+            }
+
+            self.apply_op(CounterOp::Max(value))?;
+            Ok(true)
+        }
+
+        /// Get the kind of the counter.
+        pub fn counter_kind(&self) -> CounterKind {
+            self.summary().kind()
+        }
+
+        fn summary(&self) -> CounterSummary {
             match &self.inner {
-                MaybeDetached::Detached(d) => {
-                    let d = &mut d.lock().value;
-                    *d += n;
+                MaybeDetached::Detached(d) => d.lock().value.summary.clone(),
+                MaybeDetached::Attached(a) => {
+                    a.with_state(|state| state.as_counter_state().unwrap().summary().clone())
+                }
+            }
+        }
+
+        /// Change the value by `delta` with an op of the counter's kind.
+        ///
+        /// It's used to apply diffs, e.g. when undoing. Int and bounded
+        /// counters round the delta, and a max register ignores negative deltas.
+        pub(super) fn apply_delta(&self, delta: f64) -> LoroResult<()> {
+            let summary = self.summary();
+            match summary.kind() {
+                CounterKind::Float => self.increment(delta),
+                CounterKind::Int => self.apply_op(CounterOp::Int(delta.round() as i64)),
+                CounterKind::Bounded => {
+                    let lower_bound = summary.lower_bound().unwrap();
+                    let delta = delta.round() as i64;
+                    if delta >= 0 {
+                        self.apply_op(CounterOp::Bounded { delta, lower_bound })
+                    } else {
+                        self.bounded_decrement(delta.saturating_neg(), lower_bound)
+                            .map(|_| ())
+                    }
+                }
+                CounterKind::Max => {
+                    if delta > 0. {
+                        self.propose_max(summary.value_f64() + delta)?;
+                    }
                     Ok(())
                 }
-                MaybeDetached::Attached(a) => a.with_txn(|txn| self.increment_with_txn(txn, n)),
             }
         }
 
-        pub fn decrement(&self, n: f64) -> LoroResult<()> {
+        fn apply_op(&self, op: CounterOp) -> LoroResult<()> {
+            if op.kind() != CounterKind::Float && !self.is_typed() {
+                return Err(LoroError::ArgErr(
+                    format!(
+                        "Cannot apply {:?} counter ops to a counter, use a typed counter instead",
+                        op.kind()
+                    )
+                    .into_boxed_str(),
+                ));
+            }
+
             match &self.inner {
                 MaybeDetached::Detached(d) => {
-                    let d = &mut d.lock().value;
-                    *d -= n;
+                    d.lock().value.summary.apply(op, false);
                     Ok(())
                 }
-                MaybeDetached::Attached(a) => a.with_txn(|txn| self.increment_with_txn(txn, -n)),
+                MaybeDetached::Attached(a) => a.with_txn(|txn| self.apply_op_with_txn(txn, op)),
             }
         }
 
-        fn increment_with_txn(&self, txn: &mut Transaction, n: f64) -> LoroResult<()> {
+        fn apply_op_with_txn(&self, txn: &mut Transaction, op: CounterOp) -> LoroResult<()> {
             let inner = self.inner.try_attached_state()?;
+            let delta = match op {
+                CounterOp::Float(n) if !self.is_typed() => n,
+                op => {
+                    let mut summary = self.summary();
+                    let old = summary.value_f64();
+                    summary.apply(op, false);
+                    summary.value_f64() - old
+                }
+            };
+
             txn.apply_local_op(
                 inner.container_idx,
-                crate::op::RawOpContent::Counter(n),
-                EventHint::Counter(n),
+                op.to_raw(),
+                EventHint::Counter(delta),
                 &inner.doc,
             )
         }
 
+        /// Recreate the value of `summary` with a single op.
+        fn replay_with_txn(
+            &self,
+            txn: &mut Transaction,
+            summary: &CounterSummary,
+        ) -> LoroResult<()> {
+            let op = match summary.kind() {
+                CounterKind::Float => CounterOp::Float(summary.float()),
+                CounterKind::Int => CounterOp::Int(summary.value().into_i64().unwrap()),
+                CounterKind::Bounded => CounterOp::Bounded {
+                    delta: summary.value().into_i64().unwrap(),
+                    lower_bound: summary.lower_bound().unwrap(),
+                },
+                CounterKind::Max => CounterOp::Max(summary.value_f64()),
+            };
+            self.apply_op_with_txn(txn, op)
+        }
+
         pub fn is_deleted(&self) -> bool {
             match &self.inner {
                 MaybeDetached::Detached(_) => false,
@@ -4786,8 +5898,30 @@ pub mod counter {
             }
         }
 
+        /// Whether it's a typed counter, which accepts int, bounded and max ops.
+        pub fn is_typed(&self) -> bool {
+            self.kind() != ContainerType::Counter
+        }
+
+        /// Reset the counter to zero.
+        ///
+        /// A bounded counter stops at its lower bound if the bound is above
+        /// zero, and a max register can't be cleared.
         pub fn clear(&self) -> LoroResult<()> {
-            self.decrement(self.get_value().into_double().unwrap())
+            let summary = self.summary();
+            match summary.kind() {
+                CounterKind::Float => self.decrement(summary.float()),
+                CounterKind::Int => {
+                    let value = summary.value().into_i64().unwrap();
+                    self.apply_op(CounterOp::Int(value.wrapping_neg()))
+                }
+                CounterKind::Bounded => {
+                    let value = summary.value().into_i64().unwrap();
+                    self.bounded_decrement(value.max(0), summary.lower_bound().unwrap())
+                        .map(|_| ())
+                }
+                CounterKind::Max => Ok(()),
+            }
         }
     }
 
@@ -4809,22 +5943,25 @@ pub mod counter {
             self.inner.attached_handler()
         }
 
-        fn get_value(&self) -> loro_common::LoroValue {
+        fn get_value(&self) -> LoroValue {
             match &self.inner {
                 MaybeDetached::Detached(t) => {
                     let t = t.lock();
-                    t.value.into()
+                    t.value.summary.value()
                 }
                 MaybeDetached::Attached(a) => a.get_value(),
             }
         }
 
-        fn get_deep_value(&self) -> loro_common::LoroValue {
+        fn get_deep_value(&self) -> LoroValue {
             self.get_value()
         }
 
-        fn kind(&self) -> loro_common::ContainerType {
-            loro_common::ContainerType::Counter
+        fn kind(&self) -> ContainerType {
+            match &self.inner {
+                MaybeDetached::Detached(d) => d.lock().value.kind,
+                MaybeDetached::Attached(a) => a.id.container_type(),
+            }
         }
 
         fn to_handler(&self) -> super::Handler {
@@ -4850,7 +5987,7 @@ pub mod counter {
                     let inner = create_handler(parent, self_id);
                     let c = inner.into_counter().unwrap();
 
-                    c.increment_with_txn(txn, v.value)?;
+                    c.replay_with_txn(txn, &v.value.summary)?;
 
                     v.attached = c.attached_handler().cloned();
                     Ok(c)
@@ -4858,8 +5995,249 @@ pub mod counter {
                 MaybeDetached::Attached(a) => {
                     let new_inner = create_handler(a, self_id);
                     let ans = new_inner.into_counter().unwrap();
-                    let delta = *self.get_value().as_double().unwrap();
-                    ans.increment_with_txn(txn, delta)?;
+                    ans.replay_with_txn(txn, &self.summary())?;
+                    Ok(ans)
+                }
+            }
+        }
+
+        fn get_attached(&self) -> Option<Self> {
+            match &self.inner {
+                MaybeDetached::Attached(a) => Some(Self {
+                    inner: MaybeDetached::Attached(a.clone()),
+                }),
+                MaybeDetached::Detached(v) => v.lock().attached.clone().map(|x| Self {
+                    inner: MaybeDetached::Attached(x),
+                }),
+            }
+        }
+
+        fn doc(&self) -> Option<crate::LoroDoc> {
+            match &self.inner {
+                MaybeDetached::Detached(_) => None,
+                MaybeDetached::Attached(a) => Some(a.doc()),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "set")]
+pub mod set {
+    use loro_common::{LoroError, LoroResult, LoroValue};
+
+    use crate::{
+        container::set::SetOp,
+        delta::SetDiff,
+        txn::{EventHint, Transaction},
+        HandlerTrait,
+    };
+
+    use super::{create_handler, Handler, MaybeDetached};
+
+    /// A handler of an add-wins set.
+    ///
+    /// When a value is added and removed concurrently, the value stays in the set.
+    #[derive(Clone)]
+    pub struct SetHandler {
+        pub(super) inner: MaybeDetached<Vec<LoroValue>>,
+    }
+
+    impl SetHandler {
+        pub fn new_detached() -> Self {
+            Self {
+                inner: MaybeDetached::new_detached(Vec::new()),
+            }
+        }
+
+        /// Add a value to the set.
+        ///
+        /// Returns `false` if the value is already in the set.
+        /// Containers can't be added to a set.
+        pub fn add(&self, value: impl Into<LoroValue>) -> LoroResult<bool> {
+            let value = value.into();
+            if value.is_container() {
+                return Err(LoroError::ArgErr("Cannot add a container to a set".into()));
+            }
+
+            match &self.inner {
+                MaybeDetached::Detached(d) => {
+                    let d = &mut d.lock().value;
+                    if d.contains(&value) {
+                        return Ok(false);
+                    }
+                    d.push(value);
+                    Ok(true)
+                }
+                MaybeDetached::Attached(a) => a.with_txn(|txn| self.add_with_txn(txn, value)),
+            }
+        }
+
+        fn add_with_txn(&self, txn: &mut Transaction, value: LoroValue) -> LoroResult<bool> {
+            if self.contains(&value) {
+                return Ok(false);
+            }
+
+            let inner = self.inner.try_attached_state()?;
+            txn.apply_local_op(
+                inner.container_idx,
+                crate::op::RawOpContent::Set(SetOp::Add(value.clone())),
+                EventHint::Set(SetDiff {
+                    added: vec![value],
+                    removed: Vec::new(),
+                }),
+                &inner.doc,
+            )?;
+            Ok(true)
+        }
+
+        /// Remove a value from the set.
+        ///
+        /// Returns `false` if the value is not in the set.
+        pub fn remove(&self, value: &LoroValue) -> LoroResult<bool> {
+            match &self.inner {
+                MaybeDetached::Detached(d) => {
+                    let d = &mut d.lock().value;
+                    let Some(pos) = d.iter().position(|x| x == value) else {
+                        return Ok(false);
+                    };
+                    d.remove(pos);
+                    Ok(true)
+                }
+                MaybeDetached::Attached(a) => a.with_txn(|txn| self.remove_with_txn(txn, value)),
+            }
+        }
+
+        fn remove_with_txn(&self, txn: &mut Transaction, value: &LoroValue) -> LoroResult<bool> {
+            if !self.contains(value) {
+                return Ok(false);
+            }
+
+            let inner = self.inner.try_attached_state()?;
+            txn.apply_local_op(
+                inner.container_idx,
+                crate::op::RawOpContent::Set(SetOp::Remove(value.clone())),
+                EventHint::Set(SetDiff {
+                    added: Vec::new(),
+                    removed: vec![value.clone()],
+                }),
+                &inner.doc,
+            )?;
+            Ok(true)
+        }
+
+        pub fn contains(&self, value: &LoroValue) -> bool {
+            match &self.inner {
+                MaybeDetached::Detached(d) => d.lock().value.contains(value),
+                MaybeDetached::Attached(a) => {
+                    a.with_state(|state| state.as_set_state().unwrap().contains(value))
+                }
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            match &self.inner {
+                MaybeDetached::Detached(d) => d.lock().value.len(),
+                MaybeDetached::Attached(a) => {
+                    a.with_state(|state| state.as_set_state().unwrap().len())
+                }
+            }
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        pub fn is_deleted(&self) -> bool {
+            match &self.inner {
+                MaybeDetached::Detached(_) => false,
+                MaybeDetached::Attached(a) => a.is_deleted(),
+            }
+        }
+
+        pub fn clear(&self) -> LoroResult<()> {
+            match &self.inner {
+                MaybeDetached::Detached(d) => {
+                    d.lock().value.clear();
+                    Ok(())
+                }
+                MaybeDetached::Attached(a) => a.with_txn(|txn| {
+                    for value in self.get_value().into_list().unwrap().iter() {
+                        self.remove_with_txn(txn, value)?;
+                    }
+                    Ok(())
+                }),
+            }
+        }
+    }
+
+    impl std::fmt::Debug for SetHandler {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match &self.inner {
+                MaybeDetached::Detached(_) => write!(f, "SetHandler Detached"),
+                MaybeDetached::Attached(a) => write!(f, "SetHandler {}", a.id),
+            }
+        }
+    }
+
+    impl HandlerTrait for SetHandler {
+        fn is_attached(&self) -> bool {
+            matches!(&self.inner, MaybeDetached::Attached(..))
+        }
+
+        fn attached_handler(&self) -> Option<&crate::BasicHandler> {
+            self.inner.attached_handler()
+        }
+
+        fn get_value(&self) -> LoroValue {
+            match &self.inner {
+                MaybeDetached::Detached(d) => d.lock().value.clone().into(),
+                MaybeDetached::Attached(a) => a.get_value(),
+            }
+        }
+
+        fn get_deep_value(&self) -> LoroValue {
+            self.get_value()
+        }
+
+        fn kind(&self) -> loro_common::ContainerType {
+            loro_common::ContainerType::Set
+        }
+
+        fn to_handler(&self) -> super::Handler {
+            Handler::Set(self.clone())
+        }
+
+        fn from_handler(h: super::Handler) -> Option<Self> {
+            match h {
+                Handler::Set(x) => Some(x),
+                _ => None,
+            }
+        }
+
+        fn attach(
+            &self,
+            txn: &mut crate::txn::Transaction,
+            parent: &crate::BasicHandler,
+            self_id: loro_common::ContainerID,
+        ) -> LoroResult<Self> {
+            match &self.inner {
+                MaybeDetached::Detached(v) => {
+                    let mut v = v.lock();
+                    let inner = create_handler(parent, self_id);
+                    let c = inner.into_set().unwrap();
+                    for value in v.value.iter() {
+                        c.add_with_txn(txn, value.clone())?;
+                    }
+
+                    v.attached = c.attached_handler().cloned();
+                    Ok(c)
+                }
+                MaybeDetached::Attached(a) => {
+                    let new_inner = create_handler(a, self_id);
+                    let ans = new_inner.into_set().unwrap();
+                    for value in self.get_value().into_list().unwrap().iter() {
+                        ans.add_with_txn(txn, value.clone())?;
+                    }
                     Ok(ans)
                 }
             }
@@ -1,11 +1,11 @@
-use std::{collections::VecDeque, sync::Arc};
+use std::{collections::VecDeque, ops::Range, sync::Arc};
 
 use fractional_index::FractionalIndex;
 use loro_common::{
     ContainerID, ContainerType, Counter, IdFull, IdLp, LoroError, LoroResult, LoroTreeError,
     LoroValue, PeerID, TreeID, ID,
 };
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use smallvec::smallvec;
 
 use crate::{
@@ -21,6 +21,19 @@ use crate::{
 
 use super::{create_handler, Handler, MaybeDetached};
 
+/// Length statistics of the fractional indexes of a parent's children.
+///
+/// See [`TreeHandler::fractional_index_stats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FractionalIndexStats {
+    /// The number of children
+    pub count: usize,
+    /// The length in bytes of the longest fractional index
+    pub max_len: usize,
+    /// The average length in bytes of the fractional indexes
+    pub avg_len: f64,
+}
+
 #[derive(Clone)]
 pub struct TreeHandler {
     pub(super) inner: MaybeDetached<TreeInner>,
@@ -67,6 +80,16 @@ impl TreeInner {
         Ok(())
     }
 
+    fn move_many(&mut self, targets: &[TreeID], new_parent: Option<TreeID>, index: usize) {
+        for target in targets {
+            let old_parent = self.parent_links.insert(*target, new_parent).unwrap();
+            let children = self.children_links.get_mut(&old_parent).unwrap();
+            children.retain(|x| x != target);
+        }
+        let children = self.children_links.entry(new_parent).or_default();
+        children.splice(index..index, targets.iter().copied());
+    }
+
     fn delete(&mut self, id: TreeID) -> LoroResult<()> {
         self.map.remove(&id);
         let parent = self
@@ -148,6 +171,72 @@ impl TreeInner {
         ans.into()
     }
 
+    fn get_nested_value(&self, parent: Option<TreeID>) -> Vec<LoroValue> {
+        let Some(children) = self.children_links.get(&parent) else {
+            return vec![];
+        };
+
+        children
+            .iter()
+            .zip(Self::sibling_positions(children.len()))
+            .map(|(target, position)| {
+                let meta = self.map.get(target).unwrap().get_deep_value();
+                nested_node_value(
+                    *target,
+                    meta,
+                    position,
+                    self.get_nested_value(Some(*target)),
+                )
+            })
+            .collect()
+    }
+
+    /// A detached tree only keeps the order of the siblings, so their positions are
+    /// reported as if they were evenly spaced.
+    fn sibling_positions(n: usize) -> Vec<FractionalIndex> {
+        FractionalIndex::generate_n_evenly(None, None, n).unwrap_or_default()
+    }
+
+    fn import_nested(
+        &mut self,
+        parent: Option<TreeID>,
+        nodes: &[LoroValue],
+        preserve_fractional_index: bool,
+    ) -> LoroResult<Vec<TreeID>> {
+        let mut siblings = Self::sibling_positions(self.children_num(parent).unwrap_or(0));
+        let generated =
+            FractionalIndex::generate_n_evenly(siblings.last(), None, nodes.len()).unwrap();
+        let mut ans = Vec::with_capacity(nodes.len());
+        for (node, generated) in nodes.iter().zip(generated) {
+            let node = node.as_map().unwrap();
+            let position = match node.get("fractional_index") {
+                Some(LoroValue::String(s)) if preserve_fractional_index => {
+                    let position = FractionalIndex::from_hex_string(s.as_str());
+                    if siblings.contains(&position) {
+                        generated
+                    } else {
+                        position
+                    }
+                }
+                _ => generated,
+            };
+            let index = siblings.partition_point(|p| p < &position);
+            siblings.insert(index, position);
+            let target = self.create(parent, index);
+            if let Some(LoroValue::Map(meta)) = node.get("meta") {
+                let map = self.map.get(&target).unwrap().clone();
+                for (key, value) in meta.iter() {
+                    map.insert(key, value.clone())?;
+                }
+            }
+            if let Some(LoroValue::List(children)) = node.get("children") {
+                self.import_nested(Some(target), children, preserve_fractional_index)?;
+            }
+            ans.push(target);
+        }
+        Ok(ans)
+    }
+
     fn get_nodes_under(&self, root: TreeParentId) -> Vec<TreeNode> {
         let root_id = root.tree_id();
         let mut ans = vec![];
@@ -751,6 +840,261 @@ impl TreeHandler {
         }
     }
 
+    /// Move `targets` to be consecutive children of `parent`, starting at `index`.
+    ///
+    /// `index` is counted among the children of `parent` that are not being moved.
+    /// The moved nodes keep the order of `targets`. Their positions are generated in
+    /// one pass with [`FractionalIndex::generate_n_evenly`] (with jitter if configured),
+    /// so they are evenly spaced between the two neighbors.
+    pub fn move_many(
+        &self,
+        targets: &[TreeID],
+        parent: TreeParentId,
+        index: usize,
+    ) -> LoroResult<()> {
+        let siblings = self.check_move_many(targets, &parent, index)?;
+        match &self.inner {
+            MaybeDetached::Detached(t) => {
+                let mut t = t.lock();
+                t.value.move_many(targets, parent.tree_id(), index);
+                Ok(())
+            }
+            MaybeDetached::Attached(a) => a.with_txn(|txn| {
+                self.move_many_with_txn(txn, targets, parent, index, &siblings, false)
+            }),
+        }
+    }
+
+    /// Validate the input of [`TreeHandler::move_many`] and return the children of
+    /// `parent` that are not moved.
+    fn check_move_many(
+        &self,
+        targets: &[TreeID],
+        parent: &TreeParentId,
+        index: usize,
+    ) -> LoroResult<Vec<TreeID>> {
+        let is_alive = |target: &TreeID| {
+            self.contains(*target)
+                && !(self.is_attached() && self.is_node_deleted(target).unwrap_or(true))
+        };
+        let mut moved = FxHashSet::default();
+        for target in targets {
+            if !moved.insert(*target) {
+                return Err(LoroError::ArgErr(
+                    format!("TreeID {:?} appears more than once in the targets", target)
+                        .into_boxed_str(),
+                ));
+            }
+            if !is_alive(target) {
+                return Err(LoroTreeError::TreeNodeDeletedOrNotExist(*target).into());
+            }
+        }
+
+        match parent {
+            TreeParentId::Deleted | TreeParentId::Unexist => {
+                return Err(LoroTreeError::InvalidParent.into());
+            }
+            TreeParentId::Node(p) => {
+                if !is_alive(p) {
+                    return Err(LoroTreeError::TreeNodeDeletedOrNotExist(*p).into());
+                }
+                let mut ancestor = Some(*parent);
+                while let Some(TreeParentId::Node(id)) = ancestor {
+                    if moved.contains(&id) {
+                        return Err(LoroTreeError::CyclicMoveError.into());
+                    }
+                    ancestor = self.get_node_parent(&id);
+                }
+            }
+            TreeParentId::Root => {}
+        }
+
+        let siblings: Vec<TreeID> = self
+            .children(parent)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|x| !moved.contains(x))
+            .collect();
+        if index > siblings.len() {
+            return Err(LoroTreeError::IndexOutOfBound {
+                len: siblings.len(),
+                index,
+            }
+            .into());
+        }
+        Ok(siblings)
+    }
+
+    fn move_many_with_txn(
+        &self,
+        txn: &mut Transaction,
+        targets: &[TreeID],
+        parent: TreeParentId,
+        index: usize,
+        siblings: &[TreeID],
+        compact: bool,
+    ) -> LoroResult<()> {
+        let inner = self.inner.try_attached_state()?;
+        if !self.is_fractional_index_enabled() {
+            return Err(LoroTreeError::FractionalIndexNotEnabled.into());
+        }
+
+        let left = index
+            .checked_sub(1)
+            .and_then(|i| self.get_position_by_tree_id(&siblings[i]));
+        let right = siblings
+            .get(index)
+            .and_then(|id| self.get_position_by_tree_id(id));
+        let positions = if compact {
+            FractionalIndex::generate_n_compact(left.as_ref(), right.as_ref(), targets.len())
+        } else {
+            None
+        };
+        let Some(positions) = positions.or_else(|| {
+            self.generate_n_positions_evenly(left.as_ref(), right.as_ref(), targets.len())
+        }) else {
+            // The neighbors share the same position. Move the nodes one by one,
+            // so that the conflicting siblings get rearranged.
+            for (i, target) in targets.iter().enumerate() {
+                let anchor = if i == 0 {
+                    index.checked_sub(1).map(|i| siblings[i])
+                } else {
+                    Some(targets[i - 1])
+                };
+                let to = match anchor {
+                    Some(anchor) => {
+                        self.children(&parent)
+                            .unwrap_or_default()
+                            .into_iter()
+                            .filter(|x| x != target)
+                            .position(|x| x == anchor)
+                            .unwrap()
+                            + 1
+                    }
+                    None => 0,
+                };
+                self.mov_with_txn(txn, *target, parent, to, FiIfNotConfigured::Throw)?;
+            }
+            return Ok(());
+        };
+
+        for (target, position) in targets.iter().zip(positions) {
+            let old_parent = self.get_node_parent(target).unwrap();
+            let old_index = self.get_index_by_tree_id(target).unwrap();
+            let mut new_index = self
+                .get_index_by_fractional_index(
+                    &parent,
+                    &NodePosition {
+                        position: position.clone(),
+                        idlp: txn.next_idlp(),
+                    },
+                )
+                .unwrap_or(0);
+            if old_parent == parent && old_index < new_index {
+                new_index -= 1;
+            }
+            self.mov_with_position(inner, txn, *target, parent, new_index, position, old_index)?;
+        }
+        Ok(())
+    }
+
+    /// Get the length statistics of the fractional indexes of `parent`'s children.
+    ///
+    /// Returns `None` if the tree is detached, fractional index is disabled,
+    /// or `parent` does not exist.
+    pub fn fractional_index_stats(&self, parent: &TreeParentId) -> Option<FractionalIndexStats> {
+        if !self.is_attached() || !self.is_fractional_index_enabled() {
+            return None;
+        }
+        if let TreeParentId::Node(p) = parent {
+            if !self.contains(*p) {
+                return None;
+            }
+        }
+        let children = self.children(parent).unwrap_or_default();
+        let mut max_len = 0;
+        let mut total_len = 0;
+        for child in children.iter() {
+            let len = self
+                .get_position_by_tree_id(child)
+                .map(|p| p.as_bytes().len())
+                .unwrap_or(0);
+            max_len = max_len.max(len);
+            total_len += len;
+        }
+        Some(FractionalIndexStats {
+            count: children.len(),
+            max_len,
+            avg_len: if children.is_empty() {
+                0.0
+            } else {
+                total_len as f64 / children.len() as f64
+            },
+        })
+    }
+
+    /// Give the children of `parent` in `range` fresh fractional indexes, evenly spaced
+    /// between their neighbors with the shortest keys that fit. The order of the
+    /// children does not change.
+    ///
+    /// It's implemented as ordinary move ops, so it merges with concurrent edits like
+    /// any other move. A node inserted concurrently within or next to `range` may end
+    /// up in a different slot among the rebalanced nodes.
+    ///
+    /// Returns [`LoroError::MisuseDetachedContainer`] on a detached tree, which has no
+    /// fractional indexes to rebalance.
+    pub fn rebalance_fractional_index(
+        &self,
+        parent: &TreeParentId,
+        range: Range<usize>,
+    ) -> LoroResult<()> {
+        let MaybeDetached::Attached(a) = &self.inner else {
+            return Err(LoroError::MisuseDetachedContainer {
+                method: "rebalance_fractional_index",
+            });
+        };
+        let children = self.children(parent).unwrap_or_default();
+        if range.start > range.end {
+            return Err(LoroError::EndIndexLessThanStartIndex {
+                start: range.start,
+                end: range.end,
+            });
+        }
+        if range.end > children.len() {
+            return Err(LoroTreeError::IndexOutOfBound {
+                len: children.len(),
+                index: range.end,
+            }
+            .into());
+        }
+        if range.is_empty() {
+            return Ok(());
+        }
+
+        let targets = &children[range.clone()];
+        let siblings = self.check_move_many(targets, parent, range.start)?;
+        a.with_txn(|txn| {
+            self.move_many_with_txn(txn, targets, *parent, range.start, &siblings, true)
+        })
+    }
+
+    /// Rebalance all children of `parent` if any of their fractional indexes is longer
+    /// than `max_len` bytes. Returns whether a rebalance happened.
+    pub fn rebalance_fractional_index_if_needed(
+        &self,
+        parent: &TreeParentId,
+        max_len: usize,
+    ) -> LoroResult<bool> {
+        let Some(stats) = self.fractional_index_stats(parent) else {
+            return Ok(false);
+        };
+        if stats.max_len <= max_len {
+            return Ok(false);
+        }
+        self.rebalance_fractional_index(parent, 0..stats.count)?;
+        Ok(true)
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn create_with_position(
         &self,
@@ -991,6 +1335,181 @@ impl TreeHandler {
         }
     }
 
+    /// Get the forest as nested nodes with resolved metadata.
+    ///
+    /// Each node is a map of `{ "id", "meta", "fractional_index", "children" }`,
+    /// where `children` is a list of nodes in the same shape. The result can be
+    /// passed back to [`TreeHandler::import_nested`].
+    pub fn get_nested_value(&self) -> LoroValue {
+        match &self.inner {
+            MaybeDetached::Detached(t) => {
+                let t = t.lock();
+                t.value.get_nested_value(None).into()
+            }
+            MaybeDetached::Attached(_) => {
+                let nodes = self.get_all_hierarchy_nodes_under(TreeParentId::Root);
+                self.hierarchy_nodes_to_nested_value(nodes).into()
+            }
+        }
+    }
+
+    fn hierarchy_nodes_to_nested_value(&self, nodes: Vec<TreeNodeWithChildren>) -> Vec<LoroValue> {
+        nodes
+            .into_iter()
+            .map(|node| {
+                let meta = self
+                    .get_meta(node.id)
+                    .map(|m| m.get_deep_value())
+                    .unwrap_or_default();
+                nested_node_value(
+                    node.id,
+                    meta,
+                    node.fractional_index,
+                    self.hierarchy_nodes_to_nested_value(node.children),
+                )
+            })
+            .collect()
+    }
+
+    /// Create the nodes described by `nodes` under `parent` within one transaction.
+    ///
+    /// `nodes` is a list in the shape returned by [`TreeHandler::get_nested_value`].
+    /// The `id` fields are ignored, because a [`TreeID`] is derived from the op that
+    /// creates the node. Returns the ids of the created top-level nodes in order.
+    ///
+    /// Every node costs exactly one create op plus one op per metadata key. When
+    /// `preserve_fractional_index` is true, nodes carrying a `fractional_index` reuse it,
+    /// so the siblings keep the order of the source tree. A preserved position that is
+    /// already used by a sibling is replaced by a generated one. Otherwise the nodes are
+    /// appended after the existing children with evenly spaced positions.
+    ///
+    /// The input is validated before any op is emitted.
+    pub fn import_nested(
+        &self,
+        parent: TreeParentId,
+        nodes: &LoroValue,
+        preserve_fractional_index: bool,
+    ) -> LoroResult<Vec<TreeID>> {
+        match parent {
+            TreeParentId::Deleted | TreeParentId::Unexist => {
+                return Err(LoroTreeError::InvalidParent.into());
+            }
+            TreeParentId::Node(p) => {
+                if !self.contains(p) || (self.is_attached() && self.is_node_deleted(&p)?) {
+                    return Err(LoroTreeError::TreeNodeDeletedOrNotExist(p).into());
+                }
+            }
+            TreeParentId::Root => {}
+        }
+
+        let nodes = check_nested_nodes(nodes)?;
+        match &self.inner {
+            MaybeDetached::Detached(t) => {
+                let mut t = t.lock();
+                t.value
+                    .import_nested(parent.tree_id(), nodes, preserve_fractional_index)
+            }
+            MaybeDetached::Attached(a) => a.with_txn(|txn| {
+                self.import_nested_with_txn(txn, parent, nodes, preserve_fractional_index)
+            }),
+        }
+    }
+
+    fn import_nested_with_txn(
+        &self,
+        txn: &mut Transaction,
+        parent: TreeParentId,
+        nodes: &[LoroValue],
+        preserve_fractional_index: bool,
+    ) -> LoroResult<Vec<TreeID>> {
+        let inner = self.inner.try_attached_state()?;
+        let positions = self.generate_positions_after_last_child(&parent, nodes.len());
+        let mut ans = Vec::with_capacity(nodes.len());
+        for (node, position) in nodes.iter().zip(positions) {
+            let node = node.as_map().unwrap();
+            let position = match node.get("fractional_index") {
+                Some(LoroValue::String(s)) if preserve_fractional_index => {
+                    FractionalIndex::from_hex_string(s.as_str())
+                }
+                _ => position,
+            };
+            let index = self
+                .get_index_by_fractional_index(
+                    &parent,
+                    &NodePosition {
+                        position: position.clone(),
+                        idlp: txn.next_idlp(),
+                    },
+                )
+                .unwrap_or(0);
+            // A preserved position may already be taken by an existing sibling. Equal
+            // positions sort next to each other, so checking the neighbours is enough.
+            let collides = self.is_fractional_index_enabled()
+                && [index.checked_sub(1), Some(index)]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|i| self.get_child_at(&parent, i))
+                    .any(|id| self.get_position_by_tree_id(&id).as_ref() == Some(&position));
+            let target = if collides {
+                self.create_with_txn(txn, parent, index, FiIfNotConfigured::Zero)?
+            } else {
+                let target = TreeID::from_id(txn.next_id());
+                self.create_with_position(inner, txn, target, parent, index, position)?;
+                target
+            };
+            if let Some(LoroValue::Map(meta)) = node.get("meta") {
+                let map = self.get_meta(target)?;
+                for (key, value) in meta.iter() {
+                    map.insert_with_txn(txn, key, value.clone())?;
+                }
+            }
+            if let Some(LoroValue::List(children)) = node.get("children") {
+                self.import_nested_with_txn(
+                    txn,
+                    TreeParentId::Node(target),
+                    children,
+                    preserve_fractional_index,
+                )?;
+            }
+            ans.push(target);
+        }
+        Ok(ans)
+    }
+
+    /// Generate `n` evenly spaced positions after the last child of `parent`.
+    fn generate_positions_after_last_child(
+        &self,
+        parent: &TreeParentId,
+        n: usize,
+    ) -> Vec<FractionalIndex> {
+        if !self.is_fractional_index_enabled() {
+            return vec![FractionalIndex::default(); n];
+        }
+
+        let last = self
+            .children_num(parent)
+            .and_then(|len| len.checked_sub(1))
+            .and_then(|i| self.get_child_at(parent, i))
+            .and_then(|id| self.get_position_by_tree_id(&id));
+        self.generate_n_positions_evenly(last.as_ref(), None, n)
+            .unwrap()
+    }
+
+    fn generate_n_positions_evenly(
+        &self,
+        left: Option<&FractionalIndex>,
+        right: Option<&FractionalIndex>,
+        n: usize,
+    ) -> Option<Vec<FractionalIndex>> {
+        let MaybeDetached::Attached(a) = &self.inner else {
+            unreachable!()
+        };
+        a.with_state(|state| {
+            let a = state.as_tree_state_mut().unwrap();
+            a.generate_n_positions_evenly(left, right, n)
+        })
+    }
+
     #[allow(non_snake_case)]
     pub fn __internal__next_tree_id(&self) -> TreeID {
         match &self.inner {
@@ -1182,3 +1701,67 @@ impl TreeHandler {
         }
     }
 }
+
+fn nested_node_value(
+    id: TreeID,
+    meta: LoroValue,
+    fractional_index: FractionalIndex,
+    children: Vec<LoroValue>,
+) -> LoroValue {
+    let mut map = FxHashMap::default();
+    map.insert("id".to_string(), id.to_string().into());
+    map.insert("meta".to_string(), meta);
+    map.insert(
+        "fractional_index".to_string(),
+        fractional_index.to_string().into(),
+    );
+    map.insert("children".to_string(), children.into());
+    map.into()
+}
+
+/// Check that `value` is a list of nested tree nodes, as produced by
+/// [`TreeHandler::get_nested_value`].
+fn check_nested_nodes(value: &LoroValue) -> LoroResult<&[LoroValue]> {
+    let Some(list) = value.as_list() else {
+        return Err(LoroError::ArgErr(
+            "Expected a list of tree nodes".to_string().into_boxed_str(),
+        ));
+    };
+
+    for node in list.iter() {
+        let Some(node) = node.as_map() else {
+            return Err(LoroError::ArgErr(
+                format!("Expected a tree node map, but found {:?}", node).into_boxed_str(),
+            ));
+        };
+        match node.get("meta") {
+            None | Some(LoroValue::Null) | Some(LoroValue::Map(_)) => {}
+            Some(v) => {
+                return Err(LoroError::ArgErr(
+                    format!("Expected the tree node meta to be a map, but found {:?}", v)
+                        .into_boxed_str(),
+                ));
+            }
+        }
+        match node.get("fractional_index") {
+            None | Some(LoroValue::Null) => {}
+            Some(LoroValue::String(s))
+                if !s.is_empty()
+                    && s.len().is_multiple_of(2)
+                    && s.bytes().all(|b| b.is_ascii_hexdigit()) => {}
+            Some(v) => {
+                return Err(LoroError::ArgErr(
+                    format!("Invalid fractional index {:?}", v).into_boxed_str(),
+                ));
+            }
+        }
+        match node.get("children") {
+            None | Some(LoroValue::Null) => {}
+            Some(children) => {
+                check_nested_nodes(children)?;
+            }
+        }
+    }
+
+    Ok(list.as_slice())
+}
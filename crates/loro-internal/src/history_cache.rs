@@ -28,6 +28,7 @@ use crate::{
     op::{InnerContent, RichOp, SliceWithId},
     oplog::ChangeStore,
     state::{ContainerCreationContext, GcStore},
+    version::{shrink_frontiers, Frontiers},
     OpLog, VersionVector,
 };
 
@@ -48,6 +49,8 @@ pub(crate) struct ContainerHistoryCache {
 pub(crate) struct ForCheckout {
     pub(crate) map: MapHistoryCache,
     pub(crate) movable_list: MovableListHistoryCache,
+    #[cfg(feature = "set")]
+    pub(crate) set: SetHistoryCache,
 }
 
 #[derive(Clone, Copy)]
@@ -60,6 +63,8 @@ impl HistoryCacheTrait for ForCheckout {
         match op.raw_op().container.get_type() {
             ContainerType::Map => self.map.insert(op),
             ContainerType::MovableList => self.movable_list.insert(op),
+            #[cfg(feature = "set")]
+            ContainerType::Set => self.set.insert(op),
             _ => {}
         }
     }
@@ -93,6 +98,11 @@ impl ContainerHistoryCache {
                     let rich_op = RichOp::new_by_change(change, op);
                     self.for_checkout.as_mut().unwrap().insert(&rich_op)
                 }
+                #[cfg(feature = "set")]
+                ContainerType::Set if self.for_checkout.is_some() && for_checkout => {
+                    let rich_op = RichOp::new_by_change(change, op);
+                    self.for_checkout.as_mut().unwrap().insert(&rich_op)
+                }
                 ContainerType::Tree if self.for_importing.is_some() && for_importing => {
                     let container_idx = op.container;
                     let rich_op = RichOp::new_by_change(change, op);
@@ -171,6 +181,11 @@ impl ContainerHistoryCache {
                         let rich_op = RichOp::new_by_change(c, op);
                         self.for_checkout.as_mut().unwrap().insert(&rich_op)
                     }
+                    #[cfg(feature = "set")]
+                    ContainerType::Set if self.for_checkout.is_some() && for_checkout => {
+                        let rich_op = RichOp::new_by_change(c, op);
+                        self.for_checkout.as_mut().unwrap().insert(&rich_op)
+                    }
                     ContainerType::Tree if self.for_importing.is_some() && for_importing => {
                         let container_idx = op.container;
                         let rich_op = RichOp::new_by_change(c, op);
@@ -209,6 +224,10 @@ impl ContainerHistoryCache {
                     }
                     #[cfg(feature = "counter")]
                     ContainerType::Counter => continue,
+                    #[cfg(feature = "typed-counter")]
+                    ContainerType::TypedCounter => continue,
+                    #[cfg(feature = "set")]
+                    ContainerType::Set => {}
                     ContainerType::Map => {}
                     ContainerType::MovableList => {}
                     ContainerType::Tree => {}
@@ -239,6 +258,15 @@ impl ContainerHistoryCache {
                             }
                         }
                     }
+                    #[cfg(feature = "set")]
+                    crate::state::State::SetState(s) => {
+                        if for_checkout {
+                            let c = self.for_checkout.as_mut().unwrap();
+                            for (v, idlp) in s.elements() {
+                                c.set.record_shallow_root_state_entry(idx, v, *idlp);
+                            }
+                        }
+                    }
                     crate::state::State::TreeState(t) => {
                         if for_importing {
                             let c = self.for_importing.as_mut().unwrap();
@@ -601,6 +629,212 @@ impl MapHistoryCache {
 
         ans
     }
+
+    /// Get the ops on `key` that are visible at `vv` and not overwritten by
+    /// another visible op on the same key, i.e. the concurrent values of the key.
+    ///
+    /// The result is sorted from the LWW winner to the loser.
+    pub fn get_key_concurrent_ops_at_vv(
+        &self,
+        container: ContainerIdx,
+        key: &InternalString,
+        vv: &VersionVector,
+        oplog: &OpLog,
+    ) -> Vec<GroupedMapOpInfo> {
+        let Some(key_idx) = self.keys.get(key) else {
+            return Vec::new();
+        };
+
+        let key_idx = key_idx as u32;
+        let range = (
+            Bound::Included(MapHistoryCacheEntry {
+                container,
+                key: key_idx,
+                lamport: 0,
+                peer: 0,
+                counter_or_value: Either::Left(0),
+            }),
+            Bound::Included(MapHistoryCacheEntry {
+                container,
+                key: key_idx,
+                lamport: Lamport::MAX,
+                peer: PeerID::MAX,
+                counter_or_value: Either::Left(0),
+            }),
+        );
+
+        let mut visible = Vec::new();
+        let mut shallow_root_value = None;
+        for entry in self.map.range(range) {
+            match &entry.counter_or_value {
+                Either::Left(cnt) => {
+                    if vv.get(&entry.peer).copied().unwrap_or(0) > *cnt {
+                        visible.push((ID::new(entry.peer, *cnt), entry.lamport));
+                    }
+                }
+                Either::Right(v) => {
+                    shallow_root_value = Some(GroupedMapOpInfo {
+                        value: (**v).clone(),
+                        lamport: entry.lamport,
+                        peer: entry.peer,
+                    });
+                }
+            }
+        }
+
+        if visible.is_empty() {
+            // The value in the shallow root state is overwritten by any visible op
+            return shallow_root_value.into_iter().collect();
+        }
+
+        let frontiers: Frontiers = visible.iter().map(|(id, _)| *id).collect();
+        let concurrent = shrink_frontiers(&frontiers, oplog.dag()).unwrap();
+        let mut ans: Vec<GroupedMapOpInfo> = visible
+            .into_iter()
+            .filter(|(id, _)| concurrent.contains(id))
+            .map(|(id, lamport)| {
+                let op = oplog.get_op_that_includes(id).unwrap();
+                debug_assert_eq!(op.atom_len(), 1);
+                match &op.content {
+                    InnerContent::Map(map) => GroupedMapOpInfo {
+                        value: map.value.clone(),
+                        lamport,
+                        peer: id.peer,
+                    },
+                    _ => unreachable!(),
+                }
+            })
+            .collect();
+        ans.sort_unstable_by(|a, b| b.cmp(a));
+        ans
+    }
+}
+
+#[cfg(feature = "set")]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SetHistoryCacheEntry {
+    container: ContainerIdx,
+    value: u32,
+    lamport: Lamport,
+    peer: PeerID,
+    /// `None` if the entry comes from the shallow root state
+    counter: Option<Counter>,
+    is_add: bool,
+}
+
+#[cfg(feature = "set")]
+#[derive(Debug, Default)]
+pub(crate) struct SetHistoryCache {
+    values: ValueRegister<LoroValue>,
+    map: BTreeSet<SetHistoryCacheEntry>,
+}
+
+#[cfg(feature = "set")]
+impl HistoryCacheTrait for SetHistoryCache {
+    fn insert(&mut self, op: &RichOp) {
+        let container = op.raw_op().container;
+        let set_op = match &op.raw_op().content {
+            InnerContent::Future(crate::op::FutureInnerContent::Set(set_op)) => set_op,
+            _ => unreachable!(),
+        };
+
+        let value = self.values.register(set_op.value());
+        self.map.insert(SetHistoryCacheEntry {
+            container,
+            value: value as u32,
+            lamport: op.lamport(),
+            peer: op.peer,
+            counter: Some(op.counter()),
+            is_add: set_op.is_add(),
+        });
+    }
+}
+
+#[cfg(feature = "set")]
+impl SetHistoryCache {
+    fn record_shallow_root_state_entry(&mut self, idx: ContainerIdx, v: &LoroValue, idlp: IdLp) {
+        let value = self.values.register(v);
+        self.map.insert(SetHistoryCacheEntry {
+            container: idx,
+            value: value as u32,
+            lamport: idlp.lamport,
+            peer: idlp.peer,
+            counter: None,
+            is_add: true,
+        });
+    }
+
+    /// Get the elements of the set at `vv`, each with the id of its winning add op.
+    ///
+    /// A value is in the set if any op on it that isn't overwritten by another
+    /// visible op on the same value is an add.
+    pub fn get_container_elements_at_vv(
+        &self,
+        container: ContainerIdx,
+        vv: &VersionVector,
+        oplog: &OpLog,
+    ) -> FxHashMap<LoroValue, IdLp> {
+        let range = (
+            Bound::Included(SetHistoryCacheEntry {
+                container,
+                value: 0,
+                lamport: 0,
+                peer: 0,
+                counter: None,
+                is_add: false,
+            }),
+            Bound::Included(SetHistoryCacheEntry {
+                container,
+                value: u32::MAX,
+                lamport: Lamport::MAX,
+                peer: PeerID::MAX,
+                counter: Some(Counter::MAX),
+                is_add: true,
+            }),
+        );
+
+        let shallow_since_vv = oplog.shallow_since_vv();
+        let mut ans = FxHashMap::default();
+        let mut iter = self.map.range(range).peekable();
+        while let Some(first) = iter.peek() {
+            let value = first.value;
+            let mut visible = Vec::new();
+            let mut shallow_root = None;
+            let mut after_shallow_root = false;
+            while let Some(entry) = iter.next_if(|e| e.value == value) {
+                match entry.counter {
+                    Some(cnt) => {
+                        if vv.get(&entry.peer).copied().unwrap_or(0) > cnt {
+                            let id = ID::new(entry.peer, cnt);
+                            after_shallow_root |= !shallow_since_vv.includes_id(id);
+                            visible.push((id, entry));
+                        }
+                    }
+                    None => shallow_root = Some(IdLp::new(entry.peer, entry.lamport)),
+                }
+            }
+
+            let winner = if !after_shallow_root {
+                // Only the ops after the shallow root have seen the whole shallow root state
+                shallow_root
+            } else {
+                let frontiers: Frontiers = visible.iter().map(|(id, _)| *id).collect();
+                let concurrent = shrink_frontiers(&frontiers, oplog.dag()).unwrap();
+                visible
+                    .iter()
+                    .filter(|(id, e)| e.is_add && concurrent.contains(id))
+                    .map(|(_, e)| IdLp::new(e.peer, e.lamport))
+                    .max()
+            };
+
+            if let Some(idlp) = winner {
+                let v = self.values.get_value(value as usize).unwrap().clone();
+                ans.insert(v, idlp);
+            }
+        }
+
+        ans
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1,8 +1,9 @@
+use crate::event::{Index, Path};
 use crate::handler::{
     Handler, ListHandler, MapHandler, MovableListHandler, TextHandler, TreeHandler, ValueOrHandler,
 };
 use crate::jsonpath::ast::{
-    ComparisonOperator, FilterExpression, LogicalOperator, Segment, Selector,
+    ComparisonOperator, FilterExpression, LogicalOperator, Query, Segment, Selector,
 };
 use crate::jsonpath::JSONPathParser;
 use crate::{HandlerTrait, LoroDoc};
@@ -39,54 +40,167 @@ pub fn evaluate_jsonpath(
         .parse(jsonpath)
         .map_err(|e| JsonPathError::InvalidJsonPath(e.to_string()))?;
 
+    Ok(evaluate_query(root, &query))
+}
+
+/// Evaluate a parsed query.
+pub(crate) fn evaluate_query(root: &dyn PathValue, query: &Query) -> Vec<ValueOrHandler> {
     let mut results = Vec::new();
-    evaluate_segment(root, &query.segments, &mut results);
-    Ok(results)
+    evaluate_segment(root, &(), &query.segments, &mut results);
+    results.into_iter().map(|(_, node)| node).collect()
 }
 
-fn evaluate_segment(root: &dyn PathValue, segment: &Segment, results: &mut Vec<ValueOrHandler>) {
+/// Evaluate a parsed query, with the path from the root to every result.
+pub(crate) fn evaluate_jsonpath_with_paths(
+    root: &dyn PathValue,
+    query: &Query,
+) -> Vec<(Path, ValueOrHandler)> {
+    let mut results = Vec::new();
+    evaluate_segment(root, &Path::new(), &query.segments, &mut results);
+    results
+}
+
+/// Evaluate a parsed query into the parents of the selected nodes, with the index of every
+/// node in its parent.
+///
+/// Unlike the evaluation, a name selector of the last segment also selects the key when it's
+/// missing from a map, so that it can be set.
+pub(crate) fn evaluate_jsonpath_targets(
+    root: &dyn PathValue,
+    query: &Query,
+) -> Vec<(ValueOrHandler, Index)> {
+    let mut targets = Vec::new();
+    match &query.segments {
+        Segment::Root {} => {}
+        Segment::Child { left, selectors } => {
+            let mut parents = Vec::new();
+            evaluate_segment(root, &(), left, &mut parents);
+            for (_, parent) in parents {
+                for sel in selectors {
+                    if let Selector::Name { name } = sel {
+                        if is_map(&parent) {
+                            targets.push((parent.clone(), Index::Key(name.as_str().into())));
+                            continue;
+                        }
+                    }
+                    let mut children = Vec::new();
+                    apply_selectors(
+                        root,
+                        &parent,
+                        &Path::new(),
+                        std::slice::from_ref(sel),
+                        &mut children,
+                    );
+                    for (path, _) in children {
+                        targets.push((parent.clone(), path[0].clone()));
+                    }
+                }
+            }
+        }
+        Segment::Recursive { .. } => {
+            for (mut path, _) in evaluate_jsonpath_with_paths(root, query) {
+                let Some(index) = path.pop() else {
+                    continue;
+                };
+                if let Some(parent) = node_at(root, &path) {
+                    targets.push((parent, index));
+                }
+            }
+        }
+    }
+    targets
+}
+
+fn is_map(node: &ValueOrHandler) -> bool {
+    matches!(
+        node,
+        ValueOrHandler::Handler(Handler::Map(_)) | ValueOrHandler::Value(LoroValue::Map(_))
+    )
+}
+
+fn node_at(root: &dyn PathValue, path: &[Index]) -> Option<ValueOrHandler> {
+    let child_at = |node: &dyn PathValue, index: &Index| match index {
+        Index::Key(key) => node.get_by_key(key),
+        Index::Seq(i) => node.get_by_index(*i as isize),
+        Index::Node(_) => None,
+    };
+    let Some((first, rest)) = path.split_first() else {
+        return root.clone_this().ok();
+    };
+    let mut node = child_at(root, first)?;
+    for index in rest {
+        node = child_at(&node, index)?;
+    }
+    Some(node)
+}
+
+/// The location of a selected node. It's `()` when the paths are not needed, so that
+/// the evaluation doesn't build them.
+trait Location: Clone {
+    fn child(&self, index: impl FnOnce() -> Index) -> Self;
+}
+
+impl Location for () {
+    fn child(&self, _index: impl FnOnce() -> Index) -> Self {}
+}
+
+impl Location for Path {
+    fn child(&self, index: impl FnOnce() -> Index) -> Self {
+        let mut path = self.clone();
+        path.push(index());
+        path
+    }
+}
+
+fn evaluate_segment<L: Location>(
+    root: &dyn PathValue,
+    root_loc: &L,
+    segment: &Segment,
+    results: &mut Vec<(L, ValueOrHandler)>,
+) {
     match segment {
         Segment::Root {} => {
             if let Ok(cloned) = root.clone_this() {
-                results.push(cloned);
+                results.push((root_loc.clone(), cloned));
             }
         }
         Segment::Child { left, selectors } => {
             if left.is_singular() && matches!(**left, Segment::Root {}) {
-                apply_selectors(root, root, selectors, results);
+                apply_selectors(root, root, root_loc, selectors, results);
             } else {
                 let mut intermediate = Vec::new();
-                evaluate_segment(root, left, &mut intermediate);
-                for node in intermediate {
-                    apply_selectors(root, &node, selectors, results);
+                evaluate_segment(root, root_loc, left, &mut intermediate);
+                for (loc, node) in intermediate {
+                    apply_selectors(root, &node, &loc, selectors, results);
                 }
             }
         }
         Segment::Recursive { left, selectors } => {
             if left.is_singular() && matches!(**left, Segment::Root {}) {
-                recursive_descent(root, root, selectors, results);
+                recursive_descent(root, root, root_loc, selectors, results);
             } else {
                 let mut intermediate = Vec::new();
-                evaluate_segment(root, left, &mut intermediate);
-                for node in intermediate {
-                    recursive_descent(root, &node, selectors, results);
+                evaluate_segment(root, root_loc, left, &mut intermediate);
+                for (loc, node) in intermediate {
+                    recursive_descent(root, &node, &loc, selectors, results);
                 }
             }
         }
     }
 }
 
-fn apply_selectors(
+fn apply_selectors<L: Location>(
     root: &dyn PathValue,
     node: &dyn PathValue,
+    loc: &L,
     selectors: &[Selector],
-    results: &mut Vec<ValueOrHandler>,
+    results: &mut Vec<(L, ValueOrHandler)>,
 ) {
     for sel in selectors {
         match sel {
             Selector::Name { name } => {
                 if let Some(child) = node.get_by_key(name) {
-                    results.push(child);
+                    results.push((loc.child(|| Index::Key(name.as_str().into())), child));
                 }
             }
             Selector::Index { index } => {
@@ -96,7 +210,7 @@ fn apply_selectors(
                 }
                 if idx >= 0 {
                     if let Some(child) = node.get_by_index(idx as isize) {
-                        results.push(child);
+                        results.push((loc.child(|| Index::Seq(idx as usize)), child));
                     }
                 }
             }
@@ -130,7 +244,7 @@ fn apply_selectors(
                     let mut i = start_idx;
                     while i < stop_idx {
                         if let Some(child) = node.get_by_index(i as isize) {
-                            results.push(child);
+                            results.push((loc.child(|| Index::Seq(i as usize)), child));
                         }
                         i += eff_step;
                     }
@@ -140,22 +254,22 @@ fn apply_selectors(
                     let mut i = start_idx;
                     while i > stop_idx {
                         if let Some(child) = node.get_by_index(i as isize) {
-                            results.push(child);
+                            results.push((loc.child(|| Index::Seq(i as usize)), child));
                         }
                         i += eff_step;
                     }
                 }
             }
             Selector::Wild {} => {
-                node.for_each_for_path(&mut |child| {
-                    results.push(child);
+                node.for_each_for_path(&mut |index, child| {
+                    results.push((loc.child(|| index), child));
                     ControlFlow::Continue(())
                 });
             }
             Selector::Filter { expression } => {
-                node.for_each_for_path(&mut |child| {
+                node.for_each_for_path(&mut |index, child| {
                     if eval_filter_expr(root, &child, expression) {
-                        results.push(child);
+                        results.push((loc.child(|| index), child));
                     }
                     ControlFlow::Continue(())
                 });
@@ -164,18 +278,19 @@ fn apply_selectors(
     }
 }
 
-fn recursive_descent(
+fn recursive_descent<L: Location>(
     root: &dyn PathValue,
     node: &dyn PathValue,
+    loc: &L,
     selectors: &[Selector],
-    results: &mut Vec<ValueOrHandler>,
+    results: &mut Vec<(L, ValueOrHandler)>,
 ) {
     // 1. apply selectors to the *current* node
-    apply_selectors(root, node, selectors, results);
+    apply_selectors(root, node, loc, selectors, results);
 
     // 2. recurse into children
-    node.for_each_for_path(&mut |child| {
-        recursive_descent(root, &child, selectors, results);
+    node.for_each_for_path(&mut |index, child| {
+        recursive_descent(root, &child, &loc.child(|| index), selectors, results);
         ControlFlow::Continue(())
     });
 }
@@ -245,13 +360,13 @@ fn eval_expr(root: &dyn PathValue, current: &dyn PathValue, expr: &FilterExpress
         }
         FilterExpression::RelativeQuery { query } => {
             let mut query_results = Vec::new();
-            evaluate_segment(current, &query.segments, &mut query_results);
-            ExprValue::Nodes(query_results)
+            evaluate_segment(current, &(), &query.segments, &mut query_results);
+            ExprValue::Nodes(query_results.into_iter().map(|(_, node)| node).collect())
         }
         FilterExpression::RootQuery { query } => {
             let mut query_results = Vec::new();
-            evaluate_segment(root, &query.segments, &mut query_results);
-            ExprValue::Nodes(query_results)
+            evaluate_segment(root, &(), &query.segments, &mut query_results);
+            ExprValue::Nodes(query_results.into_iter().map(|(_, node)| node).collect())
         }
         FilterExpression::Function { name, args } => eval_function(root, current, name, args),
     }
@@ -466,7 +581,8 @@ fn get_other_value(v: &ExprValue) -> LoroValue {
 pub trait PathValue {
     fn get_by_key(&self, key: &str) -> Option<ValueOrHandler>;
     fn get_by_index(&self, index: isize) -> Option<ValueOrHandler>;
-    fn for_each_for_path(&self, f: &mut dyn FnMut(ValueOrHandler) -> ControlFlow<()>);
+    /// Call `f` with every child and its index in this node, until it breaks
+    fn for_each_for_path(&self, f: &mut dyn FnMut(Index, ValueOrHandler) -> ControlFlow<()>);
     fn length_for_path(&self) -> usize;
     fn get_child_by_id(&self, id: ContainerID) -> Option<Handler>;
     fn clone_this(&self) -> Result<ValueOrHandler, JsonPathError>;
@@ -488,7 +604,7 @@ impl PathValue for ValueOrHandler {
         }
     }
 
-    fn for_each_for_path(&self, f: &mut dyn FnMut(ValueOrHandler) -> ControlFlow<()>) {
+    fn for_each_for_path(&self, f: &mut dyn FnMut(Index, ValueOrHandler) -> ControlFlow<()>) {
         match self {
             ValueOrHandler::Value(v) => v.for_each_for_path(f),
             ValueOrHandler::Handler(h) => h.for_each_for_path(f),
@@ -527,13 +643,14 @@ impl PathValue for LoroDoc {
         None // LoroDoc doesn't support index-based access
     }
 
-    fn for_each_for_path(&self, f: &mut dyn FnMut(ValueOrHandler) -> ControlFlow<()>) {
+    fn for_each_for_path(&self, f: &mut dyn FnMut(Index, ValueOrHandler) -> ControlFlow<()>) {
         let roots = self.state.lock().preferred_root_containers();
         let arena = self.arena();
         for c in roots {
             let cid = arena.idx_to_id(c).unwrap();
+            let index = Index::Key(cid.name().clone());
             let h = self.get_handler(cid).unwrap();
-            if f(ValueOrHandler::Handler(h)) == ControlFlow::Break(()) {
+            if f(index, ValueOrHandler::Handler(h)) == ControlFlow::Break(()) {
                 break;
             }
         }
@@ -570,7 +687,7 @@ impl PathValue for Handler {
         }
     }
 
-    fn for_each_for_path(&self, f: &mut dyn FnMut(ValueOrHandler) -> ControlFlow<()>) {
+    fn for_each_for_path(&self, f: &mut dyn FnMut(Index, ValueOrHandler) -> ControlFlow<()>) {
         match self {
             Handler::Map(h) => h.for_each_for_path(f),
             Handler::List(h) => h.for_each_for_path(f),
@@ -616,14 +733,14 @@ impl PathValue for MapHandler {
         None
     }
 
-    fn for_each_for_path(&self, f: &mut dyn FnMut(ValueOrHandler) -> ControlFlow<()>) {
+    fn for_each_for_path(&self, f: &mut dyn FnMut(Index, ValueOrHandler) -> ControlFlow<()>) {
         let mut done = false;
-        self.for_each(|_, v| {
+        self.for_each(|k, v| {
             if done {
                 return;
             }
 
-            if let ControlFlow::Break(_) = f(v) {
+            if let ControlFlow::Break(_) = f(Index::Key(k.into()), v) {
                 done = true;
             }
         });
@@ -655,16 +772,18 @@ impl PathValue for ListHandler {
         }
     }
 
-    fn for_each_for_path(&self, f: &mut dyn FnMut(ValueOrHandler) -> ControlFlow<()>) {
+    fn for_each_for_path(&self, f: &mut dyn FnMut(Index, ValueOrHandler) -> ControlFlow<()>) {
         let mut done = false;
+        let mut i = 0;
         self.for_each(|v| {
             if done {
                 return;
             }
 
-            if let ControlFlow::Break(_) = f(v) {
+            if let ControlFlow::Break(_) = f(Index::Seq(i), v) {
                 done = true;
             }
+            i += 1;
         });
     }
 
@@ -698,16 +817,18 @@ impl PathValue for MovableListHandler {
         }
     }
 
-    fn for_each_for_path(&self, f: &mut dyn FnMut(ValueOrHandler) -> ControlFlow<()>) {
+    fn for_each_for_path(&self, f: &mut dyn FnMut(Index, ValueOrHandler) -> ControlFlow<()>) {
         let mut done = false;
+        let mut i = 0;
         self.for_each(|v| {
             if done {
                 return;
             }
 
-            if let ControlFlow::Break(_) = f(v) {
+            if let ControlFlow::Break(_) = f(Index::Seq(i), v) {
                 done = true;
             }
+            i += 1;
         })
     }
 
@@ -733,7 +854,7 @@ impl PathValue for TextHandler {
         None
     }
 
-    fn for_each_for_path(&self, _f: &mut dyn FnMut(ValueOrHandler) -> ControlFlow<()>) {
+    fn for_each_for_path(&self, _f: &mut dyn FnMut(Index, ValueOrHandler) -> ControlFlow<()>) {
         // TextHandler doesn't have children to iterate over
     }
 
@@ -759,7 +880,7 @@ impl PathValue for TreeHandler {
         None
     }
 
-    fn for_each_for_path(&self, _f: &mut dyn FnMut(ValueOrHandler) -> ControlFlow<()>) {
+    fn for_each_for_path(&self, _f: &mut dyn FnMut(Index, ValueOrHandler) -> ControlFlow<()>) {
         unimplemented!()
     }
 
@@ -802,18 +923,23 @@ impl PathValue for LoroValue {
         }
     }
 
-    fn for_each_for_path(&self, f: &mut dyn FnMut(ValueOrHandler) -> ControlFlow<()>) {
+    fn for_each_for_path(&self, f: &mut dyn FnMut(Index, ValueOrHandler) -> ControlFlow<()>) {
         match self {
             LoroValue::List(list) => {
-                for item in list.iter() {
-                    if let ControlFlow::Break(_) = f(ValueOrHandler::Value(item.clone())) {
+                for (i, item) in list.iter().enumerate() {
+                    if let ControlFlow::Break(_) =
+                        f(Index::Seq(i), ValueOrHandler::Value(item.clone()))
+                    {
                         break;
                     }
                 }
             }
             LoroValue::Map(map) => {
-                for (_, value) in map.iter() {
-                    if let ControlFlow::Break(_) = f(ValueOrHandler::Value(value.clone())) {
+                for (key, value) in map.iter() {
+                    if let ControlFlow::Break(_) = f(
+                        Index::Key(key.as_str().into()),
+                        ValueOrHandler::Value(value.clone()),
+                    ) {
                         break;
                     }
                 }
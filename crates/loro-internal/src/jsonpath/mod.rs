@@ -2,8 +2,11 @@ pub mod ast;
 pub mod errors;
 pub mod jsonpath_impl;
 pub mod subscription;
+mod write;
 
-pub use subscription::SubscribeJsonPathCallback;
+pub use subscription::{
+    JsonPathDiff, JsonPathItem, SubscribeJsonPathCallback, SubscribeJsonPathDiffCallback,
+};
 pub mod parser;
 
 pub use ast::Query;
@@ -10,6 +10,9 @@
 //!   (missing a notification when the result did change).
 //! - **Lightweight notifications**: Callbacks receive no payload; applications can
 //!   debounce/throttle and evaluate the JSONPath themselves.
+//! - **Opt-in result diffs**: `subscribe_jsonpath_diff` re-evaluates the query itself on
+//!   every event that may affect it, and delivers the results that were added, removed or
+//!   changed since the last notification.
 //! - **Efficient matching**: Uses an NFA (non-deterministic finite automaton) approach
 //!   to match event paths against compiled JSONPath queries in O(path_len × steps) time.
 //!
@@ -24,19 +27,23 @@
 //!    - Recursive steps allow staying at the same state while consuming input
 //!    - Acceptance occurs when any state reaches or exceeds `steps.len()`
 
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
-use loro_common::TreeID;
+use loro_common::{LoroValue, TreeID};
+use rustc_hash::FxHashMap;
 use smallvec::SmallVec;
 
 use crate::{
-    event::{Diff, Index},
+    event::{Diff, DiffEvent, Index, Path},
+    handler::ValueOrHandler,
     jsonpath::{
         ast::{Query, Segment, Selector},
+        jsonpath_impl::evaluate_jsonpath_with_paths,
         JSONPathParser,
     },
+    sync::Mutex,
     utils::subscription::Subscription,
-    LoroDoc, LoroError, LoroResult,
+    LoroDoc, LoroDocInner, LoroError, LoroResult,
 };
 
 /// Callback used by `subscribe_jsonpath`.
@@ -46,6 +53,34 @@ use crate::{
 /// JSONPath themselves if needed.
 pub type SubscribeJsonPathCallback = Arc<dyn Fn() + Send + Sync + 'static>;
 
+/// Callback used by `subscribe_jsonpath_diff`. It's never called with an empty diff.
+pub type SubscribeJsonPathDiffCallback = Arc<dyn Fn(&JsonPathDiff) + Send + Sync + 'static>;
+
+/// A result of a JSONPath query, with its path from the root of the document.
+#[derive(Debug, Clone)]
+pub struct JsonPathItem {
+    pub path: Vec<Index>,
+    pub value: ValueOrHandler,
+}
+
+/// How the results of a JSONPath query changed since the last notification.
+///
+/// Results are identified by their path, and a result is changed if its deep value changed.
+/// `added` and `changed` hold the current results, `removed` holds the results as they were
+/// at the last notification.
+#[derive(Debug, Clone, Default)]
+pub struct JsonPathDiff {
+    pub added: Vec<JsonPathItem>,
+    pub removed: Vec<JsonPathItem>,
+    pub changed: Vec<JsonPathItem>,
+}
+
+impl JsonPathDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
 /// Represents a single path segment used for matching against events.
 ///
 /// Path elements are derived from event paths and represent the location of a change
@@ -183,6 +218,70 @@ impl JsonPathMatcher {
 
         positions
     }
+
+    /// Returns true if the event may change the query result.
+    fn may_be_affected_by(&self, event: &DiffEvent) -> bool {
+        for container_diff in event.events.iter() {
+            // Convert the container's path to PathElem representation
+            let base_path: SmallVec<[PathElem; 8]> = container_diff
+                .path
+                .iter()
+                .map(|(_, idx)| PathElem::from(idx))
+                .collect();
+
+            // Check 1: Does the container path itself match the query?
+            if self.may_match(&base_path) {
+                return true;
+            }
+
+            // Check 2: Map-specific handling - check each changed key
+            if let Diff::Map(map) = &container_diff.diff {
+                let base_positions = self.positions_after(&base_path);
+                if base_positions.is_empty() {
+                    continue;
+                }
+
+                // If we passed through a Wildcard (filter/wild/slice), any key
+                // change could affect the result - be conservative.
+                let past_wildcard = self.passed_through_wildcard(&base_positions);
+
+                for key in map.updated.keys() {
+                    let mut extended: SmallVec<[PathElem; 8]> = base_path.clone();
+                    extended.push(PathElem::Key(key.as_ref().into()));
+
+                    let extended_positions = self.positions_after(&extended);
+
+                    // Trigger if key path could match OR we're inside a wildcard selection
+                    if !extended_positions.is_empty() || past_wildcard {
+                        return true;
+                    }
+                }
+            }
+
+            // Check 3: List/Tree/Counter/Set child mutations
+            // These container types can have child changes at unknown indices
+            let has_child_changes = matches!(
+                &container_diff.diff,
+                Diff::List(_) | Diff::Tree(_) | Diff::Unknown
+            );
+            #[cfg(feature = "counter")]
+            let has_child_changes =
+                has_child_changes || matches!(&container_diff.diff, Diff::Counter(_));
+            #[cfg(feature = "set")]
+            let has_child_changes =
+                has_child_changes || matches!(&container_diff.diff, Diff::Set(_));
+
+            if has_child_changes {
+                let mut extended: SmallVec<[PathElem; 8]> = base_path.clone();
+                extended.push(PathElem::Seq(None)); // "some child changed"
+                if !self.positions_after(&extended).is_empty() {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
 }
 
 /// Check if any selector matches the given path element.
@@ -271,81 +370,129 @@ impl LoroDoc {
                 return;
             }
 
-            let matcher = &matcher;
-            let mut fired = false;
-
-            for container_diff in event.events.iter() {
-                if fired {
-                    break;
-                }
-
-                // Convert the container's path to PathElem representation
-                let base_path: SmallVec<[PathElem; 8]> = container_diff
-                    .path
-                    .iter()
-                    .map(|(_, idx)| PathElem::from(idx))
-                    .collect();
+            if matcher.may_be_affected_by(&event) {
+                (callback)();
+            }
+        }));
 
-                // Check 1: Does the container path itself match the query?
-                if matcher.may_match(&base_path) {
-                    fired = true;
-                    break;
-                }
+        Ok(sub)
+    }
+}
 
-                // Check 2: Map-specific handling - check each changed key
-                if let Diff::Map(map) = &container_diff.diff {
-                    let base_positions = matcher.positions_after(&base_path);
-                    if base_positions.is_empty() {
-                        continue;
-                    }
+// =============================================================================
+// Result Diffs
+// =============================================================================
 
-                    // If we passed through a Wildcard (filter/wild/slice), any key
-                    // change could affect the result - be conservative.
-                    let past_wildcard = matcher.passed_through_wildcard(&base_positions);
+/// A result of the last evaluation, with its deep value to detect changes.
+struct JsonPathEntry {
+    path: Path,
+    value: ValueOrHandler,
+    deep_value: LoroValue,
+}
 
-                    for key in map.updated.keys() {
-                        let mut extended: SmallVec<[PathElem; 8]> = base_path.clone();
-                        extended.push(PathElem::Key(key.as_ref().into()));
+impl JsonPathEntry {
+    fn to_item(&self) -> JsonPathItem {
+        JsonPathItem {
+            path: self.path.to_vec(),
+            value: self.value.clone(),
+        }
+    }
+}
 
-                        let extended_positions = matcher.positions_after(&extended);
+struct JsonPathDiffInner {
+    doc: Weak<LoroDocInner>,
+    query: Query,
+    callback: SubscribeJsonPathDiffCallback,
+    results: Mutex<Vec<JsonPathEntry>>,
+}
 
-                        // Trigger if key path could match OR we're inside a wildcard selection
-                        if !extended_positions.is_empty() || past_wildcard {
-                            fired = true;
-                            break;
-                        }
-                    }
+impl JsonPathDiffInner {
+    fn evaluate(&self, doc: &LoroDoc) -> Vec<JsonPathEntry> {
+        evaluate_jsonpath_with_paths(doc, &self.query)
+            .into_iter()
+            .map(|(path, value)| JsonPathEntry {
+                deep_value: value.to_deep_value(),
+                path,
+                value,
+            })
+            .collect()
+    }
 
-                    if fired {
-                        break;
-                    }
-                }
+    /// Re-evaluate the query and notify the changed results.
+    ///
+    /// The callback is called after the lock is released, so it can edit the doc.
+    fn notify(&self) {
+        let Some(doc) = self.doc.upgrade() else {
+            return;
+        };
+        let doc = LoroDoc::from_inner(doc);
+        let diff = {
+            let mut old = self.results.lock();
+            let results = self.evaluate(&doc);
+            let diff = diff_results(&old, &results);
+            *old = results;
+            diff
+        };
+        if !diff.is_empty() {
+            (self.callback)(&diff);
+        }
+    }
+}
 
-                // Check 3: List/Tree/Counter child mutations
-                // These container types can have child changes at unknown indices
-                let has_child_changes = matches!(
-                    &container_diff.diff,
-                    Diff::List(_) | Diff::Tree(_) | Diff::Unknown
-                );
-                #[cfg(feature = "counter")]
-                let has_child_changes =
-                    has_child_changes || matches!(&container_diff.diff, Diff::Counter(_));
-
-                if has_child_changes {
-                    let mut extended: SmallVec<[PathElem; 8]> = base_path.clone();
-                    extended.push(PathElem::Seq(None)); // "some child changed"
-                    if !matcher.positions_after(&extended).is_empty() {
-                        fired = true;
-                    }
-                }
-            }
+fn diff_results(old: &[JsonPathEntry], new: &[JsonPathEntry]) -> JsonPathDiff {
+    let old_by_path: FxHashMap<&Path, &JsonPathEntry> = old.iter().map(|e| (&e.path, e)).collect();
+    let new_by_path: FxHashMap<&Path, &JsonPathEntry> = new.iter().map(|e| (&e.path, e)).collect();
+    let mut diff = JsonPathDiff::default();
+    for entry in new {
+        match old_by_path.get(&entry.path) {
+            None => diff.added.push(entry.to_item()),
+            Some(old) if old.deep_value != entry.deep_value => diff.changed.push(entry.to_item()),
+            Some(_) => {}
+        }
+    }
+    for entry in old {
+        if !new_by_path.contains_key(&entry.path) {
+            diff.removed.push(entry.to_item());
+        }
+    }
+    diff
+}
 
-            if fired {
-                (callback)();
+impl LoroDoc {
+    /// Subscribe to the changes of the results of the given JSONPath query.
+    ///
+    /// ## Behavior
+    ///
+    /// - The results at subscription time are the baseline of the first notification.
+    /// - The query is re-evaluated on every event that may affect it (see
+    ///   `subscribe_jsonpath`), so the cost of an event grows with the query results.
+    ///   Use `subscribe_jsonpath` to debounce the evaluation instead.
+    /// - The callback is only called when a result was added, removed or changed.
+    #[cfg(feature = "jsonpath")]
+    pub fn subscribe_jsonpath_diff(
+        &self,
+        jsonpath: &str,
+        callback: SubscribeJsonPathDiffCallback,
+    ) -> LoroResult<Subscription> {
+        let query = JSONPathParser::new()
+            .parse(jsonpath)
+            .map_err(|e| LoroError::ArgErr(e.to_string().into_boxed_str()))?;
+        let matcher = JsonPathMatcher::new(&query);
+        let inner = Arc::new(JsonPathDiffInner {
+            doc: Arc::downgrade(&self.inner),
+            query,
+            callback,
+            results: Mutex::new(Vec::new()),
+        });
+        *inner.results.lock() = inner.evaluate(self);
+
+        Ok(self.subscribe_root(Arc::new(move |event| {
+            if event.events.is_empty() || !matcher.may_be_affected_by(&event) {
+                return;
             }
-        }));
 
-        Ok(sub)
+            inner.notify();
+        })))
     }
 }
 
@@ -0,0 +1,212 @@
+//! # JSONPath Write Operations
+//!
+//! The nodes selected by a query are resolved to the containers that hold them, and the
+//! edits are applied to these containers. All the targets are resolved and checked before
+//! the first op is created, and the ops are created in one transaction, so an invalid query
+//! or value leaves the document untouched.
+//!
+//! Only the entries of maps and lists can be edited. The roots of the document and the
+//! values nested in a `LoroValue` can't.
+
+use std::cmp::Reverse;
+
+use loro_common::{ContainerID, InternalString, LoroError, LoroResult, LoroValue};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    event::Index,
+    handler::{
+        ensure_no_regular_container_value, with_txn, Handler, ListHandler, MapHandler,
+        MovableListHandler, ValueOrHandler,
+    },
+    jsonpath::{
+        ast::Query,
+        jsonpath_impl::{evaluate_jsonpath_targets, evaluate_query},
+        JSONPathParser,
+    },
+    HandlerTrait, LoroDoc,
+};
+
+/// A node selected by a query, as the container that holds it and its index in the container.
+enum Target {
+    Map(MapHandler, InternalString),
+    List(ListHandler, usize),
+    MovableList(MovableListHandler, usize),
+}
+
+impl Target {
+    fn list_index(&self) -> Option<usize> {
+        match self {
+            Target::Map(..) => None,
+            Target::List(_, i) | Target::MovableList(_, i) => Some(*i),
+        }
+    }
+}
+
+fn parse(jsonpath: &str) -> LoroResult<Query> {
+    JSONPathParser::new()
+        .parse(jsonpath)
+        .map_err(|e| LoroError::ArgErr(e.to_string().into_boxed_str()))
+}
+
+fn not_editable(jsonpath: &str) -> LoroError {
+    LoroError::ArgErr(
+        format!(
+            "JSONPath `{jsonpath}` selects a node that is not an entry of a map or a list container"
+        )
+        .into_boxed_str(),
+    )
+}
+
+impl LoroDoc {
+    /// Resolve the nodes selected by the query, without duplicates.
+    fn jsonpath_targets(&self, jsonpath: &str) -> LoroResult<Vec<Target>> {
+        let query = parse(jsonpath)?;
+        let mut seen: FxHashSet<(ContainerID, Index)> = FxHashSet::default();
+        let mut targets = Vec::new();
+        for (parent, index) in evaluate_jsonpath_targets(self, &query) {
+            let target = match (parent, index.clone()) {
+                (ValueOrHandler::Handler(Handler::Map(map)), Index::Key(key)) => {
+                    Target::Map(map, key)
+                }
+                (ValueOrHandler::Handler(Handler::List(list)), Index::Seq(i)) => {
+                    Target::List(list, i)
+                }
+                (ValueOrHandler::Handler(Handler::MovableList(list)), Index::Seq(i)) => {
+                    Target::MovableList(list, i)
+                }
+                _ => return Err(not_editable(jsonpath)),
+            };
+            let id = match &target {
+                Target::Map(map, _) => map.id(),
+                Target::List(list, _) => list.id(),
+                Target::MovableList(list, _) => list.id(),
+            };
+            if seen.insert((id, index)) {
+                targets.push(target);
+            }
+        }
+        Ok(targets)
+    }
+
+    /// Set every node selected by the JSONPath query to `value`, in one transaction.
+    ///
+    /// A name selector at the end of the query also selects the key when it's missing, so
+    /// `$.todos[?(@.priority > 3)].done` sets `done` on every todo with a priority over 3.
+    /// The selected nodes must be entries of map or list containers.
+    ///
+    /// Returns the number of nodes that were set.
+    pub fn jsonpath_set(&self, jsonpath: &str, value: impl Into<LoroValue>) -> LoroResult<usize> {
+        let targets = self.jsonpath_targets(jsonpath)?;
+        let value = value.into();
+        ensure_no_regular_container_value(&value)?;
+        if targets.is_empty() {
+            return Ok(0);
+        }
+
+        with_txn(self, |txn| {
+            for target in targets.iter() {
+                match target {
+                    Target::Map(map, key) => map.insert_with_txn(txn, key, value.clone())?,
+                    Target::List(list, i) => {
+                        if list.get(*i).as_ref() != Some(&value) {
+                            list.delete_with_txn(txn, *i, 1)?;
+                            list.insert_with_txn(txn, *i, value.clone())?;
+                        }
+                    }
+                    Target::MovableList(list, i) => list.set_with_txn(txn, *i, value.clone())?,
+                }
+            }
+            Ok(targets.len())
+        })
+    }
+
+    /// Delete every node selected by the JSONPath query, in one transaction.
+    ///
+    /// The selected nodes must be entries of map or list containers.
+    ///
+    /// Returns the number of deleted nodes.
+    pub fn jsonpath_delete(&self, jsonpath: &str) -> LoroResult<usize> {
+        let mut targets = self.jsonpath_targets(jsonpath)?;
+        targets.retain(|target| match target {
+            Target::Map(map, key) => map.get(key).is_some(),
+            _ => true,
+        });
+        if targets.is_empty() {
+            return Ok(0);
+        }
+
+        // Delete the last entries of a list first, so the indexes of the others don't move
+        targets.sort_by_key(|t| Reverse(t.list_index()));
+        with_txn(self, |txn| {
+            for target in targets.iter() {
+                match target {
+                    Target::Map(map, key) => map.delete_with_txn(txn, key)?,
+                    Target::List(list, i) => list.delete_with_txn(txn, *i, 1)?,
+                    Target::MovableList(list, i) => list.delete_with_txn(txn, *i, 1)?,
+                }
+            }
+            Ok(targets.len())
+        })
+    }
+
+    /// Insert `value` at `index` into every list selected by the JSONPath query, in one
+    /// transaction.
+    ///
+    /// The selected nodes must be list or movable list containers, and `index` must not be
+    /// greater than the length of any of them.
+    ///
+    /// Returns the number of lists the value was inserted into.
+    pub fn jsonpath_insert(
+        &self,
+        jsonpath: &str,
+        index: usize,
+        value: impl Into<LoroValue>,
+    ) -> LoroResult<usize> {
+        let query = parse(jsonpath)?;
+        let value = value.into();
+        ensure_no_regular_container_value(&value)?;
+        let mut seen: FxHashSet<ContainerID> = FxHashSet::default();
+        let mut lists = Vec::new();
+        for node in evaluate_query(self, &query) {
+            let (id, len) = match &node {
+                ValueOrHandler::Handler(Handler::List(list)) => (list.id(), list.len()),
+                ValueOrHandler::Handler(Handler::MovableList(list)) => (list.id(), list.len()),
+                _ => {
+                    return Err(LoroError::ArgErr(
+                        format!("JSONPath `{jsonpath}` selects a node that is not a list")
+                            .into_boxed_str(),
+                    ))
+                }
+            };
+            if index > len {
+                return Err(LoroError::OutOfBound {
+                    pos: index,
+                    info: format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+                    len,
+                });
+            }
+            if seen.insert(id) {
+                lists.push(node);
+            }
+        }
+        if lists.is_empty() {
+            return Ok(0);
+        }
+
+        with_txn(self, |txn| {
+            for list in lists.iter() {
+                match list {
+                    ValueOrHandler::Handler(Handler::List(list)) => {
+                        list.insert_with_txn(txn, index, value.clone())?
+                    }
+                    ValueOrHandler::Handler(Handler::MovableList(list)) => {
+                        list.insert_with_txn(txn, index, value.clone())?
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Ok(lists.len())
+        })
+    }
+}
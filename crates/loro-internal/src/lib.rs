@@ -12,6 +12,8 @@
 pub mod arena;
 pub mod diff;
 pub mod diff_calc;
+mod diff_summary;
+pub use diff_summary::{AuthorSummary, ChangeSummary, ContainerSummary, DiffSummary, MapKeyChange};
 pub mod handler;
 pub mod sync;
 use crate::sync::{AtomicBool, AtomicUsize};
@@ -45,7 +47,13 @@ pub use utils::subscription::SubscriberSetWithQueue;
 pub use utils::subscription::Subscription;
 pub mod allocation;
 pub mod awareness;
+mod branch;
+pub use branch::BranchMerge;
 pub mod change;
+mod change_query;
+pub use change_query::{ChangeCursor, ChangeOrder, ChangePage, ChangeQuery};
+mod compact;
+pub use compact::{Checkpoint, CompactedHistory, CompactionPolicy};
 pub mod configure;
 pub mod container;
 pub mod cursor;
@@ -60,6 +68,7 @@ pub mod loro;
 pub mod op;
 pub mod oplog;
 pub mod subscription;
+mod tag;
 pub mod txn;
 pub mod version;
 
@@ -68,6 +77,7 @@ mod error;
 pub mod fuzz;
 mod parent;
 pub mod pre_commit;
+pub mod schema;
 mod span;
 #[cfg(test)]
 pub mod tests;
@@ -81,6 +91,7 @@ pub mod event;
 pub mod estimated_size;
 pub(crate) mod history_cache;
 pub(crate) mod macros;
+mod metadata;
 pub(crate) mod state;
 pub mod undo;
 pub(crate) mod value;
@@ -169,6 +180,10 @@ pub struct LoroDocInner {
     first_commit_from_peer_subs:
         SubscriberSetWithQueue<(), FirstCommitFromPeerCallback, FirstCommitFromPeerPayload>,
     pre_commit_subs: SubscriberSetWithQueue<(), PreCommitCallback, PreCommitCallbackPayload>,
+    current_branch: sync::Mutex<Option<branch::CurrentBranch>>,
+    change_timestamps: sync::Mutex<change_query::ChangeTimestamps>,
+    /// The reason the last commit was rejected by a pre-commit callback
+    last_commit_rejection: sync::Mutex<Option<Arc<str>>>,
 }
 
 /// The version of the loro crate
@@ -44,8 +44,8 @@ use crate::{
 };
 use either::Either;
 use loro_common::{
-    ContainerID, ContainerType, HasCounterSpan, HasIdSpan, HasLamportSpan, IdSpan, LoroEncodeError,
-    LoroResult, LoroValue, ID,
+    ContainerID, ContainerType, HasCounterSpan, HasId, HasIdSpan, HasLamportSpan, IdSpan,
+    LoroEncodeError, LoroResult, LoroValue, ID,
 };
 use rle::HasLength;
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -123,6 +123,9 @@ impl LoroDoc {
                 peer_id_change_subs: SubscriberSetWithQueue::new(),
                 pre_commit_subs: SubscriberSetWithQueue::new(),
                 first_commit_from_peer_subs: SubscriberSetWithQueue::new(),
+                current_branch: Default::default(),
+                change_timestamps: Default::default(),
+                last_commit_rejection: Default::default(),
             }
         });
         LoroDoc { inner }
@@ -332,8 +335,22 @@ impl LoroDoc {
                 txn.set_msg(Some(msg.clone()));
             }
 
+            let branch_head = if txn.is_empty() {
+                None
+            } else {
+                self.record_branch_head(&mut txn)
+            };
+
+            let mut options = txn._commit().unwrap();
+            // Read the span after committing: a change rejected by a pre-commit
+            // callback leaves an empty span behind
             let id_span = txn.id_span();
-            let mut options = txn.commit().unwrap();
+            drop(txn);
+            if let Some(head) = branch_head {
+                if id_span.atom_len() > 0 {
+                    self.move_branch_head(head);
+                }
+            }
             // Empty commit returns Some(options). We may preserve parts of it for implicit commits.
             if let Some(opts) = options.as_mut() {
                 // `origin` is an event-only label and never carries across an empty commit
@@ -1096,6 +1113,47 @@ impl LoroDoc {
             .expect("The container does not exist in the document. Use `try_get_counter` or `get_container` to check for existence.")
     }
 
+    #[cfg(feature = "typed-counter")]
+    pub fn try_get_typed_counter<I: IntoContainerId>(
+        &self,
+        id: I,
+    ) -> Option<crate::handler::counter::CounterHandler> {
+        let id = id.into_container_id(&self.arena, ContainerType::TypedCounter);
+        if !self.has_container(&id) {
+            return None;
+        }
+        self.ensure_root_container(&id);
+        Handler::new_attached(id, self.clone()).into_counter().ok()
+    }
+
+    #[cfg(feature = "typed-counter")]
+    pub fn get_typed_counter<I: IntoContainerId>(
+        &self,
+        id: I,
+    ) -> crate::handler::counter::CounterHandler {
+        self.try_get_typed_counter(id)
+            .expect("The container does not exist in the document. Use `try_get_typed_counter` or `get_container` to check for existence.")
+    }
+
+    #[cfg(feature = "set")]
+    pub fn try_get_set<I: IntoContainerId>(
+        &self,
+        id: I,
+    ) -> Option<crate::handler::set::SetHandler> {
+        let id = id.into_container_id(&self.arena, ContainerType::Set);
+        if !self.has_container(&id) {
+            return None;
+        }
+        self.ensure_root_container(&id);
+        Handler::new_attached(id, self.clone()).into_set().ok()
+    }
+
+    #[cfg(feature = "set")]
+    pub fn get_set<I: IntoContainerId>(&self, id: I) -> crate::handler::set::SetHandler {
+        self.try_get_set(id)
+            .expect("The container does not exist in the document. Use `try_get_set` or `get_container` to check for existence.")
+    }
+
     #[must_use]
     pub fn has_container(&self, id: &ContainerID) -> bool {
         if id.is_root() && !id.is_mergeable() {
@@ -1125,6 +1183,7 @@ impl LoroDoc {
         id_span: IdSpan,
         container_remap: &mut FxHashMap<ContainerID, ContainerID>,
         post_transform_base: Option<&DiffBatch>,
+        scope: Option<&dyn Fn(&ContainerID) -> bool>,
         before_diff: &mut dyn FnMut(&DiffBatch),
     ) -> LoroResult<CommitWhenDrop<'_>> {
         if !self.can_edit() {
@@ -1145,7 +1204,7 @@ impl LoroDoc {
         };
 
         let spans = self.oplog.lock().split_span_based_on_deps(id_span);
-        let diff = crate::undo::undo(
+        let mut diff = crate::undo::undo(
             spans,
             match post_transform_base {
                 Some(d) => Either::Right(d),
@@ -1162,6 +1221,10 @@ impl LoroDoc {
             },
             before_diff,
         );
+        if let Some(scope) = scope {
+            // Changes outside of the scope are left as they are
+            diff.retain(scope);
+        }
 
         // println!("\nundo_internal: diff: {:?}", diff);
         // println!("container remap: {:?}", container_remap);
@@ -1189,6 +1252,94 @@ impl LoroDoc {
         })
     }
 
+    /// Generate a series of local operations that revert the changes in the given spans.
+    ///
+    /// The spans can be from any peer. The revert of each span is transformed against all the
+    /// changes after it, so the later edits, including the concurrent ones, are kept.
+    pub fn revert_changes(&self, spans: &[IdSpan]) -> LoroResult<()> {
+        if !self.can_edit() {
+            return Err(LoroError::EditWhenDetached);
+        }
+
+        let spans = {
+            let oplog = self.oplog.lock();
+            let mut ans = Vec::with_capacity(spans.len());
+            for span in spans.iter().filter(|span| span.content_len() > 0) {
+                let mut span = *span;
+                span.normalize_();
+                if !oplog.vv().includes_id(span.id_last()) {
+                    return Err(LoroError::UndoInvalidIdSpan(span.id_last()));
+                }
+                if oplog.shallow_since_vv().includes_id(span.id_start()) {
+                    return Err(LoroError::SwitchToVersionBeforeShallowRoot);
+                }
+                let split = oplog.split_span_based_on_deps(span);
+                if split
+                    .iter()
+                    .any(|(_, deps)| oplog.dag.is_before_shallow_root(deps))
+                {
+                    return Err(LoroError::SwitchToVersionBeforeShallowRoot);
+                }
+                ans.push(split);
+            }
+            ans
+        };
+        if spans.is_empty() {
+            return Ok(());
+        }
+
+        let (options, txn) = self.implicit_commit_then_stop();
+        let (was_recording, latest_frontiers) = {
+            let mut state = self.state.lock();
+            let was_recording = state.is_recording();
+            state.stop_and_clear_recording();
+            (was_recording, state.frontiers.clone())
+        };
+
+        // Every span is reverted based on the latest version, and the reverts are combined
+        // by transforming each of them against the ones before it.
+        let mut diff: Option<DiffBatch> = None;
+        for split in spans {
+            let mut revert = crate::undo::undo(
+                split,
+                Either::Left(&latest_frontiers),
+                |from, to| {
+                    self._checkout_without_emitting(from, false, false).unwrap();
+                    self.state.lock().start_recording();
+                    self._checkout_without_emitting(to, false, false).unwrap();
+                    let mut state = self.state.lock();
+                    let e = state.take_events();
+                    state.stop_and_clear_recording();
+                    DiffBatch::new(e)
+                },
+                &mut |_| {},
+            );
+            diff = Some(match diff {
+                Some(mut diff) => {
+                    revert.transform(&diff, true);
+                    diff.compose(&revert);
+                    diff
+                }
+                None => revert,
+            });
+        }
+
+        self._checkout_without_emitting(&latest_frontiers, false, false)?;
+        self.set_detached(false);
+        if was_recording {
+            self.state.lock().start_recording();
+        }
+        drop(txn);
+        self.start_auto_commit();
+        let mut diff = diff.unwrap();
+        diff.retain(|id| !id.is_metadata());
+        let ans = self._apply_diff(diff, &mut Default::default(), true);
+        if let Some(options) = options {
+            self.set_next_commit_options(options);
+        }
+        ans
+    }
+
     /// Generate a series of local operations that can revert the current doc to the target
     /// version.
     ///
@@ -1254,12 +1405,18 @@ impl LoroDoc {
         drop(txn);
         if !was_detached {
             self.set_detached(false);
-            self.renew_txn_if_auto_commit(options);
         }
+        // A detached doc can still be edited with detached editing
+        self.renew_txn_if_auto_commit(options);
         if was_recording {
             self.state.lock().start_recording();
         }
-        result.map(DiffBatch::new)
+        result.map(|e| {
+            let mut diff = DiffBatch::new(e);
+            // The metadata of the doc isn't a part of its versions
+            diff.retain(|id| !id.is_metadata());
+            diff
+        })
     }
 
     /// Apply a diff to the current state.
@@ -1939,6 +2096,8 @@ impl LoroDoc {
                         crate::diff_calc::ContainerDiffCalculator::Map(_) => unreachable!(),
                         #[cfg(feature = "counter")]
                         crate::diff_calc::ContainerDiffCalculator::Counter(_) => unreachable!(),
+                        #[cfg(feature = "set")]
+                        crate::diff_calc::ContainerDiffCalculator::Set(_) => unreachable!(),
                         crate::diff_calc::ContainerDiffCalculator::Unknown(_) => unreachable!(),
                     }
                 } else {
@@ -1993,6 +2152,10 @@ impl LoroDoc {
                         }
                         #[cfg(feature = "counter")]
                         ContainerType::Counter => unreachable!(),
+                        #[cfg(feature = "typed-counter")]
+                        ContainerType::TypedCounter => unreachable!(),
+                        #[cfg(feature = "set")]
+                        ContainerType::Set => unreachable!(),
                     }
                 }
             });
@@ -2137,6 +2300,15 @@ impl LoroDoc {
         enable();
         s
     }
+
+    /// The reason the last commit was rejected, if a pre-commit callback rejected it with
+    /// [`ChangeModifier::reject`](crate::pre_commit::ChangeModifier::reject).
+    ///
+    /// It is reset by every commit, including the implicit ones of `import`, `export` and
+    /// `checkout`, so read it right after the commit it is about.
+    pub fn last_commit_rejection(&self) -> Option<Arc<str>> {
+        self.last_commit_rejection.lock().clone()
+    }
 }
 
 fn pending_root_containers_to_materialize(oplog: &OpLog, changes: &[Change]) -> Vec<ContainerID> {
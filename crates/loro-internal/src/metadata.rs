@@ -0,0 +1,49 @@
+//! Document metadata stored in hidden maps.
+//!
+//! A metadata map replicates like any other container, but it's never reachable from a root,
+//! so it doesn't show up in the value of the document. See [`ContainerID::new_metadata`].
+use loro_common::{ContainerID, ContainerType, InternalString, LoroValue};
+
+use crate::{
+    handler::{Handler, HandlerTrait, MapHandler},
+    LoroDoc,
+};
+
+impl LoroDoc {
+    /// The handler of the metadata map with the given name.
+    pub(crate) fn metadata_map(&self, name: &str) -> MapHandler {
+        // `get_map` only returns the mergeable children that are active, which a metadata
+        // map never is
+        let id = ContainerID::new_metadata(name, ContainerType::Map);
+        Handler::new_attached(id, self.clone()).into_map().unwrap()
+    }
+
+    /// The entries of the metadata map with the given name at the latest version, even if the
+    /// doc is detached.
+    pub(crate) fn metadata_entries(&self, name: &str) -> Vec<(InternalString, LoroValue)> {
+        if !self.is_detached() {
+            let LoroValue::Map(map) = self.metadata_map(name).get_value() else {
+                unreachable!()
+            };
+            return map
+                .iter()
+                .map(|(key, value)| (key.as_str().into(), value.clone()))
+                .collect();
+        }
+
+        let id = ContainerID::new_metadata(name, ContainerType::Map);
+        let Some(idx) = self.arena.id_to_idx(&id) else {
+            return Vec::new();
+        };
+        let oplog = self.oplog().lock();
+        let latest = oplog.with_history_cache(|h| {
+            h.get_checkout_index()
+                .map
+                .get_container_latest_op_at_vv(idx, oplog.vv(), 0, &oplog)
+        });
+        latest
+            .into_iter()
+            .filter_map(|(key, op)| op.value.map(|value| (key, value)))
+            .collect()
+    }
+}
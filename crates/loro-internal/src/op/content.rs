@@ -6,6 +6,8 @@ use rle::{HasLength, Mergable, Sliceable};
 #[cfg(feature = "wasm")]
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "set")]
+use crate::container::set::SetOp;
 use crate::{
     arena::SharedArena,
     container::{
@@ -62,6 +64,12 @@ impl InnerContent {
             crate::op::InnerContent::Future(f) => match &f {
                 #[cfg(feature = "counter")]
                 crate::op::FutureInnerContent::Counter(_) => {}
+                #[cfg(feature = "counter")]
+                crate::op::FutureInnerContent::IntCounter(_)
+                | crate::op::FutureInnerContent::BoundedCounter { .. }
+                | crate::op::FutureInnerContent::MaxCounter(_) => {}
+                #[cfg(feature = "set")]
+                crate::op::FutureInnerContent::Set(_) => {}
                 crate::op::FutureInnerContent::Unknown { .. } => {}
             },
         }
@@ -83,6 +91,17 @@ impl InnerContent {
 pub enum FutureInnerContent {
     #[cfg(feature = "counter")]
     Counter(f64),
+    #[cfg(feature = "counter")]
+    IntCounter(i64),
+    #[cfg(feature = "counter")]
+    BoundedCounter {
+        delta: i64,
+        lower_bound: i64,
+    },
+    #[cfg(feature = "counter")]
+    MaxCounter(f64),
+    #[cfg(feature = "set")]
+    Set(SetOp),
     Unknown {
         prop: i32,
         value: Box<OwnedValue>,
@@ -93,6 +112,12 @@ impl FutureInnerContent {
         match self {
             #[cfg(feature = "counter")]
             FutureInnerContent::Counter(_) => 4,
+            #[cfg(feature = "counter")]
+            FutureInnerContent::IntCounter(_) | FutureInnerContent::MaxCounter(_) => 4,
+            #[cfg(feature = "counter")]
+            FutureInnerContent::BoundedCounter { .. } => 6,
+            #[cfg(feature = "set")]
+            FutureInnerContent::Set(_) => 4,
             FutureInnerContent::Unknown { .. } => 6,
         }
     }
@@ -107,6 +132,17 @@ pub enum RawOpContent<'a> {
     Tree(Arc<TreeOp>),
     #[cfg(feature = "counter")]
     Counter(f64),
+    #[cfg(feature = "counter")]
+    IntCounter(i64),
+    #[cfg(feature = "counter")]
+    BoundedCounter {
+        delta: i64,
+        lower_bound: i64,
+    },
+    #[cfg(feature = "counter")]
+    MaxCounter(f64),
+    #[cfg(feature = "set")]
+    Set(SetOp),
     Unknown {
         prop: i32,
         value: OwnedValue,
@@ -121,6 +157,17 @@ impl Clone for RawOpContent<'_> {
             Self::Tree(arg0) => Self::Tree(arg0.clone()),
             #[cfg(feature = "counter")]
             Self::Counter(x) => Self::Counter(*x),
+            #[cfg(feature = "counter")]
+            Self::IntCounter(x) => Self::IntCounter(*x),
+            #[cfg(feature = "counter")]
+            Self::BoundedCounter { delta, lower_bound } => Self::BoundedCounter {
+                delta: *delta,
+                lower_bound: *lower_bound,
+            },
+            #[cfg(feature = "counter")]
+            Self::MaxCounter(x) => Self::MaxCounter(*x),
+            #[cfg(feature = "set")]
+            Self::Set(x) => Self::Set(x.clone()),
             Self::Unknown { prop, value } => Self::Unknown {
                 prop: *prop,
                 value: value.clone(),
@@ -170,6 +217,17 @@ impl RawOpContent<'_> {
             Self::Tree(arg0) => RawOpContent::Tree(arg0.clone()),
             #[cfg(feature = "counter")]
             Self::Counter(x) => RawOpContent::Counter(*x),
+            #[cfg(feature = "counter")]
+            Self::IntCounter(x) => RawOpContent::IntCounter(*x),
+            #[cfg(feature = "counter")]
+            Self::BoundedCounter { delta, lower_bound } => RawOpContent::BoundedCounter {
+                delta: *delta,
+                lower_bound: *lower_bound,
+            },
+            #[cfg(feature = "counter")]
+            Self::MaxCounter(x) => RawOpContent::MaxCounter(*x),
+            #[cfg(feature = "set")]
+            Self::Set(x) => RawOpContent::Set(x.clone()),
             Self::Unknown { prop, value } => RawOpContent::Unknown {
                 prop: *prop,
                 value: value.clone(),
@@ -186,6 +244,12 @@ impl HasLength for RawOpContent<'_> {
             RawOpContent::Tree(x) => x.content_len(),
             #[cfg(feature = "counter")]
             RawOpContent::Counter(_) => 1,
+            #[cfg(feature = "counter")]
+            RawOpContent::IntCounter(_)
+            | RawOpContent::BoundedCounter { .. }
+            | RawOpContent::MaxCounter(_) => 1,
+            #[cfg(feature = "set")]
+            RawOpContent::Set(_) => 1,
             RawOpContent::Unknown { .. } => 1,
         }
     }
@@ -918,6 +918,23 @@ pub(crate) fn local_op_to_remote(
         crate::op::InnerContent::Future(f) => match f {
             #[cfg(feature = "counter")]
             crate::op::FutureInnerContent::Counter(c) => contents.push(RawOpContent::Counter(*c)),
+            #[cfg(feature = "counter")]
+            crate::op::FutureInnerContent::IntCounter(c) => {
+                contents.push(RawOpContent::IntCounter(*c))
+            }
+            #[cfg(feature = "counter")]
+            crate::op::FutureInnerContent::BoundedCounter { delta, lower_bound } => {
+                contents.push(RawOpContent::BoundedCounter {
+                    delta: *delta,
+                    lower_bound: *lower_bound,
+                })
+            }
+            #[cfg(feature = "counter")]
+            crate::op::FutureInnerContent::MaxCounter(c) => {
+                contents.push(RawOpContent::MaxCounter(*c))
+            }
+            #[cfg(feature = "set")]
+            crate::op::FutureInnerContent::Set(s) => contents.push(RawOpContent::Set(s.clone())),
             FutureInnerContent::Unknown { prop, value } => {
                 contents.push(crate::op::RawOpContent::Unknown {
                     prop: *prop,
@@ -883,6 +883,15 @@ impl AppDag {
         }
     }
 
+    /// Drop the version of a txn that won't be committed, so the dag is back at
+    /// the txn's deps.
+    pub(crate) fn discard_pending_txn(&mut self, deps: &Frontiers) {
+        if let Some(node) = self.pending_txn_node.take() {
+            self.vv.set_end(ID::new(node.peer, node.cnt));
+            self.frontiers = deps.clone();
+        }
+    }
+
     pub(crate) fn latest_vv_contains_peer(&self, peer: PeerID) -> bool {
         self.vv.contains_key(&peer) && *self.vv.get(&peer).unwrap() > 0
     }
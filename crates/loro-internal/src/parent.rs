@@ -81,7 +81,12 @@ impl DocState {
                 self.arena.set_parent(child_idx, Some(container));
             }
             #[cfg(feature = "counter")]
-            RawOpContent::Counter(_) => {}
+            RawOpContent::Counter(_)
+            | RawOpContent::IntCounter(_)
+            | RawOpContent::BoundedCounter { .. }
+            | RawOpContent::MaxCounter(_) => {}
+            #[cfg(feature = "set")]
+            RawOpContent::Set(_) => {}
             RawOpContent::Unknown { .. } => {}
         }
     }
@@ -20,7 +20,7 @@ pub struct PreCommitCallbackPayload {
     pub change_meta: ChangeMeta,
     /// The origin of the commit.
     pub origin: String,
-    /// The modifier of the change. You can modify the change in the callback.
+    /// The modifier of the change. You can modify or reject the change in the callback.
     pub modifier: ChangeModifier,
 }
 
@@ -38,6 +38,7 @@ pub struct ChangeModifier(Arc<Mutex<ChangeModifierInner>>);
 struct ChangeModifierInner {
     new_msg: Option<Arc<str>>,
     new_timestamp: Option<Timestamp>,
+    rejection: Option<Arc<str>>,
 }
 
 impl ChangeModifier {
@@ -56,6 +57,21 @@ impl ChangeModifier {
         self
     }
 
+    /// Reject the change.
+    ///
+    /// A rejected change never reaches the `OpLog`: its ops are rolled back from the
+    /// document state and no event is emitted for them. The callbacks registered after
+    /// this one are still called.
+    pub fn reject(&self, reason: &str) -> &Self {
+        self.0.lock().rejection = Some(Arc::from(reason));
+        self
+    }
+
+    /// The reason passed to [`ChangeModifier::reject`], if the change was rejected.
+    pub fn rejection(&self) -> Option<Arc<str>> {
+        self.0.lock().rejection.clone()
+    }
+
     pub(crate) fn modify_change(&self, change: &mut Change) {
         let m = self.0.lock();
         if let Some(msg) = &m.new_msg {
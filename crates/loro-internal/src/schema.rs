@@ -0,0 +1,662 @@
+//! # Document Schema
+//!
+//! A [`DocSchema`] declares the shape of a document: which root containers exist and what
+//! type they have, the value types of map fields, the element type of lists, and which keys
+//! are required. It can be checked on demand with [`LoroDoc::validate_schema`], enforced on
+//! local commits with [`LoroDoc::enforce_schema`], and checked after imports with
+//! [`LoroDoc::subscribe_schema_violations`].
+//!
+//! Every violation carries a normalized JSONPath (e.g. `$['users'][0]['name']`) that points
+//! at the offending value in the document's deep value, so it can be fed back to
+//! `LoroDoc::jsonpath` to inspect the value.
+//!
+//! The schema types implement `Serialize`/`Deserialize`, so a schema can also be written as
+//! JSON:
+//!
+//! ```json
+//! {
+//!   "roots": {
+//!     "users": {
+//!       "type": "list",
+//!       "items": {
+//!         "type": "map",
+//!         "fields": { "name": { "type": "string" }, "age": { "type": "int" } },
+//!         "required": ["name"]
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+
+use std::{collections::BTreeMap, fmt::Write, sync::Arc};
+
+use rustc_hash::FxHashSet;
+use loro_common::{LoroError, LoroResult, LoroValue};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    container::idx::ContainerIdx,
+    event::EventTriggerKind,
+    handler::{Handler, HandlerTrait, MapHandler, TreeHandler, ValueOrHandler},
+    utils::subscription::Subscription,
+    LoroDoc, TreeParentId,
+};
+
+/// Callback used by [`LoroDoc::enforce_schema`] and [`LoroDoc::subscribe_schema_violations`].
+pub type SchemaViolationCallback = Arc<dyn Fn(&[SchemaViolation]) + Send + Sync + 'static>;
+
+/// The expected type of a value: a root container, a map field or a list element.
+///
+/// Container schemas only match child containers, not plain `LoroValue::List` /
+/// `LoroValue::Map` values; those only match [`ValueSchema::Any`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ValueSchema {
+    /// Matches everything.
+    Any,
+    Null,
+    Bool,
+    /// An `i64` or an `f64`.
+    Number,
+    /// An `i64`.
+    Int,
+    /// An `f64`.
+    Double,
+    String,
+    Binary,
+    Map(MapSchema),
+    List {
+        items: Box<ValueSchema>,
+    },
+    MovableList {
+        items: Box<ValueSchema>,
+    },
+    Text,
+    /// A tree. When `meta` is set, the metadata map of every node is checked against it.
+    Tree {
+        #[serde(default)]
+        meta: Option<MapSchema>,
+    },
+    #[cfg(feature = "counter")]
+    Counter,
+    /// Matches when any of the variants matches.
+    OneOf {
+        variants: Vec<ValueSchema>,
+    },
+}
+
+impl ValueSchema {
+    pub fn list(items: impl Into<ValueSchema>) -> Self {
+        ValueSchema::List {
+            items: Box::new(items.into()),
+        }
+    }
+
+    pub fn movable_list(items: impl Into<ValueSchema>) -> Self {
+        ValueSchema::MovableList {
+            items: Box::new(items.into()),
+        }
+    }
+
+    pub fn tree(meta: Option<MapSchema>) -> Self {
+        ValueSchema::Tree { meta }
+    }
+
+    pub fn one_of(variants: impl IntoIterator<Item = ValueSchema>) -> Self {
+        ValueSchema::OneOf {
+            variants: variants.into_iter().collect(),
+        }
+    }
+
+    /// `schema` or `null`.
+    pub fn nullable(schema: ValueSchema) -> Self {
+        Self::one_of([schema, ValueSchema::Null])
+    }
+
+    fn is_container(&self) -> bool {
+        match self {
+            ValueSchema::Map(_)
+            | ValueSchema::List { .. }
+            | ValueSchema::MovableList { .. }
+            | ValueSchema::Text
+            | ValueSchema::Tree { .. } => true,
+            #[cfg(feature = "counter")]
+            ValueSchema::Counter => true,
+            _ => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            ValueSchema::Any => "any value".into(),
+            ValueSchema::Null => "null".into(),
+            ValueSchema::Bool => "bool".into(),
+            ValueSchema::Number => "number".into(),
+            ValueSchema::Int => "int".into(),
+            ValueSchema::Double => "double".into(),
+            ValueSchema::String => "string".into(),
+            ValueSchema::Binary => "binary".into(),
+            ValueSchema::Map(_) => "Map container".into(),
+            ValueSchema::List { .. } => "List container".into(),
+            ValueSchema::MovableList { .. } => "MovableList container".into(),
+            ValueSchema::Text => "Text container".into(),
+            ValueSchema::Tree { .. } => "Tree container".into(),
+            #[cfg(feature = "counter")]
+            ValueSchema::Counter => "Counter container".into(),
+            ValueSchema::OneOf { variants } => {
+                let variants: Vec<String> = variants.iter().map(|v| v.describe()).collect();
+                format!("one of [{}]", variants.join(", "))
+            }
+        }
+    }
+}
+
+impl From<MapSchema> for ValueSchema {
+    fn from(value: MapSchema) -> Self {
+        ValueSchema::Map(value)
+    }
+}
+
+/// The schema of a map container.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MapSchema {
+    /// The schema of each known field.
+    #[serde(default)]
+    pub fields: BTreeMap<String, ValueSchema>,
+    /// Fields that must be present.
+    #[serde(default)]
+    pub required: Vec<String>,
+    /// Whether fields that are not listed in `fields` are violations.
+    #[serde(default)]
+    pub deny_unknown_fields: bool,
+}
+
+impl MapSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare an optional field.
+    pub fn field(mut self, key: impl Into<String>, schema: impl Into<ValueSchema>) -> Self {
+        self.fields.insert(key.into(), schema.into());
+        self
+    }
+
+    /// Declare a field that must be present.
+    pub fn required_field(
+        mut self,
+        key: impl Into<String>,
+        schema: impl Into<ValueSchema>,
+    ) -> Self {
+        let key = key.into();
+        if !self.required.contains(&key) {
+            self.required.push(key.clone());
+        }
+        self.fields.insert(key, schema.into());
+        self
+    }
+
+    /// Report fields that are not declared.
+    pub fn deny_unknown_fields(mut self) -> Self {
+        self.deny_unknown_fields = true;
+        self
+    }
+}
+
+/// The schema of a whole document.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocSchema {
+    /// The schema of each declared root container. Undeclared roots are not checked.
+    #[serde(default)]
+    pub roots: BTreeMap<String, ValueSchema>,
+    /// Roots that must exist.
+    #[serde(default)]
+    pub required: Vec<String>,
+}
+
+impl DocSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a root container. `schema` must describe a container.
+    pub fn root(mut self, name: impl Into<String>, schema: impl Into<ValueSchema>) -> Self {
+        self.roots.insert(name.into(), schema.into());
+        self
+    }
+
+    /// Declare a root container that must exist.
+    pub fn required_root(
+        mut self,
+        name: impl Into<String>,
+        schema: impl Into<ValueSchema>,
+    ) -> Self {
+        let name = name.into();
+        if !self.required.contains(&name) {
+            self.required.push(name.clone());
+        }
+        self.roots.insert(name, schema.into());
+        self
+    }
+
+    fn check(&self) -> LoroResult<()> {
+        if let Some((name, schema)) = self.roots.iter().find(|(_, s)| !s.is_container()) {
+            return Err(LoroError::ArgErr(
+                format!(
+                    "Root {name:?} must be declared as a container, found {}",
+                    schema.describe()
+                )
+                .into_boxed_str(),
+            ));
+        }
+        if let Some(name) = self.required.iter().find(|n| !self.roots.contains_key(*n)) {
+            return Err(LoroError::ArgErr(
+                format!("Required root {name:?} has no schema").into_boxed_str(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn violations(&self, doc: &LoroDoc, scope: &Scope) -> Vec<SchemaViolation> {
+        let mut out = Vec::new();
+        let LoroValue::Map(roots) = doc.get_value() else {
+            unreachable!()
+        };
+        for name in self.required.iter() {
+            if !roots.contains_key(name) {
+                out.push(SchemaViolation::new(
+                    push_key("$", name),
+                    "missing required root container",
+                ));
+            }
+        }
+        for (name, schema) in self.roots.iter() {
+            let Some(LoroValue::Container(id)) = roots.get(name) else {
+                continue;
+            };
+            let Some(handler) = doc.get_handler(id.clone()) else {
+                continue;
+            };
+            check_value(
+                &ValueOrHandler::Handler(handler),
+                schema,
+                &push_key("$", name),
+                scope,
+                &mut out,
+            );
+        }
+        out
+    }
+}
+
+/// A value that does not conform to a [`DocSchema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// The normalized JSONPath of the value, e.g. `$['users'][0]['name']`.
+    pub path: String,
+    pub message: String,
+}
+
+impl SchemaViolation {
+    fn new(path: String, message: impl Into<String>) -> Self {
+        Self {
+            path,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Append a name selector to a normalized JSONPath (RFC 9535, section 2.7).
+fn push_key(path: &str, key: &str) -> String {
+    let mut ans = String::with_capacity(path.len() + key.len() + 4);
+    ans.push_str(path);
+    ans.push_str("['");
+    for c in key.chars() {
+        match c {
+            '\'' => ans.push_str("\\'"),
+            '\\' => ans.push_str("\\\\"),
+            '\u{8}' => ans.push_str("\\b"),
+            '\u{c}' => ans.push_str("\\f"),
+            '\n' => ans.push_str("\\n"),
+            '\r' => ans.push_str("\\r"),
+            '\t' => ans.push_str("\\t"),
+            c if c < '\u{20}' => {
+                write!(ans, "\\u{:04x}", c as u32).unwrap();
+            }
+            c => ans.push(c),
+        }
+    }
+    ans.push_str("']");
+    ans
+}
+
+/// The containers whose content a check looks into.
+enum Scope {
+    All,
+    /// Only the given containers. Empty containers are checked too: a container that was
+    /// just created without content has no ops of its own, but may lack required fields.
+    Changed(FxHashSet<ContainerIdx>),
+}
+
+impl Scope {
+    /// The containers touched by the pending change of `doc`, and all their ancestors.
+    fn pending_change(doc: &LoroDoc) -> Self {
+        let touched: Vec<ContainerIdx> = {
+            let oplog = doc.oplog.lock();
+            let Some(change) = oplog.uncommitted_change.as_ref() else {
+                return Scope::Changed(FxHashSet::default());
+            };
+            change.ops.iter().map(|op| op.container).collect()
+        };
+        let mut changed = FxHashSet::default();
+        for mut idx in touched {
+            while changed.insert(idx) {
+                match doc.arena.get_parent(idx) {
+                    Some(parent) => idx = parent,
+                    None => break,
+                }
+            }
+        }
+        Scope::Changed(changed)
+    }
+
+    fn visits(&self, idx: ContainerIdx, is_empty: bool) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Changed(changed) => is_empty || changed.contains(&idx),
+        }
+    }
+}
+
+fn push_index(path: &str, index: usize) -> String {
+    format!("{path}[{index}]")
+}
+
+fn describe_value(value: &ValueOrHandler) -> String {
+    match value {
+        ValueOrHandler::Value(v) => match v {
+            LoroValue::Null => "null".into(),
+            LoroValue::Bool(_) => "bool".into(),
+            LoroValue::Double(_) => "double".into(),
+            LoroValue::I64(_) => "int".into(),
+            LoroValue::Binary(_) => "binary".into(),
+            LoroValue::String(_) => "string".into(),
+            LoroValue::List(_) => "list value".into(),
+            LoroValue::Map(_) => "map value".into(),
+            LoroValue::Container(id) => format!("{} container", id.container_type()),
+        },
+        ValueOrHandler::Handler(h) => format!("{} container", h.c_type()),
+    }
+}
+
+/// Check `value` against `schema`. The type of `value` is always checked; the content of a
+/// container only when `scope` visits it.
+fn check_value(
+    value: &ValueOrHandler,
+    schema: &ValueSchema,
+    path: &str,
+    scope: &Scope,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let matched = match (schema, value) {
+        (ValueSchema::Any, _) => true,
+        (ValueSchema::OneOf { variants }, _) => variants.iter().any(|variant| {
+            let mut scratch = Vec::new();
+            check_value(value, variant, path, scope, &mut scratch);
+            scratch.is_empty()
+        }),
+        (ValueSchema::Null, ValueOrHandler::Value(LoroValue::Null))
+        | (ValueSchema::Bool, ValueOrHandler::Value(LoroValue::Bool(_)))
+        | (ValueSchema::Number, ValueOrHandler::Value(LoroValue::I64(_)))
+        | (ValueSchema::Number, ValueOrHandler::Value(LoroValue::Double(_)))
+        | (ValueSchema::Int, ValueOrHandler::Value(LoroValue::I64(_)))
+        | (ValueSchema::Double, ValueOrHandler::Value(LoroValue::Double(_)))
+        | (ValueSchema::String, ValueOrHandler::Value(LoroValue::String(_)))
+        | (ValueSchema::Binary, ValueOrHandler::Value(LoroValue::Binary(_)))
+        | (ValueSchema::Text, ValueOrHandler::Handler(Handler::Text(_))) => true,
+        #[cfg(feature = "counter")]
+        (ValueSchema::Counter, ValueOrHandler::Handler(Handler::Counter(_))) => true,
+        (ValueSchema::Map(map_schema), ValueOrHandler::Handler(Handler::Map(map))) => {
+            check_map(map, map_schema, path, scope, out);
+            true
+        }
+        (ValueSchema::List { items }, ValueOrHandler::Handler(Handler::List(list))) => {
+            if scope.visits(list.idx(), list.is_empty()) {
+                let mut index = 0;
+                list.for_each(|item| {
+                    check_value(&item, items, &push_index(path, index), scope, out);
+                    index += 1;
+                });
+            }
+            true
+        }
+        (
+            ValueSchema::MovableList { items },
+            ValueOrHandler::Handler(Handler::MovableList(list)),
+        ) => {
+            if scope.visits(list.idx(), list.is_empty()) {
+                let mut index = 0;
+                list.for_each(|item| {
+                    check_value(&item, items, &push_index(path, index), scope, out);
+                    index += 1;
+                });
+            }
+            true
+        }
+        (ValueSchema::Tree { meta }, ValueOrHandler::Handler(Handler::Tree(tree))) => {
+            if let Some(meta) = meta {
+                if scope.visits(tree.idx(), tree.is_empty()) {
+                    check_tree_nodes(tree, &TreeParentId::Root, meta, path, scope, out);
+                }
+            }
+            true
+        }
+        _ => false,
+    };
+
+    if !matched {
+        out.push(SchemaViolation::new(
+            path.to_string(),
+            format!(
+                "expected {}, found {}",
+                schema.describe(),
+                describe_value(value)
+            ),
+        ));
+    }
+}
+
+fn check_map(
+    map: &MapHandler,
+    schema: &MapSchema,
+    path: &str,
+    scope: &Scope,
+    out: &mut Vec<SchemaViolation>,
+) {
+    if !scope.visits(map.idx(), map.is_empty()) {
+        return;
+    }
+    for key in schema.required.iter() {
+        if !map.contains_key(key) {
+            out.push(SchemaViolation::new(
+                push_key(path, key),
+                "missing required field",
+            ));
+        }
+    }
+    map.for_each(|key, value| match schema.fields.get(key) {
+        Some(field) => check_value(&value, field, &push_key(path, key), scope, out),
+        None if schema.deny_unknown_fields => {
+            out.push(SchemaViolation::new(push_key(path, key), "unknown field"));
+        }
+        None => {}
+    });
+}
+
+/// Tree nodes are addressed the way they appear in the tree's deep value: nested under
+/// `children`, with their metadata under `meta`.
+fn check_tree_nodes(
+    tree: &TreeHandler,
+    parent: &TreeParentId,
+    meta: &MapSchema,
+    path: &str,
+    scope: &Scope,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let Some(children) = tree.children(parent) else {
+        return;
+    };
+    for (i, node) in children.into_iter().enumerate() {
+        let node_path = push_index(path, i);
+        if let Ok(map) = tree.get_meta(node) {
+            check_map(&map, meta, &push_key(&node_path, "meta"), scope, out);
+        }
+        check_tree_nodes(
+            tree,
+            &TreeParentId::Node(node),
+            meta,
+            &push_key(&node_path, "children"),
+            scope,
+            out,
+        );
+    }
+}
+
+impl LoroDoc {
+    /// Check the current state of the document against `schema`.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `schema` declares a root that is not a container.
+    pub fn validate_schema(&self, schema: &DocSchema) -> LoroResult<Vec<SchemaViolation>> {
+        schema.check()?;
+        Ok(schema.violations(self, &Scope::All))
+    }
+
+    /// Reject local commits that leave the document violating `schema`.
+    ///
+    /// The check runs in a pre-commit callback, so a rejected change never reaches the
+    /// `OpLog` and its edits are rolled back from the document state. `on_reject` receives
+    /// the violations of each rejected commit, and [`LoroDoc::last_commit_rejection`] tells
+    /// the committer that its commit was dropped.
+    ///
+    /// Only the containers the commit touches, and their ancestors, are checked, so the
+    /// cost follows the size of the change rather than the size of the document. Violations
+    /// left in untouched containers, e.g. by an import, do not cause a rejection.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `schema` declares a root that is not a container.
+    pub fn enforce_schema(
+        &self,
+        schema: DocSchema,
+        on_reject: SchemaViolationCallback,
+    ) -> LoroResult<Subscription> {
+        schema.check()?;
+        let doc = Arc::downgrade(&self.inner);
+        Ok(self.subscribe_pre_commit(Box::new(move |payload| {
+            let Some(inner) = doc.upgrade() else {
+                return false;
+            };
+            let doc = LoroDoc::from_inner(inner);
+            let violations = schema.violations(&doc, &Scope::pending_change(&doc));
+            if let Some(first) = violations.first() {
+                payload
+                    .modifier
+                    .reject(&format!("schema violation at {first}"));
+                on_reject(&violations);
+            }
+            true
+        })))
+    }
+
+    /// Check the document against `schema` after every import and report the violations.
+    ///
+    /// Imported changes are already part of the history, so they cannot be rejected; the
+    /// callback is only called when the imported state does not conform.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `schema` declares a root that is not a container.
+    pub fn subscribe_schema_violations(
+        &self,
+        schema: DocSchema,
+        callback: SchemaViolationCallback,
+    ) -> LoroResult<Subscription> {
+        schema.check()?;
+        let doc = Arc::downgrade(&self.inner);
+        Ok(self.subscribe_root(Arc::new(move |event| {
+            if event.event_meta.by != EventTriggerKind::Import {
+                return;
+            }
+            let Some(inner) = doc.upgrade() else {
+                return;
+            };
+            let violations = schema.violations(&LoroDoc::from_inner(inner), &Scope::All);
+            if !violations.is_empty() {
+                callback(&violations);
+            }
+        })))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalized_paths_escape_names() {
+        assert_eq!(push_key("$", "users"), "$['users']");
+        assert_eq!(push_index(&push_key("$", "a"), 3), "$['a'][3]");
+        assert_eq!(push_key("$", "it's"), "$['it\\'s']");
+        assert_eq!(push_key("$", "a\\b\n\u{1}"), "$['a\\\\b\\n\\u0001']");
+    }
+
+    #[test]
+    fn schema_round_trips_through_json() {
+        let schema = DocSchema::new().required_root(
+            "users",
+            ValueSchema::list(
+                MapSchema::new()
+                    .required_field("name", ValueSchema::String)
+                    .field("age", ValueSchema::nullable(ValueSchema::Int)),
+            ),
+        );
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "roots": {
+                    "users": {
+                        "type": "list",
+                        "items": {
+                            "type": "map",
+                            "fields": {
+                                "age": {
+                                    "type": "one_of",
+                                    "variants": [{ "type": "int" }, { "type": "null" }]
+                                },
+                                "name": { "type": "string" }
+                            },
+                            "required": ["name"],
+                            "deny_unknown_fields": false
+                        }
+                    }
+                },
+                "required": ["users"]
+            })
+        );
+        let parsed: DocSchema = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, schema);
+    }
+
+    #[test]
+    fn root_must_be_a_container() {
+        let doc = LoroDoc::new_auto_commit();
+        let schema = DocSchema::new().root("title", ValueSchema::String);
+        assert!(matches!(
+            doc.validate_schema(&schema),
+            Err(LoroError::ArgErr(_))
+        ));
+    }
+}
@@ -9,6 +9,7 @@ use dead_containers_cache::DeadContainersCache;
 use enum_as_inner::EnumAsInner;
 use enum_dispatch::enum_dispatch;
 use itertools::Itertools;
+use list_key_index::ListKeyIndex;
 use loro_common::{ContainerID, Lamport, LoroError, LoroResult, TreeID};
 use loro_delta::DeltaItem;
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -35,11 +36,14 @@ pub(crate) mod container_store;
 #[cfg(feature = "counter")]
 mod counter_state;
 mod dead_containers_cache;
+mod list_key_index;
 mod list_state;
 mod map_state;
 mod mergeable;
 mod movable_list_state;
 mod richtext_state;
+#[cfg(feature = "set")]
+mod set_state;
 mod tree_state;
 mod unknown_state;
 
@@ -47,6 +51,8 @@ pub(crate) use self::movable_list_state::{IndexType, MovableListState};
 pub(crate) use container_store::GcStore;
 pub(crate) use list_state::ListState;
 pub(crate) use map_state::MapState;
+use mergeable::mergeable_list_marker_bytes;
+pub(crate) use mergeable::translate_list_marker_with_parent;
 pub(crate) use richtext_state::RichtextState;
 pub(crate) use tree_state::FiIfNotConfigured;
 pub(crate) use tree_state::{get_meta_value, FractionalIndexGenResult, NodePosition, TreeState};
@@ -56,6 +62,8 @@ use self::{container_store::ContainerWrapper, unknown_state::UnknownState};
 
 #[cfg(feature = "counter")]
 use self::counter_state::CounterState;
+#[cfg(feature = "set")]
+use self::set_state::SetState;
 
 use super::{arena::SharedArena, event::InternalDocDiff};
 
@@ -78,6 +86,10 @@ fn visible_container_value_is_empty(kind: ContainerType, value: &LoroValue) -> b
         ContainerType::Tree => value.as_list().is_some_and(|value| value.is_empty()),
         #[cfg(feature = "counter")]
         ContainerType::Counter => false,
+        #[cfg(feature = "typed-counter")]
+        ContainerType::TypedCounter => false,
+        #[cfg(feature = "set")]
+        ContainerType::Set => value.as_list().is_some_and(|value| value.is_empty()),
         ContainerType::Unknown(_) => false,
     }
 }
@@ -85,7 +97,9 @@ fn visible_container_value_is_empty(kind: ContainerType, value: &LoroValue) -> b
 fn deleted_root_container_value_is_cleared(kind: ContainerType, value: &LoroValue) -> bool {
     match kind {
         #[cfg(feature = "counter")]
-        ContainerType::Counter => value.as_double().is_some_and(|value| *value == 0.0),
+        kind if kind.is_counter() => {
+            value.as_double().is_some_and(|value| *value == 0.0) || value.as_i64() == Some(&0)
+        }
         _ => visible_container_value_is_empty(kind, value),
     }
 }
@@ -171,6 +185,7 @@ pub struct DocState {
     event_recorder: EventRecorder,
 
     dead_containers_cache: DeadContainersCache,
+    list_key_indexes: FxHashMap<ContainerIdx, ListKeyIndex>,
 }
 
 impl std::fmt::Debug for DocState {
@@ -337,6 +352,8 @@ pub enum State {
     TreeState(Box<TreeState>),
     #[cfg(feature = "counter")]
     CounterState(Box<counter_state::CounterState>),
+    #[cfg(feature = "set")]
+    SetState(Box<SetState>),
     UnknownState(UnknownState),
 }
 
@@ -377,6 +394,13 @@ impl From<CounterState> for State {
     }
 }
 
+#[cfg(feature = "set")]
+impl From<SetState> for State {
+    fn from(s: SetState) -> Self {
+        Self::SetState(Box::new(s))
+    }
+}
+
 impl State {
     pub fn new_list(idx: ContainerIdx) -> Self {
         Self::ListState(Box::new(ListState::new(idx)))
@@ -407,6 +431,8 @@ impl State {
             State::TreeState(s) => s.encode_snapshot_fast(&mut w),
             #[cfg(feature = "counter")]
             State::CounterState(s) => s.encode_snapshot_fast(&mut w),
+            #[cfg(feature = "set")]
+            State::SetState(s) => s.encode_snapshot_fast(&mut w),
             State::UnknownState(s) => s.encode_snapshot_fast(&mut w),
         }
     }
@@ -424,6 +450,8 @@ impl State {
             State::TreeState(tree_state) => State::TreeState(tree_state.fork(config)),
             #[cfg(feature = "counter")]
             State::CounterState(counter_state) => State::CounterState(counter_state.fork(config)),
+            #[cfg(feature = "set")]
+            State::SetState(set_state) => State::SetState(set_state.fork(config)),
             State::UnknownState(unknown_state) => State::UnknownState(unknown_state.fork(config)),
         }
     }
@@ -453,6 +481,7 @@ impl DocState {
                 changed_idx_in_txn: FxHashSet::default(),
                 event_recorder: Default::default(),
                 dead_containers_cache: Default::default(),
+                list_key_indexes: Default::default(),
             },
             crate::lock::LockKind::DocState,
         ))
@@ -477,6 +506,7 @@ impl DocState {
             changed_idx_in_txn: FxHashSet::default(),
             event_recorder: Default::default(),
             dead_containers_cache: Default::default(),
+            list_key_indexes: Default::default(),
         }))
     }
 
@@ -679,6 +709,7 @@ impl DocState {
                 let to_create = std::mem::take(&mut to_revive_in_this_layer);
                 to_revive_in_this_layer = std::mem::take(&mut to_revive_in_next_layer);
                 for new in to_create {
+                    self.update_list_key_index(new);
                     let state = self.store.get_or_create_mut(new);
                     if state.is_state_empty() {
                         continue;
@@ -723,6 +754,11 @@ impl DocState {
                 }
                 crate::event::DiffVariant::Internal(_) => {
                     let cid = self.arena.idx_to_id(idx).unwrap();
+                    // Child maps inserted into an indexed list may not receive diffs
+                    // of their own (e.g. when they are revived), so they are collected
+                    // from the converted diff and indexed here.
+                    let is_indexed_list = self.has_list_key_index(idx);
+                    let mut inserted_children = Vec::new();
                     info_span!("apply diff on", container_id = ?cid).in_scope(
                         || -> LoroResult<()> {
                             if self.in_txn {
@@ -754,10 +790,26 @@ impl DocState {
                                     &external_diff,
                                     |cid| {
                                         to_revive_in_next_layer.insert(cid);
+                                        if is_indexed_list {
+                                            inserted_children.push(cid);
+                                        }
                                     },
                                     &self.arena,
                                 );
                                 diff.diff = external_diff.into();
+                            } else if is_indexed_list {
+                                let external_diff = state.apply_diff_and_convert(
+                                    internal_diff.into_internal().unwrap(),
+                                    DiffApplyContext {
+                                        mode: diff.diff_mode,
+                                        doc: &self.doc,
+                                    },
+                                );
+                                trigger_on_new_container(
+                                    &external_diff,
+                                    |cid| inserted_children.push(cid),
+                                    &self.arena,
+                                );
                             } else {
                                 state.apply_diff(
                                     internal_diff.into_internal().unwrap(),
@@ -770,6 +822,10 @@ impl DocState {
                             Ok(())
                         },
                     )?;
+                    self.update_list_key_index(idx);
+                    for child in inserted_children {
+                        self.update_list_key_index(child);
+                    }
                 }
                 crate::event::DiffVariant::External(_) => unreachable!(),
             }
@@ -784,6 +840,7 @@ impl DocState {
         while !to_revive_in_this_layer.is_empty() || !to_revive_in_next_layer.is_empty() {
             let to_create = std::mem::take(&mut to_revive_in_this_layer);
             for new in to_create {
+                self.update_list_key_index(new);
                 let state = self.store.get_or_create_mut(new);
                 if state.is_state_empty() {
                     continue;
@@ -847,6 +904,7 @@ impl DocState {
         if !ret.deleted_containers.is_empty() {
             self.dead_containers_cache.clear_alive();
         }
+        self.update_list_key_index(op.container);
 
         Ok(())
     }
@@ -860,6 +918,22 @@ impl DocState {
         self.in_txn = false;
     }
 
+    /// End the current txn by applying `diff`, which undoes the txn's local ops.
+    ///
+    /// The txn's ops were never recorded as events, so their reversal is not recorded either.
+    pub(crate) fn revert_txn(
+        &mut self,
+        diff: InternalDocDiff<'static>,
+        diff_mode: DiffMode,
+    ) -> LoroResult<()> {
+        self.in_txn = false;
+        self.changed_idx_in_txn.clear();
+        let recorder = std::mem::take(&mut self.event_recorder);
+        let ans = self.apply_diff(diff, diff_mode);
+        self.event_recorder = recorder;
+        ans
+    }
+
     pub fn iter_and_decode_all(&mut self) -> impl Iterator<Item = &mut State> {
         self.store.iter_and_decode_all()
     }
@@ -1092,6 +1166,7 @@ impl DocState {
         }
 
         self.frontiers = frontiers;
+        self.rebuild_list_key_indexes();
         Ok(())
     }
 
@@ -1115,6 +1190,11 @@ impl DocState {
         f(state)
     }
 
+    #[inline]
+    pub(crate) fn frontiers(&self) -> &Frontiers {
+        &self.frontiers
+    }
+
     pub(super) fn is_in_txn(&self) -> bool {
         self.in_txn
     }
@@ -1131,6 +1211,7 @@ impl DocState {
         self.changed_idx_in_txn.clear();
         self.event_recorder = Default::default();
         self.dead_containers_cache = Default::default();
+        self.rebuild_list_key_indexes();
     }
 
     pub fn get_value(&mut self) -> LoroValue {
@@ -1331,6 +1412,7 @@ impl DocState {
                 if container.get_type() == ContainerType::Tree {
                     get_meta_value(list.make_mut(), self);
                 } else {
+                    self.resolve_mergeable_list_markers(container, &mut list);
                     if list.iter().all(|x| !x.is_container()) {
                         return LoroValue::Map(
                             (fx_map!(
@@ -1422,6 +1504,7 @@ impl DocState {
                     // whose value is deep value of map container.
                     get_meta_value(list.make_mut(), self);
                 } else {
+                    self.resolve_mergeable_list_markers(container, &mut list);
                     if list.iter().all(|x| !x.is_container()) {
                         return LoroValue::List(list);
                     }
@@ -1503,7 +1586,7 @@ impl DocState {
 
         match value {
             LoroValue::Container(_) => unreachable!(),
-            LoroValue::List(list) => {
+            LoroValue::List(mut list) => {
                 if idx.get_type() == ContainerType::Tree {
                     // Each tree node has an associated map container to represent
                     // the metadata of this node. When the user get the deep value,
@@ -1522,6 +1605,8 @@ impl DocState {
                         }
                     }
                 } else {
+                    // Mergeable children of lists are held by markers in the list itself.
+                    self.resolve_mergeable_list_markers(idx, &mut list);
                     for item in list.iter() {
                         if let LoroValue::Container(id) = item {
                             ans.push(id.clone());
@@ -1757,11 +1842,11 @@ impl DocState {
                 return None;
             }
             #[cfg(feature = "counter")]
-            if id.container_type() == ContainerType::Counter {
-                if let LoroValue::Double(c) = value {
-                    if c.abs() < f64::EPSILON {
-                        return None;
-                    }
+            if id.container_type().is_counter() {
+                match value {
+                    LoroValue::Double(c) if c.abs() < f64::EPSILON => return None,
+                    LoroValue::I64(0) => return None,
+                    _ => {}
                 }
             }
 
@@ -1832,6 +1917,8 @@ impl DocState {
                 State::MapState(_) | State::TreeState(_) | State::UnknownState(_) => unreachable!(),
                 #[cfg(feature = "counter")]
                 State::CounterState(_) => unreachable!(),
+                #[cfg(feature = "set")]
+                State::SetState(_) => unreachable!(),
             }
         } else {
             if matches!(pos.side, crate::cursor::Side::Left) {
@@ -1849,6 +1936,8 @@ impl DocState {
                 State::MapState(_) | State::TreeState(_) | State::UnknownState(_) => unreachable!(),
                 #[cfg(feature = "counter")]
                 State::CounterState(_) => unreachable!(),
+                #[cfg(feature = "set")]
+                State::SetState(_) => unreachable!(),
             }
         }
     }
@@ -1887,18 +1976,28 @@ impl DocState {
                     let parent_state = self.store.get_container_mut(idx)?;
                     match parent_state {
                         State::ListState(l) => {
-                            let Some(LoroValue::Container(c)) = l.get(*index.as_seq()?) else {
+                            let value = l.get(*index.as_seq()?)?.clone();
+                            let LoroValue::Container(c) =
+                                loro_common::translate_mergeable_list_marker_value(
+                                    &parent_id?,
+                                    value,
+                                )
+                            else {
                                 return None;
                             };
-                            state_idx = CurContainer::Container(self.arena.register_container(c));
+                            state_idx = CurContainer::Container(self.arena.register_container(&c));
                         }
                         State::MovableListState(l) => {
-                            let Some(LoroValue::Container(c)) =
-                                l.get(*index.as_seq()?, IndexType::ForUser)
+                            let value = l.get(*index.as_seq()?, IndexType::ForUser)?.clone();
+                            let LoroValue::Container(c) =
+                                loro_common::translate_mergeable_list_marker_value(
+                                    &parent_id?,
+                                    value,
+                                )
                             else {
                                 return None;
                             };
-                            state_idx = CurContainer::Container(self.arena.register_container(c));
+                            state_idx = CurContainer::Container(self.arena.register_container(&c));
                         }
                         State::MapState(m) => {
                             let key = index.as_key()?;
@@ -1925,6 +2024,8 @@ impl DocState {
                         }
                         #[cfg(feature = "counter")]
                         State::CounterState(_) => return None,
+                        #[cfg(feature = "set")]
+                        State::SetState(_) => return None,
                         State::UnknownState(_) => unreachable!(),
                     }
                 }
@@ -1935,13 +2036,18 @@ impl DocState {
                             .arena
                             .register_container(&node.associated_meta_container());
                         let map = self.store.get_container(idx)?;
-                        let Some(LoroValue::Container(c)) =
-                            map.as_map_state().unwrap().get(internal_string)
+                        let value = map.as_map_state().unwrap().get(internal_string)?.clone();
+                        let LoroValue::Container(c) =
+                            loro_common::translate_mergeable_marker_value(
+                                &node.associated_meta_container(),
+                                internal_string,
+                                value,
+                            )
                         else {
                             return None;
                         };
 
-                        state_idx = CurContainer::Container(self.arena.register_container(c));
+                        state_idx = CurContainer::Container(self.arena.register_container(&c));
                     }
                     Index::Seq(i) => {
                         let tree_state =
@@ -1990,8 +2096,24 @@ impl DocState {
         let parent_id = self.arena.idx_to_id(parent_idx);
         let parent_state = self.store.get_or_create_mut(parent_idx);
         let value: LoroValue = match parent_state {
-            State::ListState(l) => l.get(*index.as_seq()?).cloned()?,
-            State::MovableListState(l) => l.get(*index.as_seq()?, IndexType::ForUser).cloned()?,
+            State::ListState(l) => {
+                let value = l.get(*index.as_seq()?).cloned()?;
+                match &parent_id {
+                    Some(parent_id) => {
+                        loro_common::translate_mergeable_list_marker_value(parent_id, value)
+                    }
+                    None => value,
+                }
+            }
+            State::MovableListState(l) => {
+                let value = l.get(*index.as_seq()?, IndexType::ForUser).cloned()?;
+                match &parent_id {
+                    Some(parent_id) => {
+                        loro_common::translate_mergeable_list_marker_value(parent_id, value)
+                    }
+                    None => value,
+                }
+            }
             State::MapState(m) => {
                 if let Some(key) = index.as_key() {
                     let value = m.get(key).cloned()?;
@@ -2040,6 +2162,8 @@ impl DocState {
             }
             #[cfg(feature = "counter")]
             State::CounterState(_) => unreachable!(),
+            #[cfg(feature = "set")]
+            State::SetState(_) => unreachable!(),
             State::UnknownState(_) => unreachable!(),
         };
 
@@ -2065,6 +2189,12 @@ fn create_state_(idx: ContainerIdx, config: &Configure, peer: u64) -> State {
         ContainerType::Counter => {
             State::CounterState(Box::new(counter_state::CounterState::new(idx)))
         }
+        #[cfg(feature = "typed-counter")]
+        ContainerType::TypedCounter => {
+            State::CounterState(Box::new(counter_state::CounterState::new(idx)))
+        }
+        #[cfg(feature = "set")]
+        ContainerType::Set => State::SetState(Box::new(SetState::new(idx))),
         ContainerType::Unknown(_) => State::UnknownState(UnknownState::new(idx)),
     }
 }
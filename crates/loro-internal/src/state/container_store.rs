@@ -151,6 +151,16 @@ impl ContainerStore {
             .unwrap_or_default()
     }
 
+    pub fn list_mergeable_marker_index(
+        &mut self,
+        idx: ContainerIdx,
+        marker: &[u8],
+    ) -> Option<usize> {
+        self.store.with_container_for_read(idx, |c| {
+            c.list_mergeable_marker_index(idx, ctx!(self), marker)
+        })?
+    }
+
     pub fn text_unicode_len(&mut self, idx: ContainerIdx) -> Option<usize> {
         self.store
             .with_container_for_read(idx, |c| c.text_unicode_len(idx, ctx!(self)))?
@@ -7,6 +7,8 @@ use tracing::trace;
 
 #[cfg(feature = "counter")]
 use crate::state::counter_state::CounterState;
+#[cfg(feature = "set")]
+use crate::state::set_state::SetState;
 use crate::{
     arena::SharedArena,
     container::idx::ContainerIdx,
@@ -425,6 +427,26 @@ impl ContainerWrapper {
         }
     }
 
+    /// The index of the first element holding a mergeable child marker.
+    ///
+    /// Unlike the other list reads, this decodes the state, which indexes the markers.
+    pub fn list_mergeable_marker_index(
+        &mut self,
+        idx: ContainerIdx,
+        ctx: ContainerCreationContext,
+        marker: &[u8],
+    ) -> Option<usize> {
+        let kind = self.kind;
+        let state = self.get_state(idx, ctx);
+        match kind {
+            ContainerType::List => state.as_list_state()?.get_mergeable_marker_index(marker),
+            ContainerType::MovableList => state
+                .as_movable_list_state()?
+                .get_mergeable_marker_index(marker),
+            _ => None,
+        }
+    }
+
     pub fn encode(&mut self) -> Bytes {
         let ContainerData::State(state) = &mut self.data else {
             let lazy = match &self.data {
@@ -601,6 +623,22 @@ impl ContainerWrapper {
                     b.len() - rest.len() + value_offset,
                 )
             }
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter => {
+                let (v, rest) = CounterState::decode_value(b)?;
+                (
+                    LazyDecodedValue::Value(v),
+                    b.len() - rest.len() + value_offset,
+                )
+            }
+            #[cfg(feature = "set")]
+            ContainerType::Set => {
+                let (v, rest) = SetState::decode_value(b)?;
+                (
+                    LazyDecodedValue::Value(v),
+                    b.len() - rest.len() + value_offset,
+                )
+            }
             ContainerType::Unknown(_) => {
                 let (v, rest) = UnknownState::decode_value(b)?;
                 (
@@ -651,6 +689,12 @@ impl ContainerWrapper {
             ContainerType::Tree => TreeState::decode_snapshot_fast(idx, (v, b), ctx)?.into(),
             #[cfg(feature = "counter")]
             ContainerType::Counter => CounterState::decode_snapshot_fast(idx, (v, b), ctx)?.into(),
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter => {
+                CounterState::decode_snapshot_fast(idx, (v, b), ctx)?.into()
+            }
+            #[cfg(feature = "set")]
+            ContainerType::Set => SetState::decode_snapshot_fast(idx, (v, b), ctx)?.into(),
             ContainerType::Unknown(_) => {
                 UnknownState::decode_snapshot_fast(idx, (v, b), ctx)?.into()
             }
@@ -679,7 +723,17 @@ impl ContainerWrapper {
                 }
                 ContainerType::Tree => value.as_list().is_some_and(|value| value.is_empty()),
                 #[cfg(feature = "counter")]
-                ContainerType::Counter => value.as_double().is_some_and(|value| *value == 0.0),
+                ContainerType::Counter => {
+                    value.as_double().is_some_and(|value| *value == 0.0)
+                        || value.as_i64() == Some(&0)
+                }
+                #[cfg(feature = "typed-counter")]
+                ContainerType::TypedCounter => {
+                    value.as_double().is_some_and(|value| *value == 0.0)
+                        || value.as_i64() == Some(&0)
+                }
+                #[cfg(feature = "set")]
+                ContainerType::Set => value.as_list().is_some_and(|value| value.is_empty()),
                 ContainerType::Unknown(_) => false,
             }
         }
@@ -4,9 +4,12 @@ use loro_common::{ContainerID, LoroResult, LoroValue};
 
 use crate::{
     configure::Configure,
-    container::idx::ContainerIdx,
+    container::{
+        counter::{CounterKind, CounterOp, CounterSummary},
+        idx::ContainerIdx,
+    },
     event::{Diff, Index, InternalDiff},
-    op::{Op, RawOp, RawOpContent},
+    op::{Op, RawOp},
     LoroDocInner,
 };
 
@@ -15,12 +18,23 @@ use super::{ApplyLocalOpReturn, ContainerState, DiffApplyContext};
 #[derive(Debug, Clone)]
 pub struct CounterState {
     idx: ContainerIdx,
-    value: f64,
+    summary: CounterSummary,
 }
 
 impl CounterState {
     pub(crate) fn new(idx: ContainerIdx) -> Self {
-        Self { idx, value: 0. }
+        Self {
+            idx,
+            summary: CounterSummary::default(),
+        }
+    }
+
+    pub fn kind(&self) -> CounterKind {
+        self.summary.kind()
+    }
+
+    pub(crate) fn summary(&self) -> &CounterSummary {
+        &self.summary
     }
 }
 
@@ -34,11 +48,23 @@ impl ContainerState for CounterState {
     }
 
     fn apply_diff_and_convert(&mut self, diff: InternalDiff, _ctx: DiffApplyContext) -> Diff {
-        if let InternalDiff::Counter(diff) = diff {
-            self.value += diff;
-            Diff::Counter(diff)
-        } else {
-            unreachable!()
+        match diff {
+            InternalDiff::Counter(diff) if self.summary.kind() == CounterKind::Float => {
+                self.summary.add_float(diff);
+                Diff::Counter(diff)
+            }
+            InternalDiff::Counter(diff) => {
+                // Float ops are folded into the value of a typed counter
+                let old = self.summary.value_f64();
+                self.summary.add_float(diff);
+                Diff::Counter(self.summary.value_f64() - old)
+            }
+            InternalDiff::TypedCounter(diff) => {
+                let old = self.summary.value_f64();
+                self.summary.merge(&diff);
+                Diff::Counter(self.summary.value_f64() - old)
+            }
+            _ => unreachable!(),
         }
     }
 
@@ -48,21 +74,18 @@ impl ContainerState for CounterState {
     }
 
     fn apply_local_op(&mut self, raw_op: &RawOp, _op: &Op) -> LoroResult<ApplyLocalOpReturn> {
-        if let RawOpContent::Counter(diff) = raw_op.content {
-            self.value += diff;
-            Ok(Default::default())
-        } else {
-            unreachable!()
-        }
+        let op = CounterOp::from_raw(&raw_op.content).unwrap();
+        self.summary.apply(op, false);
+        Ok(Default::default())
     }
 
     #[doc = " Convert a state to a diff, such that an empty state will be transformed into the same as this state when it\'s applied."]
     fn to_diff(&mut self, _doc: &Weak<LoroDocInner>) -> Diff {
-        Diff::Counter(self.value)
+        Diff::Counter(self.summary.value_f64())
     }
 
     fn get_value(&mut self) -> LoroValue {
-        LoroValue::Double(self.value)
+        self.summary.value()
     }
 
     #[doc = " Get the index of the child container"]
@@ -92,8 +115,14 @@ mod snapshot {
 
     impl FastStateSnapshot for CounterState {
         fn encode_snapshot_fast<W: std::io::Write>(&mut self, mut w: W) {
-            let bytes = self.value.to_le_bytes();
+            // 1. f64 sum of the float ops
+            // 2. The summary of all the ops, only if there are typed ops, which only
+            //    typed counter containers have.
+            let bytes = self.summary.float().to_le_bytes();
             w.write_all(&bytes).unwrap();
+            if !self.summary.is_float_only() {
+                postcard::to_io(&self.summary, &mut w).unwrap();
+            }
         }
 
         fn decode_value(bytes: &[u8]) -> LoroResult<(LoroValue, &[u8])> {
@@ -102,14 +131,21 @@ mod snapshot {
                 return Ok((LoroValue::Double(0.0), bytes));
             }
 
-            if bytes.len() != 8 {
+            if bytes.len() < 8 {
                 return Err(loro_common::LoroError::DecodeError(
                     "Decode counter value failed".to_string().into_boxed_str(),
                 ));
             }
 
             let value = f64::from_le_bytes(bytes[..8].try_into().unwrap());
-            Ok((LoroValue::Double(value), &bytes[8..]))
+            let rest = &bytes[8..];
+            if rest.is_empty() {
+                return Ok((LoroValue::Double(value), rest));
+            }
+
+            // The summary is left for `decode_snapshot_fast`
+            let (summary, _) = decode_summary(rest)?;
+            Ok((summary.value(), rest))
         }
 
         fn decode_snapshot_fast(
@@ -120,15 +156,29 @@ mod snapshot {
         where
             Self: Sized,
         {
-            if !v.1.is_empty() {
+            let mut counter = CounterState::new(idx);
+            if v.1.is_empty() {
+                counter.summary = CounterSummary::from_float(*v.0.as_double().unwrap());
+                return Ok(counter);
+            }
+
+            let (summary, rest) = decode_summary(v.1)?;
+            if !rest.is_empty() {
                 return Err(loro_common::LoroError::DecodeError(
                     "Decode counter state failed".to_string().into_boxed_str(),
                 ));
             }
 
-            let mut counter = CounterState::new(idx);
-            counter.value = *v.0.as_double().unwrap();
+            counter.summary = summary;
             Ok(counter)
         }
     }
+
+    fn decode_summary(bytes: &[u8]) -> LoroResult<(CounterSummary, &[u8])> {
+        postcard::take_from_bytes::<CounterSummary>(bytes).map_err(|_| {
+            loro_common::LoroError::DecodeError(
+                "Decode counter state failed".to_string().into_boxed_str(),
+            )
+        })
+    }
 }
@@ -33,6 +33,10 @@ impl DocState {
         let is_deleted = loop {
             let id = self.arena.idx_to_id(idx).unwrap();
             if id.is_mergeable() {
+                if id.is_metadata() {
+                    // Metadata is never reachable from a root, but it's never deleted either
+                    break false;
+                }
                 depends_on_mergeable_edge = true;
             }
             if let Some(parent_idx) = self.arena.get_parent(idx) {
@@ -0,0 +1,159 @@
+use loro_common::{ContainerID, ContainerType, InternalString, LoroError, LoroResult, LoroValue};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::{ContainerState, DocState};
+use crate::{container::idx::ContainerIdx, event::Index};
+
+/// A secondary index of a list or movable list, from the value of `field` in
+/// each child map to that child map.
+///
+/// An entry is refreshed whenever its child map changes, or when a diff brings
+/// the child map (back) into the list. Entries of children that were removed
+/// from the list are dropped lazily on lookup.
+#[derive(Debug, Clone)]
+pub(super) struct ListKeyIndex {
+    field: InternalString,
+    children: FxHashMap<LoroValue, FxHashSet<ContainerIdx>>,
+    keys: FxHashMap<ContainerIdx, LoroValue>,
+}
+
+impl ListKeyIndex {
+    fn new(field: InternalString) -> Self {
+        Self {
+            field,
+            children: FxHashMap::default(),
+            keys: FxHashMap::default(),
+        }
+    }
+
+    fn set(&mut self, child: ContainerIdx, key: Option<LoroValue>) {
+        if let Some(old) = self.keys.remove(&child) {
+            if let Some(children) = self.children.get_mut(&old) {
+                children.remove(&child);
+                if children.is_empty() {
+                    self.children.remove(&old);
+                }
+            }
+        }
+
+        if let Some(key) = key {
+            self.children.entry(key.clone()).or_default().insert(child);
+            self.keys.insert(child, key);
+        }
+    }
+}
+
+impl DocState {
+    /// Index the child maps of `list` by the value of their `field`.
+    ///
+    /// It replaces the existing index of `list` if there is one.
+    pub(crate) fn create_list_key_index(&mut self, list: ContainerIdx, field: InternalString) {
+        let mut index = ListKeyIndex::new(field);
+        for value in self.get_list_values(list) {
+            let LoroValue::Container(id) = value else {
+                continue;
+            };
+            if id.container_type() != ContainerType::Map {
+                continue;
+            }
+
+            let child = self.arena.register_container(&id);
+            index.set(child, self.store.map_get(child, &index.field));
+        }
+
+        self.list_key_indexes.insert(list, index);
+    }
+
+    /// Returns whether `list` had an index.
+    pub(crate) fn remove_list_key_index(&mut self, list: ContainerIdx) -> bool {
+        self.list_key_indexes.remove(&list).is_some()
+    }
+
+    /// Refresh the index entry of `child` if it's a map inside an indexed list.
+    pub(super) fn update_list_key_index(&mut self, child: ContainerIdx) {
+        if self.list_key_indexes.is_empty() || child.get_type() != ContainerType::Map {
+            return;
+        }
+
+        let Some(parent) = self.arena.get_parent(child) else {
+            return;
+        };
+        let Some(index) = self.list_key_indexes.get_mut(&parent) else {
+            return;
+        };
+        let key = self.store.map_get(child, &index.field);
+        index.set(child, key);
+    }
+
+    #[inline]
+    pub(super) fn has_list_key_index(&self, list: ContainerIdx) -> bool {
+        self.list_key_indexes.contains_key(&list)
+    }
+
+    /// Rebuild all the indexes, used after the states are replaced without applying diffs.
+    pub(super) fn rebuild_list_key_indexes(&mut self) {
+        let indexes: Vec<_> = self
+            .list_key_indexes
+            .iter()
+            .map(|(list, index)| (*list, index.field.clone()))
+            .collect();
+        for (list, field) in indexes {
+            self.create_list_key_index(list, field);
+        }
+    }
+
+    /// Find the child map of `list` whose indexed field equals `key`.
+    ///
+    /// If several children share the key, the one with the smallest index wins.
+    /// The key is found in O(1), but the position of every child with the key is
+    /// looked up in the list, which is O(log n) per child.
+    pub(crate) fn find_in_list_key_index(
+        &mut self,
+        list: ContainerIdx,
+        key: &LoroValue,
+    ) -> LoroResult<Option<(usize, ContainerID)>> {
+        let Some(index) = self.list_key_indexes.get(&list) else {
+            return Err(LoroError::ArgErr(
+                "The list has no key index. Call `create_key_index` first"
+                    .to_string()
+                    .into_boxed_str(),
+            ));
+        };
+        let field = index.field.clone();
+        let candidates: Vec<ContainerIdx> = index
+            .children
+            .get(key)
+            .map(|x| x.iter().copied().collect())
+            .unwrap_or_default();
+
+        let mut ans: Option<(usize, ContainerID)> = None;
+        for child in candidates {
+            let id = self.arena.idx_to_id(child).unwrap();
+            let state = self.store.get_or_create_mut(list);
+            let pos = if state.contains_child(&id) {
+                match state.get_child_index(&id) {
+                    Some(Index::Seq(pos)) => Some(pos),
+                    _ => None,
+                }
+            } else {
+                None
+            };
+            let current = self.store.map_get(child, &field);
+            let index = self.list_key_indexes.get_mut(&list).unwrap();
+            let Some(pos) = pos else {
+                index.set(child, None);
+                continue;
+            };
+            if current.as_ref() != Some(key) {
+                index.set(child, current);
+                continue;
+            }
+
+            if !matches!(&ans, Some((p, _)) if *p < pos) {
+                ans = Some((pos, id));
+            }
+        }
+
+        Ok(ans)
+    }
+}
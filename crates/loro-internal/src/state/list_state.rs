@@ -1,6 +1,9 @@
 use std::{io::Write, ops::RangeBounds, sync::Weak};
 
-use super::{ApplyLocalOpReturn, ContainerState, DiffApplyContext, FastStateSnapshot};
+use super::{
+    mergeable_list_marker_bytes, translate_list_marker_with_parent, ApplyLocalOpReturn,
+    ContainerState, DiffApplyContext, FastStateSnapshot,
+};
 use crate::{
     configure::Configure,
     container::{idx::ContainerIdx, list::list_op::ListOp, ContainerID},
@@ -145,10 +148,13 @@ enum ListEntries {
     Tiny {
         entries: SmallVec<[Elem; 1]>,
         child_container_to_index: FxHashMap<ContainerID, usize>,
+        /// The elements holding each mergeable child marker, see [`mergeable_list_marker_bytes`]
+        mergeable_marker_to_index: FxHashMap<Vec<u8>, SmallVec<[usize; 1]>>,
     },
     Tree {
         tree: BTree<ListImpl>,
         child_container_to_index: FxHashMap<ContainerID, LeafIndex>,
+        mergeable_marker_to_index: MarkerLeaves,
     },
 }
 
@@ -157,6 +163,7 @@ impl ListEntries {
         Self::Tiny {
             entries: SmallVec::new(),
             child_container_to_index: Default::default(),
+            mergeable_marker_to_index: Default::default(),
         }
     }
 
@@ -182,6 +189,35 @@ impl ListEntries {
     }
 }
 
+type MarkerLeaves = FxHashMap<Vec<u8>, SmallVec<[LeafIndex; 1]>>;
+
+fn add_marker_leaf(map: &mut MarkerLeaves, marker: Vec<u8>, leaf: LeafIndex) {
+    map.entry(marker).or_default().push(leaf);
+}
+
+fn remove_marker_leaf(map: &mut MarkerLeaves, marker: &[u8], leaf: LeafIndex) {
+    if let Some(leaves) = map.get_mut(marker) {
+        leaves.retain(|x| *x != leaf);
+        if leaves.is_empty() {
+            map.remove(marker);
+        }
+    }
+}
+
+fn leaf_index(tree: &BTree<ListImpl>, leaf: LeafIndex) -> usize {
+    let mut index = 0;
+    tree.visit_previous_caches(Cursor { leaf, offset: 0 }, |cache| match cache {
+        generic_btree::PreviousCache::NodeCache(cache) => {
+            index += *cache;
+        }
+        generic_btree::PreviousCache::PrevSiblingElem(..) => {
+            index += 1;
+        }
+        generic_btree::PreviousCache::ThisElemAndOffset { .. } => {}
+    });
+    index as usize
+}
+
 impl ListState {
     pub fn new(idx: ContainerIdx) -> Self {
         Self {
@@ -194,15 +230,22 @@ impl ListState {
         let ListEntries::Tiny {
             entries,
             child_container_to_index,
+            mergeable_marker_to_index,
         } = &mut self.list
         else {
             return;
         };
 
         child_container_to_index.clear();
+        mergeable_marker_to_index.clear();
         for (index, elem) in entries.iter().enumerate() {
             if let LoroValue::Container(id) = &elem.v {
                 child_container_to_index.insert(id.clone(), index);
+            } else if let Some(marker) = mergeable_list_marker_bytes(&elem.v) {
+                mergeable_marker_to_index
+                    .entry(marker.to_vec())
+                    .or_default()
+                    .push(index);
             }
         }
     }
@@ -216,17 +259,23 @@ impl ListState {
 
         let mut tree = BTree::new();
         let mut child_container_to_index = FxHashMap::default();
+        let mut mergeable_marker_to_index = FxHashMap::default();
         for elem in entries {
             let container = elem.v.as_container().cloned();
+            let marker = mergeable_list_marker_bytes(&elem.v).map(<[u8]>::to_vec);
             let leaf = tree.push(elem);
             if let Some(container) = container {
                 child_container_to_index.insert(container, leaf.leaf);
             }
+            if let Some(marker) = marker {
+                add_marker_leaf(&mut mergeable_marker_to_index, marker, leaf.leaf);
+            }
         }
 
         self.list = ListEntries::Tree {
             tree,
             child_container_to_index,
+            mergeable_marker_to_index,
         };
     }
 
@@ -243,6 +292,7 @@ impl ListState {
         self.list = ListEntries::Tiny {
             entries,
             child_container_to_index: Default::default(),
+            mergeable_marker_to_index: Default::default(),
         };
         self.rebuild_tiny_child_index();
     }
@@ -252,6 +302,7 @@ impl ListState {
             ListEntries::Tiny {
                 entries,
                 child_container_to_index,
+                ..
             } => child_container_to_index.get(id).is_some_and(|index| {
                 entries
                     .get(*index)
@@ -260,6 +311,7 @@ impl ListState {
             ListEntries::Tree {
                 tree,
                 child_container_to_index,
+                ..
             } => child_container_to_index
                 .get(id)
                 .is_some_and(|leaf| tree.get_elem(*leaf).is_some()),
@@ -271,6 +323,7 @@ impl ListState {
             ListEntries::Tiny {
                 entries,
                 child_container_to_index,
+                ..
             } => {
                 let index = *child_container_to_index.get(id)?;
                 entries.get(index)?;
@@ -279,22 +332,31 @@ impl ListState {
             ListEntries::Tree {
                 tree,
                 child_container_to_index,
+                ..
             } => (*child_container_to_index.get(id)?, tree),
         };
 
         list.get_elem(leaf)?;
-        let mut index = 0;
-        list.visit_previous_caches(Cursor { leaf, offset: 0 }, |cache| match cache {
-            generic_btree::PreviousCache::NodeCache(cache) => {
-                index += *cache;
-            }
-            generic_btree::PreviousCache::PrevSiblingElem(..) => {
-                index += 1;
-            }
-            generic_btree::PreviousCache::ThisElemAndOffset { .. } => {}
-        });
+        Some(leaf_index(list, leaf))
+    }
 
-        Some(index as usize)
+    /// Get the index of the first element holding the given mergeable child marker.
+    pub fn get_mergeable_marker_index(&self, marker: &[u8]) -> Option<usize> {
+        match &self.list {
+            ListEntries::Tiny {
+                mergeable_marker_to_index,
+                ..
+            } => mergeable_marker_to_index.get(marker)?.first().copied(),
+            ListEntries::Tree {
+                tree,
+                mergeable_marker_to_index,
+                ..
+            } => mergeable_marker_to_index
+                .get(marker)?
+                .iter()
+                .map(|leaf| leaf_index(tree, *leaf))
+                .min(),
+        }
     }
 
     pub fn insert(&mut self, index: usize, value: LoroValue, id: IdFull) {
@@ -321,6 +383,7 @@ impl ListState {
         let ListEntries::Tree {
             tree,
             child_container_to_index,
+            mergeable_marker_to_index,
         } = &mut self.list
         else {
             unreachable!()
@@ -333,6 +396,9 @@ impl ListState {
             },
         );
 
+        if let Some(marker) = mergeable_list_marker_bytes(&value) {
+            add_marker_leaf(mergeable_marker_to_index, marker.to_vec(), leaf.leaf);
+        }
         if value.is_container() {
             child_container_to_index.insert(value.into_container().unwrap(), leaf.leaf);
         }
@@ -357,6 +423,7 @@ impl ListState {
         let ListEntries::Tree {
             tree,
             child_container_to_index,
+            mergeable_marker_to_index,
         } = &mut self.list
         else {
             unreachable!()
@@ -366,6 +433,9 @@ impl ListState {
             id,
         });
 
+        if let Some(marker) = mergeable_list_marker_bytes(&value) {
+            add_marker_leaf(mergeable_marker_to_index, marker.to_vec(), leaf.leaf);
+        }
         if value.is_container() {
             child_container_to_index.insert(value.into_container().unwrap(), leaf.leaf);
         }
@@ -381,12 +451,16 @@ impl ListState {
         let ListEntries::Tree {
             tree,
             child_container_to_index,
+            mergeable_marker_to_index,
         } = &mut self.list
         else {
             unreachable!()
         };
-        let leaf = tree.query::<LengthFinder>(&index);
-        let leaf = tree.remove_leaf(leaf.unwrap().cursor).unwrap();
+        let cursor = tree.query::<LengthFinder>(&index).unwrap().cursor;
+        let leaf = tree.remove_leaf(cursor).unwrap();
+        if let Some(marker) = mergeable_list_marker_bytes(&leaf.v) {
+            remove_marker_leaf(mergeable_marker_to_index, marker, cursor.leaf);
+        }
         if leaf.v.is_container() {
             child_container_to_index.remove(leaf.v.as_container().unwrap());
         }
@@ -434,6 +508,7 @@ impl ListState {
         let ListEntries::Tree {
             tree,
             child_container_to_index,
+            mergeable_marker_to_index,
         } = &mut self.list
         else {
             unreachable!()
@@ -441,7 +516,11 @@ impl ListState {
         let q = start..end;
         let start1 = tree.query::<LengthFinder>(&q.start);
         let end1 = tree.query::<LengthFinder>(&q.end);
+        let mut drained_markers = Vec::new();
         for v in iter::Drain::new(tree, start1, end1) {
+            if let Some(marker) = mergeable_list_marker_bytes(&v.v) {
+                drained_markers.push(marker.to_vec());
+            }
             if v.v.is_container() {
                 child_container_to_index.remove(v.v.as_container().unwrap());
                 if let Some(notify_deletion) = &mut notify_deletion {
@@ -449,6 +528,15 @@ impl ListState {
                 }
             }
         }
+        // The drained leaves are gone from the tree, so drop them from the marker index
+        for marker in drained_markers {
+            if let Some(leaves) = mergeable_marker_to_index.get_mut(&marker) {
+                leaves.retain(|leaf| tree.get_elem(*leaf).is_some());
+                if leaves.is_empty() {
+                    mergeable_marker_to_index.remove(&marker);
+                }
+            }
+        }
         self.downgrade_tree_if_tiny();
     }
 
@@ -550,6 +638,7 @@ impl ContainerState for ListState {
         let mut ans: ListDiff = ListDiff::default();
         let mut index = 0;
         let doc = &doc.upgrade().unwrap();
+        let parent_id = doc.arena.idx_to_id(self.idx);
         for span in delta.iter() {
             match span {
                 crate::delta::DeltaItem::Retain { retain: len, .. } => {
@@ -567,10 +656,10 @@ impl ContainerState for ListState {
                         }
                         either::Either::Right(v) => arr.push(v.clone()),
                     }
-                    for arr in ArrayVec::from_many(
-                        arr.iter()
-                            .map(|v| ValueOrHandler::from_value(v.clone(), doc)),
-                    ) {
+                    for arr in ArrayVec::from_many(arr.iter().map(|v| {
+                        let v = translate_list_marker_with_parent(parent_id.as_ref(), v.clone());
+                        ValueOrHandler::from_value(v, doc)
+                    })) {
                         ans.push_insert(arr, Default::default());
                     }
                     let len = arr.len();
@@ -708,11 +797,11 @@ impl ContainerState for ListState {
     #[doc = " the state will be the same as this state."]
     fn to_diff(&mut self, doc: &Weak<LoroDocInner>) -> Diff {
         let doc = &doc.upgrade().unwrap();
-        Diff::List(ListDiff::from_many(
-            self.to_vec()
-                .into_iter()
-                .map(|v| ValueOrHandler::from_value(v, doc)),
-        ))
+        let parent_id = doc.arena.idx_to_id(self.idx);
+        Diff::List(ListDiff::from_many(self.to_vec().into_iter().map(|v| {
+            let v = translate_list_marker_with_parent(parent_id.as_ref(), v);
+            ValueOrHandler::from_value(v, doc)
+        })))
     }
 
     fn get_value(&mut self) -> LoroValue {
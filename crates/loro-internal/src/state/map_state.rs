@@ -1,4 +1,8 @@
-use std::{collections::BTreeMap, sync::Weak};
+use std::{
+    collections::BTreeMap,
+    ops::{Bound, RangeBounds},
+    sync::Weak,
+};
 
 use loro_common::{ContainerID, IdLp, LoroResult, PeerID};
 use rustc_hash::FxHashMap;
@@ -50,7 +54,7 @@ impl Default for MapEntries {
 
 enum MapEntriesIter<'a> {
     SortedTiny(std::slice::Iter<'a, (InternalString, MapValue)>),
-    Tree(std::collections::btree_map::Iter<'a, InternalString, MapValue>),
+    Tree(std::collections::btree_map::Range<'a, InternalString, MapValue>),
 }
 
 impl<'a> Iterator for MapEntriesIter<'a> {
@@ -64,6 +68,34 @@ impl<'a> Iterator for MapEntriesIter<'a> {
     }
 }
 
+impl DoubleEndedIterator for MapEntriesIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::SortedTiny(iter) => iter.next_back().map(|(key, value)| (key, value)),
+            Self::Tree(iter) => iter.next_back(),
+        }
+    }
+}
+
+/// Whether no key can be in the range. `BTreeMap::range` panics on such ranges.
+fn is_empty_range(start: &Bound<InternalString>, end: &Bound<InternalString>) -> bool {
+    match (start, end) {
+        (Bound::Included(s), Bound::Included(e)) => s > e,
+        (Bound::Included(s), Bound::Excluded(e))
+        | (Bound::Excluded(s), Bound::Included(e))
+        | (Bound::Excluded(s), Bound::Excluded(e)) => s >= e,
+        _ => false,
+    }
+}
+
+fn to_owned_bound<K: AsRef<str>>(bound: Bound<&K>) -> Bound<InternalString> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.as_ref().into()),
+        Bound::Excluded(key) => Bound::Excluded(key.as_ref().into()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
 impl MapEntries {
     fn debug_assert_sorted_tiny(entries: &[(InternalString, MapValue)]) {
         debug_assert!(entries.windows(2).all(|pair| {
@@ -140,12 +172,41 @@ impl MapEntries {
     }
 
     fn iter(&self) -> MapEntriesIter<'_> {
+        self.range(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    fn range(
+        &self,
+        start: Bound<InternalString>,
+        end: Bound<InternalString>,
+    ) -> MapEntriesIter<'_> {
         match self {
             Self::SortedTiny(entries) => {
                 Self::debug_assert_sorted_tiny(entries);
-                MapEntriesIter::SortedTiny(entries.iter())
+                if is_empty_range(&start, &end) {
+                    return MapEntriesIter::SortedTiny(entries[..0].iter());
+                }
+
+                let from = match &start {
+                    Bound::Included(s) => entries.partition_point(|(key, _)| key < s),
+                    Bound::Excluded(s) => entries.partition_point(|(key, _)| key <= s),
+                    Bound::Unbounded => 0,
+                };
+                let to = match &end {
+                    Bound::Included(e) => entries.partition_point(|(key, _)| key <= e),
+                    Bound::Excluded(e) => entries.partition_point(|(key, _)| key < e),
+                    Bound::Unbounded => entries.len(),
+                };
+                MapEntriesIter::SortedTiny(entries[from..to].iter())
+            }
+            Self::Tree(map) => {
+                if is_empty_range(&start, &end) {
+                    // No key is less than the empty string
+                    return MapEntriesIter::Tree(map.range(..InternalString::from("")));
+                }
+
+                MapEntriesIter::Tree(map.range((start, end)))
             }
-            Self::Tree(map) => MapEntriesIter::Tree(map.iter()),
         }
     }
 }
@@ -437,6 +498,30 @@ impl MapState {
         self.map.iter()
     }
 
+    /// Iterate over the entries whose keys are in `range`, in key order.
+    ///
+    /// Keys are compared as strings. Deleted entries are skipped.
+    pub fn range<'a, K: AsRef<str>>(
+        &'a self,
+        range: impl RangeBounds<K>,
+    ) -> impl DoubleEndedIterator<Item = (&'a InternalString, &'a LoroValue)> + 'a {
+        let start = to_owned_bound(range.start_bound());
+        let end = to_owned_bound(range.end_bound());
+        self.map
+            .range(start, end)
+            .filter_map(|(key, value)| value.value.as_ref().map(|v| (key, v)))
+    }
+
+    /// The entry with the smallest key.
+    pub fn first(&self) -> Option<(&InternalString, &LoroValue)> {
+        self.range::<&str>(..).next()
+    }
+
+    /// The entry with the largest key.
+    pub fn last(&self) -> Option<(&InternalString, &LoroValue)> {
+        self.range::<&str>(..).next_back()
+    }
+
     fn to_map(&self) -> FxHashMap<String, LoroValue> {
         let mut ans = FxHashMap::with_capacity_and_hasher(self.len(), Default::default());
         for (key, value) in self.map.iter() {
@@ -464,6 +549,10 @@ impl MapState {
         self.size
     }
 
+    pub fn get_map_value(&self, key: &str) -> Option<&MapValue> {
+        self.map.get(&key.into())
+    }
+
     pub fn get_last_edit_peer(&self, key: &str) -> Option<PeerID> {
         self.map.get(&key.into()).map(|v| v.peer)
     }
@@ -9,13 +9,48 @@
 //! user-editable strings from being reinterpreted as internal container topology, and lets the
 //! resolver fail closed unless the ref's digest matches the exact `(parent, key, kind)` being
 //! resolved.
+//!
+//! Lists and movable lists can also hold mergeable children. A list element has no key, so the
+//! child is keyed by a user-provided stable id stored in the list element's marker, and the
+//! child is active while any element of its parent list holds that marker.
 
-use loro_common::{ContainerID, ContainerType, InternalString, LoroMapValue};
+use loro_common::{
+    ContainerID, ContainerType, InternalString, LoroListValue, LoroMapValue, LoroValue,
+};
 
 use crate::{container::idx::ContainerIdx, event::Index};
 
 use super::{ContainerState, DocState};
 
+/// Apply the list marker → `Container` boundary translation when the parent cid is known.
+///
+/// This is the list counterpart of the translation `MapState` applies to its values, used where
+/// list values are surfaced to users, such as events.
+pub(crate) fn translate_list_marker_with_parent(
+    parent_id: Option<&ContainerID>,
+    value: LoroValue,
+) -> LoroValue {
+    match parent_id {
+        Some(parent) => loro_common::translate_mergeable_list_marker_value(parent, value),
+        None => value,
+    }
+}
+
+/// The raw bytes of a list element that may be a mergeable child marker.
+///
+/// List states index their elements by these bytes, so the first element holding a child can be
+/// found without scanning the list. The digest is checked by the resolver, not here.
+pub(super) fn mergeable_list_marker_bytes(value: &LoroValue) -> Option<&[u8]> {
+    match value {
+        LoroValue::Binary(bytes)
+            if bytes.starts_with(&loro_common::MERGEABLE_LIST_MARKER_MAGIC) =>
+        {
+            Some(bytes)
+        }
+        _ => None,
+    }
+}
+
 impl DocState {
     /// Resolve a child edge using the document-level logical edge model.
     ///
@@ -52,7 +87,10 @@ impl DocState {
         key: &str,
         kind: ContainerType,
     ) -> Option<Index> {
-        if parent_idx.get_type() != ContainerType::Map {
+        if !matches!(
+            parent_idx.get_type(),
+            ContainerType::Map | ContainerType::List | ContainerType::MovableList
+        ) {
             return None;
         }
 
@@ -61,6 +99,17 @@ impl DocState {
             return None;
         }
 
+        if parent_idx.get_type() != ContainerType::Map {
+            // The first element that holds the child is its position in the list. The marker
+            // is deterministic, so the list state can look it up in its marker index.
+            let marker = loro_common::mergeable_list_marker(&actual_parent_id, key, kind);
+            let marker = mergeable_list_marker_bytes(&marker)?;
+            return self
+                .store
+                .list_mergeable_marker_index(parent_idx, marker)
+                .map(Index::Seq);
+        }
+
         let value = self.store.map_get(parent_idx, key)?;
         if loro_common::parse_mergeable_marker(&actual_parent_id, key, &value) == Some(kind) {
             Some(Index::Key(key.into()))
@@ -90,4 +139,39 @@ impl DocState {
         }
         ans
     }
+
+    /// Replace the mergeable child refs in the value of a list or movable list with the cids
+    /// of the children, so the value can be walked like one with regular child containers.
+    ///
+    /// Peers that ensure the same child concurrently each insert a marker for it. Every marker
+    /// shows the child, like `len()`, `get()` and the events do, until the next ensure deletes
+    /// the duplicates.
+    pub(super) fn resolve_mergeable_list_markers(
+        &self,
+        parent_idx: ContainerIdx,
+        list: &mut LoroListValue,
+    ) {
+        if !matches!(
+            parent_idx.get_type(),
+            ContainerType::List | ContainerType::MovableList
+        ) {
+            return;
+        }
+
+        let Some(parent_id) = self.arena.idx_to_id(parent_idx) else {
+            return;
+        };
+        if !list
+            .iter()
+            .any(|v| loro_common::parse_mergeable_list_marker(&parent_id, v).is_some())
+        {
+            return;
+        }
+
+        for value in list.make_mut().iter_mut() {
+            if let Some((key, kind)) = loro_common::parse_mergeable_list_marker(&parent_id, value) {
+                *value = LoroValue::Container(ContainerID::new_mergeable(&parent_id, &key, kind));
+            }
+        }
+    }
 }
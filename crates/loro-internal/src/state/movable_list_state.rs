@@ -24,7 +24,10 @@ use self::{
     list_item_tree::{MovableListTreeTrait, OpLenQuery, UserLenQuery},
 };
 
-use super::{ApplyLocalOpReturn, ContainerState, DiffApplyContext};
+use super::{
+    mergeable_list_marker_bytes, translate_list_marker_with_parent, ApplyLocalOpReturn,
+    ContainerState, DiffApplyContext,
+};
 
 #[derive(Debug, Clone)]
 pub struct MovableListState {
@@ -288,11 +291,12 @@ mod inner {
     use generic_btree::{BTree, Cursor, LeafIndex, Query};
     use loro_common::{CompactIdLp, ContainerID, IdFull, IdLp, LoroValue, PeerID};
     use rustc_hash::{FxHashMap, FxHashSet};
+    use smallvec::SmallVec;
     use tracing::error;
 
     use super::{
         list_item_tree::{MovableListTreeTrait, OpLenQuery, UserLenQuery},
-        Element, IndexType, ListItem,
+        mergeable_list_marker_bytes, Element, IndexType, ListItem,
     };
 
     #[derive(Debug, Clone)]
@@ -304,6 +308,9 @@ mod inner {
         /// But it's guaranteed that if there is a ContainerID in the actual list,
         /// it will be mapped correctly.
         child_container_to_elem: FxHashMap<ContainerID, CompactIdLp>,
+        /// The elements holding each mergeable child marker, see [`mergeable_list_marker_bytes`].
+        /// Like `child_container_to_elem`, it may hold elements that no longer hold the marker.
+        mergeable_marker_to_elems: MarkerElems,
     }
 
     impl PartialEq for InnerState {
@@ -319,6 +326,29 @@ mod inner {
         }
     }
 
+    type MarkerElems = FxHashMap<Vec<u8>, SmallVec<[CompactIdLp; 1]>>;
+
+    fn add_marker_elem(map: &mut MarkerElems, value: &LoroValue, elem_id: CompactIdLp) {
+        if let Some(marker) = mergeable_list_marker_bytes(value) {
+            let elems = map.entry(marker.to_vec()).or_default();
+            if !elems.contains(&elem_id) {
+                elems.push(elem_id);
+            }
+        }
+    }
+
+    fn remove_marker_elem(map: &mut MarkerElems, value: &LoroValue, elem_id: CompactIdLp) {
+        let Some(marker) = mergeable_list_marker_bytes(value) else {
+            return;
+        };
+        if let Some(elems) = map.get_mut(marker) {
+            elems.retain(|x| *x != elem_id);
+            if elems.is_empty() {
+                map.remove(marker);
+            }
+        }
+    }
+
     fn eq<T: PartialEq>(a: T, b: T) -> Result<(), ()> {
         if a == b {
             Ok(())
@@ -334,6 +364,7 @@ mod inner {
                 id_to_list_leaf: FxHashMap::default(),
                 elements: FxHashMap::default(),
                 child_container_to_elem: FxHashMap::default(),
+                mergeable_marker_to_elems: FxHashMap::default(),
             }
         }
 
@@ -353,7 +384,9 @@ mod inner {
         }
 
         pub fn remove_elem_by_id(&mut self, elem_id: &CompactIdLp) {
-            self.elements.remove(elem_id);
+            if let Some(elem) = self.elements.remove(elem_id) {
+                remove_marker_elem(&mut self.mergeable_marker_to_elems, &elem.value, *elem_id);
+            }
         }
 
         #[allow(dead_code)]
@@ -482,6 +515,29 @@ mod inner {
             self.get_child_index(id, IndexType::ForUser).is_some()
         }
 
+        /// Get the index of the first element holding the given mergeable child marker.
+        pub fn get_mergeable_marker_index(
+            &self,
+            marker: &[u8],
+            index_type: IndexType,
+        ) -> Option<usize> {
+            self.mergeable_marker_to_elems
+                .get(marker)?
+                .iter()
+                .filter_map(|elem_id| {
+                    let elem = self.elements.get(elem_id)?;
+                    if mergeable_list_marker_bytes(&elem.value) != Some(marker) {
+                        return None;
+                    }
+                    let leaf = self.id_to_list_leaf.get(&elem.pos)?;
+                    if self.list.get_elem(*leaf)?.pointed_by != Some(*elem_id) {
+                        return None;
+                    }
+                    Some(self.get_index_of(*leaf, index_type) as usize)
+                })
+                .min()
+        }
+
         #[inline]
         pub fn get_list_item_by_id(&self, id: IdLp) -> Option<&ListItem> {
             self.id_to_list_leaf
@@ -525,7 +581,8 @@ mod inner {
                 if let Some(elem_id) = &item.pointed_by {
                     let elem = self.elements.get(elem_id).unwrap();
                     on_elem_id(*elem_id, elem);
-                    self.elements.remove(elem_id);
+                    let elem = self.elements.remove(elem_id).unwrap();
+                    remove_marker_elem(&mut self.mergeable_marker_to_elems, &elem.value, *elem_id);
                 }
             }
         }
@@ -671,6 +728,7 @@ mod inner {
             if let LoroValue::Container(c) = &new_value {
                 self.child_container_to_elem.insert(c.clone(), elem_id);
             }
+            add_marker_elem(&mut self.mergeable_marker_to_elems, &new_value, elem_id);
 
             if let Some(element) = self.elements.get_mut(&elem_id) {
                 if let LoroValue::Container(c) = &element.value {
@@ -678,6 +736,13 @@ mod inner {
                         self.child_container_to_elem.remove(c);
                     }
                 }
+                if element.value != new_value {
+                    remove_marker_elem(
+                        &mut self.mergeable_marker_to_elems,
+                        &element.value,
+                        elem_id,
+                    );
+                }
                 let old_value = std::mem::replace(&mut element.value, new_value);
                 element.value_id = value_id;
                 Some(old_value)
@@ -701,6 +766,11 @@ mod inner {
                 if let LoroValue::Container(c) = &elem.value {
                     self.child_container_to_elem.insert(c.clone(), elem.elem_id);
                 }
+                add_marker_elem(
+                    &mut self.mergeable_marker_to_elems,
+                    &elem.value,
+                    elem.elem_id,
+                );
                 debug_assert!(!elem.last_set_id.is_none());
                 self.elements.insert(
                     elem.elem_id,
@@ -950,6 +1020,10 @@ impl MovableListState {
         None
     }
 
+    pub(crate) fn get_index_of_child(&self, id: &ContainerID) -> Option<usize> {
+        self.inner.get_child_index(id, IndexType::ForUser)
+    }
+
     fn get_value_inner(&self) -> Vec<LoroValue> {
         let list = self
             .inner
@@ -963,6 +1037,12 @@ impl MovableListState {
         list
     }
 
+    /// Get the index of the first element holding the given mergeable child marker.
+    pub(crate) fn get_mergeable_marker_index(&self, marker: &[u8]) -> Option<usize> {
+        self.inner
+            .get_mergeable_marker_index(marker, IndexType::ForUser)
+    }
+
     pub(crate) fn get_list_item_id_at(&self, pos: usize) -> Option<IdFull> {
         let item = self.inner.get_list_item_at(pos, IndexType::ForUser);
         item.map(|x| x.id)
@@ -1111,6 +1191,7 @@ impl ContainerState for MovableListState {
 
         {
             let doc = &doc.upgrade().unwrap();
+            let parent_id = doc.arena.idx_to_id(self.idx);
             // Apply element changes
             //
             // In this block, we need to handle the events generated from the following sources:
@@ -1145,7 +1226,11 @@ impl ContainerState for MovableListState {
                                             .delete(1)
                                             .insert(
                                                 ArrayVec::from([ValueOrHandler::from_value(
-                                                    value, doc,
+                                                    translate_list_marker_with_parent(
+                                                        parent_id.as_ref(),
+                                                        value,
+                                                    ),
+                                                    doc,
                                                 )]),
                                                 ListDeltaMeta { from_move: false },
                                             )
@@ -1175,7 +1260,11 @@ impl ContainerState for MovableListState {
                                         .retain(new_index, Default::default())
                                         .insert(
                                             ArrayVec::from([ValueOrHandler::from_value(
-                                                new_value, doc,
+                                                translate_list_marker_with_parent(
+                                                    parent_id.as_ref(),
+                                                    new_value,
+                                                ),
+                                                doc,
                                             )]),
                                             ListDeltaMeta {
                                                 from_move: (result.delete.is_some()
@@ -1224,7 +1313,13 @@ impl ContainerState for MovableListState {
                                 &DeltaRopeBuilder::new()
                                     .retain(index, Default::default())
                                     .insert(
-                                        ArrayVec::from([ValueOrHandler::from_value(value, doc)]),
+                                        ArrayVec::from([ValueOrHandler::from_value(
+                                            translate_list_marker_with_parent(
+                                                parent_id.as_ref(),
+                                                value,
+                                            ),
+                                            doc,
+                                        )]),
                                         ListDeltaMeta {
                                             from_move: (result.delete.is_some() && !value_updated)
                                                 || from_delete,
@@ -1337,12 +1432,14 @@ impl ContainerState for MovableListState {
 
     fn to_diff(&mut self, doc: &Weak<LoroDocInner>) -> Diff {
         let doc = &doc.upgrade().unwrap();
+        let parent_id = doc.arena.idx_to_id(self.idx);
         Diff::List(
             DeltaRopeBuilder::new()
                 .insert_many(
-                    self.to_vec()
-                        .into_iter()
-                        .map(|v| ValueOrHandler::from_value(v, doc)),
+                    self.to_vec().into_iter().map(|v| {
+                        let v = translate_list_marker_with_parent(parent_id.as_ref(), v);
+                        ValueOrHandler::from_value(v, doc)
+                    }),
                     Default::default(),
                 )
                 .build(),
@@ -0,0 +1,240 @@
+use std::sync::Weak;
+
+use loro_common::{ContainerID, IdLp, LoroResult, LoroValue};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    configure::Configure,
+    container::{idx::ContainerIdx, set::SetOp},
+    delta::SetDiff,
+    event::{Diff, Index, InternalDiff},
+    op::{Op, RawOp, RawOpContent},
+    LoroDocInner,
+};
+
+use super::{ApplyLocalOpReturn, ContainerState, DiffApplyContext};
+
+/// The state of an add-wins set.
+///
+/// Each element stores the id of the add op that keeps it in the set. It only
+/// changes when the element is added again.
+#[derive(Debug, Clone)]
+pub struct SetState {
+    idx: ContainerIdx,
+    elements: FxHashMap<LoroValue, IdLp>,
+}
+
+impl SetState {
+    pub(crate) fn new(idx: ContainerIdx) -> Self {
+        Self {
+            idx,
+            elements: FxHashMap::default(),
+        }
+    }
+
+    pub fn contains(&self, value: &LoroValue) -> bool {
+        self.elements.contains_key(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub(crate) fn elements(&self) -> impl Iterator<Item = (&LoroValue, &IdLp)> {
+        self.elements.iter()
+    }
+
+    /// The elements ordered by their add ops, so every peer sees the same order.
+    ///
+    /// The order isn't part of the set's contract: a concurrent add changes it
+    /// without an event.
+    fn sorted_elements(&self) -> Vec<(&LoroValue, &IdLp)> {
+        let mut ans: Vec<_> = self.elements.iter().collect();
+        ans.sort_unstable_by_key(|(_, idlp)| **idlp);
+        ans
+    }
+}
+
+impl ContainerState for SetState {
+    fn container_idx(&self) -> ContainerIdx {
+        self.idx
+    }
+
+    fn is_state_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    fn apply_diff_and_convert(&mut self, diff: InternalDiff, _ctx: DiffApplyContext) -> Diff {
+        let InternalDiff::Set(delta) = diff else {
+            unreachable!()
+        };
+
+        let mut ans = SetDiff::default();
+        for (value, idlp) in delta.updated {
+            match idlp {
+                Some(idlp) => {
+                    if self.elements.insert(value.clone(), idlp).is_none() {
+                        ans.added.push(value);
+                    }
+                }
+                None => {
+                    if self.elements.remove(&value).is_some() {
+                        ans.removed.push(value);
+                    }
+                }
+            }
+        }
+
+        Diff::Set(ans)
+    }
+
+    fn apply_diff(&mut self, diff: InternalDiff, ctx: DiffApplyContext) -> LoroResult<()> {
+        let _ = self.apply_diff_and_convert(diff, ctx);
+        Ok(())
+    }
+
+    fn apply_local_op(&mut self, raw_op: &RawOp, _op: &Op) -> LoroResult<ApplyLocalOpReturn> {
+        let RawOpContent::Set(op) = &raw_op.content else {
+            unreachable!()
+        };
+
+        match op {
+            SetOp::Add(value) => {
+                self.elements
+                    .insert(value.clone(), IdLp::new(raw_op.id.peer, raw_op.lamport));
+            }
+            SetOp::Remove(value) => {
+                self.elements.remove(value);
+            }
+        }
+
+        Ok(Default::default())
+    }
+
+    #[doc = " Convert a state to a diff, such that an empty state will be transformed into the same as this state when it\'s applied."]
+    fn to_diff(&mut self, _doc: &Weak<LoroDocInner>) -> Diff {
+        Diff::Set(SetDiff {
+            added: self
+                .sorted_elements()
+                .into_iter()
+                .map(|(v, _)| v.clone())
+                .collect(),
+            removed: Vec::new(),
+        })
+    }
+
+    fn get_value(&mut self) -> LoroValue {
+        self.sorted_elements()
+            .into_iter()
+            .map(|(v, _)| v.clone())
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    #[doc = " Get the index of the child container"]
+    #[allow(unused)]
+    fn get_child_index(&self, id: &ContainerID) -> Option<Index> {
+        None
+    }
+
+    #[allow(unused)]
+    fn get_child_containers(&self) -> Vec<ContainerID> {
+        vec![]
+    }
+
+    fn contains_child(&self, _id: &ContainerID) -> bool {
+        false
+    }
+
+    fn fork(&self, _config: &Configure) -> Self {
+        self.clone()
+    }
+}
+
+mod snapshot {
+    use crate::{
+        encoding::value_register::ValueRegister,
+        state::{
+            decode_peer_from_table, decode_peer_table, read_state_leb_u64, state_decode_error,
+            FastStateSnapshot,
+        },
+    };
+
+    use super::*;
+
+    impl FastStateSnapshot for SetState {
+        fn encode_snapshot_fast<W: std::io::Write>(&mut self, mut w: W) {
+            // 1. Vec<LoroValue> elements sorted by their add ops
+            // 2. leb128 peer_num + peers (in u64)
+            // 3. Groups of (leb128 peer_idx, leb128 lamport), one for each element
+            let elements = self.sorted_elements();
+            let values: Vec<&LoroValue> = elements.iter().map(|(v, _)| *v).collect();
+            postcard::to_io(&values, &mut w).unwrap();
+
+            let mut peer_register = ValueRegister::new();
+            for (_, idlp) in elements.iter() {
+                peer_register.register(&idlp.peer);
+            }
+
+            leb128::write::unsigned(&mut w, peer_register.vec().len() as u64).unwrap();
+            for p in peer_register.vec() {
+                w.write_all(&p.to_le_bytes()).unwrap();
+            }
+
+            for (_, idlp) in elements.iter() {
+                let peer_idx = peer_register.register(&idlp.peer);
+                leb128::write::unsigned(&mut w, peer_idx as u64).unwrap();
+                leb128::write::unsigned(&mut w, idlp.lamport as u64).unwrap();
+            }
+        }
+
+        fn decode_value(bytes: &[u8]) -> LoroResult<(LoroValue, &[u8])> {
+            let (value, bytes) =
+                postcard::take_from_bytes::<Vec<LoroValue>>(bytes).map_err(|_| {
+                    loro_common::LoroError::DecodeError(
+                        "Decode set value failed".to_string().into_boxed_str(),
+                    )
+                })?;
+            Ok((value.into(), bytes))
+        }
+
+        fn decode_snapshot_fast(
+            idx: ContainerIdx,
+            (value, mut bytes): (LoroValue, &[u8]),
+            _ctx: crate::state::ContainerCreationContext,
+        ) -> LoroResult<Self>
+        where
+            Self: Sized,
+        {
+            let values = value.into_list().unwrap();
+            let peers = decode_peer_table(&mut bytes, "Decode set state failed")?;
+            let mut ans = SetState::new(idx);
+            for value in values.iter() {
+                let peer_idx =
+                    usize::try_from(read_state_leb_u64(&mut bytes, "Decode set state failed")?)
+                        .map_err(|_| {
+                            state_decode_error("Decode set state failed: peer index overflow")
+                        })?;
+                let lamport =
+                    u32::try_from(read_state_leb_u64(&mut bytes, "Decode set state failed")?)
+                        .map_err(|_| {
+                            state_decode_error("Decode set state failed: lamport overflow")
+                        })?;
+                let peer = decode_peer_from_table(&peers, peer_idx, "Decode set state failed")?;
+                ans.elements.insert(value.clone(), IdLp::new(peer, lamport));
+            }
+
+            if !bytes.is_empty() {
+                return Err(loro_common::LoroError::DecodeError(
+                    "Decode set state failed".to_string().into_boxed_str(),
+                ));
+            }
+
+            Ok(ans)
+        }
+    }
+}
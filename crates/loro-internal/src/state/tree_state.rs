@@ -8,7 +8,7 @@ use loro_common::{
 };
 use rand::SeedableRng;
 use rustc_hash::{FxHashMap, FxHashSet};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::ops::{Deref, DerefMut};
@@ -66,7 +66,7 @@ pub(crate) struct NodePosition {
     pub(crate) idlp: IdLp,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumAsInner, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumAsInner, Serialize, Deserialize)]
 pub enum TreeParentId {
     Node(TreeID),
     Root,
@@ -941,6 +941,25 @@ impl TreeState {
         }
     }
 
+    /// Generate `n` evenly spaced positions between `left` and `right`.
+    ///
+    /// The configured jitter is applied to every generated position.
+    pub(crate) fn generate_n_positions_evenly(
+        &mut self,
+        left: Option<&FractionalIndex>,
+        right: Option<&FractionalIndex>,
+        n: usize,
+    ) -> Option<Vec<FractionalIndex>> {
+        match &mut self.fractional_index_config {
+            TreeFractionalIndexConfigInner::GenerateFractionalIndex { jitter, rng }
+                if *jitter > 0 =>
+            {
+                FractionalIndex::generate_n_evenly_jitter(left, right, n, rng.as_mut(), *jitter)
+            }
+            _ => FractionalIndex::generate_n_evenly(left, right, n),
+        }
+    }
+
     pub(crate) fn is_fractional_index_enabled(&self) -> bool {
         !matches!(
             self.fractional_index_config,
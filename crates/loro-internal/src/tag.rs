@@ -0,0 +1,96 @@
+//! Tags, i.e. named versions stored in the `tags` metadata map.
+//!
+//! A tag syncs with the document but doesn't show up in its value. Unlike a branch, a tag
+//! never moves. If two peers create the same tag concurrently, the last write wins.
+use loro_common::{LoroError, LoroResult, LoroValue};
+
+use crate::{dag::Dag, version::Frontiers, LoroDoc};
+
+const TAGS: &str = "tags";
+
+impl LoroDoc {
+    /// Tag the version at the given frontiers.
+    ///
+    /// Returns [`LoroError::ArgErr`] if the tag already exists.
+    pub fn create_tag(&self, name: &str, frontiers: &Frontiers) -> LoroResult<()> {
+        if name.is_empty() {
+            return Err(LoroError::ArgErr("Tag name cannot be empty".into()));
+        }
+        if self.tag(name).is_some() {
+            return Err(LoroError::ArgErr(
+                format!("Tag {name} already exists").into_boxed_str(),
+            ));
+        }
+        {
+            let oplog = self.oplog().lock();
+            for id in frontiers.iter() {
+                if !oplog.dag.contains(id) {
+                    return Err(LoroError::FrontiersNotFound(id));
+                }
+            }
+        }
+        self.metadata_map(TAGS)
+            .insert(name, LoroValue::Binary(frontiers.encode().into()))?;
+        self.commit_then_renew();
+        Ok(())
+    }
+
+    /// Delete a tag.
+    ///
+    /// Returns [`LoroError::NotFoundError`] if the tag doesn't exist.
+    pub fn delete_tag(&self, name: &str) -> LoroResult<()> {
+        if !self
+            .metadata_entries(TAGS)
+            .iter()
+            .any(|(key, _)| key.as_str() == name)
+        {
+            return Err(LoroError::NotFoundError(
+                format!("Tag {name} doesn't exist").into_boxed_str(),
+            ));
+        }
+        self.metadata_map(TAGS).delete(name)?;
+        self.commit_then_renew();
+        Ok(())
+    }
+
+    /// All the tags with their versions, sorted by name.
+    ///
+    /// Tags of versions that are no longer in the history of a shallow doc are skipped.
+    pub fn tags(&self) -> Vec<(String, Frontiers)> {
+        let entries = self.metadata_entries(TAGS);
+        let oplog = self.oplog().lock();
+        let mut ans: Vec<(String, Frontiers)> = entries
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let LoroValue::Binary(bytes) = value else {
+                    return None;
+                };
+                let frontiers = Frontiers::decode(&bytes).ok()?;
+                if oplog.dag.is_before_shallow_root(&frontiers) {
+                    return None;
+                }
+                Some((name.to_string(), frontiers))
+            })
+            .collect();
+        ans.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        ans
+    }
+
+    /// The version of the tag, or `None` if the tag doesn't exist.
+    pub fn tag(&self, name: &str) -> Option<Frontiers> {
+        self.tags()
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, frontiers)| frontiers)
+    }
+
+    /// Checkout the version of a tag.
+    ///
+    /// Like [`LoroDoc::checkout`], the doc is detached afterwards.
+    pub fn checkout_tag(&self, name: &str) -> LoroResult<()> {
+        let frontiers = self.tag(name).ok_or_else(|| {
+            LoroError::NotFoundError(format!("Tag {name} doesn't exist").into_boxed_str())
+        })?;
+        self.checkout(&frontiers)
+    }
+}
@@ -21,6 +21,7 @@ use crate::{
         IntoContainerId,
     },
     delta::{ResolvedMapDelta, ResolvedMapValue, StyleMeta, StyleMetaItem, TreeDiff, TreeDiffItem},
+    diff_calc::DiffCalculator,
     encoding::export_fast_updates_in_range,
     event::{Diff, ListDeltaMeta, TextDiff},
     handler::{Handler, ValueOrHandler},
@@ -39,7 +40,7 @@ use super::{
     event::{InternalContainerDiff, InternalDocDiff},
     handler::{ListHandler, MapHandler, TextHandler, TreeHandler},
     oplog::OpLog,
-    state::DocState,
+    state::{translate_list_marker_with_parent, DocState},
 };
 
 impl crate::LoroDoc {
@@ -237,6 +238,8 @@ pub(super) enum EventHint {
     MarkEnd,
     #[cfg(feature = "counter")]
     Counter(f64),
+    #[cfg(feature = "set")]
+    Set(crate::delta::SetDiff),
 }
 
 impl generic_btree::rle::HasLength for EventHint {
@@ -256,6 +259,8 @@ impl generic_btree::rle::HasLength for EventHint {
             EventHint::SetList { .. } => 1,
             #[cfg(feature = "counter")]
             EventHint::Counter(_) => 1,
+            #[cfg(feature = "set")]
+            EventHint::Set(_) => 1,
         }
     }
 }
@@ -428,7 +433,7 @@ impl Transaction {
     }
 
     #[tracing::instrument(level = "debug", skip(self))]
-    fn _commit(&mut self) -> Result<Option<CommitOptions>, LoroError> {
+    pub(crate) fn _commit(&mut self) -> Result<Option<CommitOptions>, LoroError> {
         if self.finished {
             return Ok(None);
         }
@@ -437,6 +442,7 @@ impl Transaction {
             return Ok(None);
         };
         self.finished = true;
+        *doc.last_commit_rejection.lock() = None;
         if self.local_ops.is_empty() {
             let mut state = doc.state.lock();
             state.abort_txn();
@@ -476,6 +482,17 @@ impl Transaction {
 
         let mut oplog = doc.oplog.lock();
         let mut state = doc.state.lock();
+        if let Some(reason) = modifier.rejection() {
+            *doc.last_commit_rejection.lock() = Some(reason);
+            let result = discard_rejected_change(&mut oplog, &mut state, self.origin.clone());
+            drop(state);
+            drop(oplog);
+            // Nothing was committed, so the next txn reuses this txn's ids
+            self.next_counter = self.start_counter;
+            self.next_lamport = self.start_lamport;
+            self.on_commit = None;
+            return result.map(|_| None);
+        }
 
         let Some(mut change) = oplog.uncommitted_change.take() else {
             state.abort_txn();
@@ -732,6 +749,50 @@ impl Drop for Transaction {
     }
 }
 
+/// Roll back a change that a pre-commit callback rejected.
+///
+/// The txn has already applied the change to `state` and advanced the oplog version. The
+/// version is moved back first, then the change is imported into the oplog under the import
+/// rollback guard, so the diff back to the previous version can be calculated from history,
+/// and the oplog is restored afterwards. No event is emitted.
+fn discard_rejected_change(
+    oplog: &mut OpLog,
+    state: &mut DocState,
+    origin: InternalString,
+) -> LoroResult<()> {
+    let Some(change) = oplog.uncommitted_change.take() else {
+        state.abort_txn();
+        return Err(LoroError::internal(
+            "missing uncommitted change while rejecting transaction",
+        ));
+    };
+
+    oplog.dag.discard_pending_txn(&change.deps);
+    let before_vv = oplog.vv().clone();
+    let before_frontiers = oplog.frontiers().clone();
+    oplog.begin_import_rollback();
+    oplog.insert_new_change(change, false);
+    let (diff, diff_mode) = DiffCalculator::new(false).calc_diff_internal(
+        oplog,
+        oplog.vv(),
+        oplog.frontiers(),
+        &before_vv,
+        &before_frontiers,
+        None,
+    );
+    let result = state.revert_txn(
+        InternalDocDiff {
+            origin,
+            by: crate::event::EventTriggerKind::Local,
+            diff: Cow::Owned(diff),
+            new_version: Cow::Owned(before_frontiers),
+        },
+        diff_mode,
+    );
+    oplog.rollback_import();
+    result
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct TxnContainerDiff {
     pub(crate) idx: ContainerIdx,
@@ -863,13 +924,13 @@ fn change_to_diff(
                     // We should use pos from event hint because index in op may
                     // be using op index for the MovableList
                     let mut index = pos;
+                    let parent_id = doc.arena.idx_to_id(container_idx);
                     for op in ops_for_hint.iter() {
                         let (range, _) = op.content.as_list().unwrap().as_insert().unwrap();
-                        let values = doc
-                            .arena
-                            .get_values(range.to_range())
-                            .into_iter()
-                            .map(|v| ValueOrHandler::from_value(v, &doc));
+                        let values = doc.arena.get_values(range.to_range()).into_iter().map(|v| {
+                            let v = translate_list_marker_with_parent(parent_id.as_ref(), v);
+                            ValueOrHandler::from_value(v, &doc)
+                        });
                         let len = values.len();
                         ans.push(TxnContainerDiff {
                             idx: container_idx,
@@ -924,6 +985,8 @@ fn change_to_diff(
                     });
                 }
                 EventHint::Move { from, to, value } => {
+                    let parent_id = doc.arena.idx_to_id(container_idx);
+                    let value = translate_list_marker_with_parent(parent_id.as_ref(), value);
                     let mut a = DeltaRopeBuilder::new()
                         .retain(from as usize, Default::default())
                         .delete(1)
@@ -943,6 +1006,8 @@ fn change_to_diff(
                     });
                 }
                 EventHint::SetList { index, value } => {
+                    let parent_id = doc.arena.idx_to_id(container_idx);
+                    let value = translate_list_marker_with_parent(parent_id.as_ref(), value);
                     ans.push(TxnContainerDiff {
                         idx: container_idx,
                         diff: Diff::List(
@@ -967,6 +1032,13 @@ fn change_to_diff(
                         diff: Diff::Counter(diff),
                     });
                 }
+                #[cfg(feature = "set")]
+                EventHint::Set(diff) => {
+                    ans.push(TxnContainerDiff {
+                        idx: container_idx,
+                        diff: Diff::Set(diff),
+                    });
+                }
             }
 
             // Update lamport for this hint's operations
@@ -4,12 +4,14 @@ use crate::sync::{AtomicU64, Mutex};
 use either::Either;
 use loro_common::{
     ContainerID, Counter, CounterSpan, HasIdSpan, IdSpan, LoroError, LoroResult, LoroValue, PeerID,
+    ID,
 };
 use parking_lot::lock_api::ReentrantMutex;
 use rustc_hash::{FxHashMap, FxHashSet};
 use tracing::{debug_span, info_span, instrument};
 
 use crate::{
+    arena::SharedArena,
     change::{get_sys_timestamp, Timestamp},
     cursor::{AbsolutePosition, Cursor},
     delta::TreeExternalDiff,
@@ -18,6 +20,8 @@ use crate::{
     ContainerDiff, DiffEvent, DocDiff, LoroDoc, Subscription,
 };
 
+mod encode;
+
 /// A batch of diffs.
 ///
 /// You can use `loroDoc.apply_diff(diff)` to apply the diff to the document.
@@ -91,6 +95,13 @@ impl DiffBatch {
             (cid, d)
         })
     }
+
+    /// Keep only the diffs of the containers for which `f` returns true.
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(&ContainerID) -> bool) {
+        self.cid_to_events.retain(|cid, _| f(cid));
+        let cid_to_events = &self.cid_to_events;
+        self.order.retain(|cid| cid_to_events.contains_key(cid));
+    }
 }
 
 fn transform_cursor(
@@ -148,6 +159,8 @@ fn transform_cursor(
         crate::handler::Handler::Unknown(_) => {}
         #[cfg(feature = "counter")]
         crate::handler::Handler::Counter(_) => {}
+        #[cfg(feature = "set")]
+        crate::handler::Handler::Set(_) => {}
     }
 }
 
@@ -161,6 +174,7 @@ pub struct UndoManager {
     peer: Arc<AtomicU64>,
     container_remap: Arc<Mutex<FxHashMap<ContainerID, ContainerID>>>,
     inner: Arc<parking_lot::ReentrantMutex<RefCell<UndoManagerInner>>>,
+    scope: Option<Arc<UndoScope>>,
     _peer_id_change_sub: Subscription,
     _undo_sub: Subscription,
     doc: LoroDoc,
@@ -172,6 +186,7 @@ impl std::fmt::Debug for UndoManager {
             .field("peer", &self.peer)
             .field("container_remap", &self.container_remap)
             .field("inner", &self.inner)
+            .field("scope", &self.scope)
             .finish()
     }
 }
@@ -244,6 +259,48 @@ impl UndoGroup {
     }
 }
 
+/// The containers a scoped [`UndoManager`] is limited to, together with their descendants.
+struct UndoScope {
+    containers: FxHashSet<ContainerID>,
+    arena: SharedArena,
+}
+
+impl std::fmt::Debug for UndoScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UndoScope")
+            .field("containers", &self.containers)
+            .finish()
+    }
+}
+
+impl UndoScope {
+    fn contains(&self, id: &ContainerID) -> bool {
+        if self.containers.contains(id) {
+            return true;
+        }
+        let Some(mut idx) = self.arena.id_to_idx(id) else {
+            return false;
+        };
+        while let Some(parent) = self.arena.get_parent(idx) {
+            if self
+                .arena
+                .idx_to_id(parent)
+                .is_some_and(|parent| self.containers.contains(&parent))
+            {
+                return true;
+            }
+            idx = parent;
+        }
+        false
+    }
+}
+
+/// Whether the changes to the container are recorded and undone. The metadata of the doc is
+/// never undone.
+fn is_undoable(scope: Option<&UndoScope>, id: &ContainerID) -> bool {
+    !id.is_metadata() && scope.is_none_or(|scope| scope.contains(id))
+}
+
 #[derive(Debug)]
 struct Stack {
     stack: VecDeque<(VecDeque<StackItem>, Arc<Mutex<DiffBatch>>)>,
@@ -592,6 +649,25 @@ fn get_counter_end(doc: &LoroDoc, peer: PeerID) -> Counter {
 
 impl UndoManager {
     pub fn new(doc: &LoroDoc) -> Self {
+        Self::new_with_scope(doc, None)
+    }
+
+    /// Create an undo manager that only records and undoes the local changes to `containers`
+    /// and their descendants.
+    ///
+    /// Local changes to other containers are treated like remote changes: they are never undone,
+    /// and the undo of the changes in the scope is transformed against them.
+    pub fn new_scoped(doc: &LoroDoc, containers: impl IntoIterator<Item = ContainerID>) -> Self {
+        Self::new_with_scope(
+            doc,
+            Some(Arc::new(UndoScope {
+                containers: containers.into_iter().collect(),
+                arena: doc.arena().clone(),
+            })),
+        )
+    }
+
+    fn new_with_scope(doc: &LoroDoc, scope: Option<Arc<UndoScope>>) -> Self {
         let peer = Arc::new(AtomicU64::new(doc.peer_id()));
         let peer_clone = peer.clone();
         let peer_clone2 = peer.clone();
@@ -602,6 +678,7 @@ impl UndoManager {
         let inner_clone2 = inner.clone();
         let remap_containers = Arc::new(Mutex::new(FxHashMap::default()));
         let remap_containers_clone = remap_containers.clone();
+        let scope_clone = scope.clone();
         let undo_sub = doc.subscribe_root(Arc::new(move |event| match event.event_meta.by {
             EventTriggerKind::Local => {
                 // TODO: PERF undo can be significantly faster if we can get
@@ -620,11 +697,18 @@ impl UndoManager {
                         .borrow()
                         .exclude_origin_prefixes
                         .iter()
-                        .any(|x| event.event_meta.origin.starts_with(&**x));
+                        .any(|x| event.event_meta.origin.starts_with(&**x))
+                        // A commit without visible diffs, like one that only moves
+                        // branch heads in the doc metadata, has nothing to undo
+                        || event.events.is_empty()
+                        || event
+                            .events
+                            .iter()
+                            .all(|e| !is_undoable(scope_clone.as_deref(), &e.id));
                     if should_exclude {
-                        // If the event is from the excluded origin, we don't record it
-                        // in the undo stack. But we need to record its effect like it's
-                        // a remote event.
+                        // If the event is from the excluded origin or out of the scope, we
+                        // don't record it in the undo stack. But we need to record its effect
+                        // like it's a remote event.
                         let mut inner = lock.borrow_mut();
                         inner.undo_stack.compose_remote_event(event.events);
                         inner.redo_stack.compose_remote_event(event.events);
@@ -687,6 +771,7 @@ impl UndoManager {
             peer,
             container_remap: remap_containers,
             inner,
+            scope,
             _peer_id_change_sub: sub,
             _undo_sub: undo_sub,
             doc: doc.clone(),
@@ -836,6 +921,7 @@ impl UndoManager {
                 let inner = self.inner.clone();
                 // We need to clone this because otherwise <transform_delta> will be applied to the same remote diff
                 let remote_change_clone = remote_diff.lock().clone();
+                let in_scope = |id: &ContainerID| is_undoable(self.scope.as_deref(), id);
                 let commit = doc.undo_internal(
                     IdSpan {
                         peer: self.peer(),
@@ -843,6 +929,7 @@ impl UndoManager {
                     },
                     &mut self.container_remap.lock(),
                     Some(&remote_change_clone),
+                    Some(&in_scope),
                     &mut |diff| {
                         info_span!("transform remote diff").in_scope(|| {
                             let inner = inner.lock();
@@ -984,6 +1071,47 @@ impl UndoManager {
     pub fn set_top_redo_meta(&self, meta: UndoItemMeta) {
         self.inner.lock().borrow_mut().redo_stack.set_top_meta(meta);
     }
+
+    /// Encode the undo and redo stacks, so they can be restored with
+    /// [`UndoManager::import_state`] after the document is reloaded.
+    pub fn export_state(&self) -> Vec<u8> {
+        let lock = self.inner.lock();
+        let inner = lock.borrow();
+        encode::encode_state(self.peer(), &inner, &self.container_remap.lock())
+    }
+
+    /// Replace the undo and redo stacks with the ones encoded by [`UndoManager::export_state`].
+    ///
+    /// The undo manager must use the peer the state was exported with, and the document must
+    /// contain all the changes of that peer that the stacks refer to. A state of an
+    /// unsupported version is rejected with [`LoroError::DecodeError`].
+    pub fn import_state(&self, bytes: &[u8]) -> LoroResult<()> {
+        let state = encode::decode_state(bytes, &self.doc)?;
+        if state.peer != self.peer() {
+            return Err(LoroError::UndoWithDifferentPeerId {
+                expected: state.peer,
+                actual: self.peer(),
+            });
+        }
+        let end = get_counter_end(&self.doc, state.peer);
+        if let Some(counter) = state.counter_end().filter(|&counter| counter > end) {
+            return Err(LoroError::UndoInvalidIdSpan(ID::new(
+                state.peer,
+                counter - 1,
+            )));
+        }
+
+        let lock = self.inner.lock();
+        let mut inner = lock.borrow_mut();
+        inner.undo_stack = state.undo_stack;
+        inner.redo_stack = state.redo_stack;
+        inner.next_counter = state.next_counter;
+        inner.last_popped_selection = state.last_popped_selection;
+        inner.last_undo_time = 0;
+        inner.group = None;
+        *self.container_remap.lock() = state.container_remap;
+        Ok(())
+    }
 }
 
 /// Undo the given spans of operations.
@@ -0,0 +1,362 @@
+//! Encoding of the undo and redo stacks, so they can outlive the [`UndoManager`](super::UndoManager).
+//!
+//! Besides the spans and the metadata of the stack items, the remote diffs of every stack row
+//! are encoded as well. They can't be recalculated from the oplog, and undo needs them to
+//! transform the undo diffs and the cursors against the concurrent remote changes.
+//!
+//! The encoded state starts with [`MAGIC_BYTES`] and a version byte, followed by the
+//! postcard encoding of [`EncodedUndoState`]. A state with another version is rejected, so
+//! bump [`STATE_VERSION`] whenever the encoding changes.
+use std::{collections::VecDeque, sync::Arc};
+
+use loro_common::{
+    ContainerID, Counter, CounterSpan, IdLp, InternalString, LoroError, LoroResult, LoroValue,
+    PeerID,
+};
+use loro_delta::{DeltaItem, DeltaRopeBuilder};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use super::{CursorWithPos, DiffBatch, Stack, StackItem, UndoItemMeta, UndoManagerInner};
+use crate::{
+    cursor::{AbsolutePosition, Cursor, Side},
+    delta::{ResolvedMapDelta, ResolvedMapValue, TreeDiff, TreeDiffItem},
+    event::{Diff, ListDeltaMeta, ListDiffInsertItem, TextMeta},
+    handler::ValueOrHandler,
+    sync::Mutex,
+    LoroDoc, StringSlice,
+};
+
+const MAGIC_BYTES: [u8; 4] = *b"lund";
+const STATE_VERSION: u8 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct EncodedUndoState {
+    peer: PeerID,
+    next_counter: Option<Counter>,
+    undo_stack: Vec<EncodedStackRow>,
+    redo_stack: Vec<EncodedStackRow>,
+    last_popped_selection: Option<Vec<EncodedCursor>>,
+    container_remap: Vec<(ContainerID, ContainerID)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncodedStackRow {
+    items: Vec<EncodedStackItem>,
+    remote_diff: Vec<(ContainerID, EncodedDiff)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncodedStackItem {
+    start: Counter,
+    end: Counter,
+    value: LoroValue,
+    cursors: Vec<EncodedCursor>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncodedCursor {
+    cursor: Cursor,
+    pos: usize,
+    side: Side,
+}
+
+/// The variants don't depend on the enabled features, so the encoding is the same for every
+/// build.
+#[derive(Serialize, Deserialize)]
+enum EncodedDiff {
+    List(Vec<EncodedDeltaItem<Vec<LoroValue>, bool>>),
+    Text(Vec<EncodedDeltaItem<String, TextMeta>>),
+    Map(Vec<(InternalString, Option<LoroValue>, IdLp)>),
+    Tree(Vec<TreeDiffItem>),
+    Counter(f64),
+    Set {
+        added: Vec<LoroValue>,
+        removed: Vec<LoroValue>,
+    },
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize)]
+enum EncodedDeltaItem<V, A> {
+    Retain { len: usize, attr: A },
+    Replace { value: V, attr: A, delete: usize },
+}
+
+/// The undo manager state decoded by [`decode_state`].
+pub(super) struct UndoState {
+    pub peer: PeerID,
+    pub next_counter: Option<Counter>,
+    pub undo_stack: Stack,
+    pub redo_stack: Stack,
+    pub last_popped_selection: Option<Vec<CursorWithPos>>,
+    pub container_remap: FxHashMap<ContainerID, ContainerID>,
+}
+
+impl UndoState {
+    /// The end of the counter range of the peer that the state refers to.
+    pub fn counter_end(&self) -> Option<Counter> {
+        let spans = [&self.undo_stack, &self.redo_stack]
+            .into_iter()
+            .flat_map(|stack| stack.stack.iter())
+            .flat_map(|(items, _)| items.iter())
+            .map(|item| item.span.end);
+        spans.chain(self.next_counter).max()
+    }
+}
+
+pub(super) fn encode_state(
+    peer: PeerID,
+    inner: &UndoManagerInner,
+    container_remap: &FxHashMap<ContainerID, ContainerID>,
+) -> Vec<u8> {
+    let state = EncodedUndoState {
+        peer,
+        next_counter: inner.next_counter,
+        undo_stack: encode_stack(&inner.undo_stack),
+        redo_stack: encode_stack(&inner.redo_stack),
+        last_popped_selection: inner
+            .last_popped_selection
+            .as_ref()
+            .map(|cursors| cursors.iter().map(encode_cursor).collect()),
+        container_remap: container_remap
+            .iter()
+            .map(|(from, to)| (from.clone(), to.clone()))
+            .collect(),
+    };
+    let mut ans = Vec::from(MAGIC_BYTES);
+    ans.push(STATE_VERSION);
+    postcard::to_extend(&state, ans).unwrap()
+}
+
+pub(super) fn decode_state(bytes: &[u8], doc: &LoroDoc) -> LoroResult<UndoState> {
+    if bytes.len() < MAGIC_BYTES.len() + 1 {
+        return Err(LoroError::DecodeError("Invalid undo state".into()));
+    }
+    let (magic_bytes, bytes) = bytes.split_at(MAGIC_BYTES.len());
+    if magic_bytes != MAGIC_BYTES {
+        return Err(LoroError::DecodeError(
+            "Invalid magic bytes of the undo state".into(),
+        ));
+    }
+    let (version, body) = (bytes[0], &bytes[1..]);
+    if version != STATE_VERSION {
+        return Err(LoroError::DecodeError(
+            format!("unsupported undo state version: expected {STATE_VERSION}, got {version}")
+                .into_boxed_str(),
+        ));
+    }
+    let state: EncodedUndoState = postcard::from_bytes(body).map_err(|e| {
+        LoroError::DecodeError(format!("Failed to decode the undo state: {e}").into_boxed_str())
+    })?;
+    Ok(UndoState {
+        peer: state.peer,
+        next_counter: state.next_counter,
+        undo_stack: decode_stack(state.undo_stack, doc)?,
+        redo_stack: decode_stack(state.redo_stack, doc)?,
+        last_popped_selection: state
+            .last_popped_selection
+            .map(|cursors| cursors.into_iter().map(decode_cursor).collect()),
+        container_remap: state.container_remap.into_iter().collect(),
+    })
+}
+
+fn encode_stack(stack: &Stack) -> Vec<EncodedStackRow> {
+    stack
+        .stack
+        .iter()
+        .map(|(items, remote_diff)| EncodedStackRow {
+            items: items
+                .iter()
+                .map(|item| EncodedStackItem {
+                    start: item.span.start,
+                    end: item.span.end,
+                    value: item.meta.value.clone(),
+                    cursors: item.meta.cursors.iter().map(encode_cursor).collect(),
+                })
+                .collect(),
+            remote_diff: remote_diff
+                .lock()
+                .iter()
+                .map(|(cid, diff)| (cid.clone(), encode_diff(diff)))
+                .collect(),
+        })
+        .collect()
+}
+
+fn decode_stack(rows: Vec<EncodedStackRow>, doc: &LoroDoc) -> LoroResult<Stack> {
+    let mut stack = Stack {
+        stack: VecDeque::with_capacity(rows.len()),
+        size: 0,
+    };
+    for row in rows {
+        let items: VecDeque<StackItem> = row
+            .items
+            .into_iter()
+            .map(|item| StackItem {
+                span: CounterSpan::new(item.start, item.end),
+                meta: UndoItemMeta {
+                    value: item.value,
+                    cursors: item.cursors.into_iter().map(decode_cursor).collect(),
+                },
+            })
+            .collect();
+        let mut remote_diff = DiffBatch::default();
+        for (cid, diff) in row.remote_diff {
+            if remote_diff
+                .cid_to_events
+                .insert(cid.clone(), decode_diff(diff, doc)?)
+                .is_some()
+            {
+                return Err(LoroError::DecodeError(
+                    format!("Duplicate container {cid} in the undo state").into_boxed_str(),
+                ));
+            }
+            remote_diff.order.push(cid);
+        }
+        stack.size += items.len();
+        stack
+            .stack
+            .push_back((items, Arc::new(Mutex::new(remote_diff))));
+    }
+    stack.ensure_trailing_empty_row();
+    Ok(stack)
+}
+
+fn encode_cursor(cursor: &CursorWithPos) -> EncodedCursor {
+    EncodedCursor {
+        cursor: cursor.cursor.clone(),
+        pos: cursor.pos.pos,
+        side: cursor.pos.side,
+    }
+}
+
+fn decode_cursor(cursor: EncodedCursor) -> CursorWithPos {
+    CursorWithPos {
+        cursor: cursor.cursor,
+        pos: AbsolutePosition {
+            pos: cursor.pos,
+            side: cursor.side,
+        },
+    }
+}
+
+fn encode_diff(diff: &Diff) -> EncodedDiff {
+    match diff {
+        Diff::List(list) => EncodedDiff::List(
+            list.iter()
+                .map(|item| match item {
+                    DeltaItem::Retain { len, attr } => EncodedDeltaItem::Retain {
+                        len: *len,
+                        attr: attr.from_move,
+                    },
+                    DeltaItem::Replace {
+                        value,
+                        attr,
+                        delete,
+                    } => EncodedDeltaItem::Replace {
+                        value: value.iter().map(|v| v.to_value()).collect(),
+                        attr: attr.from_move,
+                        delete: *delete,
+                    },
+                })
+                .collect(),
+        ),
+        Diff::Text(text) => EncodedDiff::Text(
+            text.iter()
+                .map(|item| match item {
+                    DeltaItem::Retain { len, attr } => EncodedDeltaItem::Retain {
+                        len: *len,
+                        attr: attr.clone(),
+                    },
+                    DeltaItem::Replace {
+                        value,
+                        attr,
+                        delete,
+                    } => EncodedDeltaItem::Replace {
+                        value: value.as_str().to_string(),
+                        attr: attr.clone(),
+                        delete: *delete,
+                    },
+                })
+                .collect(),
+        ),
+        Diff::Map(map) => EncodedDiff::Map(
+            map.updated
+                .iter()
+                .map(|(key, v)| (key.clone(), v.value.as_ref().map(|v| v.to_value()), v.idlp))
+                .collect(),
+        ),
+        Diff::Tree(tree) => EncodedDiff::Tree(tree.diff.clone()),
+        #[cfg(feature = "counter")]
+        Diff::Counter(c) => EncodedDiff::Counter(*c),
+        #[cfg(feature = "set")]
+        Diff::Set(set) => EncodedDiff::Set {
+            added: set.added.clone(),
+            removed: set.removed.clone(),
+        },
+        Diff::Unknown => EncodedDiff::Unknown,
+    }
+}
+
+fn decode_diff(diff: EncodedDiff, doc: &LoroDoc) -> LoroResult<Diff> {
+    let value_or_handler = |v| ValueOrHandler::from_value(v, &doc.inner);
+    Ok(match diff {
+        EncodedDiff::List(items) => {
+            let mut builder = DeltaRopeBuilder::<ListDiffInsertItem, ListDeltaMeta>::new();
+            for item in items {
+                builder = match item {
+                    EncodedDeltaItem::Retain { len, attr } => {
+                        builder.retain(len, ListDeltaMeta { from_move: attr })
+                    }
+                    EncodedDeltaItem::Replace {
+                        value,
+                        attr,
+                        delete,
+                    } => builder
+                        .insert_many(
+                            value.into_iter().map(value_or_handler),
+                            ListDeltaMeta { from_move: attr },
+                        )
+                        .delete(delete),
+                };
+            }
+            Diff::List(builder.build())
+        }
+        EncodedDiff::Text(items) => {
+            let mut builder = DeltaRopeBuilder::<StringSlice, TextMeta>::new();
+            for item in items {
+                builder = match item {
+                    EncodedDeltaItem::Retain { len, attr } => builder.retain(len, attr),
+                    EncodedDeltaItem::Replace {
+                        value,
+                        attr,
+                        delete,
+                    } => builder.replace(StringSlice::from(value), attr, delete),
+                };
+            }
+            Diff::Text(builder.build())
+        }
+        EncodedDiff::Map(entries) => Diff::Map(ResolvedMapDelta {
+            updated: entries
+                .into_iter()
+                .map(|(key, value, idlp)| {
+                    let value = value.map(value_or_handler);
+                    (key, ResolvedMapValue { value, idlp })
+                })
+                .collect(),
+        }),
+        EncodedDiff::Tree(diff) => Diff::Tree(TreeDiff { diff }),
+        #[cfg(feature = "counter")]
+        EncodedDiff::Counter(c) => Diff::Counter(c),
+        #[cfg(feature = "set")]
+        EncodedDiff::Set { added, removed } => Diff::Set(crate::delta::SetDiff { added, removed }),
+        EncodedDiff::Unknown => Diff::Unknown,
+        #[allow(unreachable_patterns)]
+        _ => {
+            return Err(LoroError::DecodeError(
+                "The undo state refers to a container type that is not enabled".into(),
+            ))
+        }
+    })
+}
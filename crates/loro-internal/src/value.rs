@@ -241,6 +241,8 @@ enum TypeHint {
     Tree,
     #[cfg(feature = "counter")]
     Counter,
+    #[cfg(feature = "set")]
+    Set,
 }
 
 pub trait ApplyDiff {
@@ -287,6 +289,12 @@ impl ApplyDiff for LoroValue {
                 *value = s.into()
             }
             LoroValue::List(seq) => {
+                #[cfg(feature = "set")]
+                if matches!(diff.first(), Some(Diff::Set(_))) {
+                    apply_set_diff(seq.make_mut(), diff);
+                    return;
+                }
+
                 let is_tree = matches!(diff.first(), Some(Diff::Tree(_)));
                 if !is_tree {
                     let seq = seq.make_mut();
@@ -377,6 +385,12 @@ impl ApplyDiff for LoroValue {
                 *value = s.into();
             }
             LoroValue::List(seq) => {
+                #[cfg(feature = "set")]
+                if matches!(diff.first(), Some(Diff::Set(_))) {
+                    apply_set_diff(seq.make_mut(), diff);
+                    return;
+                }
+
                 let is_tree = matches!(diff.first(), Some(Diff::Tree(_)));
                 if !is_tree {
                     let seq = seq.make_mut();
@@ -453,6 +467,8 @@ impl ApplyDiff for LoroValue {
             Diff::Tree(_) => TypeHint::Tree,
             #[cfg(feature = "counter")]
             Diff::Counter(_) => TypeHint::Counter,
+            #[cfg(feature = "set")]
+            Diff::Set(_) => TypeHint::Set,
             Diff::Unknown => unreachable!(),
         };
         let value = {
@@ -479,6 +495,8 @@ impl ApplyDiff for LoroValue {
                             TypeHint::Tree => LoroValue::List(Default::default()),
                             #[cfg(feature = "counter")]
                             TypeHint::Counter => LoroValue::Double(0.),
+                            #[cfg(feature = "set")]
+                            TypeHint::Set => LoroValue::List(Default::default()),
                         })
                     }
                     Index::Seq(index) => {
@@ -511,6 +529,20 @@ impl ApplyDiff for LoroValue {
     }
 }
 
+#[cfg(feature = "set")]
+/// The order of a set is unspecified, so the added values are just appended.
+fn apply_set_diff(seq: &mut Vec<LoroValue>, diff: &[Diff]) {
+    for item in diff.iter() {
+        let diff = item.as_set().unwrap();
+        seq.retain(|v| !diff.removed.contains(v));
+        for v in diff.added.iter() {
+            if !seq.contains(v) {
+                seq.push(v.clone());
+            }
+        }
+    }
+}
+
 pub(crate) fn unresolved_to_collection(v: &ValueOrHandler) -> LoroValue {
     match v {
         ValueOrHandler::Value(v) => v.clone(),
@@ -281,7 +281,84 @@ fn mergeable_cid_roundtrips_escaped_root_base_parent() {
 }
 
 #[test]
-#[should_panic(expected = "mergeable child parent must be a map")]
+fn mergeable_cid_roundtrips_list_parents() {
+    use loro_common::{ContainerID, ContainerType, ID};
+
+    let list = ContainerID::new_root("sections", ContainerType::List);
+    let cid = ContainerID::new_mergeable(&list, "intro", ContainerType::Text);
+    let name = match &cid {
+        ContainerID::Root { name, .. } => name.as_str(),
+        _ => panic!("expected Root"),
+    };
+    assert_eq!(name, "🤝:$sections>\\lintro");
+    assert_eq!(
+        cid.parse_mergeable().unwrap(),
+        (list.clone(), "intro".to_string(), ContainerType::Text)
+    );
+
+    // A key that starts with a backslash is escaped, so it can't be read as a tag.
+    let cid = ContainerID::new_mergeable(&list, "\\lx", ContainerType::Map);
+    assert_eq!(
+        cid.parse_mergeable().unwrap(),
+        (list, "\\lx".to_string(), ContainerType::Map)
+    );
+
+    let movable = ContainerID::new_normal(ID::new(7, 3), ContainerType::MovableList);
+    let cid = ContainerID::new_mergeable(&movable, "a", ContainerType::Map);
+    assert_eq!(
+        cid.parse_mergeable().unwrap(),
+        (movable, "a".to_string(), ContainerType::Map)
+    );
+}
+
+#[test]
+fn nested_mergeable_cid_roundtrips_through_list_parents() {
+    use loro_common::{ContainerID, ContainerType};
+
+    let root = ContainerID::new_root("state", ContainerType::Map);
+    let list = ContainerID::new_mergeable(&root, "sections", ContainerType::MovableList);
+    let section = ContainerID::new_mergeable(&list, "intro", ContainerType::Map);
+    let body = ContainerID::new_mergeable(&section, "body", ContainerType::Text);
+    let name = match &body {
+        ContainerID::Root { name, .. } => name.as_str(),
+        _ => panic!("expected Root"),
+    };
+    assert_eq!(name, "🤝:$state>sections>\\vintro>body");
+
+    assert_eq!(
+        body.parse_mergeable().unwrap(),
+        (section.clone(), "body".to_string(), ContainerType::Text)
+    );
+    assert_eq!(
+        section.parse_mergeable().unwrap(),
+        (list.clone(), "intro".to_string(), ContainerType::Map)
+    );
+    assert_eq!(
+        list.parse_mergeable().unwrap(),
+        (root, "sections".to_string(), ContainerType::MovableList)
+    );
+}
+
+#[test]
+fn parent_kind_tags_are_only_valid_at_segment_start() {
+    use loro_common::{ContainerID, ContainerType};
+
+    for name in [
+        "🤝:$state>in\\lside",
+        "🤝:$state>key\\v",
+        "🤝:$state>\\l\\lkey",
+    ] {
+        let cid = ContainerID::Root {
+            name: name.into(),
+            container_type: ContainerType::Map,
+        };
+        assert!(!cid.is_mergeable(), "payload {name:?}");
+        assert_eq!(cid.parse_mergeable(), None, "payload {name:?}");
+    }
+}
+
+#[test]
+#[should_panic(expected = "mergeable child parent must be a map, list or movable list")]
 fn mergeable_cid_rejects_non_map_parent() {
     use loro_common::{ContainerID, ContainerType};
 
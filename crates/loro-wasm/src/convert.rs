@@ -452,7 +452,7 @@ pub(crate) fn handler_to_js_value(handler: Handler, for_json: bool) -> JsValue {
         Handler::Tree(t) => LoroTree { handler: t }.into(),
         Handler::MovableList(m) => LoroMovableList { handler: m }.into(),
         Handler::Counter(c) => LoroCounter { handler: c }.into(),
-        Handler::Unknown(_) => unreachable!(),
+        _ => unreachable!(),
     }
 }
 
@@ -394,7 +394,7 @@ fn container_type_to_str(ty: ContainerType) -> &'static str {
         ContainerType::MovableList => "MovableList",
         ContainerType::Tree => "Tree",
         ContainerType::Counter => "Counter",
-        ContainerType::Unknown(_) => "Unknown",
+        _ => "Unknown",
     }
 }
 
@@ -1235,7 +1235,7 @@ impl LoroDoc {
                 let counter = self.doc.get_counter(container_id);
                 LoroCounter { handler: counter }.into()
             }
-            ContainerType::Unknown(_) => {
+            _ => {
                 return Err(JsValue::from_str(
                     "You are attempting to get an unknown container",
                 ));
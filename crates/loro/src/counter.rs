@@ -1,10 +1,21 @@
+pub use loro_internal::container::counter::CounterKind;
 use loro_internal::{
-    container::ContainerID, handler::counter::CounterHandler, HandlerTrait, LoroResult,
+    container::ContainerID, handler::counter::CounterHandler, HandlerTrait, LoroResult, LoroValue,
 };
 
 use crate::{Container, ContainerTrait, LoroDoc, SealedTrait};
 
 /// A counter that can be incremented or decremented.
+///
+/// By default it's a counter of `f64` increments. With the `typed-counter`
+/// feature, a typed counter can also be used as an int counter, a bounded
+/// counter or a max register, see [`CounterKind`]. It takes the kind of the ops
+/// applied to it, and the ops of other kinds are folded into that kind. The
+/// int, bounded and max ops fail on a counter that is not typed.
+///
+/// Typed counters are a separate container type. Peers built without the
+/// `typed-counter` feature, including older versions, import them as unknown
+/// containers and skip their ops.
 #[derive(Debug, Clone)]
 pub struct LoroCounter {
     pub(crate) handler: CounterHandler,
@@ -24,6 +35,19 @@ impl LoroCounter {
         }
     }
 
+    /// Create a new typed Counter.
+    #[cfg(feature = "typed-counter")]
+    pub fn new_typed() -> Self {
+        Self {
+            handler: CounterHandler::new_typed_detached(),
+        }
+    }
+
+    /// Whether it's a typed counter, which accepts int, bounded and max ops.
+    pub fn is_typed(&self) -> bool {
+        self.handler.is_typed()
+    }
+
     /// Increment the counter by the given value.
     pub fn increment(&self, value: f64) -> LoroResult<()> {
         self.handler.increment(value)
@@ -34,14 +58,95 @@ impl LoroCounter {
         self.handler.decrement(value)
     }
 
+    /// Increment an int counter by the given value.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let likes = doc.get_typed_counter("likes");
+    /// likes.increment_int(3).unwrap();
+    /// likes.decrement_int(1).unwrap();
+    /// assert_eq!(likes.get_i64(), Some(2));
+    /// ```
+    #[cfg(feature = "typed-counter")]
+    pub fn increment_int(&self, value: i64) -> LoroResult<()> {
+        self.handler.increment_int(value)
+    }
+
+    /// Decrement an int counter by the given value.
+    #[cfg(feature = "typed-counter")]
+    pub fn decrement_int(&self, value: i64) -> LoroResult<()> {
+        self.handler.decrement_int(value)
+    }
+
+    /// Increment a bounded counter by the given value.
+    ///
+    /// The value of a bounded counter never shows below the largest
+    /// `lower_bound` used by its ops. If concurrent decrements take the sum
+    /// below it, the later increments pay the overdraft back first.
+    #[cfg(feature = "typed-counter")]
+    pub fn increment_bounded(&self, value: i64, lower_bound: i64) -> LoroResult<()> {
+        self.handler.increment_bounded(value, lower_bound)
+    }
+
+    /// Decrement a bounded counter by at most the given value, without going
+    /// below the lower bound.
+    ///
+    /// Returns the amount that is actually decremented.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let stock = doc.get_typed_counter("stock");
+    /// stock.increment_bounded(3, 0).unwrap();
+    /// assert_eq!(stock.decrement_bounded(5, 0).unwrap(), 3);
+    /// assert_eq!(stock.get_i64(), Some(0));
+    /// ```
+    #[cfg(feature = "typed-counter")]
+    pub fn decrement_bounded(&self, value: i64, lower_bound: i64) -> LoroResult<i64> {
+        self.handler.decrement_bounded(value, lower_bound)
+    }
+
+    /// Propose a value for a max register.
+    ///
+    /// Returns `false` if the register already holds a value that is not
+    /// smaller than the given value.
+    #[cfg(feature = "typed-counter")]
+    pub fn update_max(&self, value: f64) -> LoroResult<bool> {
+        self.handler.update_max(value)
+    }
+
+    /// Get the kind of the counter.
+    pub fn kind(&self) -> CounterKind {
+        self.handler.counter_kind()
+    }
+
     /// Get the current value of the counter.
+    ///
+    /// The value of an int or bounded counter is converted to `f64`.
     pub fn get_value(&self) -> f64 {
-        self.handler.get_value().into_double().unwrap()
+        self.get()
     }
 
     /// Get the current value of the counter
+    ///
+    /// The value of an int or bounded counter is converted to `f64`.
     pub fn get(&self) -> f64 {
-        self.handler.get_value().into_double().unwrap()
+        match self.handler.get_value() {
+            LoroValue::I64(v) => v as f64,
+            v => v.into_double().unwrap(),
+        }
+    }
+
+    /// Get the exact value of an int or bounded counter.
+    ///
+    /// Returns `None` for float counters and max registers.
+    pub fn get_i64(&self) -> Option<i64> {
+        self.handler.get_value().into_i64().ok()
     }
 }
 
@@ -4,6 +4,8 @@ use delta::DeltaRope;
 use enum_as_inner::EnumAsInner;
 use loro_common::IdLp;
 use loro_internal::container::ContainerID;
+#[cfg(feature = "set")]
+pub use loro_internal::delta::SetDiff;
 pub use loro_internal::delta::TreeDiff;
 use loro_internal::delta::{ResolvedMapDelta, ResolvedMapValue};
 use loro_internal::event::{EventTriggerKind, ListDeltaMeta};
@@ -65,6 +67,9 @@ pub enum Diff<'a> {
     #[cfg(feature = "counter")]
     /// A counter diff.
     Counter(f64),
+    #[cfg(feature = "set")]
+    /// A set diff.
+    Set(Cow<'a, SetDiff>),
     /// An unknown diff.
     Unknown,
 }
@@ -181,6 +186,8 @@ impl<'a> From<&'a DiffInner> for Diff<'a> {
             DiffInner::Tree(t) => Diff::Tree(Cow::Borrowed(t)),
             #[cfg(feature = "counter")]
             DiffInner::Counter(c) => Diff::Counter(*c),
+            #[cfg(feature = "set")]
+            DiffInner::Set(s) => Diff::Set(Cow::Borrowed(s)),
             DiffInner::Unknown => Diff::Unknown,
             _ => todo!(),
         }
@@ -234,6 +241,8 @@ impl From<DiffInner> for Diff<'static> {
             DiffInner::Tree(t) => Diff::Tree(Cow::Owned(t.clone())),
             #[cfg(feature = "counter")]
             DiffInner::Counter(c) => Diff::Counter(c),
+            #[cfg(feature = "set")]
+            DiffInner::Set(s) => Diff::Set(Cow::Owned(s)),
             DiffInner::Unknown => Diff::Unknown,
             _ => todo!(),
         }
@@ -330,6 +339,8 @@ fn validate_diff_for_apply(diff: &Diff<'_>) -> LoroResult<()> {
         Diff::Map(_) | Diff::Tree(_) | Diff::Unknown => {}
         #[cfg(feature = "counter")]
         Diff::Counter(_) => {}
+        #[cfg(feature = "set")]
+        Diff::Set(_) => {}
     }
 
     Ok(())
@@ -410,6 +421,8 @@ impl From<Diff<'static>> for DiffInner {
             Diff::Tree(cow) => DiffInner::Tree(cow.into_owned()),
             #[cfg(feature = "counter")]
             Diff::Counter(c) => DiffInner::Counter(c),
+            #[cfg(feature = "set")]
+            Diff::Set(s) => DiffInner::Set(s.into_owned()),
             Diff::Unknown => DiffInner::Unknown,
         }
     }
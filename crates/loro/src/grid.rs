@@ -0,0 +1,358 @@
+use rustc_hash::FxHashSet;
+
+use crate::{
+    Container, ContainerID, ContainerTrait, ContainerType, LoroError, LoroMap, LoroMovableList,
+    LoroResult, LoroValue, ValueOrContainer, ID,
+};
+
+const ROWS: &str = "rows";
+const COLS: &str = "cols";
+
+/// A grid of cells with movable rows and columns, for spreadsheets and tables.
+///
+/// Every row and column has a stable id that is assigned when it's inserted, and cells are
+/// addressed by `(row id, column id)`. Moving rows or columns doesn't touch the cells, and
+/// concurrent insertions, moves and deletions of rows and columns merge with the semantics of
+/// [`LoroMovableList`]. Only the cells that were set are stored, so sparse grids stay small.
+///
+/// A grid is stored in a [`LoroMap`] with two children, `"rows"` and `"cols"`:
+/// [`LoroMovableList`]s of [`LoroMap`]s, in display order. The id of a row or column is the id
+/// of its map, so it's found in O(log n) with [`LoroMovableList::get_index_of_child`]. A row
+/// map holds the cells of the row, keyed by column id; column maps are empty.
+///
+/// Deleting a row deletes its map, so its cells go with it, including the ones set
+/// concurrently with the deletion. Deleting a column deletes its cells from every row. A cell
+/// that is set concurrently with the deletion of its column is hidden from
+/// [`get`](Self::get) and [`get_value`](Self::get_value), and can be deleted with
+/// [`remove_orphans`](Self::remove_orphans).
+///
+/// # Example
+/// ```
+/// use loro::{LoroDoc, LoroValue};
+///
+/// let doc = LoroDoc::new();
+/// let grid = doc.get_grid("sheet");
+/// let a = grid.insert_row(0).unwrap();
+/// let b = grid.insert_row(1).unwrap();
+/// let x = grid.insert_col(0).unwrap();
+/// grid.set(&a, &x, 1).unwrap();
+/// grid.set(&b, &x, 2).unwrap();
+///
+/// grid.move_row(1, 0).unwrap();
+/// assert_eq!(grid.get_at(0, 0), Some(LoroValue::from(2)));
+/// grid.delete_row(0).unwrap();
+/// assert_eq!(grid.get(&b, &x), None);
+/// assert_eq!(grid.get_value(), vec![vec![1]].into());
+/// ```
+#[derive(Debug, Clone)]
+pub struct LoroGrid {
+    map: LoroMap,
+}
+
+impl LoroGrid {
+    /// Use `map` as the storage of a grid.
+    ///
+    /// The map must be attached to a document before rows or columns can be inserted.
+    pub fn from_map(map: LoroMap) -> Self {
+        Self { map }
+    }
+
+    /// Get the map the grid is stored in.
+    pub fn map(&self) -> &LoroMap {
+        &self.map
+    }
+
+    /// Get the number of rows.
+    pub fn row_count(&self) -> usize {
+        self.axis(ROWS).map_or(0, |axis| axis.len())
+    }
+
+    /// Get the number of columns.
+    pub fn col_count(&self) -> usize {
+        self.axis(COLS).map_or(0, |axis| axis.len())
+    }
+
+    /// Get the ids of the rows, in display order.
+    pub fn row_ids(&self) -> Vec<String> {
+        self.ids(ROWS)
+    }
+
+    /// Get the ids of the columns, in display order.
+    pub fn col_ids(&self) -> Vec<String> {
+        self.ids(COLS)
+    }
+
+    /// Get the id of the row at `index`.
+    pub fn row_id(&self, index: usize) -> Option<String> {
+        self.id_at(ROWS, index)
+    }
+
+    /// Get the id of the column at `index`.
+    pub fn col_id(&self, index: usize) -> Option<String> {
+        self.id_at(COLS, index)
+    }
+
+    /// Get the index of the row with the given id, or `None` if it was deleted.
+    pub fn row_index(&self, row: &str) -> Option<usize> {
+        self.find(ROWS, row).map(|(index, _)| index)
+    }
+
+    /// Get the index of the column with the given id, or `None` if it was deleted.
+    pub fn col_index(&self, col: &str) -> Option<usize> {
+        self.find(COLS, col).map(|(index, _)| index)
+    }
+
+    /// Insert an empty row at `index` and return its id.
+    pub fn insert_row(&self, index: usize) -> LoroResult<String> {
+        self.insert(ROWS, index, "LoroGrid::insert_row")
+    }
+
+    /// Insert an empty column at `index` and return its id.
+    pub fn insert_col(&self, index: usize) -> LoroResult<String> {
+        self.insert(COLS, index, "LoroGrid::insert_col")
+    }
+
+    /// Delete the row at `index` together with its cells.
+    pub fn delete_row(&self, index: usize) -> LoroResult<()> {
+        self.delete(ROWS, index)?;
+        Ok(())
+    }
+
+    /// Delete the column at `index` together with its cells.
+    pub fn delete_col(&self, index: usize) -> LoroResult<()> {
+        let col = self.delete(COLS, index)?;
+        for row in self.row_maps() {
+            if row.get(&col).is_some() {
+                row.delete(&col)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the row at `from` to `to`. The row is at `to` after the move.
+    pub fn move_row(&self, from: usize, to: usize) -> LoroResult<()> {
+        self.mov(ROWS, from, to)
+    }
+
+    /// Move the column at `from` to `to`. The column is at `to` after the move.
+    pub fn move_col(&self, from: usize, to: usize) -> LoroResult<()> {
+        self.mov(COLS, from, to)
+    }
+
+    /// Set the value of the cell at `(row, col)`.
+    ///
+    /// Returns [`LoroError::NotFoundError`] if the row or the column doesn't exist.
+    pub fn set(&self, row: &str, col: &str, value: impl Into<LoroValue>) -> LoroResult<()> {
+        let Some((_, row_map)) = self.find(ROWS, row) else {
+            return Err(LoroError::NotFoundError(
+                format!("Row {row:?} is not in the grid").into_boxed_str(),
+            ));
+        };
+        if self.find(COLS, col).is_none() {
+            return Err(LoroError::NotFoundError(
+                format!("Column {col:?} is not in the grid").into_boxed_str(),
+            ));
+        }
+        row_map.insert(col, value)
+    }
+
+    /// Set the value of the cell at the given row and column indexes.
+    pub fn set_at(&self, row: usize, col: usize, value: impl Into<LoroValue>) -> LoroResult<()> {
+        let (row, col) = self.entries_at(row, col)?;
+        row.insert(&col, value)
+    }
+
+    /// Get the value of the cell at `(row, col)`, or `None` if the cell is empty or its row or
+    /// column doesn't exist.
+    pub fn get(&self, row: &str, col: &str) -> Option<LoroValue> {
+        let (_, row) = self.find(ROWS, row)?;
+        self.find(COLS, col)?;
+        cell(&row, col)
+    }
+
+    /// Get the value of the cell at the given row and column indexes.
+    pub fn get_at(&self, row: usize, col: usize) -> Option<LoroValue> {
+        let (row, col) = self.entries_at(row, col).ok()?;
+        cell(&row, &col)
+    }
+
+    /// Clear the cell at `(row, col)`.
+    pub fn remove(&self, row: &str, col: &str) -> LoroResult<()> {
+        if let Some((_, row)) = self.find(ROWS, row) {
+            if row.get(col).is_some() {
+                row.delete(col)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the visible grid as a list of rows, each a list of cell values in column order.
+    /// Empty cells are [`LoroValue::Null`].
+    pub fn get_value(&self) -> LoroValue {
+        let cols = self.ids(COLS);
+        let Some(LoroValue::List(rows)) = self.axis(ROWS).map(|axis| axis.get_deep_value()) else {
+            return Vec::<LoroValue>::new().into();
+        };
+        let rows: Vec<LoroValue> = rows
+            .iter()
+            .map(|row| {
+                let row = row.as_map();
+                cols.iter()
+                    .map(|col| {
+                        row.and_then(|row| row.get(col))
+                            .cloned()
+                            .unwrap_or(LoroValue::Null)
+                    })
+                    .collect::<Vec<_>>()
+                    .into()
+            })
+            .collect();
+        rows.into()
+    }
+
+    /// Delete the cells whose column was deleted, and return how many were deleted.
+    ///
+    /// Such cells are left behind when a cell is set concurrently with the deletion of its
+    /// column. Cells set concurrently with the deletion of their row are deleted with the row.
+    pub fn remove_orphans(&self) -> LoroResult<usize> {
+        let cols: FxHashSet<String> = self.ids(COLS).into_iter().collect();
+        let mut removed = 0;
+        for row in self.row_maps() {
+            let orphans: Vec<String> = row
+                .keys()
+                .filter(|col| !cols.contains(col.as_str()))
+                .map(|col| col.to_string())
+                .collect();
+            for col in orphans {
+                row.delete(&col)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn axis(&self, key: &str) -> Option<LoroMovableList> {
+        self.map
+            .get(key)?
+            .into_container()
+            .ok()?
+            .into_movable_list()
+            .ok()
+    }
+
+    /// Find the map of the row or column with the given id, and its index.
+    fn find(&self, key: &str, id: &str) -> Option<(usize, LoroMap)> {
+        let id = ContainerID::new_normal(ID::try_from(id).ok()?, ContainerType::Map);
+        let index = self.axis(key)?.get_index_of_child(&id)?;
+        Some((index, self.entry_at(key, index)?))
+    }
+
+    fn entry_at(&self, key: &str, index: usize) -> Option<LoroMap> {
+        match self.axis(key)?.get(index)? {
+            ValueOrContainer::Container(Container::Map(entry)) => Some(entry),
+            _ => None,
+        }
+    }
+
+    fn row_maps(&self) -> Vec<LoroMap> {
+        let Some(rows) = self.axis(ROWS) else {
+            return Vec::new();
+        };
+        let mut ans = Vec::new();
+        rows.for_each(|value| {
+            if let ValueOrContainer::Container(Container::Map(row)) = value {
+                ans.push(row);
+            }
+        });
+        ans
+    }
+
+    fn ids(&self, key: &str) -> Vec<String> {
+        let Some(axis) = self.axis(key) else {
+            return Vec::new();
+        };
+        let mut ans = Vec::new();
+        axis.for_each(|value| {
+            if let ValueOrContainer::Container(Container::Map(entry)) = value {
+                ans.push(entry_id(&entry));
+            }
+        });
+        ans
+    }
+
+    fn id_at(&self, key: &str, index: usize) -> Option<String> {
+        Some(entry_id(&self.entry_at(key, index)?))
+    }
+
+    /// The row map and the column id at the given indexes.
+    fn entries_at(&self, row: usize, col: usize) -> LoroResult<(LoroMap, String)> {
+        let out_of_bound = |pos, len, axis: &str| LoroError::OutOfBound {
+            pos,
+            len,
+            info: format!("The grid has no {axis} at this index").into_boxed_str(),
+        };
+        let row_map = self
+            .entry_at(ROWS, row)
+            .ok_or_else(|| out_of_bound(row, self.row_count(), "row"))?;
+        let col_id = self
+            .col_id(col)
+            .ok_or_else(|| out_of_bound(col, self.col_count(), "column"))?;
+        Ok((row_map, col_id))
+    }
+
+    fn insert(&self, key: &str, index: usize, method: &'static str) -> LoroResult<String> {
+        if !self.map.is_attached() {
+            return Err(LoroError::MisuseDetachedContainer { method });
+        }
+        let axis = self.map.ensure_mergeable_movable_list(key)?;
+        if index > axis.len() {
+            return Err(LoroError::OutOfBound {
+                pos: index,
+                len: axis.len(),
+                info: format!("Cannot insert into the {key} of the grid").into_boxed_str(),
+            });
+        }
+        let entry = axis.insert_container(index, LoroMap::new())?;
+        Ok(entry_id(&entry))
+    }
+
+    fn delete(&self, key: &str, index: usize) -> LoroResult<String> {
+        let axis = self.axis(key);
+        let len = axis.as_ref().map_or(0, |axis| axis.len());
+        match (axis, self.id_at(key, index)) {
+            (Some(axis), Some(id)) => {
+                axis.delete(index, 1)?;
+                Ok(id)
+            }
+            _ => Err(LoroError::OutOfBound {
+                pos: index,
+                len,
+                info: format!("Cannot delete from the {key} of the grid").into_boxed_str(),
+            }),
+        }
+    }
+
+    fn mov(&self, key: &str, from: usize, to: usize) -> LoroResult<()> {
+        match self.axis(key) {
+            Some(axis) => axis.mov(from, to),
+            None => Err(LoroError::OutOfBound {
+                pos: from,
+                len: 0,
+                info: format!("Cannot move in the {key} of the grid").into_boxed_str(),
+            }),
+        }
+    }
+}
+
+/// The id of a row or column: the id of the op that created its map, which is unique in the
+/// document.
+fn entry_id(entry: &LoroMap) -> String {
+    match entry.id() {
+        ContainerID::Normal { peer, counter, .. } => ID::new(peer, counter).to_string(),
+        ContainerID::Root { .. } => unreachable!("rows and columns are child containers"),
+    }
+}
+
+fn cell(row: &LoroMap, col: &str) -> Option<LoroValue> {
+    row.get(col)?.into_value().ok()
+}
@@ -34,6 +34,7 @@ use std::cmp::Ordering;
 use std::ops::ControlFlow;
 use std::ops::Deref;
 use std::ops::Range;
+use std::ops::RangeBounds;
 use std::sync::Arc;
 use tracing::info;
 
@@ -43,6 +44,11 @@ pub use loro_internal::subscription::LocalUpdateCallback;
 pub use loro_internal::subscription::PeerIdUpdateCallback;
 pub use loro_internal::ChangeMeta;
 pub use loro_internal::LORO_VERSION;
+pub use loro_internal::{
+    AuthorSummary, ChangeSummary, ContainerSummary, DiffSummary, MapKeyChange,
+};
+pub use loro_internal::{ChangeCursor, ChangeOrder, ChangePage, ChangeQuery};
+pub use loro_internal::{Checkpoint, CompactionPolicy};
 pub mod event;
 pub use loro_internal::awareness;
 pub use loro_internal::change::Timestamp;
@@ -55,6 +61,8 @@ pub use loro_internal::delta::{TreeDeltaItem, TreeDiff, TreeDiffItem, TreeExtern
 pub use loro_internal::encoding::ImportBlobMetadata;
 pub use loro_internal::encoding::{EncodedBlobMode, ExportMode};
 pub use loro_internal::event::{EventTriggerKind, Index};
+pub use loro_internal::handler::FractionalIndexStats;
+pub use loro_internal::handler::MapConflict;
 pub use loro_internal::handler::TextDelta;
 pub use loro_internal::json;
 pub use loro_internal::json::{
@@ -66,6 +74,9 @@ pub use loro_internal::kv_store::{KvStore, MemKvStore};
 pub use loro_internal::loro::CommitOptions;
 pub use loro_internal::loro::DocAnalysis;
 pub use loro_internal::oplog::FrontiersNotIncluded;
+pub use loro_internal::schema::{
+    DocSchema, MapSchema, SchemaViolation, SchemaViolationCallback, ValueSchema,
+};
 pub use loro_internal::undo;
 pub use loro_internal::version::{Frontiers, VersionRange, VersionVector, VersionVectorDiff};
 pub use loro_internal::ApplyDiff;
@@ -89,7 +100,19 @@ pub use loro_internal::jsonpath::SubscribeJsonPathCallback;
 #[cfg(feature = "counter")]
 mod counter;
 #[cfg(feature = "counter")]
-pub use counter::LoroCounter;
+pub use counter::{CounterKind, LoroCounter};
+mod grid;
+pub use grid::LoroGrid;
+#[cfg(feature = "set")]
+mod set;
+#[cfg(feature = "set")]
+pub use set::LoroSet;
+pub mod typed;
+mod value_update;
+#[cfg(feature = "derive")]
+pub use typed::Loro;
+#[cfg(feature = "serde")]
+pub mod serde;
 
 /// `LoroDoc` is the entry for the whole document.
 /// When it's dropped, all the associated [`Container`]s will be invalidated.
@@ -162,6 +185,49 @@ impl LoroDoc {
         Ok(LoroDoc::_new(new_doc))
     }
 
+    /// Squash the history into coarse checkpoints, one change per period of
+    /// `policy.interval` seconds, in a new doc.
+    ///
+    /// Unlike a [shallow snapshot](ExportMode::ShallowSnapshot), the new doc keeps a few
+    /// version points of the old history, but not the individual edits. It needs the full
+    /// history, and the changes must have timestamps (see
+    /// [`set_record_timestamp`](Self::set_record_timestamp)); otherwise everything is squashed
+    /// into one checkpoint.
+    ///
+    /// The new doc has the same state, but its ops have new IDs, so its history is unrelated
+    /// to the history of this doc:
+    ///
+    /// - The new doc must not exchange updates with this doc or with any doc that shares its
+    ///   history. Merging the two histories would duplicate the content.
+    /// - Peers have to sync their edits into this doc before the compaction, and switch to a
+    ///   snapshot of the new doc afterwards. Edits made on the old history after that are
+    ///   lost, unless they're replayed as a diff.
+    /// - The branches and tags refer to versions of the old history, so they're not copied.
+    ///   The [`Checkpoint`]s map the old versions to the new ones.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::{CommitOptions, CompactionPolicy, LoroDoc};
+    /// let doc = LoroDoc::new();
+    /// let text = doc.get_text("text");
+    /// for (i, hour) in [0, 1, 25, 26].into_iter().enumerate() {
+    ///     text.insert(i, "a").unwrap();
+    ///     doc.commit_with(CommitOptions::new().timestamp(hour * 3600));
+    /// }
+    ///
+    /// let compacted = doc.compact_history(CompactionPolicy::daily()).unwrap();
+    /// assert_eq!(compacted.checkpoints.len(), 2);
+    /// assert_eq!(compacted.doc.get_text("text").to_string(), "aaaa");
+    /// assert_eq!(compacted.doc.len_changes(), 2);
+    /// ```
+    pub fn compact_history(&self, policy: CompactionPolicy) -> LoroResult<CompactedHistory> {
+        let compacted = self.doc.compact_history(policy)?;
+        Ok(CompactedHistory {
+            doc: LoroDoc::_new(compacted.doc),
+            checkpoints: compacted.checkpoints,
+        })
+    }
+
     /// Get the configurations of the document.
     #[inline]
     pub fn config(&self) -> &Configure {
@@ -500,6 +566,14 @@ impl LoroDoc {
         self.doc.try_get_map(id).map(|handler| LoroMap { handler })
     }
 
+    /// Get a [LoroGrid] stored in the root map with the given name.
+    ///
+    /// The grid is a view over a regular [LoroMap], see [LoroGrid] for its layout.
+    #[inline]
+    pub fn get_grid(&self, name: &str) -> LoroGrid {
+        LoroGrid::from_map(self.get_map(name))
+    }
+
     /// Get a [LoroText] by container id.
     ///
     /// If the provided id is string, it will be converted into a root container id with the name of the string.
@@ -572,6 +646,55 @@ impl LoroDoc {
             .map(|handler| LoroCounter { handler })
     }
 
+    #[cfg(feature = "typed-counter")]
+    /// Get a typed [LoroCounter] by container id.
+    ///
+    /// A typed counter can be used as an int counter, a bounded counter or a max
+    /// register. It's a different container from the counter of the same name.
+    ///
+    /// If the provided id is string, it will be converted into a root container id with the name of the string.
+    ///
+    /// Panics if the container does not exist. Use [`try_get_typed_counter`] for a safe alternative.
+    #[inline]
+    pub fn get_typed_counter<I: IntoContainerId>(&self, id: I) -> LoroCounter {
+        LoroCounter {
+            handler: self.doc.get_typed_counter(id),
+        }
+    }
+
+    #[cfg(feature = "typed-counter")]
+    /// Try to get a typed [LoroCounter] by container id.
+    ///
+    /// Returns `None` if the container does not exist in the document.
+    #[inline]
+    pub fn try_get_typed_counter<I: IntoContainerId>(&self, id: I) -> Option<LoroCounter> {
+        self.doc
+            .try_get_typed_counter(id)
+            .map(|handler| LoroCounter { handler })
+    }
+
+    #[cfg(feature = "set")]
+    /// Get a [LoroSet] by container id.
+    ///
+    /// If the provided id is string, it will be converted into a root container id with the name of the string.
+    ///
+    /// Panics if the container does not exist. Use [`try_get_set`] for a safe alternative.
+    #[inline]
+    pub fn get_set<I: IntoContainerId>(&self, id: I) -> LoroSet {
+        LoroSet {
+            handler: self.doc.get_set(id),
+        }
+    }
+
+    #[cfg(feature = "set")]
+    /// Try to get a [LoroSet] by container id.
+    ///
+    /// Returns `None` if the container does not exist in the document.
+    #[inline]
+    pub fn try_get_set<I: IntoContainerId>(&self, id: I) -> Option<LoroSet> {
+        self.doc.try_get_set(id).map(|handler| LoroSet { handler })
+    }
+
     /// Commit the cumulative auto commit transaction.
     ///
     /// There is a transaction behind every operation.
@@ -1377,6 +1500,112 @@ impl LoroDoc {
         self.doc.subscribe_jsonpath(jsonpath, callback)
     }
 
+    /// Subscribe to the results of the given JSONPath query that were added, removed or
+    /// changed since the last notification.
+    ///
+    /// Unlike [`LoroDoc::subscribe_jsonpath`], it re-evaluates the query itself on every
+    /// event that may affect the results. Use [`LoroDoc::subscribe_jsonpath`] instead to
+    /// debounce the evaluation. The callback is not called when the results didn't
+    /// actually change.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use loro::{JsonPathDiff, LoroDoc};
+    /// # use std::sync::{Arc, Mutex};
+    /// let doc = LoroDoc::new();
+    /// let users = doc.get_map("users");
+    /// users.insert("alice", 30).unwrap();
+    /// doc.commit();
+    ///
+    /// let added = Arc::new(Mutex::new(Vec::new()));
+    /// let added_ref = added.clone();
+    /// let _sub = doc
+    ///     .subscribe_jsonpath_diff(
+    ///         "$.users.*",
+    ///         Arc::new(move |diff: &JsonPathDiff| {
+    ///             let mut added = added_ref.lock().unwrap();
+    ///             added.extend(diff.added.iter().map(|item| item.path.clone()));
+    ///         }),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// users.insert("bob", 25).unwrap();
+    /// doc.commit();
+    /// assert_eq!(added.lock().unwrap().len(), 1);
+    /// ```
+    #[cfg(feature = "jsonpath")]
+    pub fn subscribe_jsonpath_diff(
+        &self,
+        jsonpath: &str,
+        callback: SubscribeJsonPathDiffCallback,
+    ) -> LoroResult<Subscription> {
+        self.doc.subscribe_jsonpath_diff(
+            jsonpath,
+            Arc::new(move |diff: &jsonpath::JsonPathDiff| callback(&JsonPathDiff::from(diff))),
+        )
+    }
+
+    /// Set every node selected by the JSONPath query to `value`, in one transaction.
+    ///
+    /// A name selector at the end of the query also selects the key when it's missing. The
+    /// selected nodes must be entries of map or list containers, otherwise nothing is changed
+    /// and an error is returned.
+    ///
+    /// Returns the number of nodes that were set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use loro::{LoroDoc, LoroMap, ToJson};
+    /// # use serde_json::json;
+    /// let doc = LoroDoc::new();
+    /// let todos = doc.get_list("todos");
+    /// for (i, priority) in [1, 5].into_iter().enumerate() {
+    ///     let todo = todos.insert_container(i, LoroMap::new()).unwrap();
+    ///     todo.insert("priority", priority).unwrap();
+    /// }
+    ///
+    /// let count = doc.jsonpath_set("$.todos[?(@.priority > 3)].done", true).unwrap();
+    /// assert_eq!(count, 1);
+    /// assert_eq!(
+    ///     doc.get_deep_value().to_json_value(),
+    ///     json!({"todos": [{"priority": 1}, {"priority": 5, "done": true}]})
+    /// );
+    /// ```
+    #[cfg(feature = "jsonpath")]
+    pub fn jsonpath_set(&self, jsonpath: &str, value: impl Into<LoroValue>) -> LoroResult<usize> {
+        self.doc.jsonpath_set(jsonpath, value)
+    }
+
+    /// Delete every node selected by the JSONPath query, in one transaction.
+    ///
+    /// The selected nodes must be entries of map or list containers, otherwise nothing is
+    /// changed and an error is returned.
+    ///
+    /// Returns the number of deleted nodes.
+    #[cfg(feature = "jsonpath")]
+    pub fn jsonpath_delete(&self, jsonpath: &str) -> LoroResult<usize> {
+        self.doc.jsonpath_delete(jsonpath)
+    }
+
+    /// Insert `value` at `index` into every list selected by the JSONPath query, in one
+    /// transaction.
+    ///
+    /// The selected nodes must be lists or movable lists that are at least `index` long,
+    /// otherwise nothing is changed and an error is returned.
+    ///
+    /// Returns the number of lists the value was inserted into.
+    #[cfg(feature = "jsonpath")]
+    pub fn jsonpath_insert(
+        &self,
+        jsonpath: &str,
+        index: usize,
+        value: impl Into<LoroValue>,
+    ) -> LoroResult<usize> {
+        self.doc.jsonpath_insert(jsonpath, index, value)
+    }
+
     /// Get the number of operations in the pending transaction.
     ///
     /// The pending transaction is the one that is not committed yet. It will be committed
@@ -1423,6 +1652,34 @@ impl LoroDoc {
         self.doc.get_changed_containers_in(id, len)
     }
 
+    /// Query a page of the changes of the doc, filtered by peer, time range, commit message,
+    /// or the containers they touch.
+    ///
+    /// The changes are returned newest first, in causal or timestamp order. In causal order
+    /// only the changes that are visited are loaded, so it's cheap to page through a large
+    /// history. The first query in timestamp order loads every change once to index their
+    /// timestamps; later pages only look up the index.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::{ChangeQuery, CommitOptions, LoroDoc};
+    /// let doc = LoroDoc::new();
+    /// for i in 0..5 {
+    ///     doc.get_text("text").insert(0, "a").unwrap();
+    ///     doc.commit_with(CommitOptions::new().commit_msg(&format!("edit {i}")));
+    /// }
+    ///
+    /// let page = doc.query_changes(&ChangeQuery::new().limit(2));
+    /// assert_eq!(page.changes[0].message(), "edit 4");
+    /// assert_eq!(page.changes.len(), 2);
+    /// let next = doc.query_changes(&ChangeQuery::new().limit(2).after(page.next.unwrap()));
+    /// assert_eq!(next.changes[0].message(), "edit 2");
+    /// ```
+    #[inline]
+    pub fn query_changes(&self, query: &ChangeQuery) -> ChangePage {
+        self.doc.query_changes(query)
+    }
+
     /// Find the operation id spans that between the `from` version and the `to` version.
     ///
     /// Useful for exporting just the changes in a range, e.g., in response to a subscription.
@@ -1470,6 +1727,210 @@ impl LoroDoc {
         self.doc.revert_to(version)
     }
 
+    /// Revert the changes in the given spans, which can be from any peer.
+    ///
+    /// Unlike [`revert_to`](Self::revert_to), only the given changes are reverted. The revert
+    /// is transformed against all the later changes, including the concurrent ones, so they
+    /// are kept. The result is applied as new local operations.
+    ///
+    /// Pitfalls:
+    /// - Every span must be included by the document's history, otherwise
+    ///   [`LoroError::UndoInvalidIdSpan`] is returned.
+    /// - The spans can't be before the shallow start of a shallow document.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::{ExportMode, IdSpan, LoroDoc};
+    /// let a = LoroDoc::new();
+    /// a.set_peer_id(1).unwrap();
+    /// a.get_text("text").insert(0, "Hello").unwrap();
+    /// a.commit();
+    /// let b = LoroDoc::new();
+    /// b.set_peer_id(2).unwrap();
+    /// b.import(&a.export(ExportMode::all_updates()).unwrap()).unwrap();
+    /// b.get_text("text").insert(5, " spam").unwrap();
+    /// b.commit();
+    /// a.import(&b.export(ExportMode::all_updates()).unwrap()).unwrap();
+    /// a.get_text("text").insert(0, "> ").unwrap();
+    /// a.commit();
+    ///
+    /// // Revert everything peer 2 has done
+    /// a.revert_changes(&[IdSpan::new(2, 0, 5)]).unwrap();
+    /// assert_eq!(a.get_text("text").to_string(), "> Hello");
+    /// ```
+    #[inline]
+    pub fn revert_changes(&self, spans: &[IdSpan]) -> LoroResult<()> {
+        self.doc.revert_changes(spans)
+    }
+
+    /// Create a named branch with the given head.
+    ///
+    /// Branches are stored in the document, so they sync with it, but they're not a part of
+    /// its value. Returns [`LoroError::ArgErr`] if the branch already exists.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::LoroDoc;
+    /// let doc = LoroDoc::new();
+    /// doc.get_text("text").insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// doc.create_branch("main", &doc.state_frontiers()).unwrap();
+    /// doc.create_branch("draft", &doc.state_frontiers()).unwrap();
+    ///
+    /// doc.checkout_branch("draft").unwrap();
+    /// doc.get_text("text").insert(5, " draft").unwrap();
+    /// doc.commit();
+    ///
+    /// doc.checkout_branch("main").unwrap();
+    /// assert_eq!(doc.get_text("text").to_string(), "Hello");
+    /// doc.checkout_branch("draft").unwrap();
+    /// assert_eq!(doc.get_text("text").to_string(), "Hello draft");
+    /// assert_eq!(doc.branches().len(), 2);
+    /// ```
+    #[inline]
+    pub fn create_branch(&self, name: &str, head: &Frontiers) -> LoroResult<()> {
+        self.doc.create_branch(name, head)
+    }
+
+    /// Move the head of a branch, creating the branch if it doesn't exist.
+    ///
+    /// The head of a branch only moves forward: the new head is merged with the heads
+    /// recorded by the other peers. If the doc is checked out to the branch, it leaves the
+    /// branch.
+    #[inline]
+    pub fn set_branch_head(&self, name: &str, head: &Frontiers) -> LoroResult<()> {
+        self.doc.set_branch_head(name, head)
+    }
+
+    /// Delete a branch.
+    ///
+    /// Returns [`LoroError::NotFoundError`] if the branch doesn't exist.
+    #[inline]
+    pub fn delete_branch(&self, name: &str) -> LoroResult<()> {
+        self.doc.delete_branch(name)
+    }
+
+    /// All the branches with their heads, sorted by name.
+    #[inline]
+    pub fn branches(&self) -> Vec<(String, Frontiers)> {
+        self.doc.branches()
+    }
+
+    /// The head of the branch, or `None` if the branch doesn't exist.
+    #[inline]
+    pub fn branch_head(&self, name: &str) -> Option<Frontiers> {
+        self.doc.branch_head(name)
+    }
+
+    /// The name of the branch the doc is checked out to.
+    ///
+    /// The doc leaves the branch when it's checked out to another version.
+    #[inline]
+    pub fn current_branch(&self) -> Option<String> {
+        self.doc.current_branch()
+    }
+
+    /// Checkout the head of a branch, and edit the branch from now on.
+    ///
+    /// This enables [detached editing](Self::set_detached_editing). Every following commit
+    /// extends the branch and moves its head, until the doc is checked out to another
+    /// version, e.g. with [`checkout_to_latest`](Self::checkout_to_latest).
+    #[inline]
+    pub fn checkout_branch(&self, name: &str) -> LoroResult<()> {
+        self.doc.checkout_branch(name)
+    }
+
+    /// Merge the `source` branch into the `target` branch.
+    ///
+    /// The head of `target` moves to include the head of `source`; the concurrent edits are
+    /// merged like any other concurrent edits. The returned [`BranchMerge`] has what changed
+    /// on each side since their common ancestor. If the doc is checked out to `target`, it's
+    /// checked out to the merged head.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::LoroDoc;
+    /// let doc = LoroDoc::new();
+    /// doc.get_text("text").insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// doc.create_branch("main", &doc.state_frontiers()).unwrap();
+    /// doc.create_branch("feature", &doc.state_frontiers()).unwrap();
+    ///
+    /// doc.checkout_branch("feature").unwrap();
+    /// doc.get_text("text").insert(5, " world").unwrap();
+    /// doc.commit();
+    /// doc.checkout_branch("main").unwrap();
+    /// doc.get_text("text").insert(0, "> ").unwrap();
+    /// doc.commit();
+    ///
+    /// let merge = doc.merge_branch("feature", "main").unwrap();
+    /// assert_eq!(merge.source_diff.iter().count(), 1);
+    /// assert_eq!(doc.get_text("text").to_string(), "> Hello world");
+    /// ```
+    pub fn merge_branch(&self, source: &str, target: &str) -> LoroResult<BranchMerge> {
+        let merge = self.doc.merge_branch(source, target)?;
+        Ok(BranchMerge {
+            base: merge.base,
+            head: merge.head,
+            source_diff: merge.source_diff.into(),
+            target_diff: merge.target_diff.into(),
+        })
+    }
+
+    /// Tag the version at the given frontiers, e.g. to mark a published version.
+    ///
+    /// Tags are stored in the document, so they sync with it, but they don't show up in
+    /// [`get_deep_value`](Self::get_deep_value). A shallow snapshot keeps the tags whose
+    /// versions are still in its history. Returns [`LoroError::ArgErr`] if the tag already
+    /// exists.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::LoroDoc;
+    /// let doc = LoroDoc::new();
+    /// doc.get_text("text").insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// doc.create_tag("v1 published", &doc.state_frontiers()).unwrap();
+    /// doc.get_text("text").insert(5, " world").unwrap();
+    /// doc.commit();
+    ///
+    /// doc.checkout_tag("v1 published").unwrap();
+    /// assert_eq!(doc.get_text("text").to_string(), "Hello");
+    /// assert_eq!(doc.tags().len(), 1);
+    /// ```
+    #[inline]
+    pub fn create_tag(&self, name: &str, frontiers: &Frontiers) -> LoroResult<()> {
+        self.doc.create_tag(name, frontiers)
+    }
+
+    /// Delete a tag.
+    ///
+    /// Returns [`LoroError::NotFoundError`] if the tag doesn't exist.
+    #[inline]
+    pub fn delete_tag(&self, name: &str) -> LoroResult<()> {
+        self.doc.delete_tag(name)
+    }
+
+    /// All the tags with their versions, sorted by name.
+    #[inline]
+    pub fn tags(&self) -> Vec<(String, Frontiers)> {
+        self.doc.tags()
+    }
+
+    /// The version of the tag, or `None` if the tag doesn't exist.
+    #[inline]
+    pub fn tag(&self, name: &str) -> Option<Frontiers> {
+        self.doc.tag(name)
+    }
+
+    /// Checkout the version of a tag.
+    ///
+    /// Like [`checkout`](Self::checkout), the doc is detached afterwards.
+    #[inline]
+    pub fn checkout_tag(&self, name: &str) -> LoroResult<()> {
+        self.doc.checkout_tag(name)
+    }
+
     /// Apply a diff to the current document state.
     ///
     /// Internally, it will apply the diff to the current state.
@@ -1497,6 +1958,37 @@ impl LoroDoc {
         self.doc.diff(a, b).map(|x| x.into())
     }
 
+    /// Summarize the difference between two versions for humans.
+    ///
+    /// For every changed container, it has the path to the container and a summary that
+    /// depends on its type: the inserted and deleted lengths of a text or a list, the changed
+    /// keys of a map with their old and new values, or the created, deleted and moved nodes of
+    /// a tree. It also has the peers whose changes are between the versions. The summary can
+    /// be serialized, e.g. to JSON.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::{ChangeSummary, ContainerTrait, LoroDoc};
+    /// let doc = LoroDoc::new();
+    /// doc.set_peer_id(1).unwrap();
+    /// doc.get_map("meta").insert("title", "Draft").unwrap();
+    /// doc.commit();
+    /// let a = doc.state_frontiers();
+    /// doc.get_map("meta").insert("title", "Final").unwrap();
+    /// doc.get_text("body").insert(0, "Hello").unwrap();
+    /// doc.commit();
+    ///
+    /// let summary = doc.diff_summary(&a, &doc.state_frontiers()).unwrap();
+    /// assert_eq!(summary.containers.len(), 2);
+    /// assert_eq!(summary.authors[0].peer, 1);
+    /// let body = summary.containers.iter().find(|c| c.id == doc.get_text("body").id()).unwrap();
+    /// assert!(matches!(body.changes, ChangeSummary::Text { inserted: 5, deleted: 0, .. }));
+    /// ```
+    #[inline]
+    pub fn diff_summary(&self, a: &Frontiers, b: &Frontiers) -> LoroResult<DiffSummary> {
+        self.doc.diff_summary(a, b)
+    }
+
     /// Check if the doc contains the target container.
     ///
     /// A root container always exists, while a normal container exists if it has
@@ -1617,7 +2109,8 @@ impl LoroDoc {
     ///   - Return `false` to automatically unsubscribe
     /// - The payload contains:
     ///   - `change_meta`: Metadata about the commit
-    ///   - `modifier`: Interface to modify commit properties
+    ///   - `modifier`: Interface to modify commit properties, or to reject the commit
+    ///     with [`ChangeModifier::reject`]
     ///
     /// # Use Cases
     /// - Add commit message prefixes or formatting
@@ -1652,6 +2145,104 @@ impl LoroDoc {
         self.doc.subscribe_pre_commit(callback)
     }
 
+    /// The reason the last commit was rejected by a pre-commit callback, e.g. by
+    /// [`LoroDoc::enforce_schema`].
+    ///
+    /// `commit()` returns normally when its change is rejected; check this right after it
+    /// to find out whether the change was dropped. It is reset by every commit, including
+    /// the implicit ones of `import`, `export` and `checkout`.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let _sub = doc.subscribe_pre_commit(Box::new(|payload| {
+    ///     payload.modifier.reject("read only");
+    ///     true
+    /// }));
+    /// doc.get_text("text").insert(0, "hello").unwrap();
+    /// doc.commit();
+    /// assert_eq!(doc.last_commit_rejection().as_deref(), Some("read only"));
+    /// assert_eq!(doc.get_text("text").to_string(), "");
+    /// ```
+    pub fn last_commit_rejection(&self) -> Option<Arc<str>> {
+        self.doc.last_commit_rejection()
+    }
+
+    /// Check the current state of the document against `schema`.
+    ///
+    /// Each [`SchemaViolation`] carries the normalized JSONPath of the offending value.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `schema` declares a root that is not a container.
+    pub fn validate_schema(&self, schema: &DocSchema) -> LoroResult<Vec<SchemaViolation>> {
+        self.doc.validate_schema(schema)
+    }
+
+    /// Reject local commits that leave the document violating `schema`.
+    ///
+    /// The check runs in a pre-commit hook: a rejected commit is rolled back from the
+    /// document state, never reaches the history and emits no events. `on_reject`
+    /// receives the violations of each rejected commit, and
+    /// [`LoroDoc::last_commit_rejection`] reports the rejection to the committer. Drop the
+    /// returned [`Subscription`] to stop enforcing the schema.
+    ///
+    /// Only the containers a commit touches, and their ancestors, are checked, so the cost
+    /// follows the size of the change rather than the size of the document.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::{DocSchema, LoroDoc, MapSchema, ValueSchema};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let schema = DocSchema::new().root(
+    ///     "user",
+    ///     MapSchema::new()
+    ///         .required_field("name", ValueSchema::String)
+    ///         .field("age", ValueSchema::Int),
+    /// );
+    /// let rejected = Arc::new(Mutex::new(Vec::new()));
+    /// let rejected_clone = rejected.clone();
+    /// let _sub = doc
+    ///     .enforce_schema(
+    ///         schema,
+    ///         Arc::new(move |violations| {
+    ///             rejected_clone.lock().unwrap().extend_from_slice(violations);
+    ///         }),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let user = doc.get_map("user");
+    /// user.insert("name", "Alice").unwrap();
+    /// doc.commit();
+    /// user.insert("age", "unknown").unwrap();
+    /// doc.commit();
+    ///
+    /// assert!(doc.last_commit_rejection().is_some());
+    /// assert!(user.get("age").is_none());
+    /// assert_eq!(rejected.lock().unwrap()[0].path, "$['user']['age']");
+    /// ```
+    pub fn enforce_schema(
+        &self,
+        schema: DocSchema,
+        on_reject: SchemaViolationCallback,
+    ) -> LoroResult<Subscription> {
+        self.doc.enforce_schema(schema, on_reject)
+    }
+
+    /// Check the document against `schema` after every import and report the violations.
+    ///
+    /// Imported changes are already part of the history and cannot be rejected. The
+    /// callback is only called when the imported state does not conform.
+    pub fn subscribe_schema_violations(
+        &self,
+        schema: DocSchema,
+        callback: SchemaViolationCallback,
+    ) -> LoroResult<Subscription> {
+        self.doc.subscribe_schema_violations(schema, callback)
+    }
+
     /// Delete all content from a root container and hide it from the document.
     ///
     /// When a root container is empty and hidden:
@@ -1680,6 +2271,78 @@ impl LoroDoc {
     }
 }
 
+/// The result of [`LoroDoc::merge_branch`].
+#[derive(Debug, Clone)]
+pub struct BranchMerge {
+    /// The common ancestor of the two branches before the merge.
+    pub base: Frontiers,
+    /// The new head of the target branch.
+    pub head: Frontiers,
+    /// What changed on the source branch since `base`.
+    pub source_diff: DiffBatch,
+    /// What changed on the target branch since `base`.
+    pub target_diff: DiffBatch,
+}
+
+/// The result of [`LoroDoc::compact_history`].
+#[derive(Debug)]
+pub struct CompactedHistory {
+    /// The new doc, with one change per checkpoint.
+    pub doc: LoroDoc,
+    /// The checkpoints, oldest first. The last one is the latest version.
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+/// A result of a JSONPath query, with its path from the root of the document.
+#[cfg(feature = "jsonpath")]
+#[derive(Debug, Clone)]
+pub struct JsonPathItem {
+    /// The path from the root of the document to the result.
+    pub path: Vec<Index>,
+    /// The value or container at the path.
+    pub value: ValueOrContainer,
+}
+
+/// How the results of a JSONPath query changed, see [`LoroDoc::subscribe_jsonpath_diff`].
+///
+/// Results are identified by their path, and a result is changed if its deep value changed.
+/// `added` and `changed` hold the current results, `removed` holds the results as they were
+/// at the last notification.
+#[cfg(feature = "jsonpath")]
+#[derive(Debug, Clone, Default)]
+pub struct JsonPathDiff {
+    /// The results whose path wasn't matched before.
+    pub added: Vec<JsonPathItem>,
+    /// The results whose path isn't matched anymore.
+    pub removed: Vec<JsonPathItem>,
+    /// The results whose deep value changed.
+    pub changed: Vec<JsonPathItem>,
+}
+
+#[cfg(feature = "jsonpath")]
+impl From<&jsonpath::JsonPathDiff> for JsonPathDiff {
+    fn from(diff: &jsonpath::JsonPathDiff) -> Self {
+        let convert = |items: &[jsonpath::JsonPathItem]| {
+            items
+                .iter()
+                .map(|item| JsonPathItem {
+                    path: item.path.clone(),
+                    value: item.value.clone().into(),
+                })
+                .collect()
+        };
+        JsonPathDiff {
+            added: convert(&diff.added),
+            removed: convert(&diff.removed),
+            changed: convert(&diff.changed),
+        }
+    }
+}
+
+/// Callback used by [`LoroDoc::subscribe_jsonpath_diff`].
+#[cfg(feature = "jsonpath")]
+pub type SubscribeJsonPathDiffCallback = Arc<dyn Fn(&JsonPathDiff) + Send + Sync + 'static>;
+
 /// It's used to prevent the user from implementing the trait directly.
 #[allow(private_bounds)]
 trait SealedTrait {}
@@ -1755,6 +2418,20 @@ pub trait ContainerTrait: SealedTrait {
 ///     })
 /// );
 /// ```
+///
+/// # Mergeable child containers
+///
+/// The `ensure_mergeable_*` methods return a child container identified by a
+/// user-provided stable key instead of by the op that created it. Peers that
+/// call the same method with the same `(parent list, key, container type)` get
+/// the same child container id, so concurrent lazy creation merges instead of
+/// forking. The child is appended as a list element the first time it is
+/// ensured; concurrent first calls may append one element each, which all hold
+/// the child until the next call removes the duplicates. Asking for a different container type under a
+/// key that already holds a mergeable child returns [`LoroError::ArgErr`].
+///
+/// Tree node metadata is a [`LoroMap`], so `tree.get_meta(node)?.ensure_mergeable_*`
+/// gives tree nodes mergeable children as well.
 #[derive(Clone, Debug)]
 pub struct LoroList {
     handler: InnerListHandler,
@@ -1834,18 +2511,196 @@ impl LoroList {
         self.handler.delete(pos, len)
     }
 
+    /// Ensure a mergeable Counter keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    #[cfg(feature = "counter")]
+    pub fn ensure_mergeable_counter(&self, key: &str) -> LoroResult<LoroCounter> {
+        Ok(LoroCounter::from_handler(
+            self.handler.ensure_mergeable_counter(key)?,
+        ))
+    }
+
+    /// Ensure a mergeable Map keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    pub fn ensure_mergeable_map(&self, key: &str) -> LoroResult<LoroMap> {
+        Ok(LoroMap::from_handler(
+            self.handler.ensure_mergeable_map(key)?,
+        ))
+    }
+
+    /// Ensure a mergeable List keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    pub fn ensure_mergeable_list(&self, key: &str) -> LoroResult<LoroList> {
+        Ok(LoroList::from_handler(
+            self.handler.ensure_mergeable_list(key)?,
+        ))
+    }
+
+    /// Ensure a mergeable MovableList keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    pub fn ensure_mergeable_movable_list(&self, key: &str) -> LoroResult<LoroMovableList> {
+        Ok(LoroMovableList::from_handler(
+            self.handler.ensure_mergeable_movable_list(key)?,
+        ))
+    }
+
+    /// Ensure a mergeable Text keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    pub fn ensure_mergeable_text(&self, key: &str) -> LoroResult<LoroText> {
+        Ok(LoroText::from_handler(
+            self.handler.ensure_mergeable_text(key)?,
+        ))
+    }
+
+    /// Ensure a mergeable Tree keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    pub fn ensure_mergeable_tree(&self, key: &str) -> LoroResult<LoroTree> {
+        Ok(LoroTree::from_handler(
+            self.handler.ensure_mergeable_tree(key)?,
+        ))
+    }
+
     /// Get the value at the given position.
     #[inline]
     pub fn get(&self, index: usize) -> Option<ValueOrContainer> {
         self.handler.get_(index).map(ValueOrContainer::from)
     }
 
+    /// Get the values in `range`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{LoroDoc, LoroValue};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let list = doc.get_list("list");
+    /// list.insert_many(0, [1, 2, 3, 4]).unwrap();
+    /// let window: Vec<_> = list
+    ///     .slice(1..3)
+    ///     .unwrap()
+    ///     .into_iter()
+    ///     .map(|v| v.into_value().unwrap())
+    ///     .collect();
+    /// assert_eq!(window, vec![LoroValue::from(2), LoroValue::from(3)]);
+    /// ```
+    pub fn slice(&self, range: Range<usize>) -> LoroResult<Vec<ValueOrContainer>> {
+        Ok(self
+            .handler
+            .slice(range)?
+            .into_iter()
+            .map(ValueOrContainer::from)
+            .collect())
+    }
+
+    /// Insert all `values` at `pos`.
+    ///
+    /// The values are recorded as a single op, so bulk-loading many rows is much cheaper
+    /// than calling [`LoroList::insert`] in a loop.
+    pub fn insert_many<V: Into<LoroValue>>(
+        &self,
+        pos: usize,
+        values: impl IntoIterator<Item = V>,
+    ) -> LoroResult<()> {
+        self.handler
+            .insert_many(pos, values.into_iter().map(Into::into).collect())
+    }
+
+    /// Replace the `len` values from `pos` with `values`, and return the deleted values.
+    ///
+    /// The new values are recorded as a single insert op.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{LoroDoc, LoroValue, ToJson};
+    /// use serde_json::json;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let list = doc.get_list("list");
+    /// list.insert_many(0, ["a", "b", "c"]).unwrap();
+    /// let deleted = list.splice(1, 1, ["x", "y"]).unwrap();
+    /// assert_eq!(deleted, vec![LoroValue::from("b")]);
+    /// assert_eq!(list.get_value().to_json_value(), json!(["a", "x", "y", "c"]));
+    /// ```
+    pub fn splice<V: Into<LoroValue>>(
+        &self,
+        pos: usize,
+        len: usize,
+        values: impl IntoIterator<Item = V>,
+    ) -> LoroResult<Vec<LoroValue>> {
+        self.handler
+            .splice(pos, len, values.into_iter().map(Into::into).collect())
+    }
+
     /// Get the deep value of the container.
     #[inline]
     pub fn get_deep_value(&self) -> LoroValue {
         self.handler.get_deep_value()
     }
 
+    /// Update the list to hold `value`, emitting only the ops needed to get there.
+    ///
+    /// `value` must be a [`LoroValue::List`]. Elements in the longest common subsequence of the
+    /// current and the new list are kept untouched. The other new elements are paired up by
+    /// position with the removed ones: child maps and lists are updated recursively, a
+    /// [`LoroText`] is updated with [`LoroText::update`] when the new element is a string, and
+    /// anything else is replaced. The remaining elements are deleted or inserted, so the emitted
+    /// ops have the same retain/delete/insert shape as the list diffs of a [`DiffBatch`].
+    ///
+    /// New nested maps and lists become child containers. [`LoroValue::Container`] values are
+    /// rejected.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::{LoroDoc, LoroValue};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let list = doc.get_list("list");
+    /// list.update_from_value(&vec![1, 2, 3, 4].into()).unwrap();
+    /// doc.commit();
+    /// let ops = doc.len_ops();
+    ///
+    /// list.update_from_value(&vec![1, 3, 4, 5].into()).unwrap();
+    /// doc.commit();
+    /// // Delete 2 and insert 5
+    /// assert_eq!(doc.len_ops() - ops, 2);
+    /// assert_eq!(list.get_deep_value(), LoroValue::from(vec![1, 3, 4, 5]));
+    /// ```
+    pub fn update_from_value(&self, value: &LoroValue) -> LoroResult<()> {
+        value_update::update_list(self, value)
+    }
+
     /// Get the shallow value of the container.
     ///
     /// This does not convert the state of sub-containers; instead, it represents them as [LoroValue::Container].
@@ -1981,6 +2836,55 @@ impl LoroList {
         self.get_value().into_list().unwrap().unwrap()
     }
 
+    /// Index the child maps of this list by the value of their `field`.
+    ///
+    /// After that, [`Self::find_by_key`] looks up a child map without scanning the
+    /// list: the key is found in O(1) and its position in O(log n). The index is updated incrementally on local edits, imports and checkouts,
+    /// so it stays correct under concurrent edits. It lives in memory only and has to
+    /// be created again after the doc is reloaded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{LoroDoc, LoroMap};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let list = doc.get_list("todos");
+    /// list.create_key_index("id").unwrap();
+    /// for (i, id) in ["a", "b", "c"].into_iter().enumerate() {
+    ///     let todo = list.insert_container(i, LoroMap::new()).unwrap();
+    ///     todo.insert("id", id).unwrap();
+    /// }
+    /// let (pos, todo) = list.find_by_key("b").unwrap().unwrap();
+    /// assert_eq!(pos, 1);
+    /// assert_eq!(todo.get("id").unwrap().into_value().unwrap(), "b".into());
+    /// list.delete(0, 1).unwrap();
+    /// assert_eq!(list.find_by_key("b").unwrap().unwrap().0, 0);
+    /// assert!(list.find_by_key("a").unwrap().is_none());
+    /// ```
+    pub fn create_key_index(&self, field: &str) -> LoroResult<()> {
+        self.handler.create_key_index(field)
+    }
+
+    /// Remove the index created by [`Self::create_key_index`].
+    ///
+    /// Returns whether there was an index.
+    pub fn remove_key_index(&self) -> bool {
+        self.handler.remove_key_index()
+    }
+
+    /// Find the child map whose indexed field equals `key`.
+    ///
+    /// Returns its current position and the map. If several children share the same
+    /// key, the one with the smallest position is returned. It returns an error if
+    /// [`Self::create_key_index`] has not been called.
+    pub fn find_by_key<V: Into<LoroValue>>(&self, key: V) -> LoroResult<Option<(usize, LoroMap)>> {
+        Ok(self
+            .handler
+            .find_by_key(&key.into())?
+            .map(|(pos, handler)| (pos, LoroMap { handler })))
+    }
+
     /// Delete all elements in the list.
     pub fn clear(&self) -> LoroResult<()> {
         self.handler.clear()
@@ -2200,6 +3104,41 @@ impl LoroMap {
         self.handler.get_deep_value()
     }
 
+    /// Update the map to hold `value`, emitting only the ops needed to get there.
+    ///
+    /// `value` must be a [`LoroValue::Map`]. Keys that are missing from `value` are deleted and
+    /// the other entries are updated recursively: entries that already hold the new value are
+    /// left alone, child maps and lists are updated in place (see
+    /// [`LoroList::update_from_value`] for lists), and a [`LoroText`] entry is updated with
+    /// [`LoroText::update`] when the new value is a string. New nested maps and lists are created
+    /// with the `ensure_mergeable_*` methods, so peers that add the same entry concurrently end up
+    /// editing the same child. [`LoroValue::Container`] values are rejected.
+    ///
+    /// This is meant for applying a new version of a JSON document received from elsewhere
+    /// without clearing the container, which would discard concurrent edits.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::{LoroDoc, LoroText, ToJson};
+    /// use serde_json::json;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let map = doc.get_map("post");
+    /// map.insert_container("body", LoroText::new()).unwrap().insert(0, "Hello").unwrap();
+    /// map.insert("draft", true).unwrap();
+    /// doc.commit();
+    ///
+    /// let value = json!({ "body": "Hello world", "tags": ["news"] });
+    /// map.update_from_value(&value.into()).unwrap();
+    /// assert_eq!(map.get_deep_value().to_json_value(), json!({
+    ///     "body": "Hello world",
+    ///     "tags": ["news"],
+    /// }));
+    /// ```
+    pub fn update_from_value(&self, value: &LoroValue) -> LoroResult<()> {
+        value_update::update_map(self, value)
+    }
+
     /// Get or create a regular child container with the given key.
     ///
     /// This legacy method creates regular op-id child containers when the key is
@@ -2321,9 +3260,109 @@ impl LoroMap {
         self.handler.values().map(ValueOrContainer::from)
     }
 
-    /// Get the peer id of the last editor on the given entry
-    pub fn get_last_editor(&self, key: &str) -> Option<PeerID> {
-        self.handler.get_last_editor(key)
+    /// Get the entries whose keys are in `range`, sorted by key.
+    ///
+    /// The map keeps its keys sorted, so the range is found in O(log n) and merging
+    /// still follows the last-writer-wins rule of each key. Keys are compared as
+    /// strings: use fixed-width keys, such as zero-padded timestamps, to make the
+    /// string order match the order you need.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let events = doc.get_map("events");
+    /// events.insert("2024-01-03", "c").unwrap();
+    /// events.insert("2024-01-01", "a").unwrap();
+    /// events.insert("2024-01-02", "b").unwrap();
+    ///
+    /// let keys: Vec<String> = events
+    ///     .range("2024-01-02"..)
+    ///     .into_iter()
+    ///     .map(|(k, _)| k)
+    ///     .collect();
+    /// assert_eq!(keys, vec!["2024-01-02", "2024-01-03"]);
+    /// assert_eq!(events.first().unwrap().0, "2024-01-01");
+    /// assert_eq!(events.last().unwrap().0, "2024-01-03");
+    /// ```
+    pub fn range<K: AsRef<str>>(
+        &self,
+        range: impl RangeBounds<K>,
+    ) -> Vec<(String, ValueOrContainer)> {
+        self.handler
+            .range(range)
+            .into_iter()
+            .map(|(k, v)| (k, ValueOrContainer::from(v)))
+            .collect()
+    }
+
+    /// Get the entry with the smallest key.
+    ///
+    /// See [`LoroMap::range`] for how keys are ordered.
+    pub fn first(&self) -> Option<(String, ValueOrContainer)> {
+        self.handler
+            .first()
+            .map(|(k, v)| (k, ValueOrContainer::from(v)))
+    }
+
+    /// Get the entry with the largest key.
+    ///
+    /// See [`LoroMap::range`] for how keys are ordered.
+    pub fn last(&self) -> Option<(String, ValueOrContainer)> {
+        self.handler
+            .last()
+            .map(|(k, v)| (k, ValueOrContainer::from(v)))
+    }
+
+    /// Get the peer id of the last editor on the given entry
+    pub fn get_last_editor(&self, key: &str) -> Option<PeerID> {
+        self.handler.get_last_editor(key)
+    }
+
+    /// Get the values written to `key` concurrently.
+    ///
+    /// `LoroMap` is last-writer-wins per key, so concurrent writes to a key only keep one
+    /// value in the state. This method gives a multi-value register view of the key: every
+    /// value that is not overwritten by another op on the key at the current version, sorted
+    /// from the LWW winner to the losers. A `None` value means the key was deleted.
+    ///
+    /// Use [`LoroMap::resolve`] to collapse the conflicts into one value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{LoroDoc, LoroValue};
+    ///
+    /// let doc_a = LoroDoc::new();
+    /// doc_a.set_peer_id(1).unwrap();
+    /// let doc_b = LoroDoc::new();
+    /// doc_b.set_peer_id(2).unwrap();
+    /// doc_a.get_map("form").insert("name", "Alice").unwrap();
+    /// doc_b.get_map("form").insert("name", "Bob").unwrap();
+    /// doc_a.import(&doc_b.export(loro::ExportMode::all_updates()).unwrap()).unwrap();
+    ///
+    /// let map = doc_a.get_map("form");
+    /// let conflicts = map.get_conflicts("name").unwrap();
+    /// assert_eq!(conflicts.len(), 2);
+    /// assert_eq!(conflicts[0].value, Some(LoroValue::from("Bob")));
+    /// assert_eq!(conflicts[1].value, Some(LoroValue::from("Alice")));
+    ///
+    /// map.resolve("name", "Alice").unwrap();
+    /// doc_a.commit();
+    /// assert_eq!(map.get_conflicts("name").unwrap().len(), 1);
+    /// ```
+    pub fn get_conflicts(&self, key: &str) -> LoroResult<Vec<MapConflict>> {
+        self.handler.get_conflicts(key)
+    }
+
+    /// Set `key` to `value`, overwriting all the conflicting values of the key.
+    ///
+    /// It always creates a new op even if `value` equals the current value, so it also works
+    /// when the user picks the value that currently wins. It syncs like a normal map op.
+    pub fn resolve(&self, key: &str, value: impl Into<LoroValue>) -> LoroResult<()> {
+        self.handler.resolve(key, value)
     }
 }
 
@@ -3096,6 +4135,131 @@ impl LoroTree {
         self.handler.mov_before(target, before)
     }
 
+    /// Move all `targets` to be consecutive children of `parent`, starting at `index`.
+    ///
+    /// `index` is counted among the children of `parent` that are not being moved.
+    /// The moved nodes keep the order in `targets`. Their fractional indexes are generated
+    /// together, evenly spaced between the two neighbors, which keeps the keys short
+    /// compared with moving the nodes one by one.
+    ///
+    /// Nothing is moved if any target does not exist, is deleted, appears twice, or is an
+    /// ancestor of `parent`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use loro::{LoroDoc, TreeParentId};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let tree = doc.get_tree("tree");
+    /// tree.enable_fractional_index(0);
+    /// let a = tree.create(None).unwrap();
+    /// let b = tree.create(None).unwrap();
+    /// let c = tree.create(None).unwrap();
+    /// let d = tree.create(None).unwrap();
+    /// // drag `c` and `d` to the front
+    /// tree.move_many(&[c, d], TreeParentId::Root, 0).unwrap();
+    /// assert_eq!(tree.roots(), vec![c, d, a, b]);
+    /// ```
+    pub fn move_many<T: Into<TreeParentId>>(
+        &self,
+        targets: &[TreeID],
+        parent: T,
+        index: usize,
+    ) -> LoroResult<()> {
+        if !self.handler.is_fractional_index_enabled() {
+            return Err(LoroTreeError::FractionalIndexNotEnabled.into());
+        }
+        self.handler.move_many(targets, parent.into(), index)
+    }
+
+    /// Get the length statistics of the fractional indexes of `parent`'s children.
+    ///
+    /// Repeatedly inserting or moving nodes into the same spot makes the fractional
+    /// indexes grow, which bloats snapshots and events. Use this to detect it and
+    /// [`LoroTree::rebalance_fractional_index`] to fix it.
+    ///
+    /// Returns `None` if fractional index is disabled or `parent` does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use loro::{LoroDoc, TreeParentId};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let tree = doc.get_tree("tree");
+    /// tree.enable_fractional_index(0);
+    /// tree.create(None).unwrap();
+    /// tree.create(None).unwrap();
+    /// // keep inserting into the middle
+    /// for _ in 0..64 {
+    ///     let len = tree.children_num(None).unwrap();
+    ///     tree.create_at(None, len / 2).unwrap();
+    /// }
+    /// let stats = tree.fractional_index_stats(TreeParentId::Root).unwrap();
+    /// assert_eq!(stats.count, 66);
+    /// assert!(stats.max_len > 32);
+    /// ```
+    pub fn fractional_index_stats<T: Into<TreeParentId>>(
+        &self,
+        parent: T,
+    ) -> Option<FractionalIndexStats> {
+        self.handler.fractional_index_stats(&parent.into())
+    }
+
+    /// Assign fresh, evenly spaced fractional indexes to the children of `parent` in `range`.
+    ///
+    /// The order of the children is unchanged. The rebalance is recorded as move ops,
+    /// so it merges with concurrent edits like any other move. Replicas converge and
+    /// the rebalanced nodes keep their order, but a node inserted concurrently within
+    /// or next to `range` may end up in a different slot among them.
+    ///
+    /// Returns an error on a detached tree, which has no fractional indexes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use loro::{LoroDoc, TreeParentId};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let tree = doc.get_tree("tree");
+    /// tree.enable_fractional_index(0);
+    /// tree.create(None).unwrap();
+    /// tree.create(None).unwrap();
+    /// for _ in 0..64 {
+    ///     let len = tree.children_num(None).unwrap();
+    ///     tree.create_at(None, len / 2).unwrap();
+    /// }
+    /// let roots = tree.roots();
+    /// tree.rebalance_fractional_index(TreeParentId::Root, 0..roots.len()).unwrap();
+    /// assert_eq!(tree.roots(), roots);
+    /// assert!(tree.fractional_index_stats(TreeParentId::Root).unwrap().max_len <= 2);
+    /// ```
+    pub fn rebalance_fractional_index<T: Into<TreeParentId>>(
+        &self,
+        parent: T,
+        range: Range<usize>,
+    ) -> LoroResult<()> {
+        if !self.handler.is_fractional_index_enabled() {
+            return Err(LoroTreeError::FractionalIndexNotEnabled.into());
+        }
+        self.handler
+            .rebalance_fractional_index(&parent.into(), range)
+    }
+
+    /// Rebalance all children of `parent` if any of their fractional indexes is
+    /// longer than `max_len` bytes.
+    ///
+    /// Returns whether a rebalance happened. See [`LoroTree::rebalance_fractional_index`].
+    pub fn rebalance_fractional_index_if_needed<T: Into<TreeParentId>>(
+        &self,
+        parent: T,
+        max_len: usize,
+    ) -> LoroResult<bool> {
+        self.handler
+            .rebalance_fractional_index_if_needed(&parent.into(), max_len)
+    }
+
     /// Delete a tree node.
     ///
     /// Note: If the deleted node has children, the children do not appear in the state
@@ -3209,6 +4373,69 @@ impl LoroTree {
         self.handler.get_deep_value()
     }
 
+    /// Return the forest as nested nodes with resolved metadata.
+    ///
+    /// Each node is a map of `{ "id", "meta", "fractional_index", "children" }`, where
+    /// `children` holds the child nodes in the same shape. The value can be passed to
+    /// [`LoroTree::import_nested`] to rebuild the tree elsewhere.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use loro::{LoroDoc, ToJson};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let tree = doc.get_tree("tree");
+    /// let root = tree.create(None).unwrap();
+    /// tree.get_meta(root).unwrap().insert("name", "docs").unwrap();
+    /// tree.create(root).unwrap();
+    /// let value = tree.get_nested_value_with_meta().to_json_value();
+    /// assert_eq!(value[0]["meta"]["name"], "docs");
+    /// assert_eq!(value[0]["children"].as_array().unwrap().len(), 1);
+    /// ```
+    pub fn get_nested_value_with_meta(&self) -> LoroValue {
+        self.handler.get_nested_value()
+    }
+
+    /// Create the nodes described by a nested value under `parent` in one transaction.
+    ///
+    /// `nodes` is a list in the shape returned by [`LoroTree::get_nested_value_with_meta`].
+    /// The `id` fields are ignored because tree ids are assigned by the document. It returns
+    /// the ids of the created top-level nodes in order.
+    ///
+    /// Each node costs one create op plus one op per metadata key. If `preserve_fractional_index`
+    /// is true, nodes carrying a `fractional_index` keep it, so siblings are ordered as in the
+    /// source tree. Otherwise the nodes are appended after the existing children.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use loro::{LoroDoc, ToJson, TreeParentId};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let tree = doc.get_tree("tree");
+    /// let root = tree.create(None).unwrap();
+    /// tree.get_meta(root).unwrap().insert("name", "docs").unwrap();
+    ///
+    /// let other = LoroDoc::new();
+    /// let copy = other.get_tree("tree");
+    /// copy.import_nested(TreeParentId::Root, &tree.get_nested_value_with_meta(), true)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     copy.get_nested_value_with_meta().to_json_value()[0]["meta"]["name"],
+    ///     "docs"
+    /// );
+    /// ```
+    pub fn import_nested<T: Into<TreeParentId>>(
+        &self,
+        parent: T,
+        nodes: &LoroValue,
+        preserve_fractional_index: bool,
+    ) -> LoroResult<Vec<TreeID>> {
+        self.handler
+            .import_nested(parent.into(), nodes, preserve_fractional_index)
+    }
+
     // This method is used for testing only.
     #[doc(hidden)]
     #[allow(non_snake_case)]
@@ -3358,6 +4585,85 @@ impl LoroMovableList {
         self.handler.delete(pos, len)
     }
 
+    /// Ensure a mergeable Counter keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](LoroList#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    #[cfg(feature = "counter")]
+    pub fn ensure_mergeable_counter(&self, key: &str) -> LoroResult<LoroCounter> {
+        Ok(LoroCounter::from_handler(
+            self.handler.ensure_mergeable_counter(key)?,
+        ))
+    }
+
+    /// Ensure a mergeable Map keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](LoroList#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    pub fn ensure_mergeable_map(&self, key: &str) -> LoroResult<LoroMap> {
+        Ok(LoroMap::from_handler(
+            self.handler.ensure_mergeable_map(key)?,
+        ))
+    }
+
+    /// Ensure a mergeable List keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](LoroList#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    pub fn ensure_mergeable_list(&self, key: &str) -> LoroResult<LoroList> {
+        Ok(LoroList::from_handler(
+            self.handler.ensure_mergeable_list(key)?,
+        ))
+    }
+
+    /// Ensure a mergeable MovableList keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](LoroList#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    pub fn ensure_mergeable_movable_list(&self, key: &str) -> LoroResult<LoroMovableList> {
+        Ok(LoroMovableList::from_handler(
+            self.handler.ensure_mergeable_movable_list(key)?,
+        ))
+    }
+
+    /// Ensure a mergeable Text keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](LoroList#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    pub fn ensure_mergeable_text(&self, key: &str) -> LoroResult<LoroText> {
+        Ok(LoroText::from_handler(
+            self.handler.ensure_mergeable_text(key)?,
+        ))
+    }
+
+    /// Ensure a mergeable Tree keyed by `key` exists in this list and return it.
+    ///
+    /// See [`LoroList`]'s [mergeable child containers](LoroList#mergeable-child-containers)
+    /// section for merge semantics.
+    ///
+    /// Returns [`LoroError::ArgErr`] if `key` already holds a mergeable child of another type.
+    /// Returns [`LoroError::MisuseDetachedContainer`] if this list is detached.
+    pub fn ensure_mergeable_tree(&self, key: &str) -> LoroResult<LoroTree> {
+        Ok(LoroTree::from_handler(
+            self.handler.ensure_mergeable_tree(key)?,
+        ))
+    }
+
     /// Get the value at the given position.
     pub fn get(&self, index: usize) -> Option<ValueOrContainer> {
         self.handler.get_(index).map(ValueOrContainer::from)
@@ -3374,6 +4680,73 @@ impl LoroMovableList {
         self.len() == 0
     }
 
+    /// Get the values in `range`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{LoroDoc, LoroValue};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let list = doc.get_movable_list("list");
+    /// list.insert_many(0, [1, 2, 3, 4]).unwrap();
+    /// let window: Vec<_> = list
+    ///     .slice(1..3)
+    ///     .unwrap()
+    ///     .into_iter()
+    ///     .map(|v| v.into_value().unwrap())
+    ///     .collect();
+    /// assert_eq!(window, vec![LoroValue::from(2), LoroValue::from(3)]);
+    /// ```
+    pub fn slice(&self, range: Range<usize>) -> LoroResult<Vec<ValueOrContainer>> {
+        Ok(self
+            .handler
+            .slice(range)?
+            .into_iter()
+            .map(ValueOrContainer::from)
+            .collect())
+    }
+
+    /// Insert all `values` at `pos`.
+    ///
+    /// The values are recorded as a single op, so bulk-loading many rows is much cheaper
+    /// than calling [`LoroMovableList::insert`] in a loop.
+    pub fn insert_many<V: Into<LoroValue>>(
+        &self,
+        pos: usize,
+        values: impl IntoIterator<Item = V>,
+    ) -> LoroResult<()> {
+        self.handler
+            .insert_many(pos, values.into_iter().map(Into::into).collect())
+    }
+
+    /// Replace the `len` values from `pos` with `values`, and return the deleted values.
+    ///
+    /// The new values are recorded as a single insert op.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{LoroDoc, LoroValue, ToJson};
+    /// use serde_json::json;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let list = doc.get_movable_list("list");
+    /// list.insert_many(0, ["a", "b", "c"]).unwrap();
+    /// let deleted = list.splice(1, 1, ["x", "y"]).unwrap();
+    /// assert_eq!(deleted, vec![LoroValue::from("b")]);
+    /// assert_eq!(list.get_value().to_json_value(), json!(["a", "x", "y", "c"]));
+    /// ```
+    pub fn splice<V: Into<LoroValue>>(
+        &self,
+        pos: usize,
+        len: usize,
+        values: impl IntoIterator<Item = V>,
+    ) -> LoroResult<Vec<LoroValue>> {
+        self.handler
+            .splice(pos, len, values.into_iter().map(Into::into).collect())
+    }
+
     /// Get the shallow value of the list.
     ///
     /// It will not convert the state of sub-containers, but represent them as [LoroValue::Container].
@@ -3388,6 +4761,14 @@ impl LoroMovableList {
         self.handler.get_deep_value()
     }
 
+    /// Update the list to hold `value`, emitting only the ops needed to get there.
+    ///
+    /// Works like [`LoroList::update_from_value`], except that replaced elements are written
+    /// with [`set`](Self::set), so they keep their position among concurrent moves.
+    pub fn update_from_value(&self, value: &LoroValue) -> LoroResult<()> {
+        value_update::update_movable_list(self, value)
+    }
+
     /// Pop the last element of the list.
     pub fn pop(&self) -> LoroResult<Option<ValueOrContainer>> {
         let ans = self.handler.pop_()?.map(ValueOrContainer::from);
@@ -3441,6 +4822,27 @@ impl LoroMovableList {
         self.handler.mov(from, to)
     }
 
+    /// Move the values in the `from` range so that they start at position `to`.
+    ///
+    /// After this op, the moved values are at `to..to + from.len()`, in their original order.
+    /// All the moves are committed together.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::{LoroDoc, ToJson};
+    /// use serde_json::json;
+    /// let doc = LoroDoc::new();
+    /// let ml = doc.get_movable_list("ml");
+    /// for (i, v) in ["a", "b", "c", "d"].into_iter().enumerate() {
+    ///     ml.insert(i, v).unwrap();
+    /// }
+    /// ml.mov_range(2..4, 0).unwrap();
+    /// assert_eq!(ml.get_deep_value().to_json_value(), json!(["c", "d", "a", "b"]));
+    /// ```
+    pub fn mov_range(&self, from: Range<usize>, to: usize) -> LoroResult<()> {
+        self.handler.mov_range(from, to)
+    }
+
     /// Insert a container at the given position.
     pub fn insert_container<C: ContainerTrait>(&self, pos: usize, child: C) -> LoroResult<C> {
         Ok(C::from_handler(
@@ -3531,6 +4933,55 @@ impl LoroMovableList {
         self.get_value().into_list().unwrap().unwrap()
     }
 
+    /// Index the child maps of this list by the value of their `field`.
+    ///
+    /// After that, [`Self::find_by_key`] looks up a child map without scanning the
+    /// list: the key is found in O(1) and its position in O(log n). The index is updated incrementally on local edits, imports and checkouts,
+    /// so it stays correct under concurrent edits. It lives in memory only and has to
+    /// be created again after the doc is reloaded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{LoroDoc, LoroMap};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let list = doc.get_movable_list("todos");
+    /// list.create_key_index("id").unwrap();
+    /// for (i, id) in ["a", "b", "c"].into_iter().enumerate() {
+    ///     let todo = list.insert_container(i, LoroMap::new()).unwrap();
+    ///     todo.insert("id", id).unwrap();
+    /// }
+    /// let (pos, todo) = list.find_by_key("b").unwrap().unwrap();
+    /// assert_eq!(pos, 1);
+    /// assert_eq!(todo.get("id").unwrap().into_value().unwrap(), "b".into());
+    /// list.delete(0, 1).unwrap();
+    /// assert_eq!(list.find_by_key("b").unwrap().unwrap().0, 0);
+    /// assert!(list.find_by_key("a").unwrap().is_none());
+    /// ```
+    pub fn create_key_index(&self, field: &str) -> LoroResult<()> {
+        self.handler.create_key_index(field)
+    }
+
+    /// Remove the index created by [`Self::create_key_index`].
+    ///
+    /// Returns whether there was an index.
+    pub fn remove_key_index(&self) -> bool {
+        self.handler.remove_key_index()
+    }
+
+    /// Find the child map whose indexed field equals `key`.
+    ///
+    /// Returns its current position and the map. If several children share the same
+    /// key, the one with the smallest position is returned. It returns an error if
+    /// [`Self::create_key_index`] has not been called.
+    pub fn find_by_key<V: Into<LoroValue>>(&self, key: V) -> LoroResult<Option<(usize, LoroMap)>> {
+        Ok(self
+            .handler
+            .find_by_key(&key.into())?
+            .map(|(pos, handler)| (pos, LoroMap { handler })))
+    }
+
     /// Delete all elements in the list.
     pub fn clear(&self) -> LoroResult<()> {
         self.handler.clear()
@@ -3551,6 +5002,13 @@ impl LoroMovableList {
         self.handler.get_creator_at(pos)
     }
 
+    /// Get the position of the child container `id`, or `None` if it's not in the list.
+    ///
+    /// The position is found in O(log n), without scanning the list.
+    pub fn get_index_of_child(&self, id: &ContainerID) -> Option<usize> {
+        self.handler.get_index_of_child(id)
+    }
+
     /// Get the last mover of the list item at the given position.
     pub fn get_last_mover_at(&self, pos: usize) -> Option<PeerID> {
         self.handler.get_last_mover_at(pos)
@@ -3647,6 +5105,9 @@ pub enum Container {
     #[cfg(feature = "counter")]
     /// [LoroCounter container]
     Counter(counter::LoroCounter),
+    #[cfg(feature = "set")]
+    /// [LoroSet container]
+    Set(set::LoroSet),
     /// Unknown container
     Unknown(LoroUnknown),
 }
@@ -3664,6 +5125,8 @@ impl ContainerTrait for Container {
             Container::MovableList(x) => x.id(),
             #[cfg(feature = "counter")]
             Container::Counter(x) => x.id(),
+            #[cfg(feature = "set")]
+            Container::Set(x) => x.id(),
             Container::Unknown(x) => x.id(),
         }
     }
@@ -3681,6 +5144,8 @@ impl ContainerTrait for Container {
             Container::MovableList(x) => Self::Handler::MovableList(x.to_handler()),
             #[cfg(feature = "counter")]
             Container::Counter(x) => Self::Handler::Counter(x.to_handler()),
+            #[cfg(feature = "set")]
+            Container::Set(x) => Self::Handler::Set(x.to_handler()),
             Container::Unknown(x) => Self::Handler::Unknown(x.to_handler()),
         }
     }
@@ -3694,6 +5159,8 @@ impl ContainerTrait for Container {
             InnerHandler::Tree(x) => Container::Tree(LoroTree { handler: x }),
             #[cfg(feature = "counter")]
             InnerHandler::Counter(x) => Container::Counter(counter::LoroCounter { handler: x }),
+            #[cfg(feature = "set")]
+            InnerHandler::Set(x) => Container::Set(set::LoroSet { handler: x }),
             InnerHandler::Unknown(x) => Container::Unknown(LoroUnknown { handler: x }),
         }
     }
@@ -3707,6 +5174,8 @@ impl ContainerTrait for Container {
             Container::MovableList(x) => x.is_attached(),
             #[cfg(feature = "counter")]
             Container::Counter(x) => x.is_attached(),
+            #[cfg(feature = "set")]
+            Container::Set(x) => x.is_attached(),
             Container::Unknown(x) => x.is_attached(),
         }
     }
@@ -3720,6 +5189,8 @@ impl ContainerTrait for Container {
             Container::Tree(x) => x.get_attached().map(Container::Tree),
             #[cfg(feature = "counter")]
             Container::Counter(x) => x.get_attached().map(Container::Counter),
+            #[cfg(feature = "set")]
+            Container::Set(x) => x.get_attached().map(Container::Set),
             Container::Unknown(x) => x.get_attached().map(Container::Unknown),
         }
     }
@@ -3740,6 +5211,8 @@ impl ContainerTrait for Container {
             Container::MovableList(x) => x.is_deleted(),
             #[cfg(feature = "counter")]
             Container::Counter(x) => x.is_deleted(),
+            #[cfg(feature = "set")]
+            Container::Set(x) => x.is_deleted(),
             Container::Unknown(x) => x.is_deleted(),
         }
     }
@@ -3752,6 +5225,8 @@ impl ContainerTrait for Container {
             Container::MovableList(x) => x.doc(),
             #[cfg(feature = "counter")]
             Container::Counter(x) => x.doc(),
+            #[cfg(feature = "set")]
+            Container::Set(x) => x.doc(),
             Container::Unknown(x) => x.doc(),
         }
     }
@@ -3772,6 +5247,10 @@ impl Container {
             ContainerType::Tree => Container::Tree(LoroTree::new()),
             #[cfg(feature = "counter")]
             ContainerType::Counter => Container::Counter(counter::LoroCounter::new()),
+            #[cfg(feature = "typed-counter")]
+            ContainerType::TypedCounter => Container::Counter(counter::LoroCounter::new_typed()),
+            #[cfg(feature = "set")]
+            ContainerType::Set => Container::Set(set::LoroSet::new()),
             ContainerType::Unknown(_) => {
                 panic!("Cannot create a detached container of type Unknown");
             }
@@ -3787,7 +5266,9 @@ impl Container {
             Container::Text(_) => ContainerType::Text,
             Container::Tree(_) => ContainerType::Tree,
             #[cfg(feature = "counter")]
-            Container::Counter(_) => ContainerType::Counter,
+            Container::Counter(x) => x.handler.kind(),
+            #[cfg(feature = "set")]
+            Container::Set(_) => ContainerType::Set,
             Container::Unknown(x) => x.handler.id().container_type(),
         }
     }
@@ -3803,6 +5284,8 @@ impl From<InnerHandler> for Container {
             InnerHandler::MovableList(x) => Container::MovableList(LoroMovableList { handler: x }),
             #[cfg(feature = "counter")]
             InnerHandler::Counter(x) => Container::Counter(counter::LoroCounter { handler: x }),
+            #[cfg(feature = "set")]
+            InnerHandler::Set(x) => Container::Set(set::LoroSet { handler: x }),
             InnerHandler::Unknown(x) => Container::Unknown(LoroUnknown { handler: x }),
         }
     }
@@ -3830,6 +5313,8 @@ impl ValueOrContainer {
                 Container::MovableList(c) => c.get_deep_value(),
                 #[cfg(feature = "counter")]
                 Container::Counter(c) => c.get_value().into(),
+                #[cfg(feature = "set")]
+                Container::Set(c) => c.get_value(),
                 Container::Unknown(_) => LoroValue::Null,
             },
         }
@@ -3863,6 +5348,34 @@ impl UndoManager {
         Self(inner)
     }
 
+    /// Create a new UndoManager that only undoes the changes to the given containers and
+    /// their descendants.
+    ///
+    /// The local changes to the other containers are never undone. They're handled like
+    /// remote changes, so undoing a change in the scope doesn't interfere with them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use loro::{ContainerTrait, LoroDoc, UndoManager};
+    /// let doc = LoroDoc::new();
+    /// let left = doc.get_text("left");
+    /// let right = doc.get_text("right");
+    /// let mut left_undo = UndoManager::new_scoped(&doc, [left.id()]);
+    /// left.insert(0, "hello").unwrap();
+    /// doc.commit();
+    /// right.insert(0, "world").unwrap();
+    /// doc.commit();
+    /// left_undo.undo().unwrap();
+    /// assert_eq!(left.to_string(), "");
+    /// assert_eq!(right.to_string(), "world");
+    /// ```
+    pub fn new_scoped(doc: &LoroDoc, containers: impl IntoIterator<Item = ContainerID>) -> Self {
+        let inner = InnerUndoManager::new_scoped(&doc.doc, containers);
+        inner.set_max_undo_steps(100);
+        Self(inner)
+    }
+
     /// Undo the last change made by the peer.
     pub fn undo(&mut self) -> LoroResult<bool> {
         self.0.undo()
@@ -3976,6 +5489,46 @@ impl UndoManager {
     pub fn top_redo_value(&self) -> Option<LoroValue> {
         self.0.top_redo_value()
     }
+
+    /// Encode the undo and redo stacks, including the values and cursors of their items.
+    ///
+    /// Store the result next to the document and restore it with
+    /// [`UndoManager::import_state`] to keep the undo history across sessions.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::{LoroDoc, UndoManager};
+    ///
+    /// let doc = LoroDoc::new();
+    /// doc.set_peer_id(1).unwrap();
+    /// let undo = UndoManager::new(&doc);
+    /// doc.get_text("text").insert(0, "hello").unwrap();
+    /// doc.commit();
+    /// let snapshot = doc.export(loro::ExportMode::snapshot()).unwrap();
+    /// let state = undo.export_state();
+    ///
+    /// // After a reload
+    /// let doc = LoroDoc::from_snapshot(&snapshot).unwrap();
+    /// doc.set_peer_id(1).unwrap();
+    /// let mut undo = UndoManager::new(&doc);
+    /// undo.import_state(&state).unwrap();
+    /// undo.undo().unwrap();
+    /// assert_eq!(doc.get_text("text").to_string(), "");
+    /// ```
+    pub fn export_state(&self) -> Vec<u8> {
+        self.0.export_state()
+    }
+
+    /// Replace the undo and redo stacks with the ones encoded by [`UndoManager::export_state`].
+    ///
+    /// Returns [`LoroError::UndoWithDifferentPeerId`] if the state was exported by an undo
+    /// manager of another peer, and [`LoroError::UndoInvalidIdSpan`] if the document doesn't
+    /// contain the changes the stacks refer to. States are versioned: one that isn't an undo
+    /// state, or that was exported with an unsupported version, is rejected with
+    /// [`LoroError::DecodeError`].
+    pub fn import_state(&self, bytes: &[u8]) -> LoroResult<()> {
+        self.0.import_state(bytes)
+    }
 }
 /// When a undo/redo item is pushed, the undo manager will call the on_push callback to get the meta data of the undo item.
 /// The returned cursors will be recorded for a new pushed undo item.
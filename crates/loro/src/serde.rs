@@ -0,0 +1,918 @@
+//! Serde support that reads from and writes to containers directly.
+//!
+//! [`from_container`] deserializes any `Deserialize` type straight out of a container, without
+//! materializing its deep value first. Map entries and list elements are read when the type asks
+//! for them, and map entries that a struct doesn't declare are never read.
+//!
+//! [`to_container`] writes any `Serialize` type into an existing container. Instead of clearing
+//! it, the serialized value is diffed against the current contents, so unchanged entries produce
+//! no ops and concurrent edits to other parts of the container survive:
+//!
+//! - Structs and maps are written to maps entry by entry; entries the value doesn't have are
+//!   deleted.
+//! - Sequences and tuples are written to lists, keeping the elements of the longest common
+//!   subsequence of the old and new list and updating the others in place where possible.
+//! - Strings are plain values, unless the entry already holds a [`LoroText`], which is then
+//!   updated with a text diff.
+//! - Enums use serde's default externally tagged form: unit variants are strings and the others
+//!   are maps with a single entry keyed by the variant name.
+//! - `None` and `()` are `null`.
+//!
+//! New child maps and lists are created with the `ensure_mergeable_*` methods, so peers that
+//! write the same new entry concurrently end up editing the same child.
+//!
+//! ```
+//! use loro::{serde::{from_container, to_container}, LoroDoc};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Profile {
+//!     name: String,
+//!     tags: Vec<String>,
+//! }
+//!
+//! let doc = LoroDoc::new();
+//! let map = doc.get_map("profile");
+//! let mut profile = Profile { name: "Ada".into(), tags: vec!["math".into()] };
+//! to_container(&profile, &map).unwrap();
+//! doc.commit();
+//!
+//! profile.tags.push("computing".into());
+//! let ops = doc.len_ops();
+//! to_container(&profile, &map).unwrap();
+//! doc.commit();
+//! assert_eq!(doc.len_ops() - ops, 1);
+//! assert_eq!(from_container::<Profile, _>(&map).unwrap(), profile);
+//! ```
+use std::fmt::Display;
+
+use ::serde::{
+    de::{self, DeserializeOwned, IntoDeserializer},
+    forward_to_deserialize_any, ser, Serialize,
+};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    typed::{describe, type_mismatch_msg},
+    value_update::{update_list, update_map, update_movable_list, update_text},
+    Container, ContainerTrait, LoroError, LoroMap, LoroValue, ValueOrContainer,
+};
+
+/// The error of [`from_container`] and [`to_container`].
+#[derive(Debug)]
+pub enum Error {
+    /// An error raised by a container operation.
+    Loro(LoroError),
+    /// An error raised by serde, or a value that doesn't fit the container.
+    Message(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Loro(e) => e.fmt(f),
+            Error::Message(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Loro(e) => Some(e),
+            Error::Message(_) => None,
+        }
+    }
+}
+
+impl From<LoroError> for Error {
+    fn from(e: LoroError) -> Self {
+        Error::Loro(e)
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Deserialize `T` from `container`, reading it lazily.
+pub fn from_container<T: DeserializeOwned, C: ContainerTrait>(container: &C) -> Result<T> {
+    T::deserialize(Deserializer::new(ValueOrContainer::Container(
+        container.to_container(),
+    )))
+}
+
+/// Write `value` into `container`, only emitting the ops needed to make it hold `value`.
+///
+/// Maps accept structs and maps, lists and movable lists accept sequences, and texts accept
+/// strings. Other combinations return [`Error::Message`] without touching the container.
+pub fn to_container<T: Serialize + ?Sized, C: ContainerTrait>(
+    value: &T,
+    container: &C,
+) -> Result<()> {
+    value.serialize(Serializer::new(container.to_container()))
+}
+
+/// Serialize `value` into a [`LoroValue`]. Structs and maps become [`LoroValue::Map`]s and
+/// sequences become [`LoroValue::List`]s.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<LoroValue> {
+    value.serialize(ValueSerializer)
+}
+
+/// A [`de::Deserializer`] that reads from a value or a container.
+#[derive(Debug)]
+pub struct Deserializer {
+    value: ValueOrContainer,
+}
+
+impl Deserializer {
+    /// Create a deserializer that reads from `value`.
+    pub fn new(value: ValueOrContainer) -> Self {
+        Self { value }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            ValueOrContainer::Value(value) => visit_value(value, visitor),
+            ValueOrContainer::Container(Container::Map(map)) => {
+                let keys: Vec<String> = map.keys().map(|k| k.to_string()).collect();
+                visitor.visit_map(MapAccess {
+                    map,
+                    keys: keys.into_iter(),
+                    value: None,
+                })
+            }
+            ValueOrContainer::Container(Container::List(list)) => visitor.visit_seq(SeqAccess {
+                len: list.len(),
+                get: Box::new(move |i| list.get(i)),
+                index: 0,
+            }),
+            ValueOrContainer::Container(Container::MovableList(list)) => {
+                visitor.visit_seq(SeqAccess {
+                    len: list.len(),
+                    get: Box::new(move |i| list.get(i)),
+                    index: 0,
+                })
+            }
+            ValueOrContainer::Container(Container::Text(text)) => {
+                visitor.visit_string(text.to_string())
+            }
+            other => visit_value(other.get_deep_value(), visitor),
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            ValueOrContainer::Value(LoroValue::Null) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Only the declared fields are read from a map container.
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.value {
+            ValueOrContainer::Container(Container::Map(map)) => {
+                let keys: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+                visitor.visit_map(MapAccess {
+                    map,
+                    keys: keys.into_iter(),
+                    value: None,
+                })
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let (variant, value) = match self.value {
+            ValueOrContainer::Value(LoroValue::String(s)) => (s.to_string(), None),
+            ValueOrContainer::Value(LoroValue::Map(m)) if m.len() == 1 => {
+                let (k, v) = m.iter().next().unwrap();
+                (k.clone(), Some(ValueOrContainer::Value(v.clone())))
+            }
+            ValueOrContainer::Container(Container::Map(map)) if map.len() == 1 => {
+                let key = map.keys().next().unwrap().to_string();
+                let value = map.get(&key);
+                (key, value)
+            }
+            other => {
+                return Err(Error::Message(type_mismatch_msg(
+                    "a string or a map with a single entry for an enum",
+                    &other,
+                )))
+            }
+        };
+        visitor.visit_enum(EnumAccess { variant, value })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map identifier ignored_any
+    }
+}
+
+fn visit_value<'de, V: de::Visitor<'de>>(value: LoroValue, visitor: V) -> Result<V::Value> {
+    match value {
+        LoroValue::Null => visitor.visit_unit(),
+        LoroValue::Bool(b) => visitor.visit_bool(b),
+        LoroValue::Double(d) => visitor.visit_f64(d),
+        LoroValue::I64(i) => visitor.visit_i64(i),
+        LoroValue::Binary(b) => visitor.visit_byte_buf(b.to_vec()),
+        LoroValue::String(s) => visitor.visit_string(s.to_string()),
+        LoroValue::List(list) => visitor.visit_seq(SeqAccess {
+            len: list.len(),
+            get: Box::new(move |i| list.get(i).cloned().map(ValueOrContainer::Value)),
+            index: 0,
+        }),
+        LoroValue::Map(map) => {
+            let entries: Vec<(String, LoroValue)> =
+                map.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            visitor.visit_map(de::value::MapDeserializer::new(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, Deserializer::new(ValueOrContainer::Value(v)))),
+            ))
+        }
+        LoroValue::Container(id) => Err(Error::Message(format!(
+            "cannot deserialize the container {id} from a value"
+        ))),
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Deserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+struct MapAccess {
+    map: LoroMap,
+    keys: std::vec::IntoIter<String>,
+    value: Option<ValueOrContainer>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        for key in self.keys.by_ref() {
+            if let Some(value) = self.map.get(&key) {
+                self.value = Some(value);
+                return seed
+                    .deserialize(IntoDeserializer::<Error>::into_deserializer(key))
+                    .map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::Message("next_value_seed called before next_key_seed".into()))?;
+        seed.deserialize(Deserializer::new(value))
+    }
+}
+
+struct SeqAccess {
+    len: usize,
+    get: Box<dyn Fn(usize) -> Option<ValueOrContainer>>,
+    index: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        if self.index >= self.len {
+            return Ok(None);
+        }
+        let Some(value) = (self.get)(self.index) else {
+            return Ok(None);
+        };
+        self.index += 1;
+        seed.deserialize(Deserializer::new(value)).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+struct EnumAccess {
+    variant: String,
+    value: Option<ValueOrContainer>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = Error;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant)> {
+        let variant =
+            seed.deserialize(IntoDeserializer::<Error>::into_deserializer(self.variant))?;
+        Ok((variant, VariantAccess { value: self.value }))
+    }
+}
+
+struct VariantAccess {
+    value: Option<ValueOrContainer>,
+}
+
+impl VariantAccess {
+    fn content(self) -> Result<Deserializer> {
+        self.value
+            .map(Deserializer::new)
+            .ok_or_else(|| Error::Message("expected a variant with content".into()))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.value {
+            None | Some(ValueOrContainer::Value(LoroValue::Null)) => Ok(()),
+            Some(other) => Err(Error::Message(type_mismatch_msg("a unit variant", &other))),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self.content()?)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_seq(self.content()?, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_struct(self.content()?, "", fields, visitor)
+    }
+}
+
+/// A [`ser::Serializer`] that writes into a container.
+///
+/// The value is serialized into a [`LoroValue`] and then applied like
+/// [`LoroMap::update_from_value`] does, see the [module docs](self) for the rules.
+#[derive(Debug)]
+pub struct Serializer {
+    container: Container,
+}
+
+impl Serializer {
+    /// Create a serializer that writes into `container`.
+    pub fn new(container: Container) -> Self {
+        Self { container }
+    }
+
+    fn write(self, value: LoroValue) -> Result<()> {
+        match (self.container, &value) {
+            (Container::Map(map), LoroValue::Map(_)) => Ok(update_map(&map, &value)?),
+            (Container::List(list), LoroValue::List(_)) => Ok(update_list(&list, &value)?),
+            (Container::MovableList(list), LoroValue::List(_)) => {
+                Ok(update_movable_list(&list, &value)?)
+            }
+            (Container::Text(text), LoroValue::String(s)) => Ok(update_text(&text, s)?),
+            (container, _) => Err(Error::Message(format!(
+                "cannot write {} into a {} container",
+                describe(&ValueOrContainer::Value(value)),
+                container.get_type()
+            ))),
+        }
+    }
+}
+
+/// Forwards every method to [`ValueSerializer`], then writes the result into the container.
+macro_rules! forward_to_value_serializer {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<()> {
+                let value = ValueSerializer.$method($($arg),*)?;
+                self.write(value)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<SeqSerializer>;
+    type SerializeTuple = Compound<SeqSerializer>;
+    type SerializeTupleStruct = Compound<SeqSerializer>;
+    type SerializeTupleVariant = Compound<VariantSerializer<SeqSerializer>>;
+    type SerializeMap = Compound<MapSerializer>;
+    type SerializeStruct = Compound<MapSerializer>;
+    type SerializeStructVariant = Compound<VariantSerializer<MapSerializer>>;
+
+    forward_to_value_serializer! {
+        serialize_bool(v: bool);
+        serialize_i8(v: i8);
+        serialize_i16(v: i16);
+        serialize_i32(v: i32);
+        serialize_i64(v: i64);
+        serialize_u8(v: u8);
+        serialize_u16(v: u16);
+        serialize_u32(v: u32);
+        serialize_u64(v: u64);
+        serialize_f32(v: f32);
+        serialize_f64(v: f64);
+        serialize_char(v: char);
+        serialize_str(v: &str);
+        serialize_bytes(v: &[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(name: &'static str);
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str);
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let value = ValueSerializer.serialize_newtype_variant(name, index, variant, value)?;
+        self.write(value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(Compound {
+            target: self,
+            inner: ValueSerializer.serialize_seq(len)?,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(Compound {
+            target: self,
+            inner: ValueSerializer.serialize_tuple_variant(name, index, variant, len)?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(Compound {
+            target: self,
+            inner: ValueSerializer.serialize_map(len)?,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        Ok(Compound {
+            target: self,
+            inner: ValueSerializer.serialize_struct(name, len)?,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(Compound {
+            target: self,
+            inner: ValueSerializer.serialize_struct_variant(name, index, variant, len)?,
+        })
+    }
+}
+
+/// A compound value that is collected by `inner` and written into the container at the end.
+#[derive(Debug)]
+pub struct Compound<S> {
+    target: Serializer,
+    inner: S,
+}
+
+macro_rules! impl_compound {
+    ($($trait:ident::$method:ident($($key:ident: $key_ty:ty)?);)*) => {
+        $(
+            impl<S: ser::$trait<Ok = LoroValue, Error = Error>> ser::$trait for Compound<S> {
+                type Ok = ();
+                type Error = Error;
+
+                fn $method<T: Serialize + ?Sized>(
+                    &mut self,
+                    $($key: $key_ty,)?
+                    value: &T,
+                ) -> Result<()> {
+                    self.inner.$method($($key,)? value)
+                }
+
+                fn end(self) -> Result<()> {
+                    let value = self.inner.end()?;
+                    self.target.write(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_compound! {
+    SerializeSeq::serialize_element();
+    SerializeTuple::serialize_element();
+    SerializeTupleStruct::serialize_field();
+    SerializeTupleVariant::serialize_field();
+    SerializeStruct::serialize_field(key: &'static str);
+    SerializeStructVariant::serialize_field(key: &'static str);
+}
+
+impl<S: ser::SerializeMap<Ok = LoroValue, Error = Error>> ser::SerializeMap for Compound<S> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.inner.serialize_key(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.inner.serialize_value(value)
+    }
+
+    fn end(self) -> Result<()> {
+        let value = self.inner.end()?;
+        self.target.write(value)
+    }
+}
+
+/// A [`ser::Serializer`] that produces a [`LoroValue`]. See [`to_value`].
+#[derive(Debug, Clone, Copy)]
+pub struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = LoroValue;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = VariantSerializer<MapSerializer>;
+
+    fn serialize_bool(self, v: bool) -> Result<LoroValue> {
+        Ok(LoroValue::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<LoroValue> {
+        Ok(LoroValue::I64(v as i64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<LoroValue> {
+        Ok(LoroValue::I64(v as i64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<LoroValue> {
+        Ok(LoroValue::I64(v as i64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<LoroValue> {
+        Ok(LoroValue::I64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<LoroValue> {
+        Ok(LoroValue::I64(v as i64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<LoroValue> {
+        Ok(LoroValue::I64(v as i64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<LoroValue> {
+        Ok(LoroValue::I64(v as i64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<LoroValue> {
+        i64::try_from(v)
+            .map(LoroValue::I64)
+            .map_err(|_| Error::Message(format!("{v} is out of the range of i64")))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<LoroValue> {
+        Ok(LoroValue::Double(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<LoroValue> {
+        Ok(LoroValue::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<LoroValue> {
+        Ok(LoroValue::from(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<LoroValue> {
+        Ok(LoroValue::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<LoroValue> {
+        Ok(LoroValue::from(v))
+    }
+
+    fn serialize_none(self) -> Result<LoroValue> {
+        Ok(LoroValue::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<LoroValue> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<LoroValue> {
+        Ok(LoroValue::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<LoroValue> {
+        Ok(LoroValue::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<LoroValue> {
+        Ok(LoroValue::from(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<LoroValue> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<LoroValue> {
+        let mut map = FxHashMap::default();
+        map.insert(variant.to_string(), value.serialize(self)?);
+        Ok(LoroValue::Map(map.into()))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<SeqSerializer>> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer> {
+        Ok(MapSerializer {
+            map: FxHashMap::with_capacity_and_hasher(len.unwrap_or(0), Default::default()),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<MapSerializer>> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SeqSerializer(Vec<LoroValue>);
+
+macro_rules! impl_seq_serializer {
+    ($($trait:ident::$method:ident;)*) => {
+        $(
+            impl ser::$trait for SeqSerializer {
+                type Ok = LoroValue;
+                type Error = Error;
+
+                fn $method<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+                    self.0.push(value.serialize(ValueSerializer)?);
+                    Ok(())
+                }
+
+                fn end(self) -> Result<LoroValue> {
+                    Ok(LoroValue::List(self.0.into()))
+                }
+            }
+        )*
+    };
+}
+
+impl_seq_serializer! {
+    SerializeSeq::serialize_element;
+    SerializeTuple::serialize_element;
+    SerializeTupleStruct::serialize_field;
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct MapSerializer {
+    map: FxHashMap<String, LoroValue>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = LoroValue;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = match key.serialize(ValueSerializer)? {
+            LoroValue::String(s) => s.to_string(),
+            LoroValue::I64(i) => i.to_string(),
+            LoroValue::Bool(b) => b.to_string(),
+            other => {
+                return Err(Error::Message(format!(
+                    "map keys must be strings, found {}",
+                    describe(&ValueOrContainer::Value(other))
+                )))
+            }
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Message("serialize_value called before serialize_key".into()))?;
+        self.map.insert(key, value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<LoroValue> {
+        Ok(LoroValue::Map(self.map.into()))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = LoroValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.map
+            .insert(key.to_string(), value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<LoroValue> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> VariantSerializer<S> {
+    fn wrap(variant: &str, value: LoroValue) -> LoroValue {
+        let mut map = FxHashMap::default();
+        map.insert(variant.to_string(), value);
+        LoroValue::Map(map.into())
+    }
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+    type Ok = LoroValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<LoroValue> {
+        let value = ser::SerializeSeq::end(self.inner)?;
+        Ok(Self::wrap(self.variant, value))
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<MapSerializer> {
+    type Ok = LoroValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<LoroValue> {
+        let value = ser::SerializeMap::end(self.inner)?;
+        Ok(Self::wrap(self.variant, value))
+    }
+}
@@ -0,0 +1,124 @@
+use loro_internal::{
+    container::ContainerID, handler::set::SetHandler, HandlerTrait, LoroResult, LoroValue,
+};
+
+use crate::{Container, ContainerTrait, LoroDoc, SealedTrait};
+
+/// An add-wins set of values.
+///
+/// When the same value is added and removed concurrently, the value stays in
+/// the set after merging. Containers can't be added to a set.
+///
+/// The order of the values is unspecified. It's the same on every peer, but a
+/// concurrent add can move a value without an event, so a list built from the
+/// events can have another order. Compare sets as sets.
+#[derive(Debug, Clone)]
+pub struct LoroSet {
+    pub(crate) handler: SetHandler,
+}
+
+impl Default for LoroSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoroSet {
+    /// Create a new Set.
+    pub fn new() -> Self {
+        Self {
+            handler: SetHandler::new_detached(),
+        }
+    }
+
+    /// Add a value to the set.
+    ///
+    /// Returns `false` if the value is already in the set.
+    ///
+    /// # Example
+    /// ```
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let set = doc.get_set("tags");
+    /// assert!(set.add("a").unwrap());
+    /// assert!(!set.add("a").unwrap());
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn add(&self, value: impl Into<LoroValue>) -> LoroResult<bool> {
+        self.handler.add(value)
+    }
+
+    /// Remove a value from the set.
+    ///
+    /// Returns `false` if the value is not in the set.
+    pub fn remove(&self, value: impl Into<LoroValue>) -> LoroResult<bool> {
+        self.handler.remove(&value.into())
+    }
+
+    /// Whether the set contains the value.
+    pub fn contains(&self, value: impl Into<LoroValue>) -> bool {
+        self.handler.contains(&value.into())
+    }
+
+    /// Get the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.handler.len()
+    }
+
+    /// Whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.handler.is_empty()
+    }
+
+    /// Remove all the values in the set.
+    pub fn clear(&self) -> LoroResult<()> {
+        self.handler.clear()
+    }
+
+    /// Get the values of the set as a list, in an unspecified order.
+    pub fn get_value(&self) -> LoroValue {
+        self.handler.get_value()
+    }
+}
+
+impl SealedTrait for LoroSet {}
+impl ContainerTrait for LoroSet {
+    type Handler = SetHandler;
+
+    fn id(&self) -> ContainerID {
+        self.handler.id()
+    }
+
+    fn to_container(&self) -> Container {
+        Container::Set(self.clone())
+    }
+
+    fn to_handler(&self) -> Self::Handler {
+        self.handler.clone()
+    }
+
+    fn from_handler(handler: Self::Handler) -> Self {
+        Self { handler }
+    }
+
+    fn is_attached(&self) -> bool {
+        self.handler.is_attached()
+    }
+
+    fn get_attached(&self) -> Option<Self> {
+        self.handler.get_attached().map(Self::from_handler)
+    }
+
+    fn try_from_container(container: Container) -> Option<Self> {
+        container.into_set().ok()
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.handler.is_deleted()
+    }
+
+    fn doc(&self) -> Option<LoroDoc> {
+        self.handler.doc().map(LoroDoc::_new)
+    }
+}
@@ -0,0 +1,687 @@
+//! Typed bindings between Rust types and Loro containers.
+//!
+//! [`LoroType`] describes how a Rust value is stored in a map entry or a list element, and
+//! [`LoroMapped`] is implemented by types that are stored as a whole [`LoroMap`]. Both are
+//! usually implemented with `#[derive(Loro)]`, which is available behind the `derive` feature.
+//!
+//! | Rust type                           | Stored as                                        |
+//! |-------------------------------------|--------------------------------------------------|
+//! | `bool`, integers, floats, `String`  | a plain value                                    |
+//! | `String` with `#[loro(text)]`       | a [`LoroText`] child                             |
+//! | `Option<T>`                         | `T`, or a missing key / `null`                   |
+//! | `Vec<T>`                            | a [`LoroList`] child                             |
+//! | a struct deriving `Loro`            | a [`LoroMap`] child with one entry per field     |
+//! | an enum deriving `Loro`             | a [`LoroMap`] child tagged with the variant name |
+//!
+//! Saving only writes the entries that differ from what the container already holds: unchanged
+//! fields produce no ops, text is updated with a diff, and lists of plain values are diffed like
+//! [`LoroList::update_from_value`] does. Child containers of map fields are created with
+//! the `ensure_mergeable_*` methods, so peers that save the same new field concurrently end up
+//! editing the same child.
+use std::{fmt::Debug, marker::PhantomData};
+
+use crate::{
+    value_update::{child_container, update_list, update_text},
+    Container, LoroError, LoroList, LoroMap, LoroResult, LoroText, LoroValue, ValueOrContainer,
+};
+
+/// Derive [`LoroMapped`] for a struct or an enum, together with a typed handle.
+///
+/// For a type `Foo`, the derive also generates `FooHandle`, which wraps the [`LoroMap`] the
+/// value is stored in and implements [`MappedHandle`]. For structs, the handle has one accessor
+/// per field that returns the field's [`LoroType::Handle`]: [`ValueHandle`] for plain values,
+/// [`ListHandle`] for `Vec`s, [`LoroText`] for `#[loro(text)]` fields and the generated handle
+/// for nested types.
+///
+/// Field attributes:
+/// - `#[loro(rename = "key")]`: store the field under another key.
+/// - `#[loro(text)]`: store a `String` field as a [`LoroText`].
+/// - `#[loro(default)]`: use [`Default::default`] when the key is missing.
+/// - `#[loro(skip)]`: don't store the field; it's [`Default::default`] when loaded.
+///
+/// Enums are stored as maps whose `"type"` entry holds the variant name (override the key with
+/// `#[loro(tag = "kind")]` on the enum and the name with `#[loro(rename = "name")]` on the
+/// variant). Fields of tuple variants are stored under `"0"`, `"1"`, ... Switching to another
+/// variant clears the map before writing the new fields.
+///
+/// # Example
+///
+/// ```
+/// use loro::{typed::MappedHandle, Loro, LoroDoc};
+///
+/// #[derive(Loro, Debug, PartialEq)]
+/// struct Task {
+///     title: String,
+///     #[loro(text)]
+///     notes: String,
+///     done: bool,
+///     tags: Vec<String>,
+///     priority: Option<Priority>,
+/// }
+///
+/// #[derive(Loro, Debug, PartialEq)]
+/// enum Priority {
+///     Low,
+///     High { reason: String },
+/// }
+///
+/// let doc = LoroDoc::new();
+/// let handle = TaskHandle::from(doc.get_map("task"));
+/// let mut task = Task {
+///     title: "Write docs".into(),
+///     notes: "Start with the README".into(),
+///     done: false,
+///     tags: vec!["docs".into()],
+///     priority: Some(Priority::High { reason: "release".into() }),
+/// };
+/// handle.save(&task).unwrap();
+/// doc.commit();
+///
+/// // Only the changed field produces ops
+/// task.done = true;
+/// handle.save(&task).unwrap();
+/// assert_eq!(handle.load().unwrap(), task);
+///
+/// // Accessors return typed handles for each field
+/// handle.tags().unwrap().push(&"v1".to_string()).unwrap();
+/// handle.notes().unwrap().insert(0, "TODO: ").unwrap();
+/// assert_eq!(handle.done().unwrap().get().unwrap(), true);
+/// assert_eq!(handle.load().unwrap().notes, "TODO: Start with the README");
+/// ```
+#[cfg(feature = "derive")]
+pub use loro_derive::Loro;
+
+/// A Rust type that can be stored in a map entry or a list element.
+///
+/// It's implemented for plain values, `Option<T>`, `Vec<T>` and every [`LoroMapped`] type.
+pub trait LoroType: Sized {
+    /// The typed accessor of a map entry holding this type.
+    type Handle;
+
+    /// Read a value from a map entry or a list element.
+    fn from_loro(value: ValueOrContainer) -> LoroResult<Self>;
+
+    /// The value of a map entry that doesn't exist, or `None` if the entry is required.
+    fn missing() -> Option<Self> {
+        None
+    }
+
+    /// Save the value to `map[key]`. Nothing is written if the entry already holds it.
+    fn save_in_map(&self, map: &LoroMap, key: &str) -> LoroResult<()>;
+
+    /// Insert the value as a new list element at `pos`.
+    fn insert_in_list(&self, list: &LoroList, pos: usize) -> LoroResult<()>;
+
+    /// Overwrite the list element `current` at `pos`. Nothing is written if it already holds
+    /// the value.
+    fn update_in_list(
+        &self,
+        list: &LoroList,
+        pos: usize,
+        current: ValueOrContainer,
+    ) -> LoroResult<()>;
+
+    /// The plain value this is stored as, or `None` if it's stored as a container.
+    fn to_plain_value(&self) -> Option<LoroValue> {
+        None
+    }
+
+    /// Get the accessor of `map[key]`, creating the child container if needed.
+    fn handle(map: &LoroMap, key: &str) -> LoroResult<Self::Handle>;
+}
+
+/// A Rust type that is stored as a whole [`LoroMap`].
+///
+/// Implement it with `#[derive(Loro)]`.
+pub trait LoroMapped: Sized {
+    /// The typed handle of a map holding this type.
+    type Handle: MappedHandle<Value = Self>;
+
+    /// Read the value from `map`.
+    fn load(map: &LoroMap) -> LoroResult<Self>;
+
+    /// Write the value to `map`, only touching the entries that changed.
+    fn save(&self, map: &LoroMap) -> LoroResult<()>;
+}
+
+/// A typed wrapper around the [`LoroMap`] a [`LoroMapped`] value is stored in.
+pub trait MappedHandle: From<LoroMap> {
+    /// The type stored in the map.
+    type Value: LoroMapped;
+
+    /// The underlying map.
+    fn map(&self) -> &LoroMap;
+
+    /// Read the value from the map.
+    fn load(&self) -> LoroResult<Self::Value> {
+        Self::Value::load(self.map())
+    }
+
+    /// Write the value to the map, only touching the entries that changed.
+    fn save(&self, value: &Self::Value) -> LoroResult<()> {
+        value.save(self.map())
+    }
+}
+
+/// The accessor of a map entry that holds a plain value.
+pub struct ValueHandle<T> {
+    map: LoroMap,
+    key: String,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Debug for ValueHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ValueHandle")
+            .field("map", &self.map)
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+impl<T> Clone for ValueHandle<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.map.clone(), self.key.clone())
+    }
+}
+
+impl<T> ValueHandle<T> {
+    /// Create the accessor of `map[key]`.
+    pub fn new(map: LoroMap, key: impl Into<String>) -> Self {
+        Self {
+            map,
+            key: key.into(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The map holding the entry.
+    pub fn map(&self) -> &LoroMap {
+        &self.map
+    }
+
+    /// The key of the entry.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl<T: LoroType> ValueHandle<T> {
+    /// Read the value of the entry.
+    pub fn get(&self) -> LoroResult<T> {
+        load_field(&self.map, &self.key)
+    }
+
+    /// Write the value of the entry. Nothing is written if the entry already holds it.
+    pub fn set(&self, value: &T) -> LoroResult<()> {
+        value.save_in_map(&self.map, &self.key)
+    }
+}
+
+/// A typed wrapper around a [`LoroList`] whose elements are `T`.
+pub struct ListHandle<T> {
+    list: LoroList,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Debug for ListHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ListHandle").field(&self.list).finish()
+    }
+}
+
+impl<T> Clone for ListHandle<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.list.clone())
+    }
+}
+
+impl<T> From<LoroList> for ListHandle<T> {
+    fn from(list: LoroList) -> Self {
+        Self::new(list)
+    }
+}
+
+impl<T> ListHandle<T> {
+    /// Wrap `list`.
+    pub fn new(list: LoroList) -> Self {
+        Self {
+            list,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The underlying list.
+    pub fn list(&self) -> &LoroList {
+        &self.list
+    }
+
+    /// The number of elements.
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    /// Whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Delete `len` elements starting at `pos`.
+    pub fn delete(&self, pos: usize, len: usize) -> LoroResult<()> {
+        self.list.delete(pos, len)
+    }
+}
+
+impl<T: LoroType> ListHandle<T> {
+    /// Read the element at `index`, or `None` if it's out of bound.
+    pub fn get(&self, index: usize) -> LoroResult<Option<T>> {
+        self.list.get(index).map(T::from_loro).transpose()
+    }
+
+    /// Overwrite the element at `index`. Nothing is written if it already holds `value`.
+    pub fn set(&self, index: usize, value: &T) -> LoroResult<()> {
+        let current = self.list.get(index).ok_or_else(|| LoroError::OutOfBound {
+            pos: index,
+            len: self.list.len(),
+            info: format!("Position: {}:{}", file!(), line!()).into_boxed_str(),
+        })?;
+        value.update_in_list(&self.list, index, current)
+    }
+
+    /// Insert `value` at `pos`.
+    pub fn insert(&self, pos: usize, value: &T) -> LoroResult<()> {
+        value.insert_in_list(&self.list, pos)
+    }
+
+    /// Append `value` to the end of the list.
+    pub fn push(&self, value: &T) -> LoroResult<()> {
+        value.insert_in_list(&self.list, self.list.len())
+    }
+
+    /// Read all the elements.
+    pub fn load(&self) -> LoroResult<Vec<T>> {
+        load_list(&self.list)
+    }
+
+    /// Make the list hold `values`, only touching the elements that changed.
+    pub fn save(&self, values: &[T]) -> LoroResult<()> {
+        save_list(&self.list, values)
+    }
+}
+
+impl<T: LoroMapped> LoroType for T {
+    type Handle = T::Handle;
+
+    fn from_loro(value: ValueOrContainer) -> LoroResult<Self> {
+        match value {
+            ValueOrContainer::Container(Container::Map(map)) => T::load(&map),
+            other => Err(type_mismatch("Map container", &other)),
+        }
+    }
+
+    fn save_in_map(&self, map: &LoroMap, key: &str) -> LoroResult<()> {
+        self.save(&child_container(
+            map,
+            key,
+            LoroMap::ensure_mergeable_map,
+            LoroMap::new,
+        )?)
+    }
+
+    fn insert_in_list(&self, list: &LoroList, pos: usize) -> LoroResult<()> {
+        self.save(&list.insert_container(pos, LoroMap::new())?)
+    }
+
+    fn update_in_list(
+        &self,
+        list: &LoroList,
+        pos: usize,
+        current: ValueOrContainer,
+    ) -> LoroResult<()> {
+        match current {
+            ValueOrContainer::Container(Container::Map(map)) => self.save(&map),
+            _ => {
+                list.delete(pos, 1)?;
+                self.insert_in_list(list, pos)
+            }
+        }
+    }
+
+    fn handle(map: &LoroMap, key: &str) -> LoroResult<Self::Handle> {
+        child_container(map, key, LoroMap::ensure_mergeable_map, LoroMap::new).map(Into::into)
+    }
+}
+
+impl<T: LoroType> LoroType for Vec<T> {
+    type Handle = ListHandle<T>;
+
+    fn from_loro(value: ValueOrContainer) -> LoroResult<Self> {
+        match value {
+            ValueOrContainer::Container(Container::List(list)) => load_list(&list),
+            other => Err(type_mismatch("List container", &other)),
+        }
+    }
+
+    fn save_in_map(&self, map: &LoroMap, key: &str) -> LoroResult<()> {
+        save_list(
+            &child_container(map, key, LoroMap::ensure_mergeable_list, LoroList::new)?,
+            self,
+        )
+    }
+
+    fn insert_in_list(&self, list: &LoroList, pos: usize) -> LoroResult<()> {
+        save_list(&list.insert_container(pos, LoroList::new())?, self)
+    }
+
+    fn update_in_list(
+        &self,
+        list: &LoroList,
+        pos: usize,
+        current: ValueOrContainer,
+    ) -> LoroResult<()> {
+        match current {
+            ValueOrContainer::Container(Container::List(child)) => save_list(&child, self),
+            _ => {
+                list.delete(pos, 1)?;
+                self.insert_in_list(list, pos)
+            }
+        }
+    }
+
+    fn handle(map: &LoroMap, key: &str) -> LoroResult<Self::Handle> {
+        child_container(map, key, LoroMap::ensure_mergeable_list, LoroList::new).map(Into::into)
+    }
+}
+
+impl<T: LoroType> LoroType for Option<T> {
+    type Handle = ValueHandle<Option<T>>;
+
+    fn from_loro(value: ValueOrContainer) -> LoroResult<Self> {
+        match value {
+            ValueOrContainer::Value(LoroValue::Null) => Ok(None),
+            other => T::from_loro(other).map(Some),
+        }
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+
+    fn save_in_map(&self, map: &LoroMap, key: &str) -> LoroResult<()> {
+        match self {
+            Some(value) => value.save_in_map(map, key),
+            None => match map.get(key) {
+                None | Some(ValueOrContainer::Value(LoroValue::Null)) => Ok(()),
+                Some(_) => map.delete(key),
+            },
+        }
+    }
+
+    fn insert_in_list(&self, list: &LoroList, pos: usize) -> LoroResult<()> {
+        match self {
+            Some(value) => value.insert_in_list(list, pos),
+            None => list.insert(pos, LoroValue::Null),
+        }
+    }
+
+    fn update_in_list(
+        &self,
+        list: &LoroList,
+        pos: usize,
+        current: ValueOrContainer,
+    ) -> LoroResult<()> {
+        match (self, current) {
+            (None, ValueOrContainer::Value(LoroValue::Null)) => Ok(()),
+            (Some(value), ValueOrContainer::Value(LoroValue::Null)) => {
+                list.delete(pos, 1)?;
+                value.insert_in_list(list, pos)
+            }
+            (Some(value), current) => value.update_in_list(list, pos, current),
+            (None, _) => {
+                list.delete(pos, 1)?;
+                list.insert(pos, LoroValue::Null)
+            }
+        }
+    }
+
+    fn to_plain_value(&self) -> Option<LoroValue> {
+        match self {
+            Some(value) => value.to_plain_value(),
+            None => Some(LoroValue::Null),
+        }
+    }
+
+    fn handle(map: &LoroMap, key: &str) -> LoroResult<Self::Handle> {
+        Ok(ValueHandle::new(map.clone(), key))
+    }
+}
+
+macro_rules! impl_value_type {
+    ($($ty:ty => $expected:literal, |$this:ident| $to:expr, |$value:ident| $from:expr;)*) => {
+        $(
+            impl LoroType for $ty {
+                type Handle = ValueHandle<$ty>;
+
+                fn from_loro(value: ValueOrContainer) -> LoroResult<Self> {
+                    let ans = match &value {
+                        ValueOrContainer::Value($value) => $from,
+                        ValueOrContainer::Container(_) => None,
+                    };
+                    ans.ok_or_else(|| type_mismatch($expected, &value))
+                }
+
+                fn save_in_map(&self, map: &LoroMap, key: &str) -> LoroResult<()> {
+                    let value = plain_value(self, $expected)?;
+                    if let Some(ValueOrContainer::Value(current)) = map.get(key) {
+                        if current == value {
+                            return Ok(());
+                        }
+                    }
+                    map.insert(key, value)
+                }
+
+                fn insert_in_list(&self, list: &LoroList, pos: usize) -> LoroResult<()> {
+                    list.insert(pos, plain_value(self, $expected)?)
+                }
+
+                fn update_in_list(
+                    &self,
+                    list: &LoroList,
+                    pos: usize,
+                    current: ValueOrContainer,
+                ) -> LoroResult<()> {
+                    let value = plain_value(self, $expected)?;
+                    if matches!(&current, ValueOrContainer::Value(v) if *v == value) {
+                        return Ok(());
+                    }
+                    list.delete(pos, 1)?;
+                    list.insert(pos, value)
+                }
+
+                fn to_plain_value(&self) -> Option<LoroValue> {
+                    let $this = self;
+                    $to
+                }
+
+                fn handle(map: &LoroMap, key: &str) -> LoroResult<Self::Handle> {
+                    Ok(ValueHandle::new(map.clone(), key))
+                }
+            }
+        )*
+    };
+}
+
+impl_value_type! {
+    bool => "bool", |v| Some(LoroValue::Bool(*v)), |v| v.as_bool().copied();
+    i8 => "int", |v| Some(LoroValue::I64(*v as i64)), |v| v.as_i64().and_then(|v| (*v).try_into().ok());
+    i16 => "int", |v| Some(LoroValue::I64(*v as i64)), |v| v.as_i64().and_then(|v| (*v).try_into().ok());
+    i32 => "int", |v| Some(LoroValue::I64(*v as i64)), |v| v.as_i64().and_then(|v| (*v).try_into().ok());
+    i64 => "int", |v| Some(LoroValue::I64(*v)), |v| v.as_i64().copied();
+    isize => "int", |v| Some(LoroValue::I64(*v as i64)), |v| v.as_i64().and_then(|v| (*v).try_into().ok());
+    u8 => "int", |v| Some(LoroValue::I64(*v as i64)), |v| v.as_i64().and_then(|v| (*v).try_into().ok());
+    u16 => "int", |v| Some(LoroValue::I64(*v as i64)), |v| v.as_i64().and_then(|v| (*v).try_into().ok());
+    u32 => "int", |v| Some(LoroValue::I64(*v as i64)), |v| v.as_i64().and_then(|v| (*v).try_into().ok());
+    u64 => "int", |v| i64::try_from(*v).ok().map(LoroValue::I64), |v| v.as_i64().and_then(|v| (*v).try_into().ok());
+    usize => "int", |v| i64::try_from(*v).ok().map(LoroValue::I64), |v| v.as_i64().and_then(|v| (*v).try_into().ok());
+    f32 => "double", |v| Some(LoroValue::Double(*v as f64)), |v| number(v).map(|v| v as f32);
+    f64 => "double", |v| Some(LoroValue::Double(*v)), |v| number(v);
+    String => "string", |v| Some(LoroValue::from(v.as_str())), |v| v.as_string().map(|s| s.to_string());
+    LoroValue => "value", |v| Some(v.clone()), |v| Some(v.clone());
+}
+
+/// Doubles are also read from ints, since JSON-like sources don't keep them apart.
+fn number(value: &LoroValue) -> Option<f64> {
+    match value {
+        LoroValue::Double(v) => Some(*v),
+        LoroValue::I64(v) => Some(*v as f64),
+        _ => None,
+    }
+}
+
+fn plain_value<T: LoroType>(value: &T, expected: &str) -> LoroResult<LoroValue> {
+    value.to_plain_value().ok_or_else(|| {
+        LoroError::ArgErr(format!("The value cannot be stored as {expected}").into_boxed_str())
+    })
+}
+
+/// Read `map[key]`. Used by the code generated by `#[derive(Loro)]`.
+pub fn load_field<T: LoroType>(map: &LoroMap, key: &str) -> LoroResult<T> {
+    match map.get(key) {
+        Some(value) => T::from_loro(value).map_err(|e| match e {
+            LoroError::DecodeError(msg) => {
+                LoroError::DecodeError(format!("field {key:?}: {msg}").into_boxed_str())
+            }
+            e => e,
+        }),
+        None => T::missing()
+            .ok_or_else(|| LoroError::NotFoundError(format!("field {key:?}").into_boxed_str())),
+    }
+}
+
+/// Read `map[key]`, or the default value if the entry doesn't exist. Used by the code generated
+/// for `#[loro(default)]` fields.
+pub fn load_field_or_default<T: LoroType + Default>(map: &LoroMap, key: &str) -> LoroResult<T> {
+    if map.get(key).is_none() {
+        return Ok(T::default());
+    }
+    load_field(map, key)
+}
+
+/// Read the [`LoroText`] at `map[key]` as a string. Used by the code generated for
+/// `#[loro(text)]` fields.
+pub fn load_text(map: &LoroMap, key: &str) -> LoroResult<String> {
+    match map.get(key) {
+        Some(ValueOrContainer::Container(Container::Text(text))) => Ok(text.to_string()),
+        Some(ValueOrContainer::Value(LoroValue::String(s))) => Ok(s.to_string()),
+        Some(other) => Err(LoroError::DecodeError(
+            format!(
+                "field {key:?}: {}",
+                type_mismatch_msg("Text container", &other)
+            )
+            .into_boxed_str(),
+        )),
+        None => Err(LoroError::NotFoundError(
+            format!("field {key:?}").into_boxed_str(),
+        )),
+    }
+}
+
+/// Make the [`LoroText`] at `map[key]` hold `value`, applying the difference as text edits.
+/// Used by the code generated for `#[loro(text)]` fields.
+pub fn save_text(map: &LoroMap, key: &str, value: &str) -> LoroResult<()> {
+    update_text(&text_handle(map, key)?, value)
+}
+
+/// Get the [`LoroText`] at `map[key]`, creating it if needed. Used by the code generated for
+/// `#[loro(text)]` fields.
+pub fn text_handle(map: &LoroMap, key: &str) -> LoroResult<LoroText> {
+    child_container(map, key, LoroMap::ensure_mergeable_text, LoroText::new)
+}
+
+/// Read the variant tag of an enum stored in `map`. Used by the code generated for enums.
+pub fn load_tag(map: &LoroMap, tag: &str) -> LoroResult<String> {
+    load_field(map, tag)
+}
+
+/// Write the variant tag of an enum stored in `map`. The map is cleared first if it holds
+/// another variant, so fields of the old variant don't linger. Used by the code generated for
+/// enums.
+pub fn save_tag(map: &LoroMap, tag: &str, variant: &str) -> LoroResult<()> {
+    match map.get(tag) {
+        Some(ValueOrContainer::Value(LoroValue::String(current))) if *current == *variant => {
+            return Ok(());
+        }
+        None if map.is_empty() => {}
+        _ => map.clear()?,
+    }
+    map.insert(tag, variant)
+}
+
+/// The error returned when an enum stored in a map has an unknown variant tag.
+pub fn unknown_variant(ty: &str, variant: &str) -> LoroError {
+    LoroError::DecodeError(format!("unknown variant {variant:?} of {ty}").into_boxed_str())
+}
+
+fn load_list<T: LoroType>(list: &LoroList) -> LoroResult<Vec<T>> {
+    (0..list.len())
+        .map(|i| {
+            T::from_loro(list.get(i).unwrap()).map_err(|e| match e {
+                LoroError::DecodeError(msg) => {
+                    LoroError::DecodeError(format!("element {i}: {msg}").into_boxed_str())
+                }
+                e => e,
+            })
+        })
+        .collect()
+}
+
+/// Lists of plain values are diffed with a longest common subsequence, so inserting or removing
+/// elements doesn't rewrite the rest of the list. Other lists are updated
+/// element by element, which keeps the identity of child containers at the same index.
+fn save_list<T: LoroType>(list: &LoroList, values: &[T]) -> LoroResult<()> {
+    if let Some(new) = values
+        .iter()
+        .map(T::to_plain_value)
+        .collect::<Option<Vec<_>>>()
+    {
+        return update_list(list, &LoroValue::List(new.into()));
+    }
+
+    let len = list.len();
+    for (i, value) in values.iter().enumerate().take(len) {
+        value.update_in_list(list, i, list.get(i).unwrap())?;
+    }
+    if len > values.len() {
+        list.delete(values.len(), len - values.len())?;
+    }
+    for (i, value) in values.iter().enumerate().skip(len) {
+        value.insert_in_list(list, i)?;
+    }
+    Ok(())
+}
+
+fn type_mismatch(expected: &str, found: &ValueOrContainer) -> LoroError {
+    LoroError::DecodeError(type_mismatch_msg(expected, found).into_boxed_str())
+}
+
+pub(crate) fn type_mismatch_msg(expected: &str, found: &ValueOrContainer) -> String {
+    format!("expected {expected}, found {}", describe(found))
+}
+
+/// The type of `value`, as named in type mismatch errors.
+pub(crate) fn describe(value: &ValueOrContainer) -> String {
+    match value {
+        ValueOrContainer::Value(v) => match v {
+            LoroValue::Null => "null".into(),
+            LoroValue::Bool(_) => "bool".into(),
+            LoroValue::Double(_) => "double".into(),
+            LoroValue::I64(_) => "int".into(),
+            LoroValue::Binary(_) => "binary".into(),
+            LoroValue::String(_) => "string".into(),
+            LoroValue::List(_) => "list value".into(),
+            LoroValue::Map(_) => "map value".into(),
+            LoroValue::Container(id) => format!("{} container", id.container_type()),
+        },
+        ValueOrContainer::Container(c) => format!("{} container", c.get_type()),
+    }
+}
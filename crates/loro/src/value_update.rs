@@ -0,0 +1,300 @@
+//! Minimal-diff reconciliation of containers against plain values.
+//!
+//! This backs [`LoroMap::update_from_value`], [`LoroList::update_from_value`] and
+//! [`LoroMovableList::update_from_value`], and is shared by the serde and typed bindings.
+use crate::{
+    Container, ContainerTrait, LoroError, LoroList, LoroMap, LoroMovableList, LoroResult, LoroText,
+    LoroValue, UpdateOptions, ValueOrContainer,
+};
+
+/// Lists whose changed middle part would need a larger LCS table than this are updated element
+/// by element instead.
+const MAX_LCS_CELLS: usize = 1 << 22;
+
+pub(crate) fn update_map(map: &LoroMap, value: &LoroValue) -> LoroResult<()> {
+    let LoroValue::Map(value) = value else {
+        return Err(expected("map", value));
+    };
+    let stale: Vec<String> = map
+        .keys()
+        .filter(|k| !value.contains_key(k.as_str()))
+        .map(|k| k.to_string())
+        .collect();
+    for key in stale {
+        map.delete(&key)?;
+    }
+    let mut entries: Vec<_> = value.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in entries {
+        update_map_entry(map, key, value)?;
+    }
+    Ok(())
+}
+
+fn update_map_entry(map: &LoroMap, key: &str, value: &LoroValue) -> LoroResult<()> {
+    check_insertable(value)?;
+    match (map.get(key), value) {
+        (Some(ValueOrContainer::Container(Container::Text(text))), LoroValue::String(s)) => {
+            update_text(&text, s)
+        }
+        (Some(ValueOrContainer::Container(Container::MovableList(list))), LoroValue::List(_)) => {
+            update_movable_list(&list, value)
+        }
+        (_, LoroValue::Map(_)) => update_map(
+            &child_container(map, key, LoroMap::ensure_mergeable_map, LoroMap::new)?,
+            value,
+        ),
+        (_, LoroValue::List(_)) => update_list(
+            &child_container(map, key, LoroMap::ensure_mergeable_list, LoroList::new)?,
+            value,
+        ),
+        (Some(ValueOrContainer::Value(current)), value) if current == *value => Ok(()),
+        (_, value) => map.insert(key, value.clone()),
+    }
+}
+
+pub(crate) fn update_text(text: &LoroText, value: &str) -> LoroResult<()> {
+    if text.to_string() == value {
+        return Ok(());
+    }
+    text.update(value, UpdateOptions::default())
+        .map_err(|_| LoroError::Unknown("Text diff timed out".into()))
+}
+
+pub(crate) fn update_list(list: &LoroList, value: &LoroValue) -> LoroResult<()> {
+    update_sequence(list, value)
+}
+
+pub(crate) fn update_movable_list(list: &LoroMovableList, value: &LoroValue) -> LoroResult<()> {
+    update_sequence(list, value)
+}
+
+/// The list operations the LCS walk needs. Lists replace an element by deleting and inserting
+/// it, movable lists use `set` so the element keeps its position among concurrent moves.
+trait Sequence {
+    fn deep_value(&self) -> LoroValue;
+    fn get(&self, pos: usize) -> Option<ValueOrContainer>;
+    fn delete(&self, pos: usize, len: usize) -> LoroResult<()>;
+    fn insert(&self, pos: usize, value: &LoroValue) -> LoroResult<()>;
+    fn replace(&self, pos: usize, value: &LoroValue) -> LoroResult<()>;
+}
+
+impl Sequence for LoroList {
+    fn deep_value(&self) -> LoroValue {
+        self.get_deep_value()
+    }
+
+    fn get(&self, pos: usize) -> Option<ValueOrContainer> {
+        LoroList::get(self, pos)
+    }
+
+    fn delete(&self, pos: usize, len: usize) -> LoroResult<()> {
+        LoroList::delete(self, pos, len)
+    }
+
+    fn insert(&self, pos: usize, value: &LoroValue) -> LoroResult<()> {
+        match value {
+            LoroValue::Map(_) => update_map(&self.insert_container(pos, LoroMap::new())?, value),
+            LoroValue::List(_) => update_list(&self.insert_container(pos, LoroList::new())?, value),
+            value => LoroList::insert(self, pos, value.clone()),
+        }
+    }
+
+    fn replace(&self, pos: usize, value: &LoroValue) -> LoroResult<()> {
+        LoroList::delete(self, pos, 1)?;
+        Sequence::insert(self, pos, value)
+    }
+}
+
+impl Sequence for LoroMovableList {
+    fn deep_value(&self) -> LoroValue {
+        self.get_deep_value()
+    }
+
+    fn get(&self, pos: usize) -> Option<ValueOrContainer> {
+        LoroMovableList::get(self, pos)
+    }
+
+    fn delete(&self, pos: usize, len: usize) -> LoroResult<()> {
+        LoroMovableList::delete(self, pos, len)
+    }
+
+    fn insert(&self, pos: usize, value: &LoroValue) -> LoroResult<()> {
+        match value {
+            LoroValue::Map(_) => update_map(&self.insert_container(pos, LoroMap::new())?, value),
+            LoroValue::List(_) => update_list(&self.insert_container(pos, LoroList::new())?, value),
+            value => LoroMovableList::insert(self, pos, value.clone()),
+        }
+    }
+
+    fn replace(&self, pos: usize, value: &LoroValue) -> LoroResult<()> {
+        match value {
+            LoroValue::Map(_) => update_map(&self.set_container(pos, LoroMap::new())?, value),
+            LoroValue::List(_) => update_list(&self.set_container(pos, LoroList::new())?, value),
+            value => self.set(pos, value.clone()),
+        }
+    }
+}
+
+/// Elements that are equal in the old and new list are kept, using their longest common
+/// subsequence. Between two kept elements, removed and added elements are paired up by position:
+/// a pair whose old element is a container of the matching kind is updated recursively, and any
+/// other pair is replaced. The rest of the removed elements are deleted and the rest of the added
+/// ones inserted, so the emitted ops follow the retain/delete/insert shape of a list diff.
+fn update_sequence<S: Sequence>(list: &S, value: &LoroValue) -> LoroResult<()> {
+    let LoroValue::List(new) = value else {
+        return Err(expected("list", value));
+    };
+    new.iter().try_for_each(check_insertable)?;
+    let LoroValue::List(old) = list.deep_value() else {
+        unreachable!()
+    };
+
+    let mut pos = 0;
+    let (mut i, mut j) = (0, 0);
+    let kept = lcs(&old, new);
+    for (old_end, new_end) in kept.into_iter().chain([(old.len(), new.len())]) {
+        let paired = (old_end - i).min(new_end - j);
+        for value in &new[j..j + paired] {
+            update_element(list, pos, value)?;
+            pos += 1;
+        }
+        if old_end - i > paired {
+            list.delete(pos, old_end - i - paired)?;
+        }
+        for value in &new[j + paired..new_end] {
+            list.insert(pos, value)?;
+            pos += 1;
+        }
+        // Skip the kept element
+        pos += 1;
+        i = old_end + 1;
+        j = new_end + 1;
+    }
+    Ok(())
+}
+
+fn update_element<S: Sequence>(list: &S, pos: usize, value: &LoroValue) -> LoroResult<()> {
+    match (list.get(pos), value) {
+        (Some(ValueOrContainer::Container(Container::Map(map))), LoroValue::Map(_)) => {
+            update_map(&map, value)
+        }
+        (Some(ValueOrContainer::Container(Container::List(child))), LoroValue::List(_)) => {
+            update_list(&child, value)
+        }
+        (Some(ValueOrContainer::Container(Container::MovableList(child))), LoroValue::List(_)) => {
+            update_movable_list(&child, value)
+        }
+        (Some(ValueOrContainer::Container(Container::Text(text))), LoroValue::String(s)) => {
+            update_text(&text, s)
+        }
+        (Some(ValueOrContainer::Value(current)), value) if current == *value => Ok(()),
+        _ => list.replace(pos, value),
+    }
+}
+
+/// The index pairs of a longest common subsequence of `old` and `new`, in increasing order.
+///
+/// The common prefix and suffix are matched directly; only the part between them goes through
+/// the quadratic LCS table, and only if it is small enough.
+fn lcs(old: &[LoroValue], new: &[LoroValue]) -> Vec<(usize, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ans: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    let (n, m) = (old_mid.len(), new_mid.len());
+    if n > 0 && m > 0 && n * m <= MAX_LCS_CELLS {
+        // table[i][j] is the LCS length of old_mid[i..] and new_mid[j..]
+        let width = m + 1;
+        let mut table = vec![0u32; (n + 1) * width];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                table[i * width + j] = if old_mid[i] == new_mid[j] {
+                    table[(i + 1) * width + j + 1] + 1
+                } else {
+                    table[(i + 1) * width + j].max(table[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if old_mid[i] == new_mid[j] {
+                ans.push((prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+    ans.extend((0..suffix).map(|k| (old.len() - suffix + k, new.len() - suffix + k)));
+    ans
+}
+
+/// Get the child container of kind `C` at `map[key]`, replacing whatever else is there.
+///
+/// New children of attached maps are mergeable, so concurrent updates of the same entry merge.
+/// Detached maps can't hold mergeable children yet and get a regular child instead.
+pub(crate) fn child_container<C: ContainerTrait>(
+    map: &LoroMap,
+    key: &str,
+    ensure: impl FnOnce(&LoroMap, &str) -> LoroResult<C>,
+    new: impl FnOnce() -> C,
+) -> LoroResult<C> {
+    match map.get(key) {
+        Some(ValueOrContainer::Container(c)) => {
+            if let Some(c) = C::try_from_container(c) {
+                return Ok(c);
+            }
+            map.delete(key)?;
+        }
+        Some(ValueOrContainer::Value(_)) => map.delete(key)?,
+        None => {}
+    }
+
+    if map.is_attached() {
+        ensure(map, key)
+    } else {
+        map.insert_container(key, new())
+    }
+}
+
+/// Container ids can't be written as values: the container they point to would not become a
+/// child of the updated one.
+fn check_insertable(value: &LoroValue) -> LoroResult<()> {
+    match value {
+        LoroValue::Container(id) => Err(LoroError::ArgErr(
+            format!("Cannot update a container with the container id {id}").into_boxed_str(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn expected(kind: &str, value: &LoroValue) -> LoroError {
+    LoroError::ArgErr(
+        format!("Expected a {kind} value, found {}", value_kind(value)).into_boxed_str(),
+    )
+}
+
+fn value_kind(value: &LoroValue) -> &'static str {
+    match value {
+        LoroValue::Null => "null",
+        LoroValue::Bool(_) => "bool",
+        LoroValue::Double(_) => "double",
+        LoroValue::I64(_) => "int",
+        LoroValue::Binary(_) => "binary",
+        LoroValue::String(_) => "string",
+        LoroValue::List(_) => "list",
+        LoroValue::Map(_) => "map",
+        LoroValue::Container(_) => "container",
+    }
+}
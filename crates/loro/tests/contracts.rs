@@ -2,8 +2,16 @@
 mod apply_diff_value;
 #[path = "contracts/awareness.rs"]
 mod awareness;
+#[path = "contracts/batch_move.rs"]
+mod batch_move;
+#[path = "contracts/branches.rs"]
+mod branches;
+#[path = "contracts/change_query.rs"]
+mod change_query;
 #[path = "contracts/change_store_large_blocks.rs"]
 mod change_store_large_blocks;
+#[path = "contracts/compact_history.rs"]
+mod compact_history;
 #[path = "contracts/container_enum.rs"]
 mod container_enum;
 #[path = "contracts/container_handlers.rs"]
@@ -14,6 +22,8 @@ mod counter;
 mod cursor_recovery;
 #[path = "contracts/detached_containers.rs"]
 mod detached_containers;
+#[path = "contracts/diff_summary.rs"]
+mod diff_summary;
 #[path = "contracts/doc_analysis.rs"]
 mod doc_analysis;
 #[path = "contracts/doc_export.rs"]
@@ -22,28 +32,52 @@ mod doc_export;
 mod doc_lifecycle;
 #[path = "contracts/events_subscriptions.rs"]
 mod events_subscriptions;
+#[path = "contracts/grid.rs"]
+mod grid;
 #[path = "contracts/handler_edges.rs"]
 mod handler_edges;
 #[path = "contracts/history_shallow.rs"]
 mod history_shallow;
 #[path = "contracts/jsonpath_advanced.rs"]
 mod jsonpath_advanced;
+#[path = "contracts/jsonpath_diff.rs"]
+mod jsonpath_diff;
 #[path = "contracts/jsonpath_functions.rs"]
 mod jsonpath_functions;
 #[path = "contracts/jsonpath_paths.rs"]
 mod jsonpath_paths;
 #[path = "contracts/jsonpath_value.rs"]
 mod jsonpath_value;
+#[path = "contracts/jsonpath_write.rs"]
+mod jsonpath_write;
+#[path = "contracts/list_key_index.rs"]
+mod list_key_index;
 #[path = "contracts/list_movable_boundary.rs"]
 mod list_movable_boundary;
+#[path = "contracts/list_range_ops.rs"]
+mod list_range_ops;
+#[path = "contracts/map_conflicts.rs"]
+mod map_conflicts;
+#[path = "contracts/map_range.rs"]
+mod map_range;
 #[path = "contracts/movable_list_diff_apply.rs"]
 mod movable_list_diff_apply;
+#[path = "contracts/revert_changes.rs"]
+mod revert_changes;
+#[path = "contracts/schema.rs"]
+mod schema;
+#[path = "contracts/serde_container.rs"]
+mod serde_container;
+#[path = "contracts/set_container.rs"]
+mod set_container;
 #[path = "contracts/smoke.rs"]
 mod smoke;
 #[path = "contracts/storage_encoding.rs"]
 mod storage_encoding;
 #[path = "contracts/sync_import.rs"]
 mod sync_import;
+#[path = "contracts/tags.rs"]
+mod tags;
 #[path = "contracts/text_handler_semantics.rs"]
 mod text_handler_semantics;
 #[path = "contracts/text_large_import_diff.rs"]
@@ -60,14 +94,28 @@ mod tree_advanced;
 mod tree_concurrent_delete_move;
 #[path = "contracts/tree_edges.rs"]
 mod tree_edges;
+#[path = "contracts/tree_fractional_index_rebalance.rs"]
+mod tree_fractional_index_rebalance;
 #[path = "contracts/tree_history_semantics.rs"]
 mod tree_history_semantics;
 #[path = "contracts/tree_many_siblings.rs"]
 mod tree_many_siblings;
 #[path = "contracts/tree_movable.rs"]
 mod tree_movable;
+#[path = "contracts/tree_nested_value.rs"]
+mod tree_nested_value;
 #[path = "contracts/tree_position.rs"]
 mod tree_position;
+#[path = "contracts/typed_counter.rs"]
+mod typed_counter;
+#[path = "contracts/typed_derive.rs"]
+mod typed_derive;
+#[path = "contracts/undo_scope.rs"]
+mod undo_scope;
+#[path = "contracts/undo_state.rs"]
+mod undo_state;
+#[path = "contracts/update_from_value.rs"]
+mod update_from_value;
 #[path = "contracts/value_conversion.rs"]
 mod value_conversion;
 #[path = "contracts/value_diff.rs"]
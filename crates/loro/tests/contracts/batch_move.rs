@@ -0,0 +1,140 @@
+use loro::{ExportMode, LoroDoc, LoroError, LoroResult, LoroTreeError, ToJson, TreeParentId};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+#[test]
+fn tree_move_many_keeps_relative_order_and_spacing() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let tree = doc.get_tree("tree");
+    tree.enable_fractional_index(0);
+    let parent = tree.create(TreeParentId::Root)?;
+    let nodes = (0..6)
+        .map(|_| tree.create(parent))
+        .collect::<LoroResult<Vec<_>>>()?;
+    let other_root = tree.create(TreeParentId::Root)?;
+
+    // move n4, n1 and a node from another parent between n2 and n3
+    tree.move_many(&[nodes[4], nodes[1], other_root], parent, 2)?;
+    assert_eq!(
+        tree.children(parent).unwrap(),
+        vec![nodes[0], nodes[2], nodes[4], nodes[1], other_root, nodes[3], nodes[5]]
+    );
+    assert_eq!(tree.roots(), vec![parent]);
+
+    let positions: Vec<_> = tree
+        .children(parent)
+        .unwrap()
+        .into_iter()
+        .map(|id| tree.fractional_index(id).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    Ok(())
+}
+
+#[test]
+fn tree_move_many_rejects_invalid_targets_without_moving() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let tree = doc.get_tree("tree");
+    tree.enable_fractional_index(0);
+    let a = tree.create(TreeParentId::Root)?;
+    let b = tree.create(TreeParentId::Root)?;
+    let child = tree.create(a)?;
+    doc.commit();
+    let ops = doc.len_ops();
+
+    assert!(matches!(
+        tree.move_many(&[b, a], child, 0),
+        Err(LoroError::TreeError(LoroTreeError::CyclicMoveError))
+    ));
+    assert!(matches!(
+        tree.move_many(&[b, b], a, 0),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(matches!(
+        tree.move_many(&[b], a, 2),
+        Err(LoroError::TreeError(LoroTreeError::IndexOutOfBound { .. }))
+    ));
+    doc.commit();
+    assert_eq!(doc.len_ops(), ops);
+    assert_eq!(tree.roots(), vec![a, b]);
+    Ok(())
+}
+
+#[test]
+fn concurrent_tree_move_many_converges_and_keeps_relative_order() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let tree_a = doc_a.get_tree("tree");
+    tree_a.enable_fractional_index(0);
+    let nodes = (0..6)
+        .map(|_| tree_a.create(TreeParentId::Root))
+        .collect::<LoroResult<Vec<_>>>()?;
+    doc_a.commit();
+
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_b.import(&doc_a.export(ExportMode::Snapshot)?)?;
+    let tree_b = doc_b.get_tree("tree");
+    tree_b.enable_fractional_index(0);
+
+    tree_a.move_many(&[nodes[4], nodes[5]], TreeParentId::Root, 1)?;
+    tree_b.move_many(&[nodes[2], nodes[3]], TreeParentId::Root, 1)?;
+    doc_a.import(&doc_b.export(ExportMode::all_updates())?)?;
+    doc_b.import(&doc_a.export(ExportMode::all_updates())?)?;
+
+    let roots = tree_a.roots();
+    assert_eq!(roots, tree_b.roots());
+    let pos = |id| roots.iter().position(|x| *x == id).unwrap();
+    assert!(pos(nodes[0]) < pos(nodes[4]));
+    assert!(pos(nodes[4]) < pos(nodes[5]));
+    assert!(pos(nodes[2]) < pos(nodes[3]));
+    assert!(pos(nodes[5]) < pos(nodes[1]));
+    assert!(pos(nodes[3]) < pos(nodes[1]));
+    Ok(())
+}
+
+#[test]
+fn movable_list_mov_range_moves_block_in_both_directions() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_movable_list("list");
+    for (i, v) in ["a", "b", "c", "d", "e", "f"].into_iter().enumerate() {
+        list.insert(i, v)?;
+    }
+
+    list.mov_range(1..3, 3)?;
+    assert_eq!(
+        list.get_deep_value().to_json_value(),
+        json!(["a", "d", "e", "b", "c", "f"])
+    );
+    list.mov_range(3..5, 0)?;
+    assert_eq!(
+        list.get_deep_value().to_json_value(),
+        json!(["b", "c", "a", "d", "e", "f"])
+    );
+    list.mov_range(2..2, 0)?;
+    assert_eq!(
+        list.get_deep_value().to_json_value(),
+        json!(["b", "c", "a", "d", "e", "f"])
+    );
+
+    assert!(matches!(
+        list.mov_range(4..7, 0),
+        Err(LoroError::OutOfBound { .. })
+    ));
+    assert!(matches!(
+        list.mov_range(0..2, 5),
+        Err(LoroError::OutOfBound { .. })
+    ));
+
+    let detached = loro::LoroMovableList::new();
+    for (i, v) in ["a", "b", "c"].into_iter().enumerate() {
+        detached.insert(i, v)?;
+    }
+    detached.mov_range(0..2, 1)?;
+    assert_eq!(
+        detached.get_deep_value().to_json_value(),
+        json!(["c", "a", "b"])
+    );
+    Ok(())
+}
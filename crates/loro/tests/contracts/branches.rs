@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use loro::{
+    DocSchema, ExportMode, LoroDoc, LoroError, LoroResult, MapSchema, ToJson, UndoManager,
+    ValueSchema,
+};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+fn sync(a: &LoroDoc, b: &LoroDoc) -> LoroResult<()> {
+    b.import(&a.export(ExportMode::updates(&b.oplog_vv())).unwrap())?;
+    a.import(&b.export(ExportMode::updates(&a.oplog_vv())).unwrap())?;
+    Ok(())
+}
+
+#[test]
+fn branches_diverge_and_merge_back() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let text = doc.get_text("text");
+    text.insert(0, "Hello")?;
+    doc.commit();
+    let base = doc.state_frontiers();
+    doc.create_branch("main", &base)?;
+    doc.create_branch("feature", &base)?;
+
+    doc.checkout_branch("feature")?;
+    assert_eq!(doc.current_branch().as_deref(), Some("feature"));
+    text.insert(5, " world")?;
+    doc.commit();
+    doc.checkout_branch("main")?;
+    assert_eq!(text.to_string(), "Hello");
+    text.insert(0, "> ")?;
+    doc.commit();
+    assert_eq!(doc.current_branch().as_deref(), Some("main"));
+
+    let merge = doc.merge_branch("feature", "main")?;
+    assert_eq!(merge.base, base);
+    assert_eq!(doc.branch_head("main"), Some(merge.head.clone()));
+    assert_eq!(merge.source_diff.iter().count(), 1);
+    assert_eq!(merge.target_diff.iter().count(), 1);
+    assert_eq!(doc.current_branch().as_deref(), Some("main"));
+    assert_eq!(text.to_string(), "> Hello world");
+
+    // The source branch is left as it was
+    doc.checkout_branch("feature")?;
+    assert_eq!(text.to_string(), "Hello world");
+    doc.checkout_to_latest();
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({ "text": "> Hello world" })
+    );
+    Ok(())
+}
+
+#[test]
+fn branches_sync_with_the_document() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    a.get_map("map").insert("version", 1)?;
+    a.commit();
+    a.create_branch("v1", &a.state_frontiers())?;
+    a.get_map("map").insert("version", 2)?;
+    a.commit();
+
+    let b = LoroDoc::new();
+
+    b.set_peer_id(2)?;
+    sync(&a, &b)?;
+    assert_eq!(b.branches(), a.branches());
+    assert_eq!(
+        b.get_deep_value().to_json_value(),
+        json!({ "map": { "version": 2 } })
+    );
+    b.checkout_branch("v1")?;
+    assert_eq!(
+        b.get_deep_value().to_json_value(),
+        json!({ "map": { "version": 1 } })
+    );
+
+    let restored = LoroDoc::from_snapshot(&a.export(ExportMode::snapshot()).unwrap())?;
+    assert_eq!(restored.branches(), a.branches());
+    Ok(())
+}
+
+#[test]
+fn concurrent_edits_of_a_branch_are_merged_into_its_head() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_text("text").insert(0, "base")?;
+    a.commit();
+    a.create_branch("shared", &a.state_frontiers())?;
+    sync(&a, &b)?;
+
+    a.checkout_branch("shared")?;
+    a.get_text("text").insert(0, "A")?;
+    a.commit();
+    b.checkout_branch("shared")?;
+    b.get_text("text").insert(4, "B")?;
+    b.commit();
+    sync(&a, &b)?;
+
+    let head = a.branch_head("shared").unwrap();
+    assert_eq!(head.len(), 2);
+    assert_eq!(b.branch_head("shared"), Some(head));
+    a.checkout_branch("shared")?;
+    assert_eq!(a.get_text("text").to_string(), "AbaseB");
+    Ok(())
+}
+
+#[test]
+fn leaving_a_branch_stops_moving_its_head() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    doc.get_text("text").insert(0, "a")?;
+    doc.commit();
+    let head = doc.state_frontiers();
+    doc.create_branch("x", &head)?;
+
+    doc.checkout_branch("x")?;
+    doc.checkout_to_latest();
+    assert_eq!(doc.current_branch(), None);
+    doc.get_text("text").insert(1, "b")?;
+    doc.commit();
+    assert_eq!(doc.branch_head("x"), Some(head.clone()));
+
+    assert!(matches!(
+        doc.create_branch("x", &head),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(matches!(
+        doc.checkout_branch("missing"),
+        Err(LoroError::NotFoundError(_))
+    ));
+    doc.delete_branch("x")?;
+    assert!(doc.branches().is_empty());
+    assert!(matches!(
+        doc.delete_branch("x"),
+        Err(LoroError::NotFoundError(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn rejected_commits_keep_the_doc_on_its_branch() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let user = doc.get_map("user");
+    user.insert("age", 1)?;
+    doc.commit();
+    doc.create_branch("b", &doc.state_frontiers())?;
+    doc.checkout_branch("b")?;
+    let schema = DocSchema::new().root("user", MapSchema::new().field("age", ValueSchema::Int));
+    let _sub = doc.enforce_schema(schema, Arc::new(|_| {}))?;
+
+    user.insert("age", 2)?;
+    doc.commit();
+    assert_eq!(doc.current_branch().as_deref(), Some("b"));
+    user.insert("age", "unknown")?;
+    doc.commit();
+    assert_eq!(doc.current_branch().as_deref(), Some("b"));
+    assert_eq!(doc.branch_head("b"), Some(doc.state_frontiers()));
+
+    user.insert("age", 3)?;
+    doc.commit();
+    assert_eq!(doc.current_branch().as_deref(), Some("b"));
+    assert_eq!(doc.branch_head("b"), Some(doc.state_frontiers()));
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({ "user": { "age": 3 } })
+    );
+    Ok(())
+}
+
+#[test]
+fn branches_are_not_undone() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let mut undo = UndoManager::new(&doc);
+    doc.get_text("text").insert(0, "a")?;
+    doc.commit();
+    doc.create_branch("x", &doc.state_frontiers())?;
+    assert_eq!(undo.undo_count(), 1);
+
+    assert!(undo.undo()?);
+    assert_eq!(doc.get_text("text").to_string(), "");
+    assert!(doc.branch_head("x").is_some());
+    Ok(())
+}
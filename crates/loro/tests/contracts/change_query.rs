@@ -0,0 +1,181 @@
+use loro::{
+    ChangeMeta, ChangeOrder, ChangeQuery, CommitOptions, ContainerTrait, ExportMode, LoroDoc,
+    LoroResult,
+};
+use pretty_assertions::assert_eq;
+
+fn sync(a: &LoroDoc, b: &LoroDoc) -> LoroResult<()> {
+    b.import(&a.export(ExportMode::updates(&b.oplog_vv())).unwrap())?;
+    a.import(&b.export(ExportMode::updates(&a.oplog_vv())).unwrap())?;
+    Ok(())
+}
+
+fn commit(doc: &LoroDoc, msg: &str, timestamp: i64) {
+    doc.commit_with(CommitOptions::new().commit_msg(msg).timestamp(timestamp));
+}
+
+fn messages(changes: &[ChangeMeta]) -> Vec<&str> {
+    changes.iter().map(|c| c.message()).collect()
+}
+
+fn all_pages(doc: &LoroDoc, query: ChangeQuery, limit: usize) -> Vec<ChangeMeta> {
+    let mut ans = Vec::new();
+    let mut page = doc.query_changes(&query.clone().limit(limit));
+    loop {
+        assert!(page.changes.len() <= limit);
+        ans.extend(page.changes);
+        let Some(next) = page.next else {
+            return ans;
+        };
+        page = doc.query_changes(&query.clone().limit(limit).after(next));
+    }
+}
+
+#[test]
+fn pages_cover_the_history_newest_first() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    for i in 0..6 {
+        let doc = if i % 3 == 0 { &b } else { &a };
+        doc.get_text("text").insert(0, "x")?;
+        commit(doc, &format!("edit {i}"), i);
+        if i % 2 == 0 {
+            sync(&a, &b)?;
+        }
+    }
+    sync(&a, &b)?;
+
+    let all = a.query_changes(&ChangeQuery::new()).changes;
+    assert_eq!(all.len(), 6);
+    let key = |c: &ChangeMeta| (c.lamport + c.len as u32, c.id.peer);
+    assert!(all.windows(2).all(|w| key(&w[0]) > key(&w[1])));
+    for limit in 1..=4 {
+        assert_eq!(all_pages(&a, ChangeQuery::new(), limit), all);
+    }
+    assert_eq!(b.query_changes(&ChangeQuery::new()).changes, all);
+    Ok(())
+}
+
+#[test]
+fn timestamp_order_differs_from_causal_order() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_text("text").insert(0, "a")?;
+    commit(&a, "a1", 10);
+    a.get_text("text").insert(0, "a")?;
+    commit(&a, "a2", 20);
+    b.get_text("text").insert(0, "bbbbbbbbbb")?;
+    commit(&b, "b1", 15);
+    sync(&a, &b)?;
+
+    let causal = a.query_changes(&ChangeQuery::new()).changes;
+    assert_eq!(messages(&causal), vec!["b1", "a2", "a1"]);
+    let query = ChangeQuery::new().order(ChangeOrder::Timestamp);
+    let by_time = a.query_changes(&query).changes;
+    assert_eq!(messages(&by_time), vec!["a2", "b1", "a1"]);
+    assert_eq!(all_pages(&a, query.clone(), 1), by_time);
+
+    let in_range = a.query_changes(&query.clone().time_range(12..20)).changes;
+    assert_eq!(messages(&in_range), vec!["b1"]);
+    assert_eq!(all_pages(&a, query.clone().time_range(10..20), 1).len(), 2);
+    assert_eq!(
+        messages(&a.query_changes(&query.clone().peer(1)).changes),
+        vec!["a2", "a1"]
+    );
+    Ok(())
+}
+
+#[test]
+fn timestamp_order_catches_up_with_new_changes() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_text("text").insert(0, "a")?;
+    commit(&a, "a1", 10);
+    let query = ChangeQuery::new().order(ChangeOrder::Timestamp);
+    assert_eq!(messages(&a.query_changes(&query).changes), vec!["a1"]);
+
+    // Both a new change and a commit merged into the last change are indexed
+    a.get_text("text").insert(0, "a")?;
+    commit(&a, "a1", 10);
+    b.get_text("text").insert(0, "b")?;
+    commit(&b, "b1", 5);
+    a.import(&b.export(ExportMode::all_updates()).unwrap())?;
+    a.get_text("text").insert(0, "a")?;
+    commit(&a, "a2", 30);
+    let changes = a.query_changes(&query).changes;
+    assert_eq!(messages(&changes), vec!["a2", "a1", "b1"]);
+    assert_eq!(changes[1].len, 2);
+    assert_eq!(all_pages(&a, query, 1), changes);
+    Ok(())
+}
+
+#[test]
+fn filters_by_peer_message_and_container() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_text("title").insert(0, "Title")?;
+    commit(&a, "fix: title", 1);
+    a.get_map("meta").insert("author", "a")?;
+    commit(&a, "feat: author", 2);
+    b.get_text("body").insert(0, "Body")?;
+    commit(&b, "feat: body", 3);
+    a.import(&b.export(ExportMode::updates(&a.oplog_vv())).unwrap())?;
+
+    let query = |q: ChangeQuery| messages(&a.query_changes(&q).changes).join(", ");
+    assert_eq!(
+        query(ChangeQuery::new().peer(1)),
+        "feat: author, fix: title"
+    );
+    assert_eq!(
+        query(ChangeQuery::new().message_contains("feat")),
+        "feat: author, feat: body"
+    );
+    assert_eq!(
+        query(ChangeQuery::new().container(a.get_text("title").id())),
+        "fix: title"
+    );
+    assert_eq!(
+        query(
+            ChangeQuery::new()
+                .container(a.get_text("title").id())
+                .container(a.get_text("body").id())
+        ),
+        "fix: title, feat: body"
+    );
+    assert_eq!(
+        query(ChangeQuery::new().peer(2).message_contains("fix")),
+        ""
+    );
+    Ok(())
+}
+
+#[test]
+fn shallow_docs_only_query_the_retained_history() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    for i in 0..4 {
+        doc.get_text("text").insert(0, "x")?;
+        commit(&doc, &format!("edit {i}"), i);
+    }
+    let frontiers = doc.state_frontiers();
+    doc.get_text("text").insert(0, "x")?;
+    commit(&doc, "edit 4", 4);
+
+    let shallow = LoroDoc::from_snapshot(
+        &doc.export(ExportMode::shallow_snapshot(&frontiers))
+            .unwrap(),
+    )?;
+    let changes = shallow.query_changes(&ChangeQuery::new()).changes;
+    assert_eq!(messages(&changes)[0], "edit 4");
+    assert!(changes.len() < 5);
+    assert_eq!(all_pages(&shallow, ChangeQuery::new(), 1), changes);
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use loro::{
+    CommitOptions, CompactionPolicy, ExportMode, LoroDoc, LoroError, LoroList, LoroResult,
+    LoroText, ToJson, TreeParentId,
+};
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+
+const HOUR: i64 = 60 * 60;
+
+fn commit_at(doc: &LoroDoc, timestamp: i64) {
+    doc.commit_with(CommitOptions::new().timestamp(timestamp));
+}
+
+/// The deep value without the IDs of the tree nodes, which differ in a compacted doc.
+fn shape(doc: &LoroDoc) -> Value {
+    fn strip(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                map.remove("id");
+                map.remove("parent");
+                map.values_mut().for_each(strip);
+            }
+            Value::Array(list) => list.iter_mut().for_each(strip),
+            _ => {}
+        }
+    }
+    let mut value = doc.get_deep_value().to_json_value();
+    strip(&mut value);
+    value
+}
+
+fn sync(a: &LoroDoc, b: &LoroDoc) -> LoroResult<()> {
+    b.import(&a.export(ExportMode::updates(&b.oplog_vv())).unwrap())?;
+    a.import(&b.export(ExportMode::updates(&a.oplog_vv())).unwrap())?;
+    Ok(())
+}
+
+#[test]
+fn checkpoints_keep_the_state_of_every_period() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    // A merged change keeps the timestamp of its first commit
+    a.set_change_merge_interval(-1);
+    let nested = a
+        .get_map("map")
+        .insert_container("nested", LoroText::new())?;
+    nested.insert(0, "x")?;
+    let tree = a.get_tree("tree");
+    let node = tree.create(TreeParentId::Root)?;
+    tree.get_meta(node)?.insert("name", "root")?;
+    commit_at(&a, 10);
+    for minute in 1..30 {
+        nested.insert(nested.len_unicode(), "y")?;
+        commit_at(&a, minute * 60);
+    }
+    sync(&a, &b)?;
+
+    // Concurrent edits in the next hours
+    let list = b.get_map("map").insert_container("list", LoroList::new())?;
+    list.push(1)?;
+    commit_at(&b, HOUR + 10);
+    nested.insert(0, "> ")?;
+    commit_at(&a, HOUR + 20);
+    sync(&a, &b)?;
+    let child = b.get_tree("tree").create(node)?;
+    b.get_tree("tree")
+        .get_meta(child)?
+        .insert("name", "child")?;
+    commit_at(&b, 3 * HOUR);
+    sync(&a, &b)?;
+
+    let compacted = a.compact_history(CompactionPolicy::hourly())?;
+    let new_doc = &compacted.doc;
+    assert_eq!(compacted.checkpoints.len(), 3);
+    assert_eq!(new_doc.len_changes(), 3);
+    assert_eq!(shape(new_doc), shape(&a));
+    assert_eq!(
+        compacted.checkpoints.last().unwrap().source,
+        a.oplog_frontiers()
+    );
+
+    for checkpoint in &compacted.checkpoints {
+        a.checkout(&checkpoint.source)?;
+        new_doc.checkout(&checkpoint.target)?;
+        assert_eq!(shape(new_doc), shape(&a));
+    }
+    assert_eq!(
+        compacted
+            .checkpoints
+            .iter()
+            .map(|c| c.timestamp)
+            .collect::<Vec<_>>(),
+        vec![29 * 60, HOUR + 20, 3 * HOUR]
+    );
+
+    // The compacted doc is a new history that other peers can start from
+    new_doc.checkout_to_latest();
+    let restored = LoroDoc::from_snapshot(&new_doc.export(ExportMode::snapshot()).unwrap())?;
+    assert_eq!(shape(&restored), shape(&a));
+    Ok(())
+}
+
+#[test]
+fn compaction_needs_a_positive_interval_and_the_full_history() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    doc.get_text("text").insert(0, "a")?;
+    commit_at(&doc, 0);
+    let frontiers = doc.state_frontiers();
+    doc.get_text("text").insert(1, "b")?;
+    commit_at(&doc, HOUR);
+
+    assert!(matches!(
+        doc.compact_history(CompactionPolicy { interval: 0 }),
+        Err(LoroError::ArgErr(_))
+    ));
+    let shallow = LoroDoc::from_snapshot(
+        &doc.export(ExportMode::shallow_snapshot(&frontiers))
+            .unwrap(),
+    )?;
+    assert!(matches!(
+        shallow.compact_history(CompactionPolicy::daily()),
+        Err(LoroError::ArgErr(_))
+    ));
+
+    let compacted = doc.compact_history(CompactionPolicy::daily())?;
+    assert_eq!(compacted.checkpoints.len(), 1);
+    assert_eq!(compacted.doc.get_text("text").to_string(), "ab");
+    Ok(())
+}
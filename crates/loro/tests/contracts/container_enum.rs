@@ -20,6 +20,10 @@ fn seed_container(container: &Container, label: &str) -> LoroResult<()> {
         }
         #[cfg(feature = "counter")]
         Container::Counter(counter) => counter.increment(label.len() as f64)?,
+        #[cfg(feature = "set")]
+        Container::Set(set) => {
+            set.add(label)?;
+        }
         Container::Unknown(_) => unreachable!("Container::new cannot create Unknown"),
     }
     Ok(())
@@ -34,6 +38,8 @@ fn container_json(container: &Container) -> Value {
         Container::Tree(tree) => tree.get_value_with_meta().to_json_value(),
         #[cfg(feature = "counter")]
         Container::Counter(counter) => json!(counter.get()),
+        #[cfg(feature = "set")]
+        Container::Set(set) => set.get_value().to_json_value(),
         Container::Unknown(_) => unreachable!("test never constructs unknown containers"),
     }
 }
@@ -54,6 +60,10 @@ fn expected_json(kind: ContainerType, label: &str) -> Value {
         }]),
         #[cfg(feature = "counter")]
         ContainerType::Counter => json!(label.len() as f64),
+        #[cfg(feature = "typed-counter")]
+        ContainerType::TypedCounter => json!(label.len() as f64),
+        #[cfg(feature = "set")]
+        ContainerType::Set => json!([label]),
         ContainerType::Unknown(_) => unreachable!("Container::new cannot create Unknown"),
     }
 }
@@ -96,6 +106,10 @@ fn container_enum_trait_dispatch_attaches_all_container_kinds() -> LoroResult<()
         ContainerType::Tree,
         #[cfg(feature = "counter")]
         ContainerType::Counter,
+        #[cfg(feature = "typed-counter")]
+        ContainerType::TypedCounter,
+        #[cfg(feature = "set")]
+        ContainerType::Set,
     ];
 
     let mut attached = Vec::new();
@@ -25,6 +25,8 @@ fn container_name(container: &Container) -> &'static str {
         Container::Unknown(_) => "unknown",
         #[cfg(feature = "counter")]
         Container::Counter(_) => "counter",
+        #[cfg(feature = "set")]
+        Container::Set(_) => "set",
     }
 }
 
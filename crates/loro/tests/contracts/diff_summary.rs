@@ -0,0 +1,126 @@
+use loro::{ChangeSummary, DiffSummary, ExportMode, LoroDoc, LoroResult, TreeParentId};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+fn changes_of(summary: &DiffSummary, name: &str) -> ChangeSummary {
+    summary
+        .containers
+        .iter()
+        .find(|c| c.id.is_root() && c.id.name().as_str() == name)
+        .unwrap()
+        .changes
+        .clone()
+}
+
+#[test]
+fn summarizes_every_container_type() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let text = doc.get_text("text");
+    let list = doc.get_movable_list("list");
+    let tree = doc.get_tree("tree");
+    text.insert(0, "Hello world")?;
+    for i in 0..3 {
+        list.push(i)?;
+    }
+    let root = tree.create(TreeParentId::Root)?;
+    let child = tree.create(TreeParentId::Root)?;
+    let gone = tree.create(TreeParentId::Root)?;
+    doc.get_map("map").insert("keep", 1)?;
+    doc.get_map("map").insert("remove", "x")?;
+    doc.commit();
+    let a = doc.state_frontiers();
+
+    text.delete(5, 6)?;
+    // Lengths are in unicode chars, or UTF-16 units with the wasm feature, which agree on "é"
+    text.insert(5, ", Loró! é")?;
+    list.mov(0, 2)?;
+    list.insert(0, 10)?;
+    list.delete(1, 1)?;
+    let created = tree.create(TreeParentId::Root)?;
+    tree.mov(child, root)?;
+    tree.delete(gone)?;
+    doc.get_map("map").insert("keep", 2)?;
+    doc.get_map("map").delete("remove")?;
+    doc.get_map("map").insert("add", true)?;
+    doc.commit();
+    let b = doc.state_frontiers();
+
+    let summary = doc.diff_summary(&a, &b)?;
+    assert_eq!(
+        changes_of(&summary, "text"),
+        ChangeSummary::Text {
+            inserted: 9,
+            deleted: 6,
+            formatted: 0
+        }
+    );
+    let ChangeSummary::List {
+        inserted, moved, ..
+    } = changes_of(&summary, "list")
+    else {
+        panic!("not a list summary");
+    };
+    assert_eq!((inserted, moved), (1, 1));
+    assert_eq!(
+        changes_of(&summary, "tree"),
+        ChangeSummary::Tree {
+            created: vec![created],
+            deleted: vec![gone],
+            moved: vec![child],
+        }
+    );
+    assert_eq!(
+        serde_json::to_value(changes_of(&summary, "map")).unwrap(),
+        json!({
+            "type": "map",
+            "keys": [
+                { "key": "add", "old": null, "new": true },
+                { "key": "keep", "old": 1, "new": 2 },
+                { "key": "remove", "old": "x", "new": null },
+            ]
+        })
+    );
+    let path = summary.containers[0].path.clone().unwrap();
+    assert_eq!(path.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn authors_are_the_peers_between_the_versions() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_text("text").insert(0, "a")?;
+    a.commit();
+    b.import(&a.export(ExportMode::all_updates()).unwrap())?;
+    let base = a.state_frontiers();
+
+    a.get_text("text").insert(1, "b")?;
+    a.commit();
+    b.get_text("text").insert(0, "cd")?;
+    b.commit();
+    a.import(&b.export(ExportMode::updates(&a.oplog_vv())).unwrap())?;
+    let latest = a.state_frontiers();
+
+    let summary = a.diff_summary(&base, &latest)?;
+    let authors: Vec<(u64, usize)> = summary.authors.iter().map(|a| (a.peer, a.ops)).collect();
+    assert_eq!(authors, vec![(1, 1), (2, 2)]);
+
+    // Going back reverts the same changes
+    let back = a.diff_summary(&latest, &base)?;
+    assert_eq!(back.authors, summary.authors);
+    assert_eq!(
+        changes_of(&back, "text"),
+        ChangeSummary::Text {
+            inserted: 0,
+            deleted: 3,
+            formatted: 0
+        }
+    );
+
+    let json = serde_json::to_string(&summary).unwrap();
+    assert_eq!(serde_json::from_str::<DiffSummary>(&json).unwrap(), summary);
+    Ok(())
+}
@@ -50,6 +50,9 @@ fn capture_event(event: loro::event::DiffEvent<'_>) -> CapturedEvent {
                     Diff::Text(_) => CapturedDiffKind::Text,
                     Diff::Tree(_) => CapturedDiffKind::Tree,
                     Diff::Counter(_) => CapturedDiffKind::Counter,
+                    // No contract in this file inspects set diffs.
+                    #[cfg(feature = "set")]
+                    Diff::Set(_) => CapturedDiffKind::Unknown,
                     Diff::List(items) => CapturedDiffKind::List {
                         has_move: items
                             .iter()
@@ -0,0 +1,267 @@
+use loro::{ExportMode, LoroDoc, LoroError, LoroGrid, LoroMap, LoroResult, LoroValue, ToJson};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+fn sync(a: &LoroDoc, b: &LoroDoc) -> LoroResult<()> {
+    b.import(&a.export(ExportMode::updates(&b.oplog_vv())).unwrap())?;
+    a.import(&b.export(ExportMode::updates(&a.oplog_vv())).unwrap())?;
+    Ok(())
+}
+
+/// A grid with `rows` rows and `cols` columns, and every cell set to `"r{row}c{col}"`.
+fn filled(doc: &LoroDoc, rows: usize, cols: usize) -> LoroResult<LoroGrid> {
+    let grid = doc.get_grid("sheet");
+    for i in 0..rows {
+        grid.insert_row(i)?;
+    }
+    for j in 0..cols {
+        grid.insert_col(j)?;
+    }
+    for i in 0..rows {
+        for j in 0..cols {
+            grid.set_at(i, j, format!("r{i}c{j}"))?;
+        }
+    }
+    doc.commit();
+    Ok(grid)
+}
+
+#[test]
+fn cells_follow_their_rows_and_columns() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let grid = filled(&doc, 2, 2)?;
+    let rows = grid.row_ids();
+    let cols = grid.col_ids();
+    assert_eq!(rows.len(), 2);
+    assert_ne!(rows[0], rows[1]);
+
+    grid.move_row(1, 0)?;
+    grid.move_col(0, 1)?;
+    assert_eq!(grid.row_ids(), vec![rows[1].clone(), rows[0].clone()]);
+    assert_eq!(grid.row_index(&rows[0]), Some(1));
+    assert_eq!(grid.col_index(&cols[0]), Some(1));
+    assert_eq!(grid.get(&rows[0], &cols[0]), Some(LoroValue::from("r0c0")));
+    assert_eq!(
+        grid.get_value().to_json_value(),
+        json!([["r1c1", "r1c0"], ["r0c1", "r0c0"]])
+    );
+
+    let new_row = grid.insert_row(1)?;
+    grid.remove(&rows[0], &cols[1])?;
+    assert_eq!(grid.get(&new_row, &cols[0]), None);
+    assert_eq!(
+        grid.get_value().to_json_value(),
+        json!([["r1c1", "r1c0"], [null, null], [null, "r0c0"]])
+    );
+    Ok(())
+}
+
+#[test]
+fn deleting_rows_and_columns_deletes_their_cells() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let grid = filled(&doc, 3, 3)?;
+    let rows = grid.row_ids();
+    let cols = grid.col_ids();
+
+    grid.delete_row(1)?;
+    grid.delete_col(0)?;
+    assert_eq!(grid.row_count(), 2);
+    assert_eq!(grid.col_count(), 2);
+    assert_eq!(grid.get(&rows[1], &cols[1]), None);
+    assert_eq!(
+        grid.get_value().to_json_value(),
+        json!([["r0c1", "r0c2"], ["r2c1", "r2c2"]])
+    );
+
+    // Nothing of the deleted row and column is left in the storage
+    let stored = grid.map().get_deep_value().to_json_value();
+    assert_eq!(
+        stored["rows"],
+        json!([
+            { cols[1].clone(): "r0c1", cols[2].clone(): "r0c2" },
+            { cols[1].clone(): "r2c1", cols[2].clone(): "r2c2" },
+        ])
+    );
+    assert_eq!(stored["cols"], json!([{}, {}]));
+    assert_eq!(grid.remove_orphans()?, 0);
+    Ok(())
+}
+
+#[test]
+fn concurrent_row_and_column_edits_merge() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    let grid_a = filled(&a, 2, 1)?;
+    sync(&a, &b)?;
+    let grid_b = b.get_grid("sheet");
+
+    let row_a = grid_a.insert_row(1)?;
+    grid_a.set_at(1, 0, "from a")?;
+    let row_b = grid_b.insert_row(1)?;
+    grid_b.set_at(1, 0, "from b")?;
+    grid_b.move_row(0, 2)?;
+    a.commit();
+    b.commit();
+    sync(&a, &b)?;
+
+    assert_ne!(row_a, row_b);
+    assert_eq!(grid_a.row_count(), 4);
+    assert_eq!(grid_a.get_value(), grid_b.get_value());
+    assert_eq!(grid_a.get_at(3, 0), Some(LoroValue::from("r0c0")));
+    let values = grid_a.get_value().to_json_value();
+    assert!(values.as_array().unwrap().contains(&json!(["from a"])));
+    assert!(values.as_array().unwrap().contains(&json!(["from b"])));
+    Ok(())
+}
+
+#[test]
+fn cells_set_concurrently_with_a_deletion_are_hidden() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    let grid_a = filled(&a, 2, 2)?;
+    let rows = grid_a.row_ids();
+    let cols = grid_a.col_ids();
+    // An empty cell, so deleting its column doesn't write a concurrent delete for it
+    grid_a.remove(&rows[1], &cols[1])?;
+    a.commit();
+    sync(&a, &b)?;
+    let grid_b = b.get_grid("sheet");
+
+    grid_a.delete_row(0)?;
+    grid_a.delete_col(1)?;
+    grid_b.set(&rows[0], &cols[0], "late")?;
+    grid_b.set(&rows[1], &cols[1], "late")?;
+    a.commit();
+    b.commit();
+    sync(&a, &b)?;
+
+    for grid in [&grid_a, &grid_b] {
+        assert_eq!(grid.get(&rows[0], &cols[0]), None);
+        assert_eq!(grid.get(&rows[1], &cols[1]), None);
+        assert_eq!(grid.get_value().to_json_value(), json!([["r1c0"]]));
+    }
+    // The deleted row is gone with its cells; the cell in the deleted column is left behind
+    for grid in [&grid_a, &grid_b] {
+        assert_eq!(
+            grid.map().get_deep_value().to_json_value()["rows"],
+            json!([{ cols[0].clone(): "r1c0", cols[1].clone(): "late" }])
+        );
+    }
+    assert_eq!(grid_a.remove_orphans()?, 1);
+    assert_eq!(grid_a.remove_orphans()?, 0);
+    a.commit();
+    assert_eq!(
+        grid_a.map().get_deep_value().to_json_value()["rows"],
+        json!([{ cols[0].clone(): "r1c0" }])
+    );
+    Ok(())
+}
+
+#[test]
+fn cells_set_concurrently_with_a_row_deletion_go_with_the_row() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    let grid_a = filled(&a, 1, 1)?;
+    let empty = grid_a.insert_row(1)?;
+    a.commit();
+    sync(&a, &b)?;
+    let grid_b = b.get_grid("sheet");
+    let col = grid_b.col_id(0).unwrap();
+
+    grid_a.delete_row(1)?;
+    grid_b.set(&empty, &col, "late")?;
+    a.commit();
+    b.commit();
+    sync(&a, &b)?;
+
+    for grid in [&grid_a, &grid_b] {
+        assert_eq!(grid.row_index(&empty), None);
+        assert_eq!(grid.get_value().to_json_value(), json!([["r0c0"]]));
+        assert_eq!(
+            grid.map().get_deep_value().to_json_value()["rows"],
+            json!([{ col.clone(): "r0c0" }])
+        );
+        assert_eq!(grid.remove_orphans()?, 0);
+    }
+    Ok(())
+}
+
+#[test]
+fn invalid_positions_and_ids_are_rejected() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let grid = filled(&doc, 1, 1)?;
+    let row = grid.row_id(0).unwrap();
+    assert!(matches!(
+        grid.set(&row, "missing", 1),
+        Err(LoroError::NotFoundError(_))
+    ));
+    assert!(matches!(
+        grid.set_at(1, 0, 1),
+        Err(LoroError::OutOfBound { .. })
+    ));
+    assert!(matches!(
+        grid.insert_col(3),
+        Err(LoroError::OutOfBound { .. })
+    ));
+    assert!(matches!(
+        grid.delete_row(1),
+        Err(LoroError::OutOfBound { .. })
+    ));
+    assert_eq!(grid.get(&row, "missing"), None);
+    assert_eq!(grid.get_at(0, 1), None);
+
+    let detached = LoroGrid::from_map(LoroMap::new());
+    assert!(matches!(
+        detached.insert_row(0),
+        Err(LoroError::MisuseDetachedContainer { .. })
+    ));
+    assert_eq!(detached.row_count(), 0);
+    assert_eq!(
+        detached.get_value(),
+        LoroValue::from(Vec::<LoroValue>::new())
+    );
+    Ok(())
+}
+
+#[test]
+fn sparse_grids_have_compact_snapshots() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let grid = doc.get_grid("sheet");
+    for i in 0..200 {
+        grid.insert_row(i)?;
+    }
+    for j in 0..50 {
+        grid.insert_col(j)?;
+    }
+    for i in 0..10 {
+        grid.set_at(i * 20, i * 5, i as i64)?;
+    }
+    doc.commit();
+    let snapshot = doc.export(ExportMode::snapshot()).unwrap();
+
+    // The same sheet stored as nested lists has to store every empty cell
+    let dense = LoroDoc::new();
+    dense.set_peer_id(1)?;
+    dense
+        .get_list("sheet")
+        .update_from_value(&grid.get_value())?;
+    dense.commit();
+    let dense_snapshot = dense.export(ExportMode::snapshot()).unwrap();
+    assert!(
+        snapshot.len() < dense_snapshot.len(),
+        "{} >= {}",
+        snapshot.len(),
+        dense_snapshot.len()
+    );
+
+    let restored = LoroDoc::from_snapshot(&snapshot)?;
+    assert_eq!(restored.get_grid("sheet").get_value(), grid.get_value());
+    Ok(())
+}
@@ -54,6 +54,8 @@ fn value_kind(value: &ValueOrContainer) -> String {
             Container::MovableList(_) => "container:movable_list".to_string(),
             #[cfg(feature = "counter")]
             Container::Counter(_) => "container:counter".to_string(),
+            #[cfg(feature = "set")]
+            Container::Set(_) => "container:set".to_string(),
             Container::Unknown(_) => "container:unknown".to_string(),
         },
     }
@@ -0,0 +1,164 @@
+#![cfg(feature = "jsonpath")]
+
+use loro::{Index, JsonPathDiff, JsonPathItem, LoroDoc, LoroList, LoroMap, ToJson};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+fn build_doc() -> anyhow::Result<(LoroDoc, LoroList)> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let books = doc.get_list("books");
+    for (i, (title, price)) in [("1984", 10), ("Dune", 12)].into_iter().enumerate() {
+        let book = books.insert_container(i, LoroMap::new())?;
+        book.insert("title", title)?;
+        book.insert("price", price)?;
+    }
+    doc.commit();
+    Ok((doc, books))
+}
+
+fn collect(diffs: &Arc<Mutex<Vec<JsonPathDiff>>>) -> Arc<dyn Fn(&JsonPathDiff) + Send + Sync> {
+    let diffs = diffs.clone();
+    Arc::new(move |diff: &JsonPathDiff| diffs.lock().unwrap().push(diff.clone()))
+}
+
+fn items(items: &[JsonPathItem]) -> Vec<(Vec<Index>, Value)> {
+    items
+        .iter()
+        .map(|item| {
+            (
+                item.path.clone(),
+                item.value.get_deep_value().to_json_value(),
+            )
+        })
+        .collect()
+}
+
+fn path(key: &str, index: usize, field: &str) -> Vec<Index> {
+    vec![
+        Index::Key(key.into()),
+        Index::Seq(index),
+        Index::Key(field.into()),
+    ]
+}
+
+#[test]
+fn jsonpath_diff_reports_added_removed_and_changed_results() -> anyhow::Result<()> {
+    let (doc, books) = build_doc()?;
+    let diffs = Arc::new(Mutex::new(Vec::new()));
+    let _sub = doc.subscribe_jsonpath_diff("$.books[*].title", collect(&diffs))?;
+
+    let book = books.insert_container(2, LoroMap::new())?;
+    book.insert("title", "Emma")?;
+    doc.commit();
+    books
+        .get(0)
+        .unwrap()
+        .into_container()
+        .unwrap()
+        .into_map()
+        .unwrap()
+        .insert("title", "Animal Farm")?;
+    doc.commit();
+    books.delete(2, 1)?;
+    doc.commit();
+
+    let diffs = diffs.lock().unwrap();
+    assert_eq!(diffs.len(), 3);
+    assert_eq!(
+        items(&diffs[0].added),
+        vec![(path("books", 2, "title"), json!("Emma"))]
+    );
+    assert!(diffs[0].removed.is_empty() && diffs[0].changed.is_empty());
+    assert_eq!(
+        items(&diffs[1].changed),
+        vec![(path("books", 0, "title"), json!("Animal Farm"))]
+    );
+    assert!(diffs[1].added.is_empty() && diffs[1].removed.is_empty());
+    assert_eq!(
+        items(&diffs[2].removed),
+        vec![(path("books", 2, "title"), json!("Emma"))]
+    );
+    assert!(diffs[2].added.is_empty() && diffs[2].changed.is_empty());
+    Ok(())
+}
+
+#[test]
+fn jsonpath_diff_suppresses_callbacks_when_results_are_unchanged() -> anyhow::Result<()> {
+    let (doc, books) = build_doc()?;
+    let diffs = Arc::new(Mutex::new(Vec::new()));
+    let _sub = doc.subscribe_jsonpath_diff("$.books[*].title", collect(&diffs))?;
+
+    // The price is under the wildcard, so the query is re-evaluated, but no title changed
+    let first = books
+        .get(0)
+        .unwrap()
+        .into_container()
+        .unwrap()
+        .into_map()
+        .unwrap();
+    first.insert("price", 11)?;
+    doc.commit();
+    // Setting the same title doesn't change the result either
+    first.insert("title", "1984")?;
+    doc.commit();
+    assert!(diffs.lock().unwrap().is_empty());
+    Ok(())
+}
+
+#[test]
+fn jsonpath_diff_delivers_every_commit_without_polling() -> anyhow::Result<()> {
+    let (doc, books) = build_doc()?;
+    let diffs = Arc::new(Mutex::new(Vec::new()));
+    let _sub = doc.subscribe_jsonpath_diff("$.books[*]", collect(&diffs))?;
+
+    let first = books
+        .get(0)
+        .unwrap()
+        .into_container()
+        .unwrap()
+        .into_map()
+        .unwrap();
+    first.insert("price", 11)?;
+    doc.commit();
+    let book = books.insert_container(2, LoroMap::new())?;
+    book.insert("title", "Emma")?;
+    doc.commit();
+
+    // The last commit of the burst is delivered as soon as it's committed
+    let diffs = diffs.lock().unwrap();
+    assert_eq!(diffs.len(), 2);
+    assert_eq!(
+        items(&diffs[0].changed),
+        vec![(
+            vec![Index::Key("books".into()), Index::Seq(0)],
+            json!({"title": "1984", "price": 11})
+        )]
+    );
+    assert!(diffs[0].changed[0].value.is_container());
+    assert_eq!(
+        items(&diffs[1].added),
+        vec![(
+            vec![Index::Key("books".into()), Index::Seq(2)],
+            json!({"title": "Emma"})
+        )]
+    );
+    Ok(())
+}
+
+#[test]
+fn jsonpath_diff_stops_after_unsubscribe() -> anyhow::Result<()> {
+    let (doc, books) = build_doc()?;
+    let diffs = Arc::new(Mutex::new(Vec::new()));
+    let sub = doc.subscribe_jsonpath_diff("$.books[*].title", collect(&diffs))?;
+    assert!(doc
+        .subscribe_jsonpath_diff("$.books[", collect(&diffs))
+        .is_err());
+
+    sub.unsubscribe();
+    books.delete(0, 1)?;
+    doc.commit();
+    assert!(diffs.lock().unwrap().is_empty());
+    Ok(())
+}
@@ -0,0 +1,161 @@
+#![cfg(feature = "jsonpath")]
+
+use loro::{loro_value, LoroDoc, LoroError, LoroList, LoroMap, LoroMovableList, ToJson};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+fn build_doc() -> anyhow::Result<LoroDoc> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let todos = doc.get_list("todos");
+    for (i, (title, priority)) in [("a", 1), ("b", 5), ("c", 4), ("d", 2)]
+        .into_iter()
+        .enumerate()
+    {
+        let todo = todos.insert_container(i, LoroMap::new())?;
+        todo.insert("title", title)?;
+        todo.insert("priority", priority)?;
+        let tags = todo.insert_container("tags", LoroList::new())?;
+        tags.push(title)?;
+    }
+    let board = doc.get_map("board");
+    let columns = board.insert_container("columns", LoroMovableList::new())?;
+    columns.push("todo")?;
+    columns.push("done")?;
+    board.insert("meta", loro_value!({"owner": "alice"}))?;
+    doc.commit();
+    Ok(doc)
+}
+
+#[test]
+fn jsonpath_set_updates_filtered_nodes_in_one_change() -> anyhow::Result<()> {
+    let doc = build_doc()?;
+    // Keep the writes apart from the change of build_doc
+    doc.set_change_merge_interval(-1);
+    let changes = doc.len_changes();
+
+    assert_eq!(
+        doc.jsonpath_set("$.todos[?(@.priority > 3)].done", true)?,
+        2
+    );
+    assert_eq!(doc.jsonpath_set("$.todos[0].priority", 3)?, 1);
+    assert_eq!(doc.jsonpath_set("$.board.columns[-1]", "finished")?, 1);
+    assert_eq!(doc.jsonpath_set("$.todos[*].tags[0]", "x")?, 4);
+    assert_eq!(
+        doc.jsonpath_set("$.todos[?(@.priority > 10)].done", true)?,
+        0
+    );
+    doc.commit();
+
+    assert_eq!(doc.len_changes(), changes + 1);
+    assert_eq!(
+        doc.jsonpath("$.todos[*]")?
+            .into_iter()
+            .map(|todo| todo.get_deep_value().to_json_value())
+            .collect::<Vec<_>>(),
+        vec![
+            json!({"title": "a", "priority": 3, "tags": ["x"]}),
+            json!({"title": "b", "priority": 5, "tags": ["x"], "done": true}),
+            json!({"title": "c", "priority": 4, "tags": ["x"], "done": true}),
+            json!({"title": "d", "priority": 2, "tags": ["x"]}),
+        ]
+    );
+    assert_eq!(
+        doc.get_map("board").get_deep_value().to_json_value()["columns"],
+        json!(["todo", "finished"])
+    );
+    Ok(())
+}
+
+#[test]
+fn jsonpath_delete_removes_list_entries_and_map_keys() -> anyhow::Result<()> {
+    let doc = build_doc()?;
+
+    // Several entries of the same list
+    assert_eq!(doc.jsonpath_delete("$.todos[?(@.priority >= 4)]")?, 2);
+    assert_eq!(doc.jsonpath_delete("$..title")?, 2);
+    assert_eq!(doc.jsonpath_delete("$.todos[*].missing")?, 0);
+    assert_eq!(doc.jsonpath_delete("$.board.columns[0]")?, 1);
+    doc.commit();
+
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({
+            "todos": [
+                {"priority": 1, "tags": ["a"]},
+                {"priority": 2, "tags": ["d"]},
+            ],
+            "board": {"columns": ["done"], "meta": {"owner": "alice"}},
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn jsonpath_insert_adds_to_every_selected_list() -> anyhow::Result<()> {
+    let doc = build_doc()?;
+
+    assert_eq!(
+        doc.jsonpath_insert("$.todos[?(@.priority < 3)].tags", 0, "low")?,
+        2
+    );
+    assert_eq!(doc.jsonpath_insert("$.board.columns", 2, "archived")?, 1);
+    doc.commit();
+
+    assert_eq!(
+        doc.jsonpath("$.todos[*].tags")?
+            .into_iter()
+            .map(|tags| tags.get_deep_value().to_json_value())
+            .collect::<Vec<_>>(),
+        vec![
+            json!(["low", "a"]),
+            json!(["b"]),
+            json!(["c"]),
+            json!(["low", "d"]),
+        ]
+    );
+    assert_eq!(
+        doc.get_map("board").get_deep_value().to_json_value()["columns"],
+        json!(["todo", "done", "archived"])
+    );
+    Ok(())
+}
+
+#[test]
+fn jsonpath_writes_reject_invalid_targets_without_editing() -> anyhow::Result<()> {
+    let doc = build_doc()?;
+    doc.jsonpath_insert("$.todos[0].tags", 1, "long")?;
+    doc.commit();
+    let before = doc.get_deep_value();
+
+    // The roots and the values nested in a value are not entries of a container
+    assert!(matches!(
+        doc.jsonpath_set("$.todos", 1),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(matches!(
+        doc.jsonpath_set("$.board.meta.owner", "bob"),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(matches!(
+        doc.jsonpath_delete("$.board.meta.owner"),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(matches!(
+        doc.jsonpath_insert("$.todos[0]", 0, 1),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(matches!(
+        doc.jsonpath_set("$.todos[", 1),
+        Err(LoroError::ArgErr(_))
+    ));
+    // Every list is checked before the first insertion, and only the first one is long enough
+    assert!(matches!(
+        doc.jsonpath_insert("$..tags", 2, "late"),
+        Err(LoroError::OutOfBound { .. })
+    ));
+    doc.commit();
+
+    assert_eq!(doc.get_deep_value(), before);
+    Ok(())
+}
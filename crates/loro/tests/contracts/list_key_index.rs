@@ -0,0 +1,139 @@
+use loro::{ContainerTrait, ExportMode, LoroDoc, LoroError, LoroList, LoroMap, LoroResult};
+use pretty_assertions::assert_eq;
+
+fn find(list: &LoroList, key: &str) -> Option<usize> {
+    list.find_by_key(key).unwrap().map(|(pos, _)| pos)
+}
+
+fn push_item(list: &LoroList, id: &str) -> LoroResult<LoroMap> {
+    let item = list.insert_container(list.len(), LoroMap::new())?;
+    item.insert("id", id)?;
+    Ok(item)
+}
+
+#[test]
+fn key_index_follows_local_edits() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_list("items");
+    for id in ["a", "b"] {
+        push_item(&list, id)?;
+    }
+    list.insert(0, "not a map")?;
+    list.create_key_index("id")?;
+    assert_eq!(find(&list, "a"), Some(1));
+    assert_eq!(find(&list, "b"), Some(2));
+
+    let c = push_item(&list, "c")?;
+    assert_eq!(find(&list, "c"), Some(3));
+    let (_, found) = list.find_by_key("c")?.unwrap();
+    assert_eq!(found.id(), c.id());
+
+    c.insert("id", "renamed")?;
+    assert_eq!(find(&list, "c"), None);
+    assert_eq!(find(&list, "renamed"), Some(3));
+
+    list.delete(0, 2)?;
+    assert_eq!(find(&list, "a"), None);
+    assert_eq!(find(&list, "b"), Some(0));
+
+    // duplicated keys resolve to the first one
+    push_item(&list, "b")?;
+    assert_eq!(find(&list, "b"), Some(0));
+    list.delete(0, 1)?;
+    assert_eq!(find(&list, "b"), Some(1));
+
+    assert!(list.remove_key_index());
+    assert!(matches!(list.find_by_key("b"), Err(LoroError::ArgErr(_))));
+    Ok(())
+}
+
+#[test]
+fn movable_list_key_index_follows_moves_and_sets() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_movable_list("items");
+    list.create_key_index("id")?;
+    for (i, id) in ["a", "b", "c"].into_iter().enumerate() {
+        list.insert_container(i, LoroMap::new())?.insert("id", id)?;
+    }
+
+    list.mov(0, 2)?;
+    assert_eq!(list.find_by_key("a")?.unwrap().0, 2);
+    assert_eq!(list.find_by_key("b")?.unwrap().0, 0);
+
+    list.set_container(1, LoroMap::new())?.insert("id", "d")?;
+    assert!(list.find_by_key("c")?.is_none());
+    assert_eq!(list.find_by_key("d")?.unwrap().0, 1);
+    Ok(())
+}
+
+#[test]
+fn key_index_survives_concurrent_edits_and_checkout() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let list_a = doc_a.get_list("items");
+    push_item(&list_a, "a")?;
+    doc_a.commit();
+
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_b.import(&doc_a.export(ExportMode::Snapshot)?)?;
+    let list_b = doc_b.get_list("items");
+
+    list_a.create_key_index("id")?;
+    push_item(&list_a, "from_a")?;
+    push_item(&list_b, "from_b")?;
+    list_b
+        .get(0)
+        .unwrap()
+        .into_container()
+        .unwrap()
+        .into_map()
+        .unwrap()
+        .insert("id", "a2")?;
+    doc_a.commit();
+    doc_b.commit();
+    doc_a.import(&doc_b.export(ExportMode::all_updates())?)?;
+
+    assert_eq!(find(&list_a, "a"), None);
+    assert_eq!(find(&list_a, "a2"), Some(0));
+    let from_a = find(&list_a, "from_a").unwrap();
+    let from_b = find(&list_a, "from_b").unwrap();
+    assert_eq!(
+        list_a.get(from_a).unwrap().into_container().unwrap().id(),
+        list_a.find_by_key("from_a")?.unwrap().1.id()
+    );
+    assert_eq!(from_a + from_b, 3);
+
+    // a deleted map comes back when checking out an older version
+    let before_delete = doc_a.state_frontiers();
+    list_a.delete(0, 1)?;
+    doc_a.commit();
+    assert_eq!(find(&list_a, "a2"), None);
+    doc_a.checkout(&before_delete)?;
+    assert_eq!(find(&list_a, "a2"), Some(0));
+    doc_a.checkout_to_latest();
+    assert_eq!(find(&list_a, "a2"), None);
+    Ok(())
+}
+
+#[test]
+fn key_index_is_rebuilt_after_snapshot_import() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_list("items");
+    for id in ["a", "b"] {
+        push_item(&list, id)?;
+    }
+    doc.commit();
+
+    let other = LoroDoc::new();
+    let other_list = other.get_list("items");
+    other_list.create_key_index("id")?;
+    other.import(&doc.export(ExportMode::Snapshot)?)?;
+    assert_eq!(find(&other_list, "b"), Some(1));
+
+    assert!(matches!(
+        LoroList::new().create_key_index("id"),
+        Err(LoroError::MisuseDetachedContainer { .. })
+    ));
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use loro::{
+    ContainerTrait, ExportMode, LoroDoc, LoroError, LoroList, LoroMovableList, LoroResult,
+    LoroValue, ToJson,
+};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+#[test]
+fn insert_many_inserts_all_values_in_one_change() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_list("list");
+    list.insert_many(0, 0..1000)?;
+    doc.commit();
+    assert_eq!(doc.len_ops(), 1000);
+    assert_eq!(doc.len_changes(), 1);
+    assert_eq!(list.len(), 1000);
+
+    let movable = doc.get_movable_list("movable");
+    movable.insert_many(0, ["a", "b", "c"])?;
+    movable.insert_many(1, ["x"])?;
+    doc.commit();
+    assert_eq!(
+        movable.get_value().to_json_value(),
+        json!(["a", "x", "b", "c"])
+    );
+
+    let other = LoroDoc::new();
+    other.import(&doc.export(ExportMode::Snapshot)?)?;
+    assert_eq!(other.get_deep_value(), doc.get_deep_value());
+    Ok(())
+}
+
+#[test]
+fn insert_many_emits_one_contiguous_insert() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_list("list");
+    list.push("first")?;
+    doc.commit();
+
+    let diff = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let diff_clone = diff.clone();
+    let _sub = doc.subscribe(
+        &list.id(),
+        std::sync::Arc::new(move |e| {
+            for event in e.events {
+                let list_diff = event.diff.as_list().unwrap();
+                diff_clone.lock().unwrap().push(list_diff.len());
+            }
+        }),
+    );
+    list.insert_many(0, [1, 2, 3])?;
+    doc.commit();
+    assert_eq!(*diff.lock().unwrap(), vec![1]);
+    Ok(())
+}
+
+#[test]
+fn slice_and_splice_on_both_list_kinds() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_list("list");
+    let movable = doc.get_movable_list("movable");
+    list.insert_many(0, ["a", "b", "c", "d"])?;
+    movable.insert_many(0, ["a", "b", "c", "d"])?;
+
+    let to_values = |values: Vec<loro::ValueOrContainer>| -> Vec<LoroValue> {
+        values
+            .into_iter()
+            .map(|v| v.into_value().unwrap())
+            .collect()
+    };
+    assert_eq!(
+        to_values(list.slice(1..3)?),
+        vec![LoroValue::from("b"), LoroValue::from("c")]
+    );
+    assert_eq!(to_values(movable.slice(4..4)?), vec![]);
+
+    assert_eq!(
+        list.splice(1, 2, [1, 2, 3])?,
+        vec![LoroValue::from("b"), LoroValue::from("c")]
+    );
+    assert_eq!(
+        movable.splice(0, 4, Vec::<LoroValue>::new())?,
+        ["a", "b", "c", "d"].map(LoroValue::from).to_vec()
+    );
+    assert_eq!(list.get_value().to_json_value(), json!(["a", 1, 2, 3, "d"]));
+    assert!(movable.is_empty());
+
+    let child = list.insert_container(0, loro::LoroText::new())?;
+    child.insert(0, "text")?;
+    let slice = list.slice(0..1)?;
+    assert_eq!(slice[0].as_container().unwrap().id(), child.id());
+    Ok(())
+}
+
+#[test]
+fn range_ops_validate_arguments() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_list("list");
+    list.insert_many(0, [1, 2])?;
+    assert!(matches!(
+        list.insert_many(3, [3]),
+        Err(LoroError::OutOfBound { .. })
+    ));
+    assert!(matches!(
+        list.splice(1, 2, [3]),
+        Err(LoroError::OutOfBound { .. })
+    ));
+    assert!(matches!(
+        list.slice(1..3),
+        Err(LoroError::OutOfBound { .. })
+    ));
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = 2..1;
+    assert!(matches!(
+        list.slice(reversed),
+        Err(LoroError::EndIndexLessThanStartIndex { .. })
+    ));
+    doc.commit();
+    assert_eq!(doc.len_ops(), 2);
+
+    let detached = LoroList::new();
+    detached.insert_many(0, [1, 2, 3])?;
+    assert_eq!(detached.splice(0, 1, ["x"])?, vec![LoroValue::from(1)]);
+    assert_eq!(detached.get_value().to_json_value(), json!(["x", 2, 3]));
+    let detached = LoroMovableList::new();
+    detached.insert_many(0, [1, 2])?;
+    assert_eq!(detached.slice(1..2)?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn concurrent_range_ops_converge() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_a.get_movable_list("list").insert_many(0, [1, 2, 3])?;
+    doc_a.commit();
+    doc_b.import(&doc_a.export(ExportMode::all_updates())?)?;
+
+    doc_a.get_movable_list("list").splice(1, 1, [20, 21])?;
+    doc_b.get_movable_list("list").insert_many(3, [4, 5])?;
+    doc_a.import(&doc_b.export(ExportMode::all_updates())?)?;
+    doc_b.import(&doc_a.export(ExportMode::all_updates())?)?;
+    assert_eq!(doc_a.get_deep_value(), doc_b.get_deep_value());
+    assert_eq!(
+        doc_a.get_deep_value().to_json_value(),
+        json!({"list": [1, 20, 21, 3, 4, 5]})
+    );
+    Ok(())
+}
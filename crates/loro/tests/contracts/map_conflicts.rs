@@ -0,0 +1,107 @@
+use loro::{ExportMode, IdLp, LoroDoc, LoroMap, LoroResult, LoroValue};
+use pretty_assertions::assert_eq;
+
+fn values(map: &LoroMap, key: &str) -> Vec<Option<LoroValue>> {
+    map.get_conflicts(key)
+        .unwrap()
+        .into_iter()
+        .map(|x| x.value)
+        .collect()
+}
+
+fn sync(a: &LoroDoc, b: &LoroDoc) -> LoroResult<()> {
+    a.import(&b.export(ExportMode::all_updates())?)?;
+    b.import(&a.export(ExportMode::all_updates())?)?;
+    Ok(())
+}
+
+fn new_peer(peer: u64) -> LoroResult<LoroDoc> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(peer)?;
+    Ok(doc)
+}
+
+#[test]
+fn sequential_writes_have_no_conflicts() -> LoroResult<()> {
+    let doc = new_peer(1)?;
+    let map = doc.get_map("form");
+    assert_eq!(values(&map, "name"), vec![]);
+    map.insert("name", "a")?;
+    doc.commit();
+    map.insert("name", "b")?;
+    assert_eq!(values(&map, "name"), vec![Some("b".into())]);
+    doc.commit();
+    let conflicts = map.get_conflicts("name")?;
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].value, Some("b".into()));
+    assert_eq!(conflicts[0].idlp, IdLp::new(1, 1));
+
+    map.delete("name")?;
+    doc.commit();
+    assert_eq!(values(&map, "name"), vec![None]);
+    assert!(LoroMap::new().get_conflicts("name").is_err());
+    Ok(())
+}
+
+#[test]
+fn concurrent_writes_are_all_reported_and_resolved() -> LoroResult<()> {
+    let doc_a = new_peer(1)?;
+    let doc_b = new_peer(2)?;
+    let doc_c = new_peer(3)?;
+    doc_a.get_map("form").insert("name", "a")?;
+    doc_b.get_map("form").insert("name", "b")?;
+    doc_c.get_map("form").insert("other", 1)?;
+    doc_c.get_map("form").insert("name", "c")?;
+    doc_c.get_map("form").delete("name")?;
+    doc_a.commit();
+    doc_b.commit();
+    doc_c.commit();
+    sync(&doc_a, &doc_b)?;
+    sync(&doc_a, &doc_c)?;
+    sync(&doc_a, &doc_b)?;
+
+    for doc in [&doc_a, &doc_b, &doc_c] {
+        let map = doc.get_map("form");
+        assert_eq!(
+            values(&map, "name"),
+            vec![None, Some("b".into()), Some("a".into())]
+        );
+        assert_eq!(values(&map, "other"), vec![Some(1.into())]);
+    }
+
+    // the resolution wins even if it picks the current value
+    doc_b.get_map("form").resolve("name", "b")?;
+    doc_b.get_map("form").resolve("other", 1)?;
+    doc_b.commit();
+    sync(&doc_a, &doc_b)?;
+    sync(&doc_c, &doc_b)?;
+    for doc in [&doc_a, &doc_b, &doc_c] {
+        let map = doc.get_map("form");
+        assert_eq!(values(&map, "name"), vec![Some("b".into())]);
+        assert_eq!(map.get_conflicts("other")?[0].idlp.peer, 2);
+    }
+    Ok(())
+}
+
+#[test]
+fn conflicts_follow_checkout() -> LoroResult<()> {
+    let doc_a = new_peer(1)?;
+    let doc_b = new_peer(2)?;
+    doc_a.get_map("form").insert("name", "a")?;
+    doc_b.get_map("form").insert("name", "b")?;
+    sync(&doc_a, &doc_b)?;
+    let conflicted = doc_a.state_frontiers();
+    doc_a.get_map("form").resolve("name", "a")?;
+    doc_a.commit();
+    assert_eq!(
+        values(&doc_a.get_map("form"), "name"),
+        vec![Some("a".into())]
+    );
+
+    doc_a.checkout(&conflicted)?;
+    assert_eq!(
+        values(&doc_a.get_map("form"), "name"),
+        vec![Some("b".into()), Some("a".into())]
+    );
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use std::ops::Bound;
+
+use loro::{ExportMode, LoroDoc, LoroMap, LoroResult, LoroText, LoroValue, ValueOrContainer};
+use pretty_assertions::assert_eq;
+
+fn keys(entries: Vec<(String, ValueOrContainer)>) -> Vec<String> {
+    entries.into_iter().map(|(k, _)| k).collect()
+}
+
+fn new_peer(peer: u64) -> LoroResult<LoroDoc> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(peer)?;
+    Ok(doc)
+}
+
+fn fill(map: &LoroMap, n: usize) -> LoroResult<()> {
+    // Insert out of order so the result order comes from the map itself
+    for i in (0..n).rev() {
+        map.insert(&format!("k{i:03}"), i as i64)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn range_returns_sorted_entries_within_bounds() -> LoroResult<()> {
+    // 3 keys use the small sorted vec, 40 keys use the B-tree
+    for n in [3, 40] {
+        let doc = LoroDoc::new();
+        let map = doc.get_map("m");
+        fill(&map, n)?;
+
+        let all = keys(map.range::<&str>(..));
+        let mut expected: Vec<String> = (0..n).map(|i| format!("k{i:03}")).collect();
+        assert_eq!(all, expected);
+
+        assert_eq!(keys(map.range("k001".."k002")), vec!["k001".to_string()]);
+        assert_eq!(
+            keys(map.range("k001"..="k002")),
+            vec!["k001".to_string(), "k002".to_string()]
+        );
+        assert_eq!(
+            keys(map.range::<&str>((Bound::Excluded("k000"), Bound::Included("k001")))),
+            vec!["k001".to_string()]
+        );
+        assert_eq!(keys(map.range(..="k000")), vec!["k000".to_string()]);
+        expected.remove(0);
+        assert_eq!(keys(map.range("k0001"..)), expected);
+
+        let (key, value) = map.range("k002"..="k002").pop().unwrap();
+        assert_eq!(key, "k002");
+        assert_eq!(value.into_value().unwrap(), LoroValue::I64(2));
+    }
+    Ok(())
+}
+
+#[test]
+fn empty_and_inverted_ranges_are_empty() -> LoroResult<()> {
+    for n in [3, 40] {
+        let doc = LoroDoc::new();
+        let map = doc.get_map("m");
+        fill(&map, n)?;
+        assert!(map.range("k002".."k001").is_empty());
+        assert!(map.range("k001".."k001").is_empty());
+        assert!(map
+            .range::<&str>((Bound::Excluded("k001"), Bound::Excluded("k001")))
+            .is_empty());
+        assert!(map.range("z"..).is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn deleted_keys_are_skipped_by_range_first_and_last() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("m");
+    assert!(map.first().is_none());
+    assert!(map.last().is_none());
+
+    fill(&map, 10)?;
+    map.delete("k000")?;
+    map.delete("k009")?;
+    map.delete("k004")?;
+    doc.commit();
+
+    assert_eq!(map.first().unwrap().0, "k001");
+    assert_eq!(map.last().unwrap().0, "k008");
+    assert_eq!(
+        keys(map.range("k003".."k006")),
+        vec!["k003".to_string(), "k005".to_string()]
+    );
+
+    map.clear()?;
+    assert!(map.first().is_none());
+    assert!(map.range::<&str>(..).is_empty());
+    Ok(())
+}
+
+#[test]
+fn range_follows_lww_merge_across_peers() -> LoroResult<()> {
+    let a = new_peer(1)?;
+    let b = new_peer(2)?;
+    a.get_map("m").insert("2024-01-01", "a")?;
+    a.get_map("m").insert("2024-01-03", "a")?;
+    b.get_map("m").insert("2024-01-01", "b")?;
+    b.get_map("m").insert("2024-01-02", "b")?;
+    a.commit();
+    b.commit();
+    a.import(&b.export(ExportMode::all_updates())?)?;
+    b.import(&a.export(ExportMode::all_updates())?)?;
+
+    let entries = |doc: &LoroDoc| -> Vec<(String, LoroValue)> {
+        doc.get_map("m")
+            .range("2024-01-01".."2024-01-03")
+            .into_iter()
+            .map(|(k, v)| (k, v.get_deep_value()))
+            .collect()
+    };
+    assert_eq!(entries(&a), entries(&b));
+    assert_eq!(
+        entries(&a),
+        vec![
+            ("2024-01-01".to_string(), LoroValue::from("b")),
+            ("2024-01-02".to_string(), LoroValue::from("b")),
+        ]
+    );
+    assert_eq!(a.get_map("m").last().unwrap().0, "2024-01-03");
+    Ok(())
+}
+
+#[test]
+fn range_works_after_snapshot_import() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    fill(&doc.get_map("m"), 20)?;
+    doc.commit();
+
+    let restored = LoroDoc::new();
+    restored.import(&doc.export(ExportMode::snapshot())?)?;
+    let map = restored.get_map("m");
+    assert_eq!(
+        keys(map.range("k010".."k013")),
+        vec!["k010".to_string(), "k011".to_string(), "k012".to_string()]
+    );
+    assert_eq!(map.first().unwrap().0, "k000");
+    assert_eq!(map.last().unwrap().0, "k019");
+    Ok(())
+}
+
+#[test]
+fn range_returns_child_containers_and_works_on_detached_maps() -> LoroResult<()> {
+    let detached = LoroMap::new();
+    detached.insert("b", 2)?;
+    detached.insert("a", 1)?;
+    detached.insert("c", 3)?;
+    assert_eq!(
+        keys(detached.range("b"..)),
+        vec!["b".to_string(), "c".to_string()]
+    );
+    assert_eq!(detached.first().unwrap().0, "a");
+    assert_eq!(detached.last().unwrap().0, "c");
+
+    let doc = LoroDoc::new();
+    let map = doc.get_map("m");
+    let text = map.insert_container("note", LoroText::new())?;
+    text.insert(0, "hi")?;
+    let (key, value) = map.first().unwrap();
+    assert_eq!(key, "note");
+    let child = value.into_container().unwrap().into_text().unwrap();
+    assert_eq!(child.to_string(), "hi");
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use loro::{ExportMode, IdSpan, LoroDoc, LoroError, LoroResult, ToJson};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+fn sync(a: &LoroDoc, b: &LoroDoc) -> LoroResult<()> {
+    b.import(&a.export(ExportMode::updates(&b.oplog_vv())).unwrap())?;
+    a.import(&b.export(ExportMode::updates(&a.oplog_vv())).unwrap())?;
+    Ok(())
+}
+
+#[test]
+fn reverting_another_peers_change_keeps_later_edits() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_text("text").insert(0, "abc")?;
+    a.commit();
+    sync(&a, &b)?;
+
+    // Peer 2 deletes "b", while peer 1 concurrently appends to the text
+    b.get_text("text").delete(1, 1)?;
+    b.commit();
+    a.get_text("text").insert(3, "d")?;
+    a.commit();
+    sync(&a, &b)?;
+    a.get_text("text").insert(0, ">")?;
+    a.commit();
+    assert_eq!(a.get_text("text").to_string(), ">acd");
+
+    a.revert_changes(&[IdSpan::new(2, 0, 1)])?;
+    assert_eq!(a.get_text("text").to_string(), ">abcd");
+
+    // The revert is a local change that can be synced like any other one
+    assert_eq!(a.oplog_vv().get(&1).copied(), Some(6));
+    sync(&a, &b)?;
+    assert_eq!(b.get_text("text").to_string(), ">abcd");
+    Ok(())
+}
+
+#[test]
+fn map_and_list_changes_are_reverted_selectively() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_map("map").insert("title", "draft")?;
+    a.get_list("list").push(1)?;
+    a.commit();
+    sync(&a, &b)?;
+
+    b.get_map("map").insert("title", "vandalized")?;
+    b.get_list("list").push("spam")?;
+    b.commit();
+    sync(&a, &b)?;
+    a.get_map("map").insert("author", "alice")?;
+    a.get_list("list").push(2)?;
+    a.commit();
+
+    a.revert_changes(&[IdSpan::new(2, 0, 2)])?;
+    assert_eq!(
+        a.get_deep_value().to_json_value(),
+        json!({ "map": { "title": "draft", "author": "alice" }, "list": [1, 2] })
+    );
+    Ok(())
+}
+
+#[test]
+fn several_spans_are_reverted_together() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    let c = LoroDoc::new();
+    c.set_peer_id(3)?;
+    a.get_text("text").insert(0, "base")?;
+    a.commit();
+    sync(&a, &b)?;
+    sync(&a, &c)?;
+
+    b.get_text("text").insert(0, "[b]")?;
+    b.commit();
+    c.get_text("text").insert(4, "[c]")?;
+    c.commit();
+    sync(&a, &b)?;
+    sync(&a, &c)?;
+    sync(&a, &b)?;
+    a.get_text("text").insert(2, "-")?;
+    a.commit();
+    assert_eq!(a.get_text("text").to_string(), "[b-]base[c]");
+
+    a.revert_changes(&[IdSpan::new(3, 0, 3), IdSpan::new(2, 0, 3)])?;
+    assert_eq!(a.get_text("text").to_string(), "-base");
+    Ok(())
+}
+
+#[test]
+fn invalid_spans_are_rejected() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    doc.get_text("text").insert(0, "hello")?;
+    doc.commit();
+
+    assert!(matches!(
+        doc.revert_changes(&[IdSpan::new(2, 0, 1)]),
+        Err(LoroError::UndoInvalidIdSpan(_))
+    ));
+    assert!(matches!(
+        doc.revert_changes(&[IdSpan::new(1, 0, 10)]),
+        Err(LoroError::UndoInvalidIdSpan(_))
+    ));
+    doc.revert_changes(&[])?;
+    doc.revert_changes(&[IdSpan::new(1, 2, 2)])?;
+    assert_eq!(doc.get_text("text").to_string(), "hello");
+
+    // Reverting a part of a change is fine
+    doc.revert_changes(&[IdSpan::new(1, 3, 5)])?;
+    assert_eq!(doc.get_text("text").to_string(), "hel");
+    Ok(())
+}
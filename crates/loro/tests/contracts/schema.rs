@@ -0,0 +1,292 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use loro::{
+    DocSchema, ExportMode, LoroDoc, LoroError, LoroList, LoroMap, LoroResult, LoroText, LoroValue,
+    MapSchema, SchemaViolation, SchemaViolationCallback, ToJson, TreeParentId, ValueSchema,
+};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+fn user_schema() -> DocSchema {
+    DocSchema::new()
+        .required_root(
+            "users",
+            ValueSchema::list(
+                MapSchema::new()
+                    .required_field("name", ValueSchema::String)
+                    .field("age", ValueSchema::nullable(ValueSchema::Int))
+                    .field("bio", ValueSchema::Text)
+                    .deny_unknown_fields(),
+            ),
+        )
+        .root(
+            "settings",
+            MapSchema::new().field("theme", ValueSchema::String),
+        )
+}
+
+fn paths(violations: &[SchemaViolation]) -> Vec<String> {
+    violations.iter().map(|v| v.path.clone()).collect()
+}
+
+fn collector() -> (Arc<Mutex<Vec<SchemaViolation>>>, SchemaViolationCallback) {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_clone = seen.clone();
+    (
+        seen,
+        Arc::new(move |violations: &[SchemaViolation]| {
+            seen_clone.lock().unwrap().extend_from_slice(violations);
+        }),
+    )
+}
+
+fn push_user(users: &LoroList, name: impl Into<LoroValue>) -> LoroResult<LoroMap> {
+    let user = users.push_container(LoroMap::new())?;
+    user.insert("name", name)?;
+    Ok(user)
+}
+
+#[test]
+fn validate_reports_json_paths_for_each_violation() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let schema = user_schema();
+    assert_eq!(paths(&doc.validate_schema(&schema)?), vec!["$['users']"]);
+
+    let users = doc.get_list("users");
+    push_user(&users, "alice")?;
+    let bob = push_user(&users, 42)?;
+    bob.insert("age", "old")?;
+    bob.insert("nickname", "b")?;
+    let carol = users.push_container(LoroMap::new())?;
+    carol.insert("age", LoroValue::Null)?;
+    carol.insert("bio", "plain string")?;
+    users.push("not a map")?;
+    doc.get_map("settings").insert("theme", 1)?;
+    doc.commit();
+
+    let violations = doc.validate_schema(&schema)?;
+    assert_eq!(
+        paths(&violations),
+        vec![
+            "$['settings']['theme']",
+            "$['users'][1]['age']",
+            "$['users'][1]['name']",
+            "$['users'][1]['nickname']",
+            "$['users'][2]['name']",
+            "$['users'][2]['bio']",
+            "$['users'][3]",
+        ]
+    );
+    assert_eq!(
+        violations[1].message,
+        "expected one of [int, null], found string"
+    );
+    assert_eq!(violations[3].message, "unknown field");
+    assert_eq!(violations[4].message, "missing required field");
+    assert_eq!(
+        violations[6].to_string(),
+        "$['users'][3]: expected Map container, found string"
+    );
+    Ok(())
+}
+
+#[test]
+fn tree_node_meta_is_checked_with_nested_paths() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let schema = DocSchema::new().root(
+        "outline",
+        ValueSchema::tree(Some(
+            MapSchema::new().required_field("title", ValueSchema::String),
+        )),
+    );
+    let tree = doc.get_tree("outline");
+    let root = tree.create(TreeParentId::Root)?;
+    tree.get_meta(root)?.insert("title", "root")?;
+    let child = tree.create(root)?;
+    tree.get_meta(child)?.insert("title", 1)?;
+    doc.commit();
+
+    let violations = doc.validate_schema(&schema)?;
+    assert_eq!(
+        paths(&violations),
+        vec!["$['outline'][0]['children'][0]['meta']['title']"]
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_schemas_are_rejected() {
+    let doc = LoroDoc::new();
+    let (_, callback) = collector();
+    assert!(matches!(
+        doc.validate_schema(&DocSchema::new().root("n", ValueSchema::Int)),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(matches!(
+        doc.enforce_schema(DocSchema::new().root("n", ValueSchema::Any), callback),
+        Err(LoroError::ArgErr(_))
+    ));
+}
+
+#[test]
+fn enforce_schema_rolls_back_rejected_commits() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let (seen, callback) = collector();
+    let _sub = doc.enforce_schema(user_schema(), callback)?;
+
+    let users = doc.get_list("users");
+    let alice = push_user(&users, "alice")?;
+    doc.commit();
+    let vv = doc.oplog_vv();
+    let value = doc.get_deep_value();
+    assert!(seen.lock().unwrap().is_empty());
+
+    let events = Arc::new(AtomicUsize::new(0));
+    let events_clone = events.clone();
+    let _events = doc.subscribe_root(Arc::new(move |_| {
+        events_clone.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    // A rejected commit that mixes valid and invalid edits is dropped as a whole
+    let bio = alice.insert_container("bio", LoroText::new())?;
+    bio.insert(0, "hello")?;
+    alice.insert("age", "unknown")?;
+    push_user(&users, "bob")?;
+    doc.commit();
+
+    assert_eq!(
+        doc.last_commit_rejection().as_deref(),
+        Some("schema violation at $['users'][0]['age']: expected one of [int, null], found string")
+    );
+    assert_eq!(paths(&seen.lock().unwrap()), vec!["$['users'][0]['age']"]);
+    assert_eq!(doc.get_deep_value(), value);
+    assert_eq!(doc.oplog_vv(), vv);
+    assert_eq!(users.len(), 1);
+    assert_eq!(events.load(Ordering::SeqCst), 0);
+
+    // The next valid commit reuses the ids of the rejected one
+    alice.insert("age", 30)?;
+    doc.commit();
+    assert_eq!(doc.last_commit_rejection(), None);
+    assert_eq!(events.load(Ordering::SeqCst), 1);
+    let other = LoroDoc::new();
+    other.import(&doc.export(ExportMode::all_updates())?)?;
+    assert_eq!(
+        other.get_deep_value().to_json_value(),
+        json!({ "users": [{ "name": "alice", "age": 30 }] })
+    );
+    Ok(())
+}
+
+#[test]
+fn enforce_schema_rejects_commits_that_miss_required_roots() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let (seen, callback) = collector();
+    let sub = doc.enforce_schema(user_schema(), callback)?;
+
+    doc.get_map("settings").insert("theme", "dark")?;
+    doc.commit();
+    assert_eq!(paths(&seen.lock().unwrap()), vec!["$['users']"]);
+    assert!(doc.get_map("settings").get("theme").is_none());
+
+    drop(sub);
+    doc.get_map("settings").insert("theme", "dark")?;
+    doc.commit();
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({ "settings": { "theme": "dark" } })
+    );
+    Ok(())
+}
+
+#[test]
+fn enforce_schema_only_checks_the_containers_a_commit_touches() -> LoroResult<()> {
+    let remote = LoroDoc::new();
+    push_user(&remote.get_list("users"), 7)?;
+    remote.commit();
+
+    let doc = LoroDoc::new();
+    doc.import(&remote.export(ExportMode::all_updates())?)?;
+    let (seen, callback) = collector();
+    let _sub = doc.enforce_schema(user_schema(), callback)?;
+
+    // The imported user is left alone by commits that do not touch it
+    doc.get_map("settings").insert("theme", "dark")?;
+    doc.commit();
+    let users = doc.get_list("users");
+    let bob = push_user(&users, "bob")?;
+    doc.commit();
+    assert_eq!(doc.last_commit_rejection(), None);
+    assert!(seen.lock().unwrap().is_empty());
+
+    // Editing it checks it, and the edit of its sibling is checked too
+    bob.insert("age", "old")?;
+    let first = users
+        .get(0)
+        .unwrap()
+        .into_container()
+        .unwrap()
+        .into_map()
+        .unwrap();
+    first.insert("age", 3)?;
+    doc.commit();
+    assert!(doc.last_commit_rejection().is_some());
+    assert_eq!(
+        paths(&seen.lock().unwrap()),
+        vec!["$['users'][0]['name']", "$['users'][1]['age']"]
+    );
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({ "users": [{ "name": 7 }, { "name": "bob" }], "settings": { "theme": "dark" } })
+    );
+    Ok(())
+}
+
+#[test]
+fn imported_violations_are_reported() -> LoroResult<()> {
+    let remote = LoroDoc::new();
+    push_user(&remote.get_list("users"), 7)?;
+    remote.commit();
+
+    let doc = LoroDoc::new();
+    let (seen, callback) = collector();
+    let _sub = doc.subscribe_schema_violations(user_schema(), callback)?;
+    doc.import(&remote.export(ExportMode::all_updates())?)?;
+
+    // Imported changes cannot be rejected, they are only reported
+    assert_eq!(paths(&seen.lock().unwrap()), vec!["$['users'][0]['name']"]);
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({ "users": [{ "name": 7 }] })
+    );
+
+    // Local edits do not trigger the import check
+    seen.lock().unwrap().clear();
+    doc.get_map("settings").insert("theme", 1)?;
+    doc.commit();
+    assert!(seen.lock().unwrap().is_empty());
+    Ok(())
+}
+
+#[cfg(feature = "jsonpath")]
+#[test]
+fn violation_paths_resolve_with_jsonpath() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let users = doc.get_list("users");
+    push_user(&users, "alice")?.insert("it's", true)?;
+    doc.commit();
+
+    let violations = doc.validate_schema(&user_schema())?;
+    assert_eq!(paths(&violations), vec!["$['users'][0]['it\\'s']"]);
+    let mut found = doc.jsonpath(&violations[0].path).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(
+        found.pop().unwrap().into_value().unwrap(),
+        LoroValue::Bool(true)
+    );
+    Ok(())
+}
@@ -0,0 +1,252 @@
+#![cfg(feature = "serde")]
+
+use std::collections::BTreeMap;
+
+use loro::{
+    serde::{from_container, to_container, to_value, Error},
+    ExportMode, LoroDoc, LoroMap, LoroResult, LoroText, LoroValue, ToJson,
+};
+use pretty_assertions::assert_eq;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Doc {
+    title: String,
+    body: String,
+    tags: Vec<String>,
+    sections: Vec<Section>,
+    meta: BTreeMap<String, i64>,
+    status: Status,
+    parent: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Section {
+    heading: String,
+    level: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+enum Status {
+    Draft,
+    Published { at: i64 },
+    Archived(String),
+}
+
+fn doc_value() -> Doc {
+    Doc {
+        title: "Notes".into(),
+        body: "hello world".into(),
+        tags: vec!["a".into(), "b".into(), "c".into()],
+        sections: vec![
+            Section {
+                heading: "Intro".into(),
+                level: 1,
+            },
+            Section {
+                heading: "Usage".into(),
+                level: 2,
+            },
+        ],
+        meta: BTreeMap::from([("views".to_string(), 3)]),
+        status: Status::Draft,
+        parent: None,
+    }
+}
+
+/// Write `value` and return the number of ops it produced.
+fn ops_of_write(doc: &LoroDoc, map: &LoroMap, value: &Doc) -> Result<usize, Error> {
+    let before = doc.len_ops();
+    to_container(value, map)?;
+    doc.commit();
+    Ok(doc.len_ops() - before)
+}
+
+#[test]
+fn round_trip_through_a_map() -> Result<(), Error> {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("doc");
+    let value = doc_value();
+    to_container(&value, &map)?;
+    doc.commit();
+
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({
+            "doc": {
+                "title": "Notes",
+                "body": "hello world",
+                "tags": ["a", "b", "c"],
+                "sections": [
+                    { "heading": "Intro", "level": 1 },
+                    { "heading": "Usage", "level": 2 },
+                ],
+                "meta": { "views": 3 },
+                "status": "Draft",
+                "parent": null,
+            }
+        })
+    );
+    assert_eq!(from_container::<Doc, _>(&map)?, value);
+
+    let restored = LoroDoc::new();
+    restored.import(&doc.export(ExportMode::snapshot()).unwrap())?;
+    assert_eq!(from_container::<Doc, _>(&restored.get_map("doc"))?, value);
+    Ok(())
+}
+
+#[test]
+fn writes_only_emit_ops_for_changes() -> Result<(), Error> {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("doc");
+    let mut value = doc_value();
+    to_container(&value, &map)?;
+    doc.commit();
+    assert_eq!(ops_of_write(&doc, &map, &value)?, 0);
+
+    value.tags.insert(1, "x".into());
+    assert_eq!(ops_of_write(&doc, &map, &value)?, 1);
+
+    value.sections[1].level = 3;
+    value.parent = Some(7);
+    assert_eq!(ops_of_write(&doc, &map, &value)?, 2);
+
+    // Variant changes replace the value, and removed map entries are deleted
+    value.status = Status::Published { at: 10 };
+    value.meta.clear();
+    ops_of_write(&doc, &map, &value)?;
+    assert_eq!(from_container::<Doc, _>(&map)?, value);
+    assert_eq!(
+        map.get_deep_value().to_json_value()["status"],
+        json!({ "Published": { "at": 10 } })
+    );
+    Ok(())
+}
+
+#[test]
+fn existing_texts_are_updated_with_a_diff() -> Result<(), Error> {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("doc");
+    let mut value = doc_value();
+    to_container(&value, &map)?;
+    let body = map.insert_container("body", LoroText::new())?;
+    body.insert(0, "hello world")?;
+    doc.commit();
+
+    value.body = "hello brave world".into();
+    assert_eq!(ops_of_write(&doc, &map, &value)?, "brave ".len());
+    assert_eq!(body.to_string(), "hello brave world");
+    assert_eq!(from_container::<Doc, _>(&map)?, value);
+    Ok(())
+}
+
+#[test]
+fn concurrent_writes_merge() -> Result<(), Error> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let value = doc_value();
+    to_container(&value, &a.get_map("doc"))?;
+    a.commit();
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    b.import(&a.export(ExportMode::all_updates()).unwrap())?;
+
+    let mut from_a = value.clone();
+    from_a.tags.push("from a".into());
+    to_container(&from_a, &a.get_map("doc"))?;
+    a.commit();
+    let mut from_b = value.clone();
+    from_b.title = "Renamed".into();
+    to_container(&from_b, &b.get_map("doc"))?;
+    b.commit();
+
+    a.import(&b.export(ExportMode::all_updates()).unwrap())?;
+    let merged = from_container::<Doc, _>(&a.get_map("doc"))?;
+    assert_eq!(merged.title, "Renamed");
+    assert_eq!(merged.tags, vec!["a", "b", "c", "from a"]);
+    Ok(())
+}
+
+#[test]
+fn lists_and_texts_accept_matching_values() -> Result<(), Error> {
+    let doc = LoroDoc::new();
+    let list = doc.get_list("list");
+    to_container(&vec![(1, "one"), (2, "two")], &list)?;
+    assert_eq!(
+        list.get_deep_value().to_json_value(),
+        json!([[1, "one"], [2, "two"]])
+    );
+    to_container(&vec![(1, "one")], &list)?;
+    assert_eq!(
+        from_container::<Vec<(i32, String)>, _>(&list)?,
+        vec![(1, "one".into())]
+    );
+
+    let movable = doc.get_movable_list("movable");
+    to_container(&["a", "b", "c"], &movable)?;
+    let first = movable.insert_container(0, LoroMap::new())?;
+    first.insert("k", 1)?;
+    to_container(&vec![json!({ "k": 2 }), json!("b")], &movable)?;
+    assert_eq!(
+        movable.get_deep_value().to_json_value(),
+        json!([{ "k": 2 }, "b"])
+    );
+
+    let text = doc.get_text("text");
+    to_container("hello", &text)?;
+    assert_eq!(from_container::<String, _>(&text)?, "hello");
+
+    // Mismatched shapes are rejected without touching the container
+    assert!(matches!(to_container(&42, &list), Err(Error::Message(_))));
+    assert!(matches!(
+        to_container(&doc_value(), &doc.get_list("other")),
+        Err(Error::Message(_))
+    ));
+    assert_eq!(list.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn structs_only_read_declared_fields() -> Result<(), Error> {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Title {
+        title: String,
+    }
+
+    let doc = LoroDoc::new();
+    let map = doc.get_map("doc");
+    to_container(&doc_value(), &map)?;
+    map.insert("extra", vec![1, 2, 3])?;
+    assert_eq!(
+        from_container::<Title, _>(&map)?,
+        Title {
+            title: "Notes".into()
+        }
+    );
+
+    map.insert("title", 1)?;
+    let Err(Error::Message(msg)) = from_container::<Title, _>(&map) else {
+        panic!("expected a type error");
+    };
+    assert!(msg.contains("expected a string"), "{msg}");
+    Ok(())
+}
+
+#[test]
+fn to_value_matches_the_written_shape() -> LoroResult<()> {
+    let value = to_value(&doc_value()).unwrap();
+    let doc = LoroDoc::new();
+    let map = doc.get_map("doc");
+    to_container(&doc_value(), &map).unwrap();
+    assert_eq!(map.get_deep_value(), value);
+    assert!(matches!(to_value(&u64::MAX), Err(Error::Message(_))));
+    assert_eq!(
+        to_value(&BTreeMap::from([(1, true)])).unwrap(),
+        LoroValue::from(std::collections::HashMap::from([(
+            "1".to_string(),
+            LoroValue::Bool(true)
+        )]))
+    );
+    Ok(())
+}
@@ -0,0 +1,172 @@
+#![cfg(feature = "set")]
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use loro::{
+    event::Diff, Container, ContainerTrait, ContainerType, ExportMode, LoroDoc, LoroList,
+    LoroResult, LoroSet, LoroValue, ToJson,
+};
+use serde_json::json;
+
+fn sync(a: &LoroDoc, b: &LoroDoc) -> LoroResult<()> {
+    b.import(&a.export(ExportMode::updates(&b.oplog_vv()))?)?;
+    a.import(&b.export(ExportMode::updates(&a.oplog_vv()))?)?;
+    Ok(())
+}
+
+#[test]
+fn set_add_remove_and_value_follow_contract() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let set = doc.get_set("tags");
+    assert!(set.is_empty());
+
+    assert!(set.add("a")?);
+    assert!(set.add(1)?);
+    assert!(!set.add("a")?);
+    assert!(set.contains("a"));
+    assert!(set.contains(1));
+    assert!(!set.contains("b"));
+    assert_eq!(set.len(), 2);
+    let values: HashSet<LoroValue> = set
+        .get_value()
+        .into_list()
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect();
+    assert_eq!(values, HashSet::from_iter(["a".into(), 1.into()]));
+
+    assert!(set.remove("a")?);
+    assert!(!set.remove("a")?);
+    doc.commit();
+    assert_eq!(doc.get_deep_value().to_json_value(), json!({ "tags": [1] }));
+
+    set.clear()?;
+    assert!(set.is_empty());
+    assert_eq!(doc.get_deep_value().to_json_value(), json!({ "tags": [] }));
+    Ok(())
+}
+
+#[test]
+fn set_rejects_container_values() {
+    let doc = LoroDoc::new();
+    let set = doc.get_set("tags");
+    let container = LoroValue::Container(LoroList::new().id());
+    assert!(set.add(container).is_err());
+    assert!(set.is_empty());
+}
+
+#[test]
+fn concurrent_add_wins_over_remove() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_set("tags").add("x")?;
+    a.commit();
+    sync(&a, &b)?;
+
+    // `a` removes the value while `b` adds it again concurrently.
+    assert!(a.get_set("tags").remove("x")?);
+    a.commit();
+    b.get_set("tags").remove("x")?;
+    assert!(b.get_set("tags").add("x")?);
+    b.commit();
+    sync(&a, &b)?;
+
+    assert!(a.get_set("tags").contains("x"));
+    assert_eq!(a.get_deep_value(), b.get_deep_value());
+
+    // A remove that has seen every add removes the value everywhere.
+    assert!(b.get_set("tags").remove("x")?);
+    b.commit();
+    sync(&a, &b)?;
+    assert!(a.get_set("tags").is_empty());
+    assert!(b.get_set("tags").is_empty());
+    Ok(())
+}
+
+#[test]
+fn set_survives_snapshot_and_checkout() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let set = doc.get_set("tags");
+    set.add("a")?;
+    doc.commit();
+    let v1 = doc.state_frontiers();
+    set.add("b")?;
+    set.remove("a")?;
+    doc.commit();
+
+    let restored = LoroDoc::new();
+    restored.import(&doc.export(ExportMode::snapshot())?)?;
+    assert_eq!(
+        restored.get_deep_value().to_json_value(),
+        json!({ "tags": ["b"] })
+    );
+
+    restored.checkout(&v1)?;
+    assert_eq!(
+        restored.get_deep_value().to_json_value(),
+        json!({ "tags": ["a"] })
+    );
+    restored.checkout_to_latest();
+    assert_eq!(
+        restored.get_set("tags").get_value().to_json_value(),
+        json!(["b"])
+    );
+    Ok(())
+}
+
+#[test]
+fn detached_set_attaches_with_its_values() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let detached = LoroSet::new();
+    detached.add("a")?;
+    assert!(!detached.is_attached());
+
+    let attached = doc.get_map("root").insert_container("tags", detached)?;
+    assert!(attached.is_attached());
+    assert!(attached.contains("a"));
+    let child = doc
+        .get_map("root")
+        .get("tags")
+        .unwrap()
+        .into_container()
+        .unwrap();
+    assert_eq!(child.get_type(), ContainerType::Set);
+    assert!(matches!(child, Container::Set(_)));
+    Ok(())
+}
+
+#[test]
+fn set_events_report_added_and_removed_values() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let captured = Arc::clone(&events);
+    let _sub = doc.subscribe_root(Arc::new(move |event| {
+        for container_diff in event.events {
+            if let Diff::Set(diff) = container_diff.diff {
+                captured.lock().unwrap().push(diff.into_owned());
+            }
+        }
+    }));
+
+    let set = doc.get_set("tags");
+    set.add("a")?;
+    doc.commit();
+    set.remove("a")?;
+    set.add("b")?;
+    doc.commit();
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].added, vec![LoroValue::from("a")]);
+    assert!(events[0].removed.is_empty());
+    assert_eq!(events[1].added, vec![LoroValue::from("b")]);
+    assert_eq!(events[1].removed, vec![LoroValue::from("a")]);
+    Ok(())
+}
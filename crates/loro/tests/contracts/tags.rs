@@ -0,0 +1,87 @@
+use loro::{ExportMode, LoroDoc, LoroError, LoroResult, ToJson};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+#[test]
+fn tags_sync_and_stay_out_of_the_value() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    a.get_map("map").insert("title", "draft")?;
+    a.commit();
+    let v1 = a.state_frontiers();
+    a.create_tag("v1 published", &v1)?;
+    a.get_map("map").insert("title", "final")?;
+    a.commit();
+
+    let b = LoroDoc::new();
+
+    b.set_peer_id(2)?;
+    b.import(&a.export(ExportMode::all_updates()).unwrap())?;
+    assert_eq!(b.tags(), vec![("v1 published".to_string(), v1.clone())]);
+    assert_eq!(
+        b.get_deep_value().to_json_value(),
+        json!({ "map": { "title": "final" } })
+    );
+
+    b.checkout_tag("v1 published")?;
+    assert!(b.is_detached());
+    assert_eq!(
+        b.get_deep_value().to_json_value(),
+        json!({ "map": { "title": "draft" } })
+    );
+    // Tags can be read while detached
+    assert_eq!(b.tag("v1 published"), Some(v1));
+    Ok(())
+}
+
+#[test]
+fn tags_survive_shallow_snapshots_inside_the_retained_history() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let text = doc.get_text("text");
+    text.insert(0, "a")?;
+    doc.commit();
+    doc.create_tag("old", &doc.state_frontiers())?;
+    text.insert(1, "b")?;
+    doc.commit();
+    let shallow_start = doc.state_frontiers();
+    doc.create_tag("start", &shallow_start)?;
+    text.insert(2, "c")?;
+    doc.commit();
+    doc.create_tag("latest", &doc.state_frontiers())?;
+
+    let shallow = LoroDoc::from_snapshot(
+        &doc.export(ExportMode::shallow_snapshot(&shallow_start))
+            .unwrap(),
+    )?;
+    let names: Vec<String> = shallow.tags().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["latest".to_string(), "start".to_string()]);
+    shallow.checkout_tag("start")?;
+    assert_eq!(shallow.get_text("text").to_string(), "ab");
+    assert!(matches!(
+        shallow.checkout_tag("old"),
+        Err(LoroError::NotFoundError(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn tag_names_are_unique() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    doc.get_text("text").insert(0, "a")?;
+    doc.commit();
+    let v = doc.state_frontiers();
+    doc.create_tag("v", &v)?;
+    assert!(matches!(doc.create_tag("v", &v), Err(LoroError::ArgErr(_))));
+
+    doc.delete_tag("v")?;
+    assert!(doc.tags().is_empty());
+    assert!(matches!(
+        doc.delete_tag("v"),
+        Err(LoroError::NotFoundError(_))
+    ));
+    doc.create_tag("v", &v)?;
+    assert_eq!(doc.tag("v"), Some(v));
+    Ok(())
+}
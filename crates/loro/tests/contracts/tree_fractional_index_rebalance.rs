@@ -0,0 +1,135 @@
+use loro::{
+    ExportMode, LoroDoc, LoroError, LoroResult, LoroTree, LoroTreeError, TreeID, TreeParentId,
+};
+use pretty_assertions::assert_eq;
+
+fn insert_in_middle(tree: &LoroTree, parent: TreeParentId, n: usize) -> LoroResult<()> {
+    for _ in 0..n {
+        let len = tree.children_num(parent).unwrap_or(0);
+        tree.create_at(parent, len / 2)?;
+    }
+    Ok(())
+}
+
+fn positions(tree: &LoroTree, ids: &[TreeID]) -> Vec<String> {
+    ids.iter()
+        .map(|id| tree.fractional_index(*id).unwrap())
+        .collect()
+}
+
+#[test]
+fn stats_detect_growth_and_rebalance_shortens_keys() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let tree = doc.get_tree("tree");
+    tree.enable_fractional_index(0);
+    let parent = tree.create(TreeParentId::Root)?;
+    let stats = tree.fractional_index_stats(parent).unwrap();
+    assert_eq!(stats.count, 0);
+    assert_eq!(stats.max_len, 0);
+
+    insert_in_middle(&tree, parent.into(), 100)?;
+    let stats = tree.fractional_index_stats(parent).unwrap();
+    assert_eq!(stats.count, 100);
+    assert!(stats.max_len > 40);
+    assert!(stats.avg_len > 10.0);
+
+    let children = tree.children(parent).unwrap();
+    assert!(!tree.rebalance_fractional_index_if_needed(parent, stats.max_len)?);
+    assert!(tree.rebalance_fractional_index_if_needed(parent, 8)?);
+    assert_eq!(tree.children(parent).unwrap(), children);
+    let stats = tree.fractional_index_stats(parent).unwrap();
+    assert_eq!(stats.max_len, 2);
+    assert!(positions(&tree, &children).windows(2).all(|w| w[0] < w[1]));
+
+    let other = LoroDoc::new();
+    other.import(&doc.export(ExportMode::Snapshot)?)?;
+    assert_eq!(other.get_tree("tree").children(parent).unwrap(), children);
+    Ok(())
+}
+
+#[test]
+fn rebalance_range_only_touches_that_range() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let tree = doc.get_tree("tree");
+    tree.enable_fractional_index(0);
+    insert_in_middle(&tree, TreeParentId::Root, 30)?;
+    let roots = tree.roots();
+    let before = positions(&tree, &roots);
+
+    tree.rebalance_fractional_index(TreeParentId::Root, 5..25)?;
+    let after = positions(&tree, &roots);
+    assert_eq!(tree.roots(), roots);
+    assert_eq!(after[..5], before[..5]);
+    assert_eq!(after[25..], before[25..]);
+    assert!(after.windows(2).all(|w| w[0] < w[1]));
+    let longest = |keys: &[String]| keys.iter().map(|k| k.len()).max().unwrap();
+    assert!(longest(&after[5..25]) < longest(&before[5..25]));
+    Ok(())
+}
+
+#[test]
+fn rebalance_merges_with_concurrent_inserts() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let tree_a = doc_a.get_tree("tree");
+    tree_a.enable_fractional_index(0);
+    insert_in_middle(&tree_a, TreeParentId::Root, 40)?;
+    doc_a.commit();
+    let roots = tree_a.roots();
+
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_b.import(&doc_a.export(ExportMode::Snapshot)?)?;
+    let tree_b = doc_b.get_tree("tree");
+    tree_b.enable_fractional_index(0);
+
+    tree_a.rebalance_fractional_index(TreeParentId::Root, 0..roots.len())?;
+    let x = tree_b.create_at(TreeParentId::Root, 20)?;
+    let y = tree_b.create_at(TreeParentId::Root, 0)?;
+    doc_a.import(&doc_b.export(ExportMode::all_updates())?)?;
+    doc_b.import(&doc_a.export(ExportMode::all_updates())?)?;
+
+    let merged = tree_a.roots();
+    assert_eq!(merged, tree_b.roots());
+    assert_eq!(merged.len(), roots.len() + 2);
+    assert!(merged.contains(&x));
+    assert!(merged.contains(&y));
+    let old_order: Vec<_> = merged
+        .iter()
+        .filter(|id| **id != x && **id != y)
+        .copied()
+        .collect();
+    assert_eq!(old_order, roots);
+    Ok(())
+}
+
+#[test]
+fn rebalance_rejects_invalid_range_and_disabled_index() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let tree = doc.get_tree("tree");
+    tree.enable_fractional_index(0);
+    insert_in_middle(&tree, TreeParentId::Root, 3)?;
+    assert!(matches!(
+        tree.rebalance_fractional_index(TreeParentId::Root, 1..4),
+        Err(LoroError::TreeError(LoroTreeError::IndexOutOfBound { .. }))
+    ));
+
+    tree.disable_fractional_index();
+    assert!(tree.fractional_index_stats(TreeParentId::Root).is_none());
+    assert!(matches!(
+        tree.rebalance_fractional_index(TreeParentId::Root, 0..3),
+        Err(LoroError::TreeError(
+            LoroTreeError::FractionalIndexNotEnabled
+        ))
+    ));
+    assert!(!tree.rebalance_fractional_index_if_needed(TreeParentId::Root, 0)?);
+
+    let detached = LoroTree::new();
+    detached.create(TreeParentId::Root)?;
+    assert!(matches!(
+        detached.rebalance_fractional_index(TreeParentId::Root, 0..1),
+        Err(LoroError::MisuseDetachedContainer { .. })
+    ));
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use loro::{loro_value, LoroDoc, LoroError, LoroResult, LoroTree, ToJson, TreeParentId};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+
+fn strip_ids(value: &Value) -> Value {
+    Value::Array(
+        value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| {
+                json!({
+                    "meta": node["meta"],
+                    "children": strip_ids(&node["children"]),
+                })
+            })
+            .collect(),
+    )
+}
+
+#[test]
+fn nested_value_round_trips_into_another_doc() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let tree = doc.get_tree("tree");
+    tree.enable_fractional_index(0);
+    let root = tree.create(TreeParentId::Root)?;
+    let a = tree.create_at(root, 0)?;
+    let b = tree.create_at(root, 1)?;
+    let a1 = tree.create(a)?;
+    tree.get_meta(root)?.insert("name", "root")?;
+    tree.get_meta(a)?.insert("name", "a")?;
+    tree.get_meta(b)?.insert("name", "b")?;
+    tree.get_meta(a1)?.insert("name", "a1")?;
+    tree.mov_before(b, a)?;
+    doc.commit();
+
+    let nested = tree.get_nested_value_with_meta();
+    let json = nested.to_json_value();
+    assert_eq!(json[0]["id"], json!(root.to_string()));
+    assert_eq!(
+        strip_ids(&json),
+        json!([{
+            "meta": {"name": "root"},
+            "children": [
+                {"meta": {"name": "b"}, "children": []},
+                {"meta": {"name": "a"}, "children": [
+                    {"meta": {"name": "a1"}, "children": []}
+                ]},
+            ]
+        }])
+    );
+
+    let other = LoroDoc::new();
+    other.set_peer_id(2)?;
+    let copy = other.get_tree("tree");
+    copy.enable_fractional_index(0);
+    let roots = copy.import_nested(TreeParentId::Root, &nested, true)?;
+    other.commit();
+    assert_eq!(roots.len(), 1);
+    assert_eq!(copy.roots(), roots);
+    assert_eq!(
+        strip_ids(&copy.get_nested_value_with_meta().to_json_value()),
+        strip_ids(&json)
+    );
+    // one create op per node plus one op per meta key
+    assert_eq!(other.len_ops(), 8);
+    assert_eq!(other.len_changes(), 1);
+    Ok(())
+}
+
+#[test]
+fn import_nested_appends_after_existing_children() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let tree = doc.get_tree("tree");
+    tree.enable_fractional_index(0);
+    let existing = tree.create(TreeParentId::Root)?;
+    let nodes = loro_value!([
+        {"meta": {"name": "x"}, "children": []},
+        {"meta": {"name": "y"}, "children": []}
+    ]);
+    let created = tree.import_nested(TreeParentId::Root, &nodes, false)?;
+    let mut expected = vec![existing];
+    expected.extend(created);
+    assert_eq!(tree.roots(), expected);
+    let positions: Vec<_> = expected
+        .iter()
+        .map(|id| tree.fractional_index(*id).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    Ok(())
+}
+
+#[test]
+fn import_nested_rejects_malformed_input_without_emitting_ops() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let tree = doc.get_tree("tree");
+    let bad_children = loro_value!([{"meta": {"name": "ok"}, "children": [1]}]);
+    assert!(matches!(
+        tree.import_nested(TreeParentId::Root, &bad_children, false),
+        Err(LoroError::ArgErr(_))
+    ));
+    let bad_position = loro_value!([{"fractional_index": "zz"}]);
+    assert!(matches!(
+        tree.import_nested(TreeParentId::Root, &bad_position, true),
+        Err(LoroError::ArgErr(_))
+    ));
+    doc.commit();
+    assert_eq!(doc.len_ops(), 0);
+    Ok(())
+}
+
+#[test]
+fn detached_tree_reports_and_preserves_fractional_indexes() -> LoroResult<()> {
+    let tree = LoroTree::new();
+    let a = tree.create(TreeParentId::Root)?;
+    let b = tree.create(TreeParentId::Root)?;
+    let json = tree.get_nested_value_with_meta().to_json_value();
+    let index_of = |i: usize| json[i]["fractional_index"].as_str().unwrap().to_string();
+    assert!(index_of(0) < index_of(1));
+
+    // A node placed between the two existing roots keeps its position, while a
+    // position that is already taken falls back to an appended one.
+    let between = format!("{}80", index_of(0));
+    let nodes = loro_value!([
+        {"fractional_index": between, "children": []},
+        {"fractional_index": index_of(0), "children": []}
+    ]);
+    let created = tree.import_nested(TreeParentId::Root, &nodes, true)?;
+    assert_eq!(tree.roots(), vec![a, created[0], b, created[1]]);
+    Ok(())
+}
+
+#[test]
+fn import_nested_falls_back_when_preserved_index_collides() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let tree = doc.get_tree("tree");
+    tree.enable_fractional_index(0);
+    let a = tree.create(TreeParentId::Root)?;
+    let b = tree.create(TreeParentId::Root)?;
+    let taken = tree.fractional_index(a).unwrap();
+    let nodes = loro_value!([{"fractional_index": taken.clone(), "children": []}]);
+    let created = tree.import_nested(TreeParentId::Root, &nodes, true)?;
+    doc.commit();
+    let roots = tree.roots();
+    assert_eq!(roots.len(), 3);
+    assert!(roots.contains(&created[0]));
+    let positions: Vec<_> = roots
+        .iter()
+        .map(|id| tree.fractional_index(*id).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(tree.fractional_index(a).unwrap(), taken);
+    assert!(tree.fractional_index(b).is_some());
+    Ok(())
+}
@@ -0,0 +1,157 @@
+#![cfg(feature = "typed-counter")]
+
+use loro::{CounterKind, ExportMode, LoroCounter, LoroDoc, LoroResult, ToJson, VersionVector};
+use serde_json::json;
+
+fn sync_counters(a: &LoroDoc, b: &LoroDoc) -> LoroResult<()> {
+    b.import(&a.export(ExportMode::updates(&b.oplog_vv()))?)?;
+    a.import(&b.export(ExportMode::updates(&a.oplog_vv()))?)?;
+    Ok(())
+}
+
+#[test]
+fn int_counter_keeps_exact_values_across_sync_snapshot_and_json() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let counter = doc.get_typed_counter("likes");
+    counter.increment_int(i64::MAX - 2)?;
+    counter.increment_int(3)?;
+    counter.decrement_int(2)?;
+    doc.commit();
+    assert_eq!(counter.kind(), CounterKind::Int);
+    assert_eq!(counter.get_i64(), Some(i64::MAX - 1));
+    assert!(counter.is_typed());
+    assert!(doc.get_counter("likes").increment_int(1).is_err());
+
+    let peer = LoroDoc::new();
+    peer.set_peer_id(2)?;
+    sync_counters(&doc, &peer)?;
+    peer.get_typed_counter("likes").increment_int(1)?;
+    peer.commit();
+    sync_counters(&doc, &peer)?;
+    assert_eq!(counter.get_i64(), Some(i64::MAX));
+
+    let snapshot = LoroDoc::from_snapshot(&doc.export(ExportMode::snapshot())?)?;
+    assert_eq!(
+        snapshot.get_typed_counter("likes").get_i64(),
+        Some(i64::MAX)
+    );
+    assert_eq!(snapshot.get_typed_counter("likes").kind(), CounterKind::Int);
+
+    let json_updates = doc.export_json_updates(&VersionVector::default(), &doc.oplog_vv());
+    let imported = LoroDoc::new();
+    imported.import_json_updates(json_updates)?;
+    assert_eq!(
+        imported.get_typed_counter("likes").get_i64(),
+        Some(i64::MAX)
+    );
+    Ok(())
+}
+
+#[test]
+fn bounded_counter_never_shows_below_its_bound_after_merges() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_typed_counter("stock").increment_bounded(10, 0)?;
+    a.commit();
+    sync_counters(&a, &b)?;
+
+    assert_eq!(a.get_typed_counter("stock").decrement_bounded(8, 0)?, 8);
+    a.commit();
+    assert_eq!(b.get_typed_counter("stock").decrement_bounded(8, 0)?, 8);
+    b.commit();
+    sync_counters(&a, &b)?;
+    assert_eq!(a.get_typed_counter("stock").get_i64(), Some(0));
+    assert_eq!(b.get_typed_counter("stock").get_i64(), Some(0));
+    assert_eq!(a.get_typed_counter("stock").decrement_bounded(1, 0)?, 0);
+
+    // the increment pays back the overdraft of the merged decrements first
+    a.get_typed_counter("stock").increment_bounded(10, 0)?;
+    a.commit();
+    sync_counters(&a, &b)?;
+    assert_eq!(b.get_typed_counter("stock").get_i64(), Some(4));
+    let restored = LoroDoc::from_snapshot(&a.export(ExportMode::snapshot())?)?;
+    assert_eq!(restored.get_typed_counter("stock").get_i64(), Some(4));
+    assert_eq!(
+        restored.get_typed_counter("stock").kind(),
+        CounterKind::Bounded
+    );
+    Ok(())
+}
+
+#[test]
+fn max_counter_merges_to_the_largest_value_and_checks_out_history() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    assert!(a.get_typed_counter("best").update_max(3.0)?);
+    a.commit();
+    let v1 = a.state_frontiers();
+    assert!(!a.get_typed_counter("best").update_max(2.0)?);
+    assert!(b.get_typed_counter("best").update_max(5.5)?);
+    b.commit();
+    sync_counters(&a, &b)?;
+    assert_eq!(a.get_typed_counter("best").get(), 5.5);
+    assert_eq!(b.get_typed_counter("best").get(), 5.5);
+    assert_eq!(a.get_typed_counter("best").kind(), CounterKind::Max);
+
+    a.checkout(&v1)?;
+    assert_eq!(a.get_typed_counter("best").get(), 3.0);
+    a.checkout_to_latest();
+    assert_eq!(a.get_typed_counter("best").get(), 5.5);
+
+    let json_updates = a.export_json_updates(&VersionVector::default(), &a.oplog_vv());
+    let imported = LoroDoc::new();
+    imported.import_json_updates(json_updates)?;
+    assert_eq!(imported.get_typed_counter("best").get(), 5.5);
+    Ok(())
+}
+
+#[test]
+fn concurrent_ops_of_other_kinds_are_folded_into_the_winner() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_typed_counter("c").increment(2.4)?;
+    b.get_typed_counter("c").increment_int(3)?;
+    a.commit();
+    b.commit();
+    sync_counters(&a, &b)?;
+    for doc in [&a, &b] {
+        assert_eq!(doc.get_typed_counter("c").kind(), CounterKind::Int);
+        assert_eq!(doc.get_typed_counter("c").get_i64(), Some(5));
+    }
+
+    let c = LoroDoc::new();
+    c.set_peer_id(3)?;
+    c.get_typed_counter("c").update_max(4.0)?;
+    c.commit();
+    sync_counters(&a, &c)?;
+    assert_eq!(a.get_typed_counter("c").kind(), CounterKind::Max);
+    assert_eq!(a.get_typed_counter("c").get(), 5.0);
+    assert_eq!(c.get_typed_counter("c").get(), 5.0);
+    Ok(())
+}
+
+#[test]
+fn detached_typed_counter_attaches_with_its_kind() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    assert!(LoroCounter::new().increment_int(7).is_err());
+    let detached = LoroCounter::new_typed();
+    detached.increment_int(7)?;
+    assert_eq!(detached.kind(), CounterKind::Int);
+
+    let attached = doc.get_map("root").insert_container("count", detached)?;
+    assert_eq!(attached.kind(), CounterKind::Int);
+    assert!(attached.is_typed());
+    assert_eq!(attached.get_i64(), Some(7));
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({ "root": { "count": 7 } })
+    );
+    Ok(())
+}
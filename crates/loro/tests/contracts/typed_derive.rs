@@ -0,0 +1,294 @@
+#![cfg(feature = "derive")]
+
+use loro::{
+    typed::{LoroMapped, MappedHandle},
+    ContainerTrait, ExportMode, Loro, LoroDoc, LoroError, LoroMap, LoroResult, LoroValue, ToJson,
+};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+#[derive(Loro, Debug, Clone, PartialEq)]
+struct Board {
+    title: String,
+    #[loro(text)]
+    description: String,
+    columns: Vec<Column>,
+    settings: Settings,
+    #[loro(rename = "pinned_ids")]
+    pinned: Vec<i64>,
+    #[loro(default)]
+    archived: bool,
+    #[loro(skip)]
+    dirty: bool,
+}
+
+#[derive(Loro, Debug, Clone, PartialEq)]
+struct Column {
+    name: String,
+    limit: Option<u32>,
+    cards: Vec<Card>,
+}
+
+#[derive(Loro, Debug, Clone, PartialEq)]
+#[loro(tag = "kind")]
+enum Card {
+    Note {
+        #[loro(text)]
+        body: String,
+    },
+    #[loro(rename = "link")]
+    Link(String, Option<String>),
+    Divider,
+}
+
+#[derive(Loro, Debug, Clone, PartialEq)]
+struct Settings {
+    theme: String,
+    zoom: f64,
+}
+
+fn board() -> Board {
+    Board {
+        title: "Roadmap".into(),
+        description: "hello world".into(),
+        columns: vec![Column {
+            name: "Todo".into(),
+            limit: Some(3),
+            cards: vec![
+                Card::Note {
+                    body: "write docs".into(),
+                },
+                Card::Link("https://loro.dev".into(), None),
+                Card::Divider,
+            ],
+        }],
+        settings: Settings {
+            theme: "dark".into(),
+            zoom: 1.0,
+        },
+        pinned: vec![1, 2, 3, 4],
+        archived: false,
+        dirty: true,
+    }
+}
+
+fn saved(doc: &LoroDoc, value: &Board) -> LoroResult<BoardHandle> {
+    let handle = BoardHandle::from(doc.get_map("board"));
+    handle.save(value)?;
+    doc.commit();
+    Ok(handle)
+}
+
+/// Save `value` and return the number of ops it produced.
+fn ops_of_save(doc: &LoroDoc, handle: &BoardHandle, value: &Board) -> LoroResult<usize> {
+    let before = doc.len_ops();
+    handle.save(value)?;
+    doc.commit();
+    Ok(doc.len_ops() - before)
+}
+
+#[test]
+fn save_and_load_round_trip_through_containers() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let handle = saved(&doc, &board())?;
+
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({
+            "board": {
+                "title": "Roadmap",
+                "description": "hello world",
+                "columns": [{
+                    "name": "Todo",
+                    "limit": 3,
+                    "cards": [
+                        { "kind": "Note", "body": "write docs" },
+                        { "kind": "link", "0": "https://loro.dev" },
+                        { "kind": "Divider" },
+                    ],
+                }],
+                "settings": { "theme": "dark", "zoom": 1.0 },
+                "pinned_ids": [1, 2, 3, 4],
+                "archived": false,
+            }
+        })
+    );
+
+    let restored = LoroDoc::new();
+    restored.import(&doc.export(ExportMode::snapshot())?)?;
+    let loaded = Board::load(&restored.get_map("board"))?;
+    assert_eq!(
+        loaded,
+        Board {
+            dirty: false,
+            ..board()
+        }
+    );
+    assert_eq!(handle.load()?, loaded);
+    Ok(())
+}
+
+#[test]
+fn saves_only_write_what_changed() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let mut value = board();
+    let handle = saved(&doc, &value)?;
+    assert_eq!(ops_of_save(&doc, &handle, &value)?, 0);
+
+    value.archived = true;
+    assert_eq!(ops_of_save(&doc, &handle, &value)?, 1);
+
+    // Text is diffed: only the inserted characters are new ops
+    value.description = "hello brave world".into();
+    assert_eq!(ops_of_save(&doc, &handle, &value)?, "brave ".len());
+
+    // Lists of plain values only replace the changed range
+    value.pinned = vec![1, 9, 3, 4];
+    assert_eq!(ops_of_save(&doc, &handle, &value)?, 2);
+    value.pinned = vec![0, 1, 9, 3, 4];
+    assert_eq!(ops_of_save(&doc, &handle, &value)?, 1);
+
+    // Nested containers are updated in place
+    let settings = doc
+        .get_map("board")
+        .get("settings")
+        .unwrap()
+        .into_container()
+        .unwrap()
+        .into_map()
+        .unwrap();
+    value.settings.zoom = 1.5;
+    value.columns[0].limit = None;
+    assert_eq!(ops_of_save(&doc, &handle, &value)?, 2);
+    assert_eq!(handle.settings()?.map().id(), settings.id());
+    assert_eq!(handle.load()?.columns[0].limit, None);
+    Ok(())
+}
+
+#[test]
+fn enum_variant_changes_replace_the_old_fields() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("card");
+    Card::Link("https://loro.dev".into(), Some("Loro".into())).save(&map)?;
+    assert_eq!(
+        map.get_deep_value().to_json_value(),
+        json!({ "kind": "link", "0": "https://loro.dev", "1": "Loro" })
+    );
+
+    let note = Card::Note {
+        body: "hello".into(),
+    };
+    note.save(&map)?;
+    assert_eq!(
+        map.get_deep_value().to_json_value(),
+        json!({ "kind": "Note", "body": "hello" })
+    );
+    assert_eq!(Card::load(&map)?, note);
+
+    map.insert("kind", "Poll")?;
+    assert!(matches!(Card::load(&map), Err(LoroError::DecodeError(_))));
+    Ok(())
+}
+
+#[test]
+fn load_reports_missing_and_mistyped_fields() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("settings");
+    map.insert("theme", "dark")?;
+    assert!(matches!(
+        Settings::load(&map),
+        Err(LoroError::NotFoundError(_))
+    ));
+
+    map.insert("zoom", "large")?;
+    let Err(LoroError::DecodeError(msg)) = Settings::load(&map) else {
+        panic!("expected a decode error");
+    };
+    assert_eq!(&*msg, "field \"zoom\": expected double, found string");
+
+    // Ints are accepted where doubles are expected, and missing options are `None`
+    map.insert("zoom", 2)?;
+    assert_eq!(Settings::load(&map)?.zoom, 2.0);
+    let column = doc.get_map("column");
+    column.insert("name", "Done")?;
+    column.insert_container("cards", loro::LoroList::new())?;
+    assert_eq!(Column::load(&column)?.limit, None);
+    Ok(())
+}
+
+#[test]
+fn handles_expose_typed_field_accessors() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let handle = saved(&doc, &board())?;
+
+    handle.title()?.set(&"Roadmap 2025".to_string())?;
+    handle.description()?.insert(0, "Say ")?;
+    handle.pinned()?.push(&5)?;
+    assert_eq!(handle.pinned()?.get(4)?, Some(5));
+    assert_eq!(handle.settings()?.theme()?.get()?, "dark");
+
+    let columns = handle.columns()?;
+    assert_eq!(columns.len(), 1);
+    let mut column = columns.get(0)?.unwrap();
+    column.cards.pop();
+    columns.set(0, &column)?;
+    columns.push(&Column {
+        name: "Done".into(),
+        limit: None,
+        cards: vec![],
+    })?;
+    doc.commit();
+
+    let loaded = handle.load()?;
+    assert_eq!(loaded.title, "Roadmap 2025");
+    assert_eq!(loaded.description, "Say hello world");
+    assert_eq!(loaded.pinned, vec![1, 2, 3, 4, 5]);
+    assert_eq!(loaded.columns.len(), 2);
+    assert_eq!(loaded.columns[0].cards.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn concurrent_saves_of_new_fields_merge() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+
+    let mut value = board();
+    value.description = "A".into();
+    saved(&a, &value)?;
+    value.description = "B".into();
+    saved(&b, &value)?;
+    a.import(&b.export(ExportMode::all_updates())?)?;
+    b.import(&a.export(ExportMode::all_updates())?)?;
+
+    // Both peers created the text with `ensure_mergeable_text`, so their edits merge
+    let loaded = Board::load(&a.get_map("board"))?;
+    assert_eq!(loaded.description.len(), 2);
+    assert_eq!(
+        a.get_deep_value().to_json_value(),
+        b.get_deep_value().to_json_value()
+    );
+    Ok(())
+}
+
+#[test]
+fn detached_maps_can_be_saved_and_attached() -> LoroResult<()> {
+    let detached = LoroMap::new();
+    let settings = Settings {
+        theme: "light".into(),
+        zoom: 0.5,
+    };
+    settings.save(&detached)?;
+    assert_eq!(Settings::load(&detached)?, settings);
+
+    let doc = LoroDoc::new();
+    let attached = doc.get_map("root").insert_container("settings", detached)?;
+    assert_eq!(Settings::load(&attached)?, settings);
+    assert_eq!(
+        attached.get("zoom").unwrap().into_value().unwrap(),
+        LoroValue::Double(0.5)
+    );
+    Ok(())
+}
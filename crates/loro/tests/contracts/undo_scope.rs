@@ -0,0 +1,108 @@
+use loro::{ContainerTrait, LoroDoc, LoroMap, LoroResult, LoroText, ToJson, UndoManager};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+#[test]
+fn each_pane_undoes_only_its_own_edits() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let left = doc.get_text("left");
+    let right = doc.get_text("right");
+    let mut left_undo = UndoManager::new_scoped(&doc, [left.id()]);
+    let mut right_undo = UndoManager::new_scoped(&doc, [right.id()]);
+
+    left.insert(0, "hello")?;
+    doc.commit();
+    right.insert(0, "world")?;
+    doc.commit();
+    left.insert(5, "!")?;
+    doc.commit();
+    right.insert(0, "> ")?;
+    doc.commit();
+    assert_eq!(left_undo.undo_count(), 2);
+    assert_eq!(right_undo.undo_count(), 2);
+
+    assert!(left_undo.undo()?);
+    assert_eq!(left.to_string(), "hello");
+    assert_eq!(right.to_string(), "> world");
+    assert!(right_undo.undo()?);
+    assert!(right_undo.undo()?);
+    assert_eq!(left.to_string(), "hello");
+    assert_eq!(right.to_string(), "");
+    assert!(!right_undo.undo()?);
+
+    // The undo of one pane isn't recorded by the other one
+    assert_eq!(left_undo.undo_count(), 1);
+    assert!(left_undo.redo()?);
+    assert_eq!(left.to_string(), "hello!");
+    assert!(right_undo.redo()?);
+    assert_eq!(right.to_string(), "world");
+    Ok(())
+}
+
+#[test]
+fn descendants_of_the_scope_are_included() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let form = doc.get_map("form");
+    let mut undo = UndoManager::new_scoped(&doc, [form.id()]);
+
+    let title = form.insert_container("title", LoroText::new())?;
+    title.insert(0, "Title")?;
+    doc.commit();
+    let fields = form.insert_container("fields", LoroMap::new())?;
+    fields.insert("name", "Alice")?;
+    doc.get_map("other").insert("x", 1)?;
+    doc.commit();
+    fields.insert("name", "Bob")?;
+    doc.commit();
+    assert_eq!(undo.undo_count(), 3);
+
+    assert!(undo.undo()?);
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({ "form": { "title": "Title", "fields": { "name": "Alice" } }, "other": { "x": 1 } })
+    );
+    // Only the part of the commit that is in the scope is undone
+    assert!(undo.undo()?);
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({ "form": { "title": "Title" }, "other": { "x": 1 } })
+    );
+    Ok(())
+}
+
+#[test]
+fn changes_outside_of_the_scope_are_kept_on_undo_and_redo() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let list = doc.get_list("list");
+    let log = doc.get_list("log");
+    let mut undo = UndoManager::new_scoped(&doc, [list.id()]);
+
+    list.insert(0, 1)?;
+    doc.commit();
+    list.insert(1, 2)?;
+    doc.commit();
+    log.push("first")?;
+    doc.commit();
+    assert_eq!(undo.undo_count(), 2);
+
+    assert!(undo.undo()?);
+    log.push("second")?;
+    doc.commit();
+    assert!(undo.undo()?);
+    assert!(!undo.can_undo());
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({ "list": [], "log": ["first", "second"] })
+    );
+
+    assert!(undo.redo()?);
+    assert!(undo.redo()?);
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({ "list": [1, 2], "log": ["first", "second"] })
+    );
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use loro::{
+    cursor::Side, ExportMode, LoroDoc, LoroError, LoroResult, LoroValue, ToJson, UndoItemMeta,
+    UndoManager,
+};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+
+fn deep_json(doc: &LoroDoc) -> Value {
+    doc.get_deep_value().to_json_value()
+}
+
+/// Reload `doc` from a snapshot, and restore the undo manager from `state`.
+fn reload(doc: &LoroDoc, state: &[u8]) -> LoroResult<(LoroDoc, UndoManager)> {
+    let restored = LoroDoc::from_snapshot(&doc.export(ExportMode::snapshot()).unwrap())?;
+    restored.set_peer_id(doc.peer_id())?;
+    let undo = UndoManager::new(&restored);
+    undo.import_state(state)?;
+    Ok((restored, undo))
+}
+
+#[test]
+fn undo_and_redo_work_the_same_after_a_reload() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let mut undo = UndoManager::new(&doc);
+    doc.get_text("text").insert(0, "hello")?;
+    doc.commit();
+    doc.get_map("map").insert("a", 1)?;
+    doc.commit();
+    doc.get_text("text").insert(5, " world")?;
+    doc.commit();
+    undo.undo()?;
+
+    let (restored, mut restored_undo) = reload(&doc, &undo.export_state())?;
+    assert_eq!(restored_undo.undo_count(), undo.undo_count());
+    assert_eq!(restored_undo.redo_count(), 1);
+
+    assert!(restored_undo.redo()?);
+    assert!(undo.redo()?);
+    assert_eq!(deep_json(&restored), deep_json(&doc));
+    for _ in 0..3 {
+        assert!(restored_undo.undo()?);
+        assert!(undo.undo()?);
+        assert_eq!(deep_json(&restored), deep_json(&doc));
+    }
+    assert_eq!(deep_json(&restored), json!({ "text": "", "map": {} }));
+    assert!(!restored_undo.undo()?);
+    Ok(())
+}
+
+#[test]
+fn remote_changes_before_the_reload_are_respected() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let undo = UndoManager::new(&a);
+    a.get_text("text").insert(0, "hello")?;
+    a.commit();
+
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    b.import(&a.export(ExportMode::all_updates()).unwrap())?;
+    b.get_text("text").insert(0, "> ")?;
+    b.commit();
+    a.import(&b.export(ExportMode::all_updates()).unwrap())?;
+
+    // Undoing the insertion of "hello" has to skip the concurrent "> "
+    let (restored, mut restored_undo) = reload(&a, &undo.export_state())?;
+    assert!(restored_undo.undo()?);
+    assert_eq!(restored.get_text("text").to_string(), "> ");
+    assert!(restored_undo.redo()?);
+    assert_eq!(restored.get_text("text").to_string(), "> hello");
+    Ok(())
+}
+
+#[test]
+fn item_values_and_cursors_are_restored() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let text = doc.get_text("text");
+    text.insert(0, "hello")?;
+    doc.commit();
+
+    let mut undo = UndoManager::new(&doc);
+    let cursor = text.get_cursor(5, Side::Middle).unwrap();
+    let pushed_cursor = cursor.clone();
+    undo.set_on_push(Some(Box::new(move |_, _, _| {
+        let mut meta = UndoItemMeta::new();
+        meta.set_value(LoroValue::from("typing"));
+        meta.add_cursor(&pushed_cursor);
+        meta
+    })));
+    text.insert(5, " world")?;
+    doc.commit();
+
+    let (_restored, mut restored_undo) = reload(&doc, &undo.export_state())?;
+    let meta = restored_undo.top_undo_meta().unwrap();
+    assert_eq!(meta.value, LoroValue::from("typing"));
+    assert_eq!(meta.cursors.len(), 1);
+    assert_eq!(meta.cursors[0].cursor, cursor);
+    assert_eq!(meta.cursors[0].pos.pos, 5);
+
+    let popped = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let popped_clone = std::sync::Arc::clone(&popped);
+    restored_undo.set_on_pop(Some(Box::new(move |_, _, meta| {
+        popped_clone.lock().unwrap().push(meta);
+    })));
+    assert!(restored_undo.undo()?);
+    let popped = popped.lock().unwrap();
+    assert_eq!(popped[0].value, LoroValue::from("typing"));
+    assert_eq!(popped[0].cursors[0].pos.pos, 5);
+    Ok(())
+}
+
+#[test]
+fn states_that_do_not_match_the_document_are_rejected() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let undo = UndoManager::new(&doc);
+    doc.get_text("text").insert(0, "hello")?;
+    doc.commit();
+    let state = undo.export_state();
+
+    let other_peer = LoroDoc::from_snapshot(&doc.export(ExportMode::snapshot()).unwrap())?;
+    other_peer.set_peer_id(2)?;
+    let other_undo = UndoManager::new(&other_peer);
+    assert!(matches!(
+        other_undo.import_state(&state),
+        Err(LoroError::UndoWithDifferentPeerId {
+            expected: 1,
+            actual: 2
+        })
+    ));
+
+    let missing_changes = LoroDoc::new();
+    missing_changes.set_peer_id(1)?;
+    let missing_undo = UndoManager::new(&missing_changes);
+    assert!(matches!(
+        missing_undo.import_state(&state),
+        Err(LoroError::UndoInvalidIdSpan(_))
+    ));
+    assert!(matches!(
+        missing_undo.import_state(&[0xff, 0xff, 0xff]),
+        Err(LoroError::DecodeError(_))
+    ));
+
+    // The state is prefixed with a magic and a version
+    let same_peer = LoroDoc::from_snapshot(&doc.export(ExportMode::snapshot()).unwrap())?;
+    same_peer.set_peer_id(1)?;
+    let same_undo = UndoManager::new(&same_peer);
+    let mut future = state.clone();
+    future[4] += 1;
+    assert!(matches!(
+        same_undo.import_state(&future),
+        Err(LoroError::DecodeError(msg)) if msg.contains("unsupported undo state version")
+    ));
+    assert!(matches!(
+        same_undo.import_state(&state[5..]),
+        Err(LoroError::DecodeError(_))
+    ));
+    assert!(!same_undo.can_undo());
+    same_undo.import_state(&state)?;
+    assert!(same_undo.can_undo());
+    assert!(!missing_undo.can_undo());
+    Ok(())
+}
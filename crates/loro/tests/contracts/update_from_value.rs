@@ -0,0 +1,272 @@
+use loro::{
+    ContainerTrait, ExportMode, LoroDoc, LoroError, LoroList, LoroMap, LoroResult, LoroText,
+    LoroValue, ToJson,
+};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+
+/// Apply `value` to `map`, commit, and return the number of ops it produced.
+fn ops_of_update(doc: &LoroDoc, map: &LoroMap, value: Value) -> LoroResult<usize> {
+    let before = doc.len_ops();
+    map.update_from_value(&value.into())?;
+    doc.commit();
+    Ok(doc.len_ops() - before)
+}
+
+fn child_map(map: &LoroMap, key: &str) -> LoroMap {
+    map.get(key)
+        .unwrap()
+        .into_container()
+        .unwrap()
+        .into_map()
+        .unwrap()
+}
+
+#[test]
+fn map_updates_only_emit_ops_for_changed_entries() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("root");
+    let value = json!({
+        "title": "Notes",
+        "count": 1,
+        "settings": { "theme": "dark", "zoom": 1.0 },
+        "tags": ["a", "b"],
+    });
+    ops_of_update(&doc, &map, value.clone())?;
+    assert_eq!(map.get_deep_value().to_json_value(), value);
+    assert_eq!(ops_of_update(&doc, &map, value)?, 0);
+
+    let settings = child_map(&map, "settings");
+    let ops = ops_of_update(
+        &doc,
+        &map,
+        json!({
+            "title": "Notes",
+            "settings": { "theme": "light", "zoom": 1.0 },
+            "tags": ["a", "b"],
+        }),
+    )?;
+    // Delete "count" and set "theme"
+    assert_eq!(ops, 2);
+    assert_eq!(child_map(&map, "settings").id(), settings.id());
+    assert_eq!(
+        settings.get_deep_value().to_json_value(),
+        json!({ "theme": "light", "zoom": 1.0 })
+    );
+
+    // Values of another kind replace the entry
+    ops_of_update(
+        &doc,
+        &map,
+        json!({ "title": ["Notes"], "settings": null, "tags": { "a": true } }),
+    )?;
+    assert_eq!(
+        map.get_deep_value().to_json_value(),
+        json!({ "title": ["Notes"], "settings": null, "tags": { "a": true } })
+    );
+    Ok(())
+}
+
+#[test]
+fn texts_are_updated_with_a_diff() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("root");
+    let body = map.insert_container("body", LoroText::new())?;
+    body.insert(0, "hello world")?;
+    doc.commit();
+
+    let ops = ops_of_update(&doc, &map, json!({ "body": "hello brave world" }))?;
+    assert_eq!(ops, "brave ".len());
+    assert_eq!(body.to_string(), "hello brave world");
+
+    // Strings that are plain values stay plain values
+    ops_of_update(
+        &doc,
+        &map,
+        json!({ "body": "hello brave world", "note": "a" }),
+    )?;
+    assert!(map.get("note").unwrap().into_value().is_ok());
+    Ok(())
+}
+
+#[test]
+fn list_updates_keep_the_longest_common_subsequence() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_list("list");
+    let ops_of = |value: Value| -> LoroResult<usize> {
+        let before = doc.len_ops();
+        list.update_from_value(&value.into())?;
+        doc.commit();
+        Ok(doc.len_ops() - before)
+    };
+
+    assert_eq!(ops_of(json!([1, 2, 3, 4, 5, 6]))?, 6);
+    // Move 6 to the front: one delete and one insert
+    assert_eq!(ops_of(json!([6, 1, 2, 3, 4, 5]))?, 2);
+    // Replace 2 with 7 and insert 8
+    assert_eq!(ops_of(json!([6, 1, 7, 3, 4, 8, 5]))?, 3);
+    assert_eq!(
+        list.get_deep_value().to_json_value(),
+        json!([6, 1, 7, 3, 4, 8, 5])
+    );
+    assert_eq!(ops_of(json!([]))?, 7);
+    assert!(list.is_empty());
+    Ok(())
+}
+
+#[test]
+fn list_elements_that_are_containers_are_updated_in_place() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_list("list");
+    list.update_from_value(
+        &json!([{ "id": 1, "done": false }, { "id": 2, "done": false }, ["x"]]).into(),
+    )?;
+    doc.commit();
+    let first = list
+        .get(0)
+        .unwrap()
+        .into_container()
+        .unwrap()
+        .into_map()
+        .unwrap();
+    let nested = list
+        .get(2)
+        .unwrap()
+        .into_container()
+        .unwrap()
+        .into_list()
+        .unwrap();
+
+    let before = doc.len_ops();
+    list.update_from_value(
+        &json!([{ "id": 1, "done": true }, { "id": 2, "done": false }, ["x", "y"]]).into(),
+    )?;
+    doc.commit();
+    assert_eq!(doc.len_ops() - before, 2);
+    assert_eq!(
+        list.get(0)
+            .unwrap()
+            .into_container()
+            .unwrap()
+            .into_map()
+            .unwrap()
+            .id(),
+        first.id()
+    );
+    assert_eq!(nested.get_deep_value().to_json_value(), json!(["x", "y"]));
+    Ok(())
+}
+
+#[test]
+fn movable_lists_replace_elements_with_set() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_movable_list("list");
+    list.update_from_value(&json!(["a", "b", "c"]).into())?;
+    doc.commit();
+
+    let before = doc.len_ops();
+    list.update_from_value(&json!(["a", "x", "c", "d"]).into())?;
+    doc.commit();
+    // Set "b" to "x" and insert "d"
+    assert_eq!(doc.len_ops() - before, 2);
+    assert_eq!(
+        list.get_deep_value().to_json_value(),
+        json!(["a", "x", "c", "d"])
+    );
+
+    // Movable lists nested in maps are updated in place too
+    let root = doc.get_map("root");
+    let nested = root.insert_container("items", loro::LoroMovableList::new())?;
+    nested.push(1)?;
+    root.update_from_value(&json!({ "items": [1, 2] }).into())?;
+    assert_eq!(nested.get_deep_value().to_json_value(), json!([1, 2]));
+    Ok(())
+}
+
+#[test]
+fn updates_preserve_concurrent_edits() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let initial = json!({ "title": "Notes", "tags": ["a"], "meta": { "views": 1 } });
+    a.get_map("root").update_from_value(&initial.into())?;
+    a.commit();
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    b.import(&a.export(ExportMode::all_updates()).unwrap())?;
+
+    // A edits locally, B applies a new version received from a server
+    child_map(&a.get_map("root"), "meta").insert("likes", 3)?;
+    a.commit();
+    b.get_map("root").update_from_value(
+        &json!({ "title": "Notes v2", "tags": ["a", "b"], "meta": { "views": 1 } }).into(),
+    )?;
+    b.commit();
+
+    a.import(&b.export(ExportMode::all_updates()).unwrap())?;
+    assert_eq!(
+        a.get_map("root").get_deep_value().to_json_value(),
+        json!({
+            "title": "Notes v2",
+            "tags": ["a", "b"],
+            "meta": { "views": 1, "likes": 3 },
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn concurrently_added_children_merge() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_map("root")
+        .update_from_value(&json!({ "meta": { "a": 1 } }).into())?;
+    b.get_map("root")
+        .update_from_value(&json!({ "meta": { "b": 2 } }).into())?;
+    a.commit();
+    b.commit();
+
+    a.import(&b.export(ExportMode::all_updates()).unwrap())?;
+    assert_eq!(
+        a.get_map("root").get_deep_value().to_json_value(),
+        json!({ "meta": { "a": 1, "b": 2 } })
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_values_are_rejected() {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("root");
+    let list = doc.get_list("list");
+    assert!(matches!(
+        map.update_from_value(&json!([1]).into()),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(matches!(
+        list.update_from_value(&json!({ "a": 1 }).into()),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(matches!(
+        list.update_from_value(&vec![LoroValue::Container(map.id())].into()),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(list.is_empty());
+}
+
+#[test]
+fn detached_containers_can_be_updated() -> LoroResult<()> {
+    let map = LoroMap::new();
+    map.update_from_value(&json!({ "a": { "b": [1, 2] } }).into())?;
+    map.update_from_value(&json!({ "a": { "b": [2, 3] } }).into())?;
+    assert_eq!(
+        map.get_deep_value().to_json_value(),
+        json!({ "a": { "b": [2, 3] } })
+    );
+
+    let list = LoroList::new();
+    list.update_from_value(&json!([{ "x": 1 }]).into())?;
+    assert_eq!(list.get_deep_value().to_json_value(), json!([{ "x": 1 }]));
+    Ok(())
+}
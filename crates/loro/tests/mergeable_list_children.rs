@@ -0,0 +1,284 @@
+//! Coverage for mergeable child containers keyed inside `LoroList` / `LoroMovableList`
+//! and inside `LoroTree` node metadata.
+
+use std::sync::{Arc, Mutex};
+
+use loro::{
+    event::{Diff, ListDiffItem},
+    ContainerTrait, ExportMode, Index, LoroDoc, LoroError, LoroList, ToJson, TreeParentId,
+    ValueOrContainer,
+};
+use serde_json::json;
+use serial_test::parallel;
+
+fn doc(peer: u64) -> LoroDoc {
+    let d = LoroDoc::new();
+    d.set_peer_id(peer).unwrap();
+    d
+}
+
+fn sync(a: &LoroDoc, b: &LoroDoc) {
+    a.import(&b.export(ExportMode::updates(&a.oplog_vv())).unwrap())
+        .unwrap();
+    b.import(&a.export(ExportMode::updates(&b.oplog_vv())).unwrap())
+        .unwrap();
+}
+
+/// Concurrent first ensures of the same key converge on one child, and the next ensure
+/// removes the duplicate element each peer appended.
+#[test]
+#[parallel]
+fn concurrent_list_ensures_merge_and_dedupe() {
+    let a = doc(1);
+    let b = doc(2);
+
+    let a_text = a
+        .get_list("sections")
+        .ensure_mergeable_text("intro")
+        .unwrap();
+    let b_text = b
+        .get_list("sections")
+        .ensure_mergeable_text("intro")
+        .unwrap();
+    assert_eq!(a_text.id(), b_text.id());
+    assert!(a_text.id().is_mergeable());
+
+    a_text.insert(0, "Hello").unwrap();
+    b_text.insert(0, "World").unwrap();
+    a.commit();
+    b.commit();
+    sync(&a, &b);
+
+    let list = a.get_list("sections");
+    assert_eq!(list.len(), 2, "each peer appended its own element");
+    let merged = a_text.to_string();
+    assert!(merged.contains("Hello") && merged.contains("World"));
+
+    a.get_list("sections")
+        .ensure_mergeable_text("intro")
+        .unwrap();
+    a.commit();
+    sync(&a, &b);
+    assert_eq!(a.get_list("sections").len(), 1);
+    assert_eq!(
+        a.get_deep_value().to_json_value(),
+        json!({ "sections": [merged] })
+    );
+    assert_eq!(
+        b.get_deep_value().to_json_value(),
+        a.get_deep_value().to_json_value()
+    );
+}
+
+/// Before anyone ensures again, every element left by concurrent ensures shows the child, in
+/// the deep value as in `len()`, and paths point at the first one.
+#[test]
+#[parallel]
+fn concurrent_list_ensures_show_the_child_at_each_element() {
+    let a = doc(1);
+    let b = doc(2);
+    a.get_list("list").push("a").unwrap();
+    let counter = a
+        .get_list("list")
+        .ensure_mergeable_counter("votes")
+        .unwrap();
+    counter.increment(1.).unwrap();
+    b.get_list("list").push("b").unwrap();
+    b.get_list("list")
+        .ensure_mergeable_counter("votes")
+        .unwrap()
+        .increment(2.)
+        .unwrap();
+    a.get_movable_list("movable")
+        .ensure_mergeable_text("note")
+        .unwrap();
+    b.get_movable_list("movable")
+        .ensure_mergeable_text("note")
+        .unwrap();
+    a.commit();
+    b.commit();
+    sync(&a, &b);
+
+    assert_eq!(a.get_list("list").len(), 4);
+    let value = a.get_deep_value().to_json_value();
+    assert_eq!(value, b.get_deep_value().to_json_value());
+    assert_eq!(value["list"].as_array().unwrap().len(), 4);
+    assert_eq!(
+        value["list"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|v| v.as_f64() == Some(3.))
+            .count(),
+        2
+    );
+    assert_eq!(value["movable"], json!(["", ""]));
+
+    // The path points at the first element, also once the list outgrows its tiny form
+    a.get_list("list").push("c").unwrap();
+    a.commit();
+    let path = a.get_path_to_container(&counter.id()).unwrap();
+    assert_eq!(path.last().unwrap().1, Index::Seq(1));
+}
+
+/// Elements that hold a mergeable child read back as containers, next to plain values.
+#[test]
+#[parallel]
+fn list_get_returns_mergeable_child_container() {
+    let d = doc(1);
+    let list = d.get_list("items");
+    list.push(1).unwrap();
+    let map = list.ensure_mergeable_map("meta").unwrap();
+    map.insert("title", "doc").unwrap();
+    d.commit();
+
+    assert_eq!(list.get(0).unwrap().into_value().unwrap(), 1.into());
+    let ValueOrContainer::Container(container) = list.get(1).unwrap() else {
+        panic!("expected the mergeable child container");
+    };
+    assert_eq!(container.id(), map.id());
+    assert_eq!(
+        d.get_deep_value().to_json_value(),
+        json!({ "items": [1, { "title": "doc" }] })
+    );
+
+    // Repeated ensures reuse the existing element
+    assert_eq!(list.ensure_mergeable_map("meta").unwrap().id(), map.id());
+    assert_eq!(list.len(), 2);
+}
+
+#[test]
+#[parallel]
+fn movable_list_mergeable_children_survive_snapshot_and_moves() {
+    let a = doc(1);
+    let list = a.get_movable_list("tasks");
+    list.push("first").unwrap();
+    let notes = list.ensure_mergeable_list("notes").unwrap();
+    notes.push("todo").unwrap();
+    list.mov(1, 0).unwrap();
+    a.commit();
+
+    let b = doc(2);
+    b.import(&a.export(ExportMode::snapshot()).unwrap())
+        .unwrap();
+    let b_list = b.get_movable_list("tasks");
+    assert_eq!(
+        b.get_deep_value().to_json_value(),
+        json!({ "tasks": [["todo"], "first"] })
+    );
+    let b_notes = b_list.ensure_mergeable_list("notes").unwrap();
+    assert_eq!(b_notes.id(), notes.id());
+    assert_eq!(b_list.len(), 2);
+}
+
+#[test]
+#[parallel]
+fn list_ensure_rejects_kind_change_and_detached_lists() {
+    let d = doc(1);
+    let list = d.get_list("items");
+    list.ensure_mergeable_text("body").unwrap();
+    assert!(matches!(
+        list.ensure_mergeable_map("body"),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert_eq!(list.len(), 1);
+
+    assert!(matches!(
+        LoroList::new().ensure_mergeable_text("body"),
+        Err(LoroError::MisuseDetachedContainer { .. })
+    ));
+}
+
+#[test]
+#[parallel]
+fn list_mergeable_child_path_round_trips() {
+    let d = doc(1);
+    let list = d.get_list("items");
+    list.push("plain").unwrap();
+    let text = list.ensure_mergeable_text("intro").unwrap();
+    text.insert(0, "hi").unwrap();
+    d.commit();
+
+    let indexes = d
+        .get_path_to_container(&text.id())
+        .expect("list child should have a logical path")
+        .into_iter()
+        .map(|(_, index)| index)
+        .collect::<Vec<_>>();
+    assert_eq!(indexes, vec![Index::Key("items".into()), Index::Seq(1)]);
+
+    let ValueOrContainer::Container(container) = d.get_by_path(&indexes).unwrap() else {
+        panic!("expected mergeable child container from get_by_path");
+    };
+    assert_eq!(container.id(), text.id());
+}
+
+#[test]
+#[parallel]
+fn list_mergeable_child_insert_events_carry_containers() {
+    let d = doc(1);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_clone = seen.clone();
+    let _sub = d.subscribe_root(Arc::new(move |batch| {
+        for e in batch.events {
+            if let Diff::List(items) = e.diff {
+                for item in items {
+                    if let ListDiffItem::Insert { insert, .. } = item {
+                        for v in insert {
+                            if let ValueOrContainer::Container(c) = v {
+                                seen_clone.lock().unwrap().push(c.id());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }));
+
+    let text = d.get_list("items").ensure_mergeable_text("intro").unwrap();
+    d.commit();
+    assert_eq!(*seen.lock().unwrap(), vec![text.id()]);
+}
+
+/// Tree node metadata is a map, so concurrent lazy children there merge as well.
+#[test]
+#[parallel]
+fn tree_node_meta_mergeable_children_merge_across_peers() {
+    let a = doc(1);
+    let b = doc(2);
+    let node = a.get_tree("tree").create(TreeParentId::Root).unwrap();
+    a.commit();
+    sync(&a, &b);
+
+    let a_text = a
+        .get_tree("tree")
+        .get_meta(node)
+        .unwrap()
+        .ensure_mergeable_text("body")
+        .unwrap();
+    let b_text = b
+        .get_tree("tree")
+        .get_meta(node)
+        .unwrap()
+        .ensure_mergeable_text("body")
+        .unwrap();
+    assert_eq!(a_text.id(), b_text.id());
+    a_text.insert(0, "A").unwrap();
+    b_text.insert(0, "B").unwrap();
+    a.commit();
+    b.commit();
+    sync(&a, &b);
+    assert_eq!(a_text.to_string(), b_text.to_string());
+    assert_eq!(a_text.len_unicode(), 2);
+
+    let indexes = a
+        .get_path_to_container(&a_text.id())
+        .expect("tree meta child should have a logical path")
+        .into_iter()
+        .map(|(_, index)| index)
+        .collect::<Vec<_>>();
+    let ValueOrContainer::Container(container) = a.get_by_path(&indexes).unwrap() else {
+        panic!("expected mergeable child container from get_by_path");
+    };
+    assert_eq!(container.id(), a_text.id());
+}